@@ -0,0 +1,156 @@
+//! Shortcuts for prompt commands, defined in the `[aliases]` table of the
+//! config file, e.g. `b = "break $1"`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Error, bail};
+
+/// How many aliases deep an expansion may go before it's taken to be a loop,
+/// as with `a = "b"` and `b = "a"`.
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
+/// The prompt's aliases, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptAliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl PromptAliases {
+    /// Checks the aliases in `aliases`, none of which may be named like one
+    /// of the `builtins` or have spaces in its name.
+    pub fn new(aliases: BTreeMap<String, String>, builtins: &[&str]) -> Result<Self, Error> {
+        for (name, command) in &aliases {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                bail!("alias names can't be empty or contain spaces, found {name:?}");
+            }
+            if builtins.contains(&name.as_str()) {
+                bail!("the alias '{name}' would hide the built-in command");
+            }
+            if command.trim().is_empty() {
+                bail!("the alias '{name}' is empty");
+            }
+        }
+        Ok(Self { aliases })
+    }
+
+    /// Returns each alias's name and command line, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(name, command)| (name.as_str(), command.as_str()))
+    }
+
+    /// Expands the alias `input` starts with, if any, and then any alias
+    /// that expands to, see [`substitute`]. Fails if that goes more than
+    /// [`MAX_ALIAS_DEPTH`] aliases deep.
+    pub fn expand(&self, input: &str) -> Result<String, Error> {
+        let mut expanded = input.trim().to_string();
+        for _ in 0..=MAX_ALIAS_DEPTH {
+            let (name, args) = expanded.split_once(' ').unwrap_or((&expanded, ""));
+            let Some(command) = self.aliases.get(name) else {
+                return Ok(expanded);
+            };
+            let args = args.split_whitespace().collect::<Vec<_>>();
+            expanded = substitute(command, &args).trim().to_string();
+        }
+        let name = input.split_whitespace().next().unwrap_or_default();
+        bail!("the alias '{name}' expands more than {MAX_ALIAS_DEPTH} aliases deep")
+    }
+}
+
+/// Fills the arguments an alias was given into its command line: `$1` to
+/// `$9` for one argument each, and `$*` for all of them. When the command
+/// mentions none of them, the arguments are added to its end instead.
+pub fn substitute(command: &str, args: &[&str]) -> String {
+    let mut substituted = String::new();
+    let mut used_args = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('$', Some('*')) => {
+                chars.next();
+                substituted.push_str(&args.join(" "));
+                used_args = true;
+            }
+            ('$', Some(digit @ '1'..='9')) => {
+                let idx = *digit as usize - '1' as usize;
+                chars.next();
+                substituted.push_str(args.get(idx).copied().unwrap_or_default());
+                used_args = true;
+            }
+            _ => substituted.push(c),
+        }
+    }
+    if !used_args && !args.is_empty() {
+        substituted.push(' ');
+        substituted.push_str(&args.join(" "));
+    }
+    substituted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(aliases: &[(&str, &str)]) -> Result<PromptAliases, Error> {
+        let aliases = aliases
+            .iter()
+            .map(|(name, command)| (name.to_string(), command.to_string()))
+            .collect();
+        PromptAliases::new(aliases, &["break", "trace"])
+    }
+
+    #[test]
+    fn arguments_are_substituted() {
+        assert_eq!(substitute("break $1", &["setup", "x"]), "break setup");
+        assert_eq!(substitute("raw echo $*", &["a", "b"]), "raw echo a b");
+        assert_eq!(substitute("raw echo $2-$1", &["a", "b"]), "raw echo b-a");
+        assert_eq!(substitute("raw echo $3", &["a"]), "raw echo ");
+        assert_eq!(substitute("trace", &["on"]), "trace on");
+        assert_eq!(substitute("trace", &[]), "trace");
+        assert_eq!(substitute("raw echo $HOME $", &["a"]), "raw echo $HOME $ a");
+    }
+
+    #[test]
+    fn aliases_expand_through_other_aliases() {
+        let aliases = aliases(&[("b", "break $1"), ("bs", "b setup"), ("t", "trace")]).unwrap();
+        assert_eq!(aliases.expand("b teardown").unwrap(), "break teardown");
+        assert_eq!(aliases.expand("bs").unwrap(), "break setup");
+        assert_eq!(aliases.expand(" t on ").unwrap(), "trace on");
+        assert_eq!(aliases.expand("break all").unwrap(), "break all");
+        assert_eq!(aliases.expand("").unwrap(), "");
+    }
+
+    #[test]
+    fn alias_loops_are_cut_off() {
+        let looping = aliases(&[("a", "b $*"), ("b", "a $*"), ("me", "me")]).unwrap();
+        let err = looping.expand("a x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("the alias 'a' expands more than {MAX_ALIAS_DEPTH} aliases deep")
+        );
+        assert!(looping.expand("me").is_err());
+
+        // Going exactly as deep as allowed is fine.
+        let chain = (0..MAX_ALIAS_DEPTH)
+            .map(|level| (format!("a{level}"), format!("a{}", level + 1)))
+            .collect::<Vec<_>>();
+        let chain = chain
+            .iter()
+            .map(|(name, command)| (name.as_str(), command.as_str()))
+            .collect::<Vec<_>>();
+        let chained = aliases(&chain).unwrap();
+        assert_eq!(chained.expand("a0").unwrap(), format!("a{MAX_ALIAS_DEPTH}"));
+    }
+
+    #[test]
+    fn aliases_cant_hide_builtins() {
+        let err = aliases(&[("break", "trace on")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the alias 'break' would hide the built-in command"
+        );
+        assert!(aliases(&[("two words", "trace on")]).is_err());
+        assert!(aliases(&[("t", " ")]).is_err());
+    }
+}
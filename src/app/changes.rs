@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// A command that sets or unsets a variable, as it was queued.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct QueuedChange {
+    name: String,
+    /// The value set, or `None` if the command unsets the variable.
+    value: Option<String>,
+    /// The command as it appears in the output.
+    command: String,
+}
+
+/// How the queued commands leave a variable compared to the environment the
+/// shell paused with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VarChange {
+    pub name: String,
+    /// The value when the shell paused, or `None` if it wasn't set.
+    pub old: Option<String>,
+    /// The value once the commands run, or `None` if they unset it.
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VarChangeKind {
+    Added,
+    Modified,
+    Unset,
+}
+
+impl VarChange {
+    pub fn kind(&self) -> VarChangeKind {
+        match (&self.old, &self.new) {
+            (None, _) => VarChangeKind::Added,
+            (Some(_), None) => VarChangeKind::Unset,
+            (Some(_), Some(_)) => VarChangeKind::Modified,
+        }
+    }
+}
+
+/// Every variable change queued in the session, oldest first.
+///
+/// Commands can be deleted or edited on the Output screen afterwards, so the
+/// changes are only worked out from the ones still in the output when
+/// they're asked for.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct ChangeLog {
+    queued: Vec<QueuedChange>,
+}
+
+impl ChangeLog {
+    /// Records that `command` sets `name` to `value`, or unsets it if `value`
+    /// is `None`.
+    pub fn record(&mut self, name: &str, value: Option<&str>, command: &str) {
+        self.queued.push(QueuedChange {
+            name: name.to_string(),
+            value: value.map(str::to_string),
+            command: command.to_string(),
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.queued.clear();
+    }
+
+    /// Returns the net change to each variable made by the recorded commands
+    /// still in `output`, sorted by name. `original` looks up a variable's
+    /// value when the shell paused. Variables that end up as they started
+    /// aren't changed.
+    pub fn changes<'a>(
+        &self,
+        output: &str,
+        original: impl Fn(&str) -> Option<&'a str>,
+    ) -> Vec<VarChange> {
+        let output = format!("\n{output}");
+        let mut latest = BTreeMap::new();
+        for change in &self.queued {
+            if output.contains(&format!("\n{}\n", change.command)) {
+                latest.insert(change.name.as_str(), change.value.as_deref());
+            }
+        }
+        latest
+            .into_iter()
+            .map(|(name, new)| VarChange {
+                name: name.to_string(),
+                old: original(name).map(str::to_string),
+                new: new.map(str::to_string),
+            })
+            .filter(|change| change.old != change.new)
+            .collect()
+    }
+}
+
+/// How one environment differs from another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvDiff {
+    /// The variables that differ, sorted by name, each going from its value
+    /// in the baseline to its value in the other environment.
+    pub changes: Vec<VarChange>,
+    /// How many variables are set to the same value in both.
+    pub unchanged: usize,
+}
+
+impl EnvDiff {
+    /// Returns how many variables changed in the way `kind` says.
+    pub fn count(&self, kind: VarChangeKind) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.kind() == kind)
+            .count()
+    }
+}
+
+/// What reloading the environment changed, from underneath the commands the
+/// user queued.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvReload {
+    pub diff: EnvDiff,
+    /// The variables the queued commands change whose values changed
+    /// underneath them, so that the commands may no longer do what the user
+    /// meant.
+    pub conflicts: BTreeSet<String>,
+}
+
+impl EnvReload {
+    /// Works out the conflicts between `diff`, how reloading changed the
+    /// environment, and `edits`, how the queued commands changed it from
+    /// before the reload.
+    ///
+    /// An edited variable conflicts once its value changes underneath the
+    /// edit, or if it already did in an earlier reload, in `conflicted`,
+    /// unless the value it's been reloaded with is the one the edit sets.
+    /// The commands are left as they are either way.
+    pub fn new(diff: EnvDiff, edits: &[VarChange], conflicted: &BTreeSet<String>) -> Self {
+        let conflicts = edits
+            .iter()
+            .filter(|edit| {
+                let reloaded = diff.changes.iter().find(|change| change.name == edit.name);
+                let underneath = reloaded.is_some() || conflicted.contains(&edit.name);
+                let now = reloaded.map_or(&edit.old, |change| &change.new);
+                underneath && *now != edit.new
+            })
+            .map(|edit| edit.name.clone())
+            .collect();
+        Self { diff, conflicts }
+    }
+
+    /// Sums up the reload for a toast, like "12 changed, 3 new, 1 removed".
+    pub fn summary(&self) -> String {
+        if self.diff.changes.is_empty() && self.conflicts.is_empty() {
+            return "Reloaded the environment, nothing changed".to_string();
+        }
+        let mut summary = format!(
+            "Reloaded the environment: {} changed, {} new, {} removed",
+            self.diff.count(VarChangeKind::Modified),
+            self.diff.count(VarChangeKind::Added),
+            self.diff.count(VarChangeKind::Unset),
+        );
+        if !self.conflicts.is_empty() {
+            summary.push_str(&format!(
+                ", {} conflicting with queued changes",
+                self.conflicts.len()
+            ));
+        }
+        summary
+    }
+}
+
+/// Compares the environment `current` against `baseline`. Variables only in
+/// `current` were added, ones only in `baseline` were unset, and ones in both
+/// with different values were modified.
+pub fn diff_envs<'a>(
+    baseline: &HashMap<String, String>,
+    current: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> EnvDiff {
+    let current = current.into_iter().collect::<HashMap<_, _>>();
+    let mut changes = baseline
+        .iter()
+        .filter(|(name, _)| !current.contains_key(name.as_str()))
+        .map(|(name, old)| VarChange {
+            name: name.clone(),
+            old: Some(old.clone()),
+            new: None,
+        })
+        .collect::<Vec<_>>();
+    let mut unchanged = 0;
+    for (name, value) in current {
+        match baseline.get(name) {
+            Some(old) if old == value => unchanged += 1,
+            old => changes.push(VarChange {
+                name: name.to_string(),
+                old: old.cloned(),
+                new: Some(value.to_string()),
+            }),
+        }
+    }
+    changes.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    EnvDiff { changes, unchanged }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn original(name: &str) -> Option<&'static str> {
+        match name {
+            "FOO" => Some("bar"),
+            "BAR" => Some("1"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn changes_follow_the_commands_left_in_the_output() {
+        let mut log = ChangeLog::default();
+        log.record("FOO", Some("baz"), "export FOO=baz");
+        log.record("BAR", None, "unset BAR");
+        log.record("NEW", Some("x"), "export NEW=x");
+        log.record("FOO", Some("bar"), "export FOO=bar");
+        let output = "export FOO=baz\nunset BAR\nexport NEW=x\n";
+
+        let changes = log.changes(output, original);
+        let kinds = changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("BAR", VarChangeKind::Unset),
+                ("FOO", VarChangeKind::Modified),
+                ("NEW", VarChangeKind::Added),
+            ]
+        );
+        assert_eq!(changes[1].new.as_deref(), Some("baz"));
+
+        // Setting a variable back to where it started undoes the change.
+        let output = format!("{output}export FOO=bar\n");
+        let changes = log.changes(&output, original);
+        assert!(changes.iter().all(|change| change.name != "FOO"));
+    }
+
+    fn change(name: &str, old: Option<&str>, new: Option<&str>) -> VarChange {
+        VarChange {
+            name: name.to_string(),
+            old: old.map(str::to_string),
+            new: new.map(str::to_string),
+        }
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn reloads_conflict_with_edits_whose_values_changed_underneath() {
+        let edits = [
+            // Edited, and changed underneath to something else.
+            change("EDITED", Some("a"), Some("mine")),
+            // Edited, and changed underneath to what the edit sets.
+            change("AGREED", Some("a"), Some("mine")),
+            // Edited, and left alone by the reload.
+            change("UNTOUCHED", Some("a"), Some("mine")),
+            // Unset by the user, and by the reload too.
+            change("GONE", Some("a"), None),
+            // Unset by the user, but changed underneath.
+            change("UNSET", Some("a"), None),
+            // Added by the user, and by the reload with another value.
+            change("ADDED", None, Some("mine")),
+        ];
+        let diff = EnvDiff {
+            changes: vec![
+                change("ADDED", None, Some("theirs")),
+                change("AGREED", Some("a"), Some("mine")),
+                change("EDITED", Some("a"), Some("theirs")),
+                change("GONE", Some("a"), None),
+                change("NEW", None, Some("x")),
+                change("UNSET", Some("a"), Some("b")),
+            ],
+            unchanged: 10,
+        };
+        let reload = EnvReload::new(diff, &edits, &BTreeSet::new());
+        assert_eq!(reload.conflicts, names(&["ADDED", "EDITED", "UNSET"]));
+        assert_eq!(
+            reload.summary(),
+            "Reloaded the environment: 3 changed, 2 new, 1 removed, 3 conflicting with queued changes"
+        );
+    }
+
+    #[test]
+    fn conflicts_last_until_the_value_agrees_again() {
+        let edits = [
+            change("STILL", Some("theirs"), Some("mine")),
+            change("SETTLED", Some("theirs"), Some("mine")),
+        ];
+        // Neither changed this time, but both had in an earlier reload.
+        let diff = EnvDiff {
+            changes: vec![change("SETTLED", Some("theirs"), Some("mine"))],
+            unchanged: 1,
+        };
+        let earlier = names(&["STILL", "SETTLED", "NO_LONGER_EDITED"]);
+        let reload = EnvReload::new(diff, &edits, &earlier);
+        assert_eq!(reload.conflicts, names(&["STILL"]));
+        assert_eq!(
+            reload.summary(),
+            "Reloaded the environment: 1 changed, 0 new, 0 removed, 1 conflicting with queued changes"
+        );
+
+        let nothing = EnvReload::new(EnvDiff::default(), &edits, &BTreeSet::new());
+        assert!(nothing.conflicts.is_empty());
+        assert_eq!(
+            nothing.summary(),
+            "Reloaded the environment, nothing changed"
+        );
+    }
+
+    #[test]
+    fn envs_diff_by_name_and_value() {
+        let baseline = [
+            ("KEPT", "1"),
+            ("GONE", "2"),
+            ("EDITED", "old"),
+            ("EMPTY", ""),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .into();
+        let current = [("KEPT", "1"), ("EDITED", "new"), ("EMPTY", ""), ("NEW", "")];
+        let diff = diff_envs(&baseline, current);
+        let kinds = diff
+            .changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("EDITED", VarChangeKind::Modified),
+                ("GONE", VarChangeKind::Unset),
+                ("NEW", VarChangeKind::Added),
+            ]
+        );
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.count(VarChangeKind::Added), 1);
+        assert_eq!(diff.changes[0].old.as_deref(), Some("old"));
+        assert_eq!(diff.changes[1].new, None);
+
+        let same = diff_envs(
+            &baseline,
+            [
+                ("KEPT", "1"),
+                ("GONE", "2"),
+                ("EDITED", "old"),
+                ("EMPTY", ""),
+            ],
+        );
+        assert_eq!(
+            same,
+            EnvDiff {
+                changes: Vec::new(),
+                unchanged: 4
+            }
+        );
+    }
+}
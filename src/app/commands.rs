@@ -0,0 +1,1425 @@
+//! The shell commands the debugger prints for the shell to run when it exits.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::app::{Shell, TRACEPOINT_VAR_NAME, glob::glob_match};
+
+/// The sh variable that holds a value with trailing newlines while the
+/// command using it runs.
+const QUOTED_VAR_NAME: &str = "FLOX_DBG_QUOTED";
+
+impl Shell {
+    /// Quotes `value` so that the shell reads it back as a single word, byte
+    /// for byte, with no expansions, and returns the command `command` builds
+    /// around that word. Everything that writes a value into a command goes
+    /// through here.
+    ///
+    /// Plain values are single-quoted. Values with newlines or other control
+    /// characters are written with escapes instead, so the command stays on
+    /// one line and nothing invisible ends up in it: `$'...'` for bash and
+    /// zsh, escapes between quoted runs for fish, and `printf %b` for sh.
+    /// Command substitution drops trailing newlines, so for sh those values
+    /// are printed with a `_` after them into `FLOX_DBG_QUOTED`, and the `_`
+    /// is stripped again before `command` reads the variable.
+    pub fn quote(&self, value: &str, command: impl FnOnce(&str) -> String) -> String {
+        if !value.contains(char::is_control) {
+            let quoted = match self {
+                Shell::Bash | Shell::Zsh | Shell::Posix => {
+                    format!("'{}'", value.replace('\'', r"'\''"))
+                }
+                Shell::Fish => fish_quote(value),
+            };
+            return command(&quoted);
+        }
+        match self {
+            Shell::Bash | Shell::Zsh => command(&ansi_c_quote(value)),
+            Shell::Fish => command(&fish_quote(value)),
+            Shell::Posix if value.ends_with('\n') => format!(
+                "{QUOTED_VAR_NAME}={}; {QUOTED_VAR_NAME}=\"${{{QUOTED_VAR_NAME}%_}}\"; {}",
+                printf_quote(&format!("{value}_")),
+                command(&format!("\"${QUOTED_VAR_NAME}\""))
+            ),
+            Shell::Posix => command(&printf_quote(value)),
+        }
+    }
+}
+
+/// Quotes `value` as a bash or zsh `$'...'` string.
+fn ansi_c_quote(value: &str) -> String {
+    let mut quoted = String::from("$'");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str(r"\\"),
+            '\'' => quoted.push_str(r"\'"),
+            '\n' => quoted.push_str(r"\n"),
+            '\t' => quoted.push_str(r"\t"),
+            c if c.is_control() => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    quoted.push_str(&format!(r"\x{byte:02x}"));
+                }
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Quotes `value` for fish. Only `\\` and `\'` are escapes inside fish's
+/// single quotes, so control characters are escaped between quoted runs.
+fn fish_quote(value: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str(r"\\"),
+            '\'' => quoted.push_str(r"\'"),
+            '\n' => quoted.push_str(r"'\n'"),
+            '\t' => quoted.push_str(r"'\t'"),
+            c if c.is_control() && c.is_ascii() => {
+                quoted.push_str(&format!(r"'\x{:02x}'", c as u32));
+            }
+            c if c.is_control() => quoted.push_str(&format!(r"'\u{:04x}'", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Quotes `value` for sh as the output of `printf %b`, which expands octal
+/// escapes everywhere `printf` is POSIX.
+fn printf_quote(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '\'' => escaped.push_str(r"'\''"),
+            c if c.is_control() => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    escaped.push_str(&format!(r"\0{byte:03o}"));
+                }
+            }
+            c => escaped.push(c),
+        }
+    }
+    format!("\"$(printf %b '{escaped}')\"")
+}
+
+/// Why a name can't be used for a variable in a queued command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidVarName {
+    Empty,
+    /// The name has a character that can't appear there in a shell
+    /// identifier, which would split or change the command it's written
+    /// into.
+    BadChar {
+        name: String,
+        bad: char,
+    },
+}
+
+impl fmt::Display for InvalidVarName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidVarName::Empty => write!(f, "variable name is empty"),
+            InvalidVarName::BadChar { name, bad } => {
+                write!(f, "variable name {name:?} contains {bad:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidVarName {}
+
+/// Checks that `name` can be written unquoted into a command as the name of
+/// a variable, which it can if it's a shell identifier.
+pub fn check_var_name(name: &str) -> Result<(), InvalidVarName> {
+    if name.is_empty() {
+        return Err(InvalidVarName::Empty);
+    }
+    if is_name(name) {
+        return Ok(());
+    }
+    // Either the name starts with a digit or some character isn't allowed
+    // anywhere.
+    let bad = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '_'))
+        .unwrap_or_else(|| name.chars().next().unwrap_or_default());
+    Err(InvalidVarName::BadChar {
+        name: name.to_string(),
+        bad,
+    })
+}
+
+/// Returns whether `name` is a shell identifier: letters, digits and
+/// underscores, not starting with a digit.
+pub fn is_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Why a function can't be written into a queued command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidFunction {
+    /// The name is empty, starts with `-`, or has characters other than
+    /// letters, digits, `_`, `-`, `.` and `:`.
+    BadName(String),
+    /// The body spans lines, but every queued command is a single line.
+    MultiLineBody,
+    /// The body closes a block it didn't open, which would end the
+    /// definition early, or leaves one open.
+    Unbalanced,
+}
+
+impl fmt::Display for InvalidFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidFunction::BadName(name) => write!(f, "invalid function name {name:?}"),
+            InvalidFunction::MultiLineBody => {
+                write!(
+                    f,
+                    "function body must be one line, separate commands with ';'"
+                )
+            }
+            InvalidFunction::Unbalanced => write!(f, "function body has unbalanced blocks"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidFunction {}
+
+/// Checks that a function called `name` with `body` can be defined on one
+/// line in `shell`.
+pub fn check_function(shell: Shell, name: &str, body: &str) -> Result<(), InvalidFunction> {
+    let name_ok = !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-.:".contains(c));
+    if !name_ok {
+        return Err(InvalidFunction::BadName(name.to_string()));
+    }
+    if body.contains('\n') {
+        return Err(InvalidFunction::MultiLineBody);
+    }
+    // Only reserved words in command position open and close blocks, which
+    // is the first word of each statement and pipeline stage.
+    let mut depth: usize = 0;
+    for statement in split_unquoted(body, &[';', '|', '&']) {
+        let mut words = statement.split_whitespace().skip_while(|word| {
+            matches!(shell, Shell::Fish) && matches!(*word, "and" | "or" | "not" | "!")
+        });
+        let change = match (shell, words.next()) {
+            (Shell::Fish, Some("function" | "if" | "for" | "while" | "begin" | "switch")) => 1,
+            (Shell::Fish, Some("end")) => -1,
+            (Shell::Bash | Shell::Zsh | Shell::Posix, Some("{")) => 1,
+            (Shell::Bash | Shell::Zsh | Shell::Posix, Some("}")) => -1,
+            _ => 0,
+        };
+        depth = depth
+            .checked_add_signed(change)
+            .ok_or(InvalidFunction::Unbalanced)?;
+    }
+    if depth != 0 {
+        return Err(InvalidFunction::Unbalanced);
+    }
+    Ok(())
+}
+
+/// Splits `line` at each of `separators` that isn't quoted or escaped.
+fn split_unquoted<'a>(line: &'a str, separators: &[char]) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            // Only fish has escapes inside single quotes, and only for
+            // backslashes and quotes, which is all that matters here.
+            (Some('\''), '\\') => escaped = true,
+            (Some(_), _) => {}
+            (None, '\\') => escaped = true,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, c) if separators.contains(&c) => {
+                parts.push(&line[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            (None, _) => {}
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// Returns the command that defines a function called `name` that runs
+/// `body`, all on one line.
+pub fn define_function_cmd(shell: Shell, name: &str, body: &str) -> String {
+    let body = body.trim().trim_end_matches(';').trim_end();
+    match shell {
+        Shell::Fish if body.is_empty() => format!("function {name}; end"),
+        Shell::Fish => format!("function {name}; {body}; end"),
+        Shell::Bash | Shell::Zsh | Shell::Posix if body.is_empty() => format!("{name}() {{ :; }}"),
+        // A command run in the background is already terminated, and `&;`
+        // is a syntax error.
+        Shell::Bash | Shell::Zsh | Shell::Posix if body.ends_with('&') && !body.ends_with("&&") => {
+            format!("{name}() {{ {body} }}")
+        }
+        Shell::Bash | Shell::Zsh | Shell::Posix => format!("{name}() {{ {body}; }}"),
+    }
+}
+
+/// Lays out a function definition written by [`define_function_cmd`] over
+/// several lines: the line that opens it, each statement of its body, and
+/// the line that closes it. Returns `None` if `line` isn't one.
+pub fn unfold_function(shell: Shell, line: &str) -> Option<Vec<String>> {
+    let (open, body, close) = match shell {
+        Shell::Fish => {
+            let rest = line.strip_prefix("function ")?.strip_suffix("; end")?;
+            let (name, body) = rest.split_once("; ").unwrap_or((rest, ""));
+            (format!("function {name}"), body, "end")
+        }
+        Shell::Bash | Shell::Zsh | Shell::Posix => {
+            let (name, rest) = line.split_once("() { ")?;
+            if name.contains(char::is_whitespace) {
+                return None;
+            }
+            let body = rest.strip_suffix("; }").or(rest.strip_suffix(" }"))?;
+            (format!("{name}() {{"), body, "}")
+        }
+    };
+    let mut lines = vec![open];
+    lines.extend(
+        split_unquoted(body, &[';'])
+            .into_iter()
+            .map(str::trim)
+            .filter(|statement| !statement.is_empty())
+            .map(|statement| format!("    {statement}")),
+    );
+    lines.push(close.to_string());
+    Some(lines)
+}
+
+/// Why an alias can't be written into a queued command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidAliasName(pub String);
+
+impl fmt::Display for InvalidAliasName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid alias name {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidAliasName {}
+
+/// Checks that `name` can be written unquoted into a command as the name of
+/// an alias, which rules out whitespace, quotes and anything else the shell
+/// would read as more than a plain word.
+pub fn check_alias_name(name: &str) -> Result<(), InvalidAliasName> {
+    let bad = |c: char| c.is_whitespace() || c.is_control() || "='\"\\$`/;|&<>()".contains(c);
+    if name.is_empty() || name.starts_with('-') || name.contains(bad) {
+        return Err(InvalidAliasName(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Returns the command that defines `name` as an alias for `value`.
+pub fn alias_cmd(shell: Shell, name: &str, value: &str) -> String {
+    shell.quote(value, |value| match shell {
+        Shell::Bash | Shell::Zsh | Shell::Posix => format!("alias {name}={value}"),
+        Shell::Fish => format!("alias {name} {value}"),
+    })
+}
+
+/// Resolves a directory typed by the user the way the shell would: a leading
+/// `~` is `home`, and relative paths are relative to `cwd`.
+pub fn resolve_dir(dir: &str, cwd: &Path, home: Option<&Path>) -> PathBuf {
+    let expanded = match (dir.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home.to_path_buf(),
+        (Some(rest), Some(home)) if rest.starts_with('/') => home.join(&rest[1..]),
+        _ => PathBuf::from(dir),
+    };
+    cwd.join(expanded)
+}
+
+/// Returns the command that sets `name` to `value` in the shell's global
+/// scope and exports it to child processes.
+pub fn set_var_cmd(shell: Shell, name: &str, value: &str) -> String {
+    shell.quote(value, |value| match shell {
+        Shell::Bash | Shell::Zsh => format!("export {name}={value}"),
+        Shell::Fish => format!("set -gx {name} {value}"),
+        // Assigning in `export` isn't supported by every historical sh.
+        Shell::Posix => format!("{name}={value}; export {name}"),
+    })
+}
+
+/// The separator between the entries of a PATH-style variable outside of
+/// fish.
+pub const PATH_SEPARATOR: char = ':';
+
+/// Returns the command that sets the PATH-style variable `name` to
+/// `entries` and exports it.
+///
+/// Most shells hold such a variable as one string with the entries joined by
+/// colons. Fish holds it as a list, and joining the entries would leave it
+/// with a single entry, so each entry is passed as its own element instead.
+/// Fish only joins a list with colons when exporting it if it's a path
+/// variable, which by default means its name ends in `PATH`, so the
+/// variable is marked as one with `--path` and children see the same value
+/// whatever it's called.
+pub fn set_path_var_cmd(shell: Shell, name: &str, entries: &[String]) -> String {
+    match shell {
+        Shell::Fish => {
+            let mut command = format!("set -gx --path {name}");
+            for entry in entries {
+                command.push(' ');
+                command.push_str(&shell.quote(entry, str::to_string));
+            }
+            command
+        }
+        Shell::Bash | Shell::Zsh | Shell::Posix => {
+            let joined = entries.join(&PATH_SEPARATOR.to_string());
+            set_var_cmd(shell, name, &joined)
+        }
+    }
+}
+
+/// Returns the entries that contain the separator, which the shell will
+/// read as more than one entry.
+pub fn entries_with_separator(entries: &[String]) -> Vec<&str> {
+    entries
+        .iter()
+        .filter(|entry| entry.contains(PATH_SEPARATOR))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Returns the command that removes `name` from the shell and the
+/// environment of its children.
+///
+/// For fish this erases the global variable, which is the one `set -gx`
+/// creates and the one the debugger sees. A universal variable of the same
+/// name is left alone, since erasing it would reach every fish session
+/// rather than just the one being debugged.
+pub fn unset_var_cmd(shell: Shell, name: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh | Shell::Posix => format!("unset {name}"),
+        Shell::Fish => format!("set -eg {name}"),
+    }
+}
+
+/// The prefix of the shell variables holding what a variable was before the
+/// debugger's commands changed it.
+pub const ORIG_VAR_PREFIX: &str = "FLOX_DBG_ORIG_";
+
+/// The function that puts back every variable saved by a
+/// [`Cmd::SaveOriginal`].
+pub const REVERT_FUNCTION_NAME: &str = "flox_dbg_revert";
+
+/// Returns the shell variable that holds the original value of `name`.
+pub fn orig_var_name(name: &str) -> String {
+    format!("{ORIG_VAR_PREFIX}{name}")
+}
+
+/// Returns the command that saves the value `name` has when it runs. If
+/// `name` is unset the saved variable is unset too, which is how reverting
+/// knows to unset it again. The saved value isn't exported, so it doesn't
+/// reach the shell's children.
+pub fn save_original_cmd(shell: Shell, name: &str) -> String {
+    let orig = orig_var_name(name);
+    match shell {
+        Shell::Bash | Shell::Zsh | Shell::Posix => {
+            format!("if [ -n \"${{{name}+x}}\" ]; then {orig}=\"${name}\"; else unset {orig}; fi")
+        }
+        Shell::Fish => {
+            format!("if set -q {name}; set -g {orig} ${name}; else; set -eg {orig}; end")
+        }
+    }
+}
+
+/// Returns the body of the function that restores each of `names` from what
+/// [`save_original_cmd`] saved, then forgets the saved values and removes
+/// itself so that it can't revert twice.
+pub fn revert_body(shell: Shell, names: &[String]) -> String {
+    let mut statements = names
+        .iter()
+        .map(|name| {
+            let orig = orig_var_name(name);
+            let restore = match shell {
+                Shell::Bash | Shell::Zsh => format!("export {name}=\"${orig}\""),
+                Shell::Posix => format!("{name}=\"${orig}\"; export {name}"),
+                Shell::Fish => format!("set -gx {name} ${orig}"),
+            };
+            let unset = unset_var_cmd(shell, name);
+            let forget = unset_var_cmd(shell, &orig);
+            match shell {
+                Shell::Bash | Shell::Zsh | Shell::Posix => format!(
+                    "if [ -n \"${{{orig}+x}}\" ]; then {restore}; {forget}; else {unset}; fi"
+                ),
+                Shell::Fish => {
+                    format!("if set -q {orig}; {restore}; {forget}; else; {unset}; end")
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    statements.push(match shell {
+        Shell::Bash | Shell::Zsh | Shell::Posix => format!("unset -f {REVERT_FUNCTION_NAME}"),
+        Shell::Fish => format!("functions -e {REVERT_FUNCTION_NAME}"),
+    });
+    statements.join("; ")
+}
+
+/// Returns whether `line` defines the function `name`, as written by
+/// [`define_function_cmd`].
+pub fn defines_function(shell: Shell, line: &str, name: &str) -> bool {
+    match shell {
+        Shell::Fish => line
+            .strip_prefix("function ")
+            .and_then(|rest| rest.strip_prefix(name))
+            .is_some_and(|rest| rest.starts_with(';')),
+        Shell::Bash | Shell::Zsh | Shell::Posix => line
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with("() {")),
+    }
+}
+
+/// A command queued for the shell, kept as what it does rather than as text
+/// so that it can be written in any shell's syntax.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Cmd {
+    SetVar {
+        name: String,
+        value: String,
+    },
+    UnsetVar {
+        name: String,
+    },
+    /// Turn on or off the shell printing every command it runs.
+    Xtrace {
+        on: bool,
+    },
+    DefineFunction {
+        name: String,
+        body: String,
+    },
+    /// Change the shell's working directory to `dir`, which should be
+    /// absolute so that it means the same wherever the shell is.
+    ChangeDir {
+        dir: String,
+    },
+    Alias {
+        name: String,
+        value: String,
+    },
+    /// Save what `name` holds so that the revert function can restore it.
+    SaveOriginal {
+        name: String,
+    },
+}
+
+impl Cmd {
+    /// Returns the command in `shell`'s syntax.
+    pub fn render(&self, shell: Shell) -> String {
+        match self {
+            Cmd::SetVar { name, value } => set_var_cmd(shell, name, value),
+            Cmd::UnsetVar { name } => unset_var_cmd(shell, name),
+            Cmd::Xtrace { on } => match (shell, on) {
+                (Shell::Bash | Shell::Zsh | Shell::Posix, true) => "set -x".to_string(),
+                (Shell::Bash | Shell::Zsh | Shell::Posix, false) => "set +x".to_string(),
+                (Shell::Fish, true) => "set -g fish_trace 1".to_string(),
+                (Shell::Fish, false) => unset_var_cmd(shell, "fish_trace"),
+            },
+            Cmd::DefineFunction { name, body } => define_function_cmd(shell, name, body),
+            // Every shell spells this the same way.
+            Cmd::ChangeDir { dir } => shell.quote(dir, |dir| format!("cd {dir}")),
+            Cmd::Alias { name, value } => alias_cmd(shell, name, value),
+            Cmd::SaveOriginal { name } => save_original_cmd(shell, name),
+        }
+    }
+
+    /// Returns the command that undoes this one, if it's a toggle. Running a
+    /// toggle twice in a row is the same as running it once, and running it
+    /// and then its inverse is the same as running neither.
+    pub fn inverse(&self) -> Option<Cmd> {
+        match self {
+            Cmd::Xtrace { on } => Some(Cmd::Xtrace { on: !on }),
+            Cmd::SetVar { .. }
+            | Cmd::UnsetVar { .. }
+            | Cmd::DefineFunction { .. }
+            | Cmd::ChangeDir { .. }
+            | Cmd::Alias { .. }
+            | Cmd::SaveOriginal { .. } => None,
+        }
+    }
+}
+
+/// The characters that make a tracepoint name a glob: `*` matches any run of
+/// characters and `?` matches any one.
+const GLOB_CHARS: [char; 2] = ['*', '?'];
+
+/// Characters that some shell's `case` or `string match` treats as pattern
+/// syntax beyond `*` and `?`. The hooks couldn't agree on what they mean, so
+/// they're not allowed in tracepoint patterns, which also keeps the classes
+/// and escapes [`glob_match`] understands out of them.
+const RESERVED_PATTERN_CHARS: &str = "[]\\()|<>^~#";
+
+/// Why a tracepoint name or glob can't be paused at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidTracepointPattern {
+    Empty,
+    /// The pattern contains whitespace, the `,` that separates patterns in
+    /// the tracepoint variable, or glob syntax the hooks don't support.
+    BadChar {
+        pattern: String,
+        bad: char,
+    },
+}
+
+impl fmt::Display for InvalidTracepointPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidTracepointPattern::Empty => write!(f, "tracepoint pattern is empty"),
+            InvalidTracepointPattern::BadChar { pattern, bad } => write!(
+                f,
+                "tracepoint pattern {pattern:?} contains {bad:?}, only '*' and '?' are wildcards"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidTracepointPattern {}
+
+/// Checks that `pattern` can be written into the tracepoint variable and
+/// matched the same way by every hook.
+pub fn check_tracepoint_pattern(pattern: &str) -> Result<(), InvalidTracepointPattern> {
+    if pattern.is_empty() {
+        return Err(InvalidTracepointPattern::Empty);
+    }
+    match pattern
+        .chars()
+        .find(|c| c.is_whitespace() || *c == ',' || RESERVED_PATTERN_CHARS.contains(*c))
+    {
+        Some(bad) => Err(InvalidTracepointPattern::BadChar {
+            pattern: pattern.to_string(),
+            bad,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns whether `pattern` has wildcards, rather than naming a single
+/// tracepoint.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(GLOB_CHARS)
+}
+
+/// Where the shell pauses after the debugger exits, which is what the
+/// tracepoint variable tells it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TracepointDisposition {
+    /// Run on without pausing.
+    Continue,
+    /// Pause at the next tracepoint reached, whichever it is.
+    PauseAtNext,
+    /// Pause at every tracepoint.
+    PauseAtAll,
+    /// Pause at any of the tracepoints matching these names or globs, which
+    /// the variable holds separated by commas. See [`glob_match`].
+    PauseAt(Vec<String>),
+}
+
+impl TracepointDisposition {
+    /// Returns the disposition the tracepoint variable holds when its value
+    /// is `value`.
+    pub fn from_var_value(value: &str) -> Self {
+        match value {
+            "next" => TracepointDisposition::PauseAtNext,
+            "all" => TracepointDisposition::PauseAtAll,
+            list => {
+                let names = list
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+                if names.is_empty() {
+                    TracepointDisposition::Continue
+                } else {
+                    TracepointDisposition::PauseAt(names)
+                }
+            }
+        }
+    }
+
+    /// Returns what the shell does once it resumes from pausing at
+    /// `paused_at` because the tracepoint variable was `value`.
+    ///
+    /// Pausing everywhere carries on, and `next` has been used up. The
+    /// tracepoint that was reached is taken out of a list, and the rest stay
+    /// until it's empty. If it isn't known which one was reached, they all
+    /// stay. Globs stay too, since they're meant to pause at every
+    /// tracepoint they match.
+    pub fn on_resume(value: &str, paused_at: Option<&str>) -> Self {
+        match Self::from_var_value(value) {
+            TracepointDisposition::PauseAtAll => TracepointDisposition::PauseAtAll,
+            TracepointDisposition::PauseAt(mut names) => {
+                names.retain(|name| is_glob(name) || Some(name.as_str()) != paused_at);
+                if names.is_empty() {
+                    TracepointDisposition::Continue
+                } else {
+                    TracepointDisposition::PauseAt(names)
+                }
+            }
+            TracepointDisposition::Continue | TracepointDisposition::PauseAtNext => {
+                TracepointDisposition::Continue
+            }
+        }
+    }
+
+    /// Says where the shell will pause after the debugger exits, e.g.
+    /// "will pause at 'setup' after exiting".
+    pub fn describe(&self) -> String {
+        match self {
+            TracepointDisposition::Continue => "will run on without pausing after exiting".into(),
+            TracepointDisposition::PauseAtNext => {
+                "will pause at the next tracepoint after exiting".into()
+            }
+            TracepointDisposition::PauseAtAll => {
+                "will pause at every tracepoint after exiting".into()
+            }
+            TracepointDisposition::PauseAt(names) => {
+                let names = names
+                    .iter()
+                    .map(|name| format!("'{name}'"))
+                    .collect::<Vec<_>>()
+                    .join(" or ");
+                format!("will pause at {names} after exiting")
+            }
+        }
+    }
+
+    /// Returns the disposition after also pausing at `name`. It's added to
+    /// the tracepoints already being paused at rather than replacing them.
+    pub fn and_pause_at(self, name: &str) -> Self {
+        match self {
+            TracepointDisposition::PauseAtAll => TracepointDisposition::PauseAtAll,
+            TracepointDisposition::PauseAt(mut names) => {
+                if !names.iter().any(|known| known == name) {
+                    names.push(name.to_string());
+                }
+                TracepointDisposition::PauseAt(names)
+            }
+            TracepointDisposition::Continue | TracepointDisposition::PauseAtNext => {
+                TracepointDisposition::PauseAt(vec![name.to_string()])
+            }
+        }
+    }
+
+    /// Returns the name or glob that makes the shell pause at the tracepoint
+    /// called `name`, if it will. `next` and `all` are returned as they are.
+    pub fn pauses_at(&self, name: &str) -> Option<&str> {
+        match self {
+            TracepointDisposition::Continue => None,
+            TracepointDisposition::PauseAtNext => Some("next"),
+            TracepointDisposition::PauseAtAll => Some("all"),
+            TracepointDisposition::PauseAt(patterns) => patterns
+                .iter()
+                .find(|pattern| glob_match(pattern, name))
+                .map(String::as_str),
+        }
+    }
+
+    /// Returns the value of the tracepoint variable for this disposition,
+    /// where empty means unset.
+    pub fn var_value(&self) -> String {
+        match self {
+            TracepointDisposition::Continue => String::new(),
+            TracepointDisposition::PauseAtNext => "next".to_string(),
+            TracepointDisposition::PauseAtAll => "all".to_string(),
+            TracepointDisposition::PauseAt(names) => names.join(","),
+        }
+    }
+}
+
+/// Returns the command that makes the tracepoint variable say
+/// `disposition`, or `None` if its value, `current`, already does.
+pub fn tracepoint_cmd(
+    shell: Shell,
+    disposition: &TracepointDisposition,
+    current: &str,
+) -> Option<String> {
+    if disposition.var_value() == current {
+        return None;
+    }
+    Some(match disposition {
+        TracepointDisposition::Continue => unset_var_cmd(shell, TRACEPOINT_VAR_NAME),
+        _ => set_var_cmd(shell, TRACEPOINT_VAR_NAME, &disposition.var_value()),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{collections::HashMap, io::ErrorKind, process::Command};
+
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// The program that runs scripts for each shell.
+    fn program(shell: Shell) -> &'static str {
+        match shell {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::Posix => "sh",
+        }
+    }
+
+    /// Runs `script` in a fresh, non-interactive `shell` and returns what it
+    /// printed, or `None` if that shell isn't installed.
+    pub(crate) fn run_in(shell: Shell, script: &str) -> Option<String> {
+        let output = match Command::new(program(shell))
+            .arg("-c")
+            .arg(script)
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == ErrorKind::NotFound => return None,
+            Err(err) => panic!("failed to run {}: {err}", program(shell)),
+        };
+        assert!(
+            output.status.success(),
+            "{shell} failed to run {script:?}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Some(String::from_utf8(output.stdout).unwrap())
+    }
+
+    /// Returns what `name` holds in the environment of a child of the shell
+    /// after it runs `command`.
+    fn exported_after(shell: Shell, command: &str, name: &str) -> Option<String> {
+        let printed = run_in(shell, &format!("{command}\nprintenv {name}"))?;
+        Some(printed.strip_suffix('\n').unwrap_or(&printed).to_string())
+    }
+
+    #[test]
+    fn unset_var_commands_use_each_shells_syntax() {
+        let cases = [
+            (Shell::Bash, "unset NAME"),
+            (Shell::Zsh, "unset NAME"),
+            (Shell::Fish, "set -eg NAME"),
+            (Shell::Posix, "unset NAME"),
+        ];
+        for (shell, expected) in cases {
+            let command = unset_var_cmd(shell, "NAME");
+            assert_eq!(command, expected, "{shell}");
+            let script = format!(
+                "{}\n{command}\nprintenv NAME || echo unset",
+                set_var_cmd(shell, "NAME", "value")
+            );
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(printed, "unset\n", "{shell} ran {command:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn path_var_commands_use_each_shells_syntax() {
+        let entries = ["/usr/bin".to_string(), "/opt/my tools/bin".to_string()];
+        let cases = [
+            (Shell::Bash, "export MYPATH='/usr/bin:/opt/my tools/bin'"),
+            (Shell::Zsh, "export MYPATH='/usr/bin:/opt/my tools/bin'"),
+            (
+                Shell::Fish,
+                "set -gx --path MYPATH '/usr/bin' '/opt/my tools/bin'",
+            ),
+            (
+                Shell::Posix,
+                "MYPATH='/usr/bin:/opt/my tools/bin'; export MYPATH",
+            ),
+        ];
+        for (shell, expected) in cases {
+            let command = set_path_var_cmd(shell, "MYPATH", &entries);
+            assert_eq!(command, expected, "{shell}");
+            // Every shell hands the same value to its children.
+            if let Some(exported) = exported_after(shell, &command, "MYPATH") {
+                assert_eq!(exported, "/usr/bin:/opt/my tools/bin", "{shell}");
+            }
+        }
+        assert_eq!(
+            set_path_var_cmd(Shell::Fish, "MYPATH", &[]),
+            "set -gx --path MYPATH"
+        );
+        assert_eq!(
+            set_path_var_cmd(Shell::Bash, "MYPATH", &[]),
+            "export MYPATH=''"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs fish installed"]
+    fn fish_joins_path_vars_not_named_path_for_children() {
+        let entries = ["/a".to_string(), "/b c".to_string()];
+        let command = set_path_var_cmd(Shell::Fish, "MY_DIRS", &entries);
+        assert_eq!(command, "set -gx --path MY_DIRS '/a' '/b c'");
+        let exported =
+            exported_after(Shell::Fish, &command, "MY_DIRS").expect("fish isn't installed");
+        assert_eq!(exported, "/a:/b c");
+    }
+
+    #[test]
+    fn fish_gets_path_vars_as_lists() {
+        let entries = ["/a".to_string(), "/b c".to_string(), String::new()];
+        let command = set_path_var_cmd(Shell::Fish, "MYPATH", &entries);
+        let script = format!("{command}\ncount $MYPATH\nprintf '%s\\n' $MYPATH");
+        if let Some(printed) = run_in(Shell::Fish, &script) {
+            assert_eq!(printed, "3\n/a\n/b c\n\n");
+        }
+    }
+
+    #[test]
+    fn entries_containing_the_separator_are_found() {
+        let entries = ["/a".to_string(), "/b:/c".to_string()];
+        assert_eq!(entries_with_separator(&entries), ["/b:/c"]);
+        assert!(entries_with_separator(&entries[..1]).is_empty());
+    }
+
+    #[test]
+    fn var_names_that_would_break_a_command_are_refused() {
+        assert_eq!(check_var_name("PATH"), Ok(()));
+        assert_eq!(check_var_name("_x1"), Ok(()));
+        assert_eq!(check_var_name(""), Err(InvalidVarName::Empty));
+        let cases = [
+            ("A=B", '='),
+            ("A B", ' '),
+            ("A\tB", '\t'),
+            ("A\n", '\n'),
+            ("a;b", ';'),
+            ("a'b", '\''),
+            ("$(touch${IFS}x)", '$'),
+            ("1A", '1'),
+            ("A-B", '-'),
+        ];
+        for (name, bad) in cases {
+            assert_eq!(
+                check_var_name(name),
+                Err(InvalidVarName::BadChar {
+                    name: name.to_string(),
+                    bad
+                })
+            );
+        }
+    }
+
+    /// Characters that shells treat specially somewhere, plus ones that
+    /// don't survive naive quoting.
+    const ADVERSARIAL: &[char] = &[
+        'a', ' ', '\'', '"', '\\', '$', '`', '!', '%', '*', '{', '}', ';', '#', '~', '-', '\n',
+        '\t', '\r', '\u{1}', '\u{1b}', '\u{7f}', '\u{85}', 'é', '€',
+    ];
+
+    /// Returns `count` strings drawn from [`ADVERSARIAL`], the same ones every
+    /// run.
+    fn adversarial_values(count: usize) -> Vec<String> {
+        let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as usize
+        };
+        (0..count)
+            .map(|_| {
+                let len = next() % 12;
+                (0..len)
+                    .map(|_| ADVERSARIAL[next() % ADVERSARIAL.len()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Checks that `values` come back byte for byte from `shell`, each set
+    /// with a one-line command. Panics if `shell` isn't installed, so that a
+    /// missing shell can't pass for a working one.
+    fn assert_round_trip(shell: Shell, values: &[String]) {
+        let mut script = String::new();
+        for (idx, value) in values.iter().enumerate() {
+            let command = set_var_cmd(shell, &format!("V{idx}"), value);
+            assert!(!command.contains('\n'), "{shell}: {command:?}");
+            script.push_str(&command);
+            script.push('\n');
+        }
+        script.push_str("env -0");
+        let printed =
+            run_in(shell, &script).unwrap_or_else(|| panic!("{} isn't installed", program(shell)));
+        let env = printed
+            .split('\0')
+            .filter_map(|pair| pair.split_once('='))
+            .collect::<HashMap<_, _>>();
+        for (idx, value) in values.iter().enumerate() {
+            assert_eq!(
+                env.get(format!("V{idx}").as_str()),
+                Some(&value.as_str()),
+                "{shell} ran {:?}",
+                set_var_cmd(shell, "V", value)
+            );
+        }
+    }
+
+    #[test]
+    fn adversarial_values_round_trip_through_bash() {
+        assert_round_trip(Shell::Bash, &adversarial_values(300));
+    }
+
+    #[test]
+    fn adversarial_values_round_trip_through_sh() {
+        assert_round_trip(Shell::Posix, &adversarial_values(300));
+    }
+
+    #[test]
+    #[ignore = "needs zsh installed"]
+    fn adversarial_values_round_trip_through_zsh() {
+        assert_round_trip(Shell::Zsh, &adversarial_values(300));
+    }
+
+    #[test]
+    #[ignore = "needs fish installed"]
+    fn adversarial_values_round_trip_through_fish() {
+        assert_round_trip(Shell::Fish, &adversarial_values(300));
+    }
+
+    #[test]
+    fn sh_keeps_trailing_newlines_on_one_line() {
+        let values = ["\n", "\x01\n", "a\n\n", "it's_\n", "_\n"].map(String::from);
+        assert_round_trip(Shell::Posix, &values);
+        assert_eq!(
+            set_var_cmd(Shell::Posix, "NAME", "\x01\n"),
+            r#"FLOX_DBG_QUOTED="$(printf %b '\0001\0012_')"; FLOX_DBG_QUOTED="${FLOX_DBG_QUOTED%_}"; NAME="$FLOX_DBG_QUOTED"; export NAME"#
+        );
+    }
+
+    #[test]
+    fn function_definitions_use_each_shells_syntax() {
+        let body = r#"printf '%s;%s\n' "$1" 'a;b'; return 0"#;
+        let cases = [
+            (Shell::Bash, format!("greet() {{ {body}; }}")),
+            (Shell::Zsh, format!("greet() {{ {body}; }}")),
+            (Shell::Fish, format!("function greet; {body}; end")),
+            (Shell::Posix, format!("greet() {{ {body}; }}")),
+        ];
+        for (shell, expected) in cases {
+            assert_eq!(check_function(shell, "greet", body), Ok(()));
+            let command = Cmd::DefineFunction {
+                name: "greet".to_string(),
+                body: body.to_string(),
+            }
+            .render(shell);
+            assert_eq!(command, expected, "{shell}");
+            if let Some(printed) = run_in(shell, &format!("{command}\ngreet hi")) {
+                assert_eq!(printed, "hi;a;b\n", "{shell} ran {command:?}");
+            }
+            let unfolded = unfold_function(shell, &command).unwrap();
+            assert_eq!(
+                unfolded[1..unfolded.len() - 1],
+                [r#"    printf '%s;%s\n' "$1" 'a;b'"#, "    return 0"],
+                "{shell}"
+            );
+        }
+        assert_eq!(
+            unfold_function(Shell::Fish, &define_function_cmd(Shell::Fish, "f", "")),
+            Some(vec!["function f".to_string(), "end".to_string()])
+        );
+        assert_eq!(unfold_function(Shell::Bash, "echo hi"), None);
+        assert_eq!(unfold_function(Shell::Fish, "set -x"), None);
+    }
+
+    #[test]
+    fn function_bodies_that_end_the_definition_early_are_refused() {
+        for (shell, body) in [
+            (Shell::Bash, "echo hi; }"),
+            (Shell::Bash, "{ echo hi"),
+            (Shell::Fish, "echo hi; end"),
+            (Shell::Fish, "if true; echo hi"),
+        ] {
+            assert_eq!(
+                check_function(shell, "f", body),
+                Err(InvalidFunction::Unbalanced),
+                "{shell} {body:?}"
+            );
+        }
+        for (shell, body) in [
+            (Shell::Bash, "{ echo '}'; }; echo \\}"),
+            (Shell::Fish, "if true; echo end; end; and begin; end"),
+            (Shell::Posix, ""),
+            (Shell::Posix, "sleep 1 &"),
+        ] {
+            assert_eq!(check_function(shell, "f", body), Ok(()), "{shell} {body:?}");
+            let command = define_function_cmd(shell, "f", body);
+            if let Some(printed) = run_in(shell, &format!("{command}\necho defined")) {
+                assert_eq!(printed, "defined\n", "{shell} ran {command:?}");
+            }
+        }
+        assert_eq!(
+            check_function(Shell::Bash, "f", "echo a\necho b"),
+            Err(InvalidFunction::MultiLineBody)
+        );
+        for name in ["", "-f", "a b", "f()", "a;b"] {
+            assert_eq!(
+                check_function(Shell::Bash, name, "true"),
+                Err(InvalidFunction::BadName(name.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn cd_commands_are_spelled_the_same_in_every_shell() {
+        let scratch = TestDir::new("debugger's cd");
+        let dir = scratch.path();
+        let cmd = Cmd::ChangeDir {
+            dir: dir.to_string_lossy().into_owned(),
+        };
+        let quoted_for = |shell: Shell| shell.quote(&dir.to_string_lossy(), str::to_string);
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let command = cmd.render(shell);
+            assert_eq!(command, format!("cd {}", quoted_for(shell)), "{shell}");
+            if let Some(printed) = run_in(shell, &format!("{command}\npwd")) {
+                assert_eq!(printed.trim_end(), dir.to_string_lossy(), "{shell}");
+            }
+        }
+        // Quoting a quote is where fish and the rest differ.
+        assert_ne!(quoted_for(Shell::Fish), quoted_for(Shell::Bash));
+    }
+
+    #[test]
+    fn directories_resolve_like_the_shell_would() {
+        let cwd = Path::new("/work/project");
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(
+            resolve_dir("sub dir", cwd, home),
+            Path::new("/work/project/sub dir")
+        );
+        assert_eq!(
+            resolve_dir("../other", cwd, home),
+            Path::new("/work/project/../other")
+        );
+        assert_eq!(resolve_dir("/abs", cwd, home), Path::new("/abs"));
+        assert_eq!(resolve_dir("~", cwd, home), Path::new("/home/me"));
+        assert_eq!(resolve_dir("~/src", cwd, home), Path::new("/home/me/src"));
+        assert_eq!(
+            resolve_dir("~other", cwd, home),
+            Path::new("/work/project/~other")
+        );
+        assert_eq!(
+            resolve_dir("~/src", cwd, None),
+            Path::new("/work/project/~/src")
+        );
+    }
+
+    #[test]
+    fn aliases_use_each_shells_syntax() {
+        let value = r#"printf '%s\n' "it's""#;
+        let cases = [
+            (Shell::Bash, r#"alias say='printf '\''%s\n'\'' "it'\''s"'"#),
+            (Shell::Zsh, r#"alias say='printf '\''%s\n'\'' "it'\''s"'"#),
+            (Shell::Fish, r#"alias say 'printf \'%s\\n\' "it\'s"'"#),
+            (Shell::Posix, r#"alias say='printf '\''%s\n'\'' "it'\''s"'"#),
+        ];
+        for (shell, expected) in cases {
+            let command = Cmd::Alias {
+                name: "say".to_string(),
+                value: value.to_string(),
+            }
+            .render(shell);
+            assert_eq!(command, expected, "{shell}");
+            // Aliases are only expanded in lines read after they're defined,
+            // and bash needs telling to expand them outside interactive use.
+            let script = match shell {
+                Shell::Bash => format!("shopt -s expand_aliases\n{command}\nsay"),
+                _ => format!("{command}\nsay"),
+            };
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(printed, "it's\n", "{shell} ran {command:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn alias_names_that_would_break_a_command_are_refused() {
+        for name in ["ll", "g.s", "k8s-ctx", "_x"] {
+            assert_eq!(check_alias_name(name), Ok(()), "{name}");
+        }
+        for name in ["", "-x", "a b", "a=b", "a'b", "a/b", "$a", "a;b"] {
+            assert_eq!(
+                check_alias_name(name),
+                Err(InvalidAliasName(name.to_string())),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn xtrace_commands_use_each_shells_syntax() {
+        let cases = [
+            (Shell::Bash, "set -x", "set +x"),
+            (Shell::Zsh, "set -x", "set +x"),
+            (Shell::Fish, "set -g fish_trace 1", "set -eg fish_trace"),
+            (Shell::Posix, "set -x", "set +x"),
+        ];
+        for (shell, on, off) in cases {
+            assert_eq!(Cmd::Xtrace { on: true }.render(shell), on, "{shell}");
+            assert_eq!(Cmd::Xtrace { on: false }.render(shell), off, "{shell}");
+            // The trace goes to stderr, so only the shell accepting the
+            // commands is checked.
+            if let Some(printed) = run_in(shell, &format!("{on}\n{off}\necho done")) {
+                assert_eq!(printed, "done\n", "{shell}");
+            }
+        }
+        assert_eq!(
+            Cmd::Xtrace { on: true }.inverse(),
+            Some(Cmd::Xtrace { on: false })
+        );
+    }
+
+    #[test]
+    fn tracepoint_commands_cover_every_disposition_and_shell() {
+        use TracepointDisposition::*;
+
+        let dispositions = [
+            Continue,
+            PauseAtNext,
+            PauseAtAll,
+            PauseAt(vec!["setup".to_string()]),
+            PauseAt(vec!["setup".to_string(), "teardown".to_string()]),
+        ];
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            for disposition in &dispositions {
+                for current in ["", "next", "all", "setup", "other", "setup,teardown"] {
+                    let command = tracepoint_cmd(shell, disposition, current);
+                    let expected = if disposition.var_value() == current {
+                        None
+                    } else if *disposition == Continue {
+                        Some(unset_var_cmd(shell, TRACEPOINT_VAR_NAME))
+                    } else {
+                        Some(set_var_cmd(
+                            shell,
+                            TRACEPOINT_VAR_NAME,
+                            &disposition.var_value(),
+                        ))
+                    };
+                    assert_eq!(
+                        command, expected,
+                        "{shell} {disposition:?} from {current:?}"
+                    );
+                    let Some(command) = command else {
+                        continue;
+                    };
+                    let script = format!(
+                        "{}\n{command}\nprintenv {TRACEPOINT_VAR_NAME} || true",
+                        set_var_cmd(shell, TRACEPOINT_VAR_NAME, current)
+                    );
+                    if let Some(printed) = run_in(shell, &script) {
+                        let value = printed.strip_suffix('\n').unwrap_or(&printed);
+                        assert_eq!(value, disposition.var_value(), "{shell} ran {command:?}");
+                    }
+                }
+            }
+            for disposition in &dispositions {
+                assert_eq!(
+                    &TracepointDisposition::from_var_value(&disposition.var_value()),
+                    disposition
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resuming_removes_the_tracepoint_that_was_reached() {
+        use TracepointDisposition::*;
+
+        let names = |names: &[&str]| PauseAt(names.iter().map(|name| name.to_string()).collect());
+        let cases = [
+            ("", Some("setup"), Continue),
+            ("next", Some("setup"), Continue),
+            ("all", Some("setup"), PauseAtAll),
+            ("setup", Some("setup"), Continue),
+            ("setup", None, names(&["setup"])),
+            ("setup,teardown", Some("setup"), names(&["teardown"])),
+            ("setup, teardown,", Some("teardown"), names(&["setup"])),
+            (
+                "setup,teardown",
+                Some("other"),
+                names(&["setup", "teardown"]),
+            ),
+            ("setup,setup", Some("setup"), Continue),
+            ("py-*", Some("py-build"), names(&["py-*"])),
+            ("py-*,setup", Some("setup"), names(&["py-*"])),
+        ];
+        for (value, paused_at, expected) in cases {
+            assert_eq!(
+                TracepointDisposition::on_resume(value, paused_at),
+                expected,
+                "{value:?} at {paused_at:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn revert_helpers_use_each_shells_syntax() {
+        assert_eq!(
+            save_original_cmd(Shell::Bash, "PATH"),
+            r#"if [ -n "${PATH+x}" ]; then FLOX_DBG_ORIG_PATH="$PATH"; else unset FLOX_DBG_ORIG_PATH; fi"#
+        );
+        assert_eq!(
+            save_original_cmd(Shell::Fish, "PATH"),
+            "if set -q PATH; set -g FLOX_DBG_ORIG_PATH $PATH; else; set -eg FLOX_DBG_ORIG_PATH; end"
+        );
+        let names = ["PATH".to_string()];
+        assert_eq!(
+            revert_body(Shell::Posix, &names),
+            r#"if [ -n "${FLOX_DBG_ORIG_PATH+x}" ]; then PATH="$FLOX_DBG_ORIG_PATH"; export PATH; unset FLOX_DBG_ORIG_PATH; else unset PATH; fi; unset -f flox_dbg_revert"#
+        );
+        assert_eq!(
+            revert_body(Shell::Fish, &names),
+            "if set -q FLOX_DBG_ORIG_PATH; set -gx PATH $FLOX_DBG_ORIG_PATH; set -eg FLOX_DBG_ORIG_PATH; else; set -eg PATH; end; functions -e flox_dbg_revert"
+        );
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let body = revert_body(shell, &names);
+            assert_eq!(check_function(shell, REVERT_FUNCTION_NAME, &body), Ok(()));
+            let define = define_function_cmd(shell, REVERT_FUNCTION_NAME, &body);
+            assert!(
+                defines_function(shell, &define, REVERT_FUNCTION_NAME),
+                "{shell}"
+            );
+            assert!(!defines_function(shell, &define, "flox_dbg"), "{shell}");
+        }
+    }
+
+    #[test]
+    fn globs_match_like_the_hooks_do() {
+        let cases = [
+            ("setup", "setup", true),
+            ("setup", "setups", false),
+            ("python-*", "python-build", true),
+            ("python-*", "python-", true),
+            ("python-*", "node-build", false),
+            ("*-build", "python-build", true),
+            ("*-build", "python-build-2", false),
+            ("py*-*", "python-build", true),
+            ("*a*b", "xaxxab", true),
+            ("*a*b", "xaxxa", false),
+            ("step?", "step1", true),
+            ("step?", "step10", false),
+            ("step?", "step", false),
+            ("*", "", true),
+            ("??*", "ab", true),
+            ("??*", "a", false),
+            ("ünï?ødé", "ünïcødé", true),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(check_tracepoint_pattern(pattern), Ok(()), "{pattern}");
+            assert_eq!(glob_match(pattern, name), expected, "{pattern} {name}");
+        }
+    }
+
+    #[test]
+    fn tracepoint_patterns_are_validated() {
+        assert_eq!(check_tracepoint_pattern("python-*"), Ok(()));
+        assert_eq!(check_tracepoint_pattern("step?"), Ok(()));
+        assert_eq!(
+            check_tracepoint_pattern(""),
+            Err(InvalidTracepointPattern::Empty)
+        );
+        for (pattern, bad) in [
+            ("a,b", ','),
+            ("a b", ' '),
+            ("step[12]", '['),
+            ("a\\*", '\\'),
+            ("@(a|b)", '('),
+            ("^a", '^'),
+        ] {
+            assert_eq!(
+                check_tracepoint_pattern(pattern),
+                Err(InvalidTracepointPattern::BadChar {
+                    pattern: pattern.to_string(),
+                    bad
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn dispositions_report_what_pauses_at_a_tracepoint() {
+        let disposition = TracepointDisposition::from_var_value("setup,py-*");
+        assert_eq!(disposition.pauses_at("setup"), Some("setup"));
+        assert_eq!(disposition.pauses_at("py-build"), Some("py-*"));
+        assert_eq!(disposition.pauses_at("build"), None);
+        assert_eq!(
+            TracepointDisposition::PauseAtAll.pauses_at("build"),
+            Some("all")
+        );
+        assert_eq!(TracepointDisposition::Continue.pauses_at("build"), None);
+    }
+
+    #[test]
+    fn breaking_adds_to_the_tracepoints_paused_at() {
+        use TracepointDisposition::*;
+
+        let names = |names: &[&str]| PauseAt(names.iter().map(|name| name.to_string()).collect());
+        assert_eq!(Continue.and_pause_at("a"), names(&["a"]));
+        assert_eq!(PauseAtNext.and_pause_at("a"), names(&["a"]));
+        assert_eq!(PauseAtAll.and_pause_at("a"), PauseAtAll);
+        assert_eq!(names(&["a"]).and_pause_at("b"), names(&["a", "b"]));
+        assert_eq!(names(&["a", "b"]).and_pause_at("a"), names(&["a", "b"]));
+    }
+
+    const NASTY: &str = "it's \"$HOME\" `id` \\n * ; $(exit 1)\nnext line";
+
+    #[test]
+    fn set_var_commands_use_each_shells_syntax() {
+        let cases = [
+            (Shell::Bash, "hello", "export NAME='hello'"),
+            (Shell::Bash, "", "export NAME=''"),
+            (
+                Shell::Bash,
+                NASTY,
+                r#"export NAME=$'it\'s "$HOME" `id` \\n * ; $(exit 1)\nnext line'"#,
+            ),
+            (Shell::Zsh, "hello", "export NAME='hello'"),
+            (Shell::Zsh, "", "export NAME=''"),
+            (
+                Shell::Zsh,
+                NASTY,
+                r#"export NAME=$'it\'s "$HOME" `id` \\n * ; $(exit 1)\nnext line'"#,
+            ),
+            (Shell::Fish, "hello", "set -gx NAME 'hello'"),
+            (Shell::Fish, "", "set -gx NAME ''"),
+            (
+                Shell::Fish,
+                NASTY,
+                r#"set -gx NAME 'it\'s "$HOME" `id` \\n * ; $(exit 1)'\n'next line'"#,
+            ),
+            (Shell::Posix, "hello", "NAME='hello'; export NAME"),
+            (Shell::Posix, "", "NAME=''; export NAME"),
+            (
+                Shell::Posix,
+                NASTY,
+                r#"NAME="$(printf %b 'it'\''s "$HOME" `id` \\n * ; $(exit 1)\0012next line')"; export NAME"#,
+            ),
+        ];
+        for (shell, value, expected) in cases {
+            let command = set_var_cmd(shell, "NAME", value);
+            assert_eq!(command, expected, "{shell}");
+            if let Some(exported) = exported_after(shell, &command, "NAME") {
+                assert_eq!(exported, value, "{shell} ran {command:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! A Unix socket that lets another program, such as the flox CLI, drive a
+//! paused debugger.
+//!
+//! Clients write one JSON request per line and read one JSON response per
+//! line back:
+//!
+//! - `{"request": "status"}` answers with where the shell is paused and how
+//!   many commands are queued.
+//! - `{"request": "queue", "cmd": "export FOO=bar"}` queues a command, as if
+//!   it had been typed after `raw` at the prompt.
+//! - `{"request": "continue"}` exits the debugger, printing the queued
+//!   commands for the shell to run.
+//!
+//! Clients are served one at a time, in the order they connect. Requests are
+//! handed to the event loop as [`Event::Control`]s so they're handled like
+//! everything else, and nothing that goes wrong with the socket or a client
+//! stops the TUI.
+
+use std::{
+    fs,
+    io::{ErrorKind, Read, Write},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use anyhow::{Context, Error, bail};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::app::{Action, App, Event, handle_event, protected::MutationOutcome};
+
+/// How often the socket thread checks for new clients and whether it should
+/// stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a client waits for the event loop to answer before it's told the
+/// debugger is busy.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest request line a client may send. Anything longer is refused
+/// and the client disconnected rather than buffered without end.
+const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// Something a client asked the debugger to do.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum ControlRequest {
+    Status,
+    Queue { cmd: String },
+    Continue,
+}
+
+/// The debugger's answer to a [`ControlRequest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum ControlResponse {
+    Status {
+        /// Always true, since the debugger only runs while the shell is
+        /// paused, but spelled out so clients don't have to know that.
+        paused: bool,
+        tracepoint: Option<String>,
+        pending_commands: usize,
+    },
+    Queued {
+        pending_commands: usize,
+    },
+    Continuing {
+        pending_commands: usize,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl ControlResponse {
+    fn error(error: impl Into<String>) -> Self {
+        ControlResponse::Error {
+            error: error.into(),
+        }
+    }
+}
+
+/// A request waiting for the event loop, along with where to send the answer.
+#[derive(Debug)]
+pub struct ControlMessage {
+    pub request: ControlRequest,
+    reply: Sender<ControlResponse>,
+}
+
+impl ControlMessage {
+    /// Sends `response` to the client. A client that has gone away doesn't
+    /// need one.
+    pub fn reply(self, response: ControlResponse) {
+        let _ = self.reply.send(response);
+    }
+}
+
+/// The listening socket and the thread serving it. The socket file is
+/// removed when this is dropped.
+#[derive(Debug)]
+pub struct ControlSocket {
+    path: PathBuf,
+    receiver: Receiver<ControlMessage>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ControlSocket {
+    /// Listens on a new socket at `path`, readable and writable only by the
+    /// current user. A socket left behind by a debugger that didn't exit
+    /// cleanly is replaced, but one that's still in use isn't.
+    pub fn bind(path: &Path) -> Result<Self, Error> {
+        remove_stale_socket(path)?;
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("failed to listen on {}", path.display()))?;
+        // The socket file is ours from here on, so dropping `socket` cleans
+        // it up if anything else fails.
+        let (sender, receiver) = mpsc::channel();
+        let mut socket = Self {
+            path: path.to_path_buf(),
+            receiver,
+            stop: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        };
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("failed to restrict access to {}", path.display()))?;
+        listener
+            .set_nonblocking(true)
+            .context("failed to set up the control socket")?;
+        let stop = Arc::clone(&socket.stop);
+        let thread = std::thread::Builder::new()
+            .name("control-socket".to_string())
+            .spawn(move || accept_clients(&listener, &sender, &stop))
+            .context("failed to start serving the control socket")?;
+        socket.thread = Some(thread);
+        Ok(socket)
+    }
+
+    /// Returns the next request waiting for the event loop, if any.
+    pub fn try_recv(&self) -> Option<ControlMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        if let Err(err) = fs::remove_file(&self.path) {
+            debug!(path = %self.path.display(), "failed to remove control socket: {err}");
+        }
+    }
+}
+
+/// Removes whatever is at `path` if it's a socket nothing is listening on.
+fn remove_stale_socket(path: &Path) -> Result<(), Error> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        bail!("{} already exists and isn't a socket", path.display());
+    }
+    if UnixStream::connect(path).is_ok() {
+        bail!("{} is already in use", path.display());
+    }
+    fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))
+}
+
+/// Serves clients one after another until told to stop.
+fn accept_clients(listener: &UnixListener, sender: &Sender<ControlMessage>, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(err) = serve_client(stream, sender, stop) {
+                    debug!("control client failed: {err:#}");
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+            Err(err) => {
+                debug!("failed to accept control client: {err}");
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Answers each line `stream` sends until it disconnects.
+fn serve_client(
+    mut stream: UnixStream,
+    sender: &Sender<ControlMessage>,
+    stop: &AtomicBool,
+) -> Result<(), Error> {
+    debug!("control client connected");
+    stream.set_nonblocking(false)?;
+    // Waking up now and then notices when the debugger is exiting, even if
+    // the client never sends anything.
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut pending = Vec::new();
+    let mut chunk = [0; 4096];
+    while !stop.load(Ordering::SeqCst) {
+        let n = match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        pending.extend_from_slice(&chunk[..n]);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line = pending.drain(..=end).collect::<Vec<_>>();
+            let response = answer(&line[..end], sender);
+            write_response(&mut stream, &response)?;
+        }
+        if pending.len() > MAX_REQUEST_LEN {
+            let response = ControlResponse::error("request is too long");
+            write_response(&mut stream, &response)?;
+            break;
+        }
+    }
+    debug!("control client disconnected");
+    Ok(())
+}
+
+/// Returns the answer to one request line, waiting for the event loop to
+/// handle it if it's valid.
+fn answer(line: &[u8], sender: &Sender<ControlMessage>) -> ControlResponse {
+    let request = match serde_json::from_slice::<ControlRequest>(line) {
+        Ok(request) => request,
+        Err(err) => return ControlResponse::error(format!("invalid request: {err}")),
+    };
+    let (reply, response) = mpsc::channel();
+    if sender.send(ControlMessage { request, reply }).is_err() {
+        return ControlResponse::error("the debugger is exiting");
+    }
+    match response.recv_timeout(REPLY_TIMEOUT) {
+        Ok(response) => response,
+        Err(RecvTimeoutError::Timeout) => ControlResponse::error("the debugger didn't respond"),
+        Err(RecvTimeoutError::Disconnected) => ControlResponse::error("the debugger is exiting"),
+    }
+}
+
+fn write_response(stream: &mut UnixStream, response: &ControlResponse) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(response)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    Ok(())
+}
+
+/// Handles every request waiting on the app's control socket, returning
+/// [`Action::Exit`] if one of them asked to continue.
+pub fn service_control_requests(app: &mut App) -> Action {
+    let mut action = Action::Continue;
+    while let Some(message) = app.control.as_ref().and_then(ControlSocket::try_recv) {
+        if handle_event(app, &Event::Control(message.request.clone())) == Action::Exit {
+            action = Action::Exit;
+        }
+        let response = app
+            .control_response
+            .take()
+            .unwrap_or_else(|| ControlResponse::error("the request wasn't handled"));
+        message.reply(response);
+        if action == Action::Exit {
+            break;
+        }
+    }
+    action
+}
+
+/// Carries out `request`, leaving the answer for
+/// [`service_control_requests`] to send back.
+pub fn handle_control_request(app: &mut App, request: &ControlRequest) -> Action {
+    let (action, response) = match request {
+        ControlRequest::Status => (
+            Action::Continue,
+            ControlResponse::Status {
+                paused: true,
+                tracepoint: app.trace().tracepoint().cloned(),
+                pending_commands: app.queued_commands().len(),
+            },
+        ),
+        ControlRequest::Queue { cmd } => (Action::Continue, queue(app, cmd)),
+        ControlRequest::Continue => (
+            Action::Exit,
+            ControlResponse::Continuing {
+                pending_commands: app.queued_commands().len(),
+            },
+        ),
+    };
+    app.control_response = Some(response);
+    action
+}
+
+fn queue(app: &mut App, cmd: &str) -> ControlResponse {
+    let cmd = cmd.trim();
+    if cmd.is_empty() {
+        return ControlResponse::error("no command given");
+    }
+    if cmd.contains('\n') {
+        return ControlResponse::error("commands must be a single line");
+    }
+    if !app.check_writable("queueing commands") {
+        return ControlResponse::error("the debugger is in read-only mode");
+    }
+    let outcome = app.queue_raw(cmd);
+    app.redraw_needed = true;
+    match outcome {
+        MutationOutcome::Queued => {}
+        MutationOutcome::Held => {
+            return ControlResponse::error(
+                "the command changes a protected variable, so it waits to be confirmed in the debugger",
+            );
+        }
+        MutationOutcome::Blocked => {
+            return ControlResponse::error("the debugger is in read-only mode");
+        }
+    }
+    ControlResponse::Queued {
+        pending_commands: app.queued_commands().len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn requests_are_tagged_json() {
+        let request = serde_json::from_str::<ControlRequest>(
+            r#"{"request": "queue", "cmd": "export FOO=bar"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            ControlRequest::Queue {
+                cmd: "export FOO=bar".to_string()
+            }
+        );
+        let response = ControlResponse::Queued {
+            pending_commands: 2,
+        };
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"response":"queued","pending_commands":2}"#
+        );
+    }
+
+    #[test]
+    fn malformed_requests_are_answered_with_an_error() {
+        let (sender, _receiver) = mpsc::channel();
+        let ControlResponse::Error { error } = answer(b"{\"request\": \"launch\"}", &sender) else {
+            panic!("expected an error");
+        };
+        assert!(error.starts_with("invalid request"), "{error}");
+    }
+
+    #[test]
+    fn sockets_are_private_and_removed_when_dropped() {
+        let dir = TestDir::new("control");
+        let path = dir.join("control.sock");
+        let socket = ControlSocket::bind(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(ControlSocket::bind(&path).is_err());
+        drop(socket);
+        assert!(!path.exists());
+
+        // A socket nobody is listening on is left over from a crash.
+        drop(UnixListener::bind(&path).unwrap());
+        let socket = ControlSocket::bind(&path).unwrap();
+        drop(socket);
+    }
+}
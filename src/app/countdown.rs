@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+/// How little time has to be left before the countdown is shown.
+pub const COUNTDOWN_DISPLAY_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Counts down to exiting on its own when the user isn't around to answer,
+/// as set by `--timeout`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Countdown {
+    /// No timeout was requested.
+    #[default]
+    Disabled,
+    /// The debugger will exit at `deadline` unless a key is pressed first.
+    Running { deadline: Instant },
+    /// A key was pressed, so the user is present and the debugger waits for
+    /// them for the rest of the session.
+    Cancelled,
+}
+
+impl Countdown {
+    /// Starts a countdown that expires `timeout` after `now`.
+    pub fn start(timeout: Duration, now: Instant) -> Self {
+        Countdown::Running {
+            deadline: now + timeout,
+        }
+    }
+
+    /// Returns how long is left before the countdown expires, or `None` if it
+    /// isn't running.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        match self {
+            Countdown::Running { deadline } => Some(deadline.saturating_duration_since(now)),
+            Countdown::Disabled | Countdown::Cancelled => None,
+        }
+    }
+
+    /// Returns how long is left if it's little enough to warn the user about.
+    pub fn visible_remaining(&self, now: Instant) -> Option<Duration> {
+        self.remaining(now)
+            .filter(|remaining| *remaining < COUNTDOWN_DISPLAY_THRESHOLD)
+    }
+
+    /// Returns whether the countdown ran out.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.remaining(now)
+            .is_some_and(|remaining| remaining.is_zero())
+    }
+
+    /// Stops a running countdown for good.
+    pub fn cancel(&mut self) {
+        if let Countdown::Running { .. } = self {
+            *self = Countdown::Cancelled;
+        }
+    }
+}
+
+/// Formats `duration` as minutes and seconds, `mm:ss`, rounding down to the
+/// second. Minutes keep counting past an hour rather than wrapping.
+pub fn format_clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clocks_show_minutes_and_seconds() {
+        let clock = |secs_f64| format_clock(Duration::from_secs_f64(secs_f64));
+        assert_eq!(clock(0.0), "00:00");
+        assert_eq!(clock(0.99), "00:00");
+        assert_eq!(clock(59.5), "00:59");
+        assert_eq!(clock(60.0), "01:00");
+        assert_eq!(clock(754.0), "12:34");
+        assert_eq!(clock(3600.0), "60:00");
+        assert_eq!(clock(6000.0 + 5.0), "100:05");
+    }
+
+    #[test]
+    fn counts_down_to_expiry() {
+        let start = Instant::now();
+        let countdown = Countdown::start(Duration::from_secs(60), start);
+        assert_eq!(countdown.remaining(start), Some(Duration::from_secs(60)));
+        assert_eq!(countdown.visible_remaining(start), None);
+
+        let later = start + Duration::from_secs(45);
+        assert_eq!(
+            countdown.visible_remaining(later),
+            Some(Duration::from_secs(15))
+        );
+        assert!(!countdown.is_expired(later));
+
+        let deadline = start + Duration::from_secs(60);
+        assert!(countdown.is_expired(deadline));
+        assert!(countdown.is_expired(deadline + Duration::from_secs(1)));
+        assert_eq!(
+            countdown.remaining(deadline + Duration::from_secs(1)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn cancelling_stops_the_countdown_for_good() {
+        let start = Instant::now();
+        let mut countdown = Countdown::start(Duration::from_secs(5), start);
+        countdown.cancel();
+        assert_eq!(countdown, Countdown::Cancelled);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(countdown.remaining(later), None);
+        assert!(!countdown.is_expired(later));
+
+        let mut disabled = Countdown::Disabled;
+        disabled.cancel();
+        assert_eq!(disabled, Countdown::Disabled);
+        assert!(!disabled.is_expired(later));
+    }
+}
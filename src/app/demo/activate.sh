@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+# Activates the demo project's environment.
+
+set -euo pipefail
+
+source "$(dirname "${BASH_SOURCE[0]}")/hooks.sh"
+
+export FLOX_ENV_PROJECT="$HOME/src/webapp"
+export FLOX_ENV="$FLOX_ENV_PROJECT/.flox/run/x86_64-linux.webapp.dev"
+
+setup_paths() {
+	PATH="$FLOX_ENV/bin:$FLOX_ENV/sbin:$PATH"
+	MANPATH="$FLOX_ENV/share/man:${MANPATH:-}"
+	export PATH MANPATH
+}
+
+start_services() {
+	if [ -n "${FLOX_SERVICES:-}" ]; then
+		echo "starting services: $FLOX_SERVICES"
+	fi
+}
+
+setup_paths
+run_hook on-activate
+start_services
+
+echo "webapp environment activated"
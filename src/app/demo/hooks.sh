@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+# Runs the hooks declared in the demo project's manifest.
+
+load_database_url() {
+	local host="${DB_HOST:-localhost}"
+	export DATABASE_URL="postgres://webapp@$host:5432/webapp_dev"
+}
+
+run_hook() {
+	local hook="$1"
+	case "$hook" in
+	on-activate)
+		load_database_url
+		fdb_tracepoint "$hook"
+		;;
+	*)
+		echo "unknown hook: $hook" >&2
+		return 1
+		;;
+	esac
+}
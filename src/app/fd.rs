@@ -0,0 +1,45 @@
+//! The file descriptors the debugger is handed on the command line, for
+//! reading the environment or printing the commands.
+
+use std::{
+    fs::File,
+    os::fd::{FromRawFd, RawFd},
+};
+
+use anyhow::{Error, bail};
+
+/// What a file descriptor has to be open for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Takes over the file descriptor `fd`, checking first that it's open for
+/// `access` and isn't one of the debugger's own standard streams, which
+/// closing it along with the returned file would take away.
+pub fn take_fd(fd: RawFd, access: Access) -> Result<File, Error> {
+    if (0..=2).contains(&fd) {
+        bail!("file descriptor {fd} is one of the debugger's own standard streams");
+    }
+    let flags = if fd < 0 {
+        -1
+    } else {
+        // SAFETY: `F_GETFL` only reads the descriptor's flags, and fails if
+        // it isn't open.
+        unsafe { libc::fcntl(fd, libc::F_GETFL) }
+    };
+    if flags == -1 {
+        bail!("file descriptor {fd} isn't open");
+    }
+    let (wrong_mode, purpose) = match access {
+        Access::Read => (libc::O_WRONLY, "reading"),
+        Access::Write => (libc::O_RDONLY, "writing"),
+    };
+    if flags & libc::O_ACCMODE == wrong_mode {
+        bail!("file descriptor {fd} isn't open for {purpose}");
+    }
+    // SAFETY: The descriptor is open, and it was handed to the debugger for
+    // one purpose alone, so nothing else uses or closes it.
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
@@ -0,0 +1,163 @@
+//! Shell-style glob patterns, for picking variables out by name and for
+//! matching tracepoints.
+//!
+//! Tracepoint patterns only take the `*` and `?` wildcards every hook agrees
+//! on, which [`check_tracepoint_pattern`] enforces before one is queued.
+//!
+//! [`check_tracepoint_pattern`]: crate::app::commands::check_tracepoint_pattern
+
+/// One piece of a parsed pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// `*`, any run of characters.
+    Any,
+    /// `?`, any one character.
+    One,
+    /// `[...]`, one character in (or, negated, not in) the ranges.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Literal(char),
+}
+
+impl Token {
+    /// Returns whether this token, which isn't [`Token::Any`], matches `c`.
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Any | Token::One => true,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+            Token::Literal(literal) => *literal == c,
+        }
+    }
+}
+
+/// Parses the class that starts just after the `[` at `chars[start - 1]`,
+/// returning it along with where the pattern goes on after its `]`. Returns
+/// `None` if it's never closed.
+fn parse_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let mut idx = start;
+    let negated = matches!(chars.get(idx), Some('!' | '^'));
+    if negated {
+        idx += 1;
+    }
+    let mut ranges = Vec::new();
+    // A `]` right at the start is part of the class rather than its end.
+    let first = idx;
+    loop {
+        let c = *chars.get(idx)?;
+        if c == ']' && idx > first {
+            return Some((Token::Class { negated, ranges }, idx + 1));
+        }
+        match (chars.get(idx + 1), chars.get(idx + 2)) {
+            (Some('-'), Some(hi)) if *hi != ']' => {
+                ranges.push((c, *hi));
+                idx += 3;
+            }
+            _ => {
+                ranges.push((c, c));
+                idx += 1;
+            }
+        }
+    }
+}
+
+fn parse(pattern: &str) -> Vec<Token> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let token = match chars[idx] {
+            '*' => Token::Any,
+            '?' => Token::One,
+            '[' => match parse_class(&chars, idx + 1) {
+                Some((class, next)) => {
+                    tokens.push(class);
+                    idx = next;
+                    continue;
+                }
+                None => Token::Literal('['),
+            },
+            '\\' if idx + 1 < chars.len() => {
+                idx += 1;
+                Token::Literal(chars[idx])
+            }
+            c => Token::Literal(c),
+        };
+        tokens.push(token);
+        idx += 1;
+    }
+    tokens
+}
+
+/// Returns whether `name` matches the glob `pattern` as a whole. `*` matches
+/// any run of characters, `?` any one character, and `[...]` one of the
+/// characters in it, which may include ranges like `a-z` and is negated by a
+/// leading `!` or `^`. A `\` makes the character after it match only itself.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let tokens = parse(pattern);
+    let name = name.chars().collect::<Vec<_>>();
+    // Where to resume after the last `*` if the rest stops matching: the
+    // token index just past it, and the name index it has consumed up to.
+    let mut backtrack = None;
+    let (mut t, mut n) = (0, 0);
+    while n < name.len() {
+        match tokens.get(t) {
+            Some(Token::Any) => {
+                backtrack = Some((t + 1, n));
+                t += 1;
+            }
+            Some(token) if token.matches(name[n]) => {
+                t += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_t, star_n)) => {
+                    backtrack = Some((star_t, star_n + 1));
+                    t = star_t;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[t..].iter().all(|token| *token == Token::Any)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_whole_names() {
+        let cases = [
+            ("NIX_*", "NIX_PATH", true),
+            ("NIX_*", "NIX_", true),
+            ("NIX_*", "MY_NIX_PATH", false),
+            ("*PATH", "MANPATH", true),
+            ("*_*_*", "A_B_C", true),
+            ("*_*_*", "A_B", false),
+            ("LC_????", "LC_TIME", true),
+            ("LC_????", "LC_CTYPE", false),
+            ("[A-C]*", "BASH", true),
+            ("[A-C]*", "HOME", false),
+            ("[!A-C]*", "HOME", true),
+            ("[^A-C]*", "BASH", false),
+            ("X[]]", "X]", true),
+            ("X[a-]", "X-", true),
+            ("[", "[", true),
+            ("a[b", "a[b", true),
+            ("\\*", "*", true),
+            ("\\*", "STAR", false),
+            ("", "", true),
+            ("*", "", true),
+            ("?", "", false),
+            ("ÜBER_?", "ÜBER_ß", true),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(glob_match(pattern, name), expected, "{pattern} {name}");
+        }
+    }
+}
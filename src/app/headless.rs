@@ -0,0 +1,123 @@
+use std::io::Write;
+
+use anyhow::{Context, Error, anyhow};
+
+use crate::{
+    app::{App, demo::comment_out},
+    cli::Cli,
+};
+
+/// Why the debugger is running without its TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessReason {
+    /// `--no-tui` was given.
+    Requested,
+    /// stderr isn't a terminal, so there's nowhere to draw the TUI.
+    NotATerminal,
+}
+
+/// Continues past the tracepoint without showing the TUI, printing the same
+/// commands the debugger would if the user exited without making changes.
+///
+/// Nothing here touches the terminal, so that running the debugger under a
+/// harness or with stderr redirected never wedges the shell that called it.
+pub fn run_headless(
+    args: &Cli,
+    tracepoint_var_value: &str,
+    reason: HeadlessReason,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> Result<(), Error> {
+    let shell = args
+        .shell
+        .ok_or_else(|| anyhow!("the --shell option is required"))?;
+    let why = match reason {
+        HeadlessReason::Requested => "--no-tui was given",
+        HeadlessReason::NotATerminal => "stderr is not a terminal",
+    };
+    let at = args
+        .tracepoint
+        .as_deref()
+        .map(|name| format!(" at tracepoint '{name}'"))
+        .unwrap_or_default();
+    // The notice is best effort, since stderr may well be closed.
+    let _ = writeln!(
+        stderr,
+        "flox-debugger: {why}, continuing{at} without stopping"
+    );
+    let output = App::initial_output_inner(shell, tracepoint_var_value, args.tracepoint.as_deref());
+    let output = if args.demo {
+        comment_out(&output)
+    } else {
+        output
+    };
+    App::print_cmds_inner(&output, stdout).context("failed to print commands")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn run(args: &[&str], tracepoint_var_value: &str, reason: HeadlessReason) -> (String, String) {
+        let args =
+            Cli::try_parse_from(std::iter::once("flox-debugger").chain(args.iter().copied()))
+                .unwrap();
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        run_headless(
+            &args,
+            tracepoint_var_value,
+            reason,
+            &mut stdout,
+            &mut stderr,
+        )
+        .unwrap();
+        (
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn prints_initial_output_without_a_terminal() {
+        let (stdout, stderr) = run(
+            &["--shell", "fish", "--tracepoint", "setup"],
+            "setup",
+            HeadlessReason::NotATerminal,
+        );
+        assert_eq!(stdout, "set -eg FLOX_DBG_TRACEPOINT\n");
+        assert_eq!(
+            stderr,
+            "flox-debugger: stderr is not a terminal, continuing at tracepoint 'setup' without stopping\n"
+        );
+    }
+
+    #[test]
+    fn no_tui_continues_quietly_on_stdout() {
+        let (stdout, stderr) = run(
+            &["--shell", "bash", "--no-tui"],
+            "all",
+            HeadlessReason::Requested,
+        );
+        assert_eq!(stdout, "");
+        assert!(stderr.contains("--no-tui was given"), "{stderr}");
+
+        let (stdout, _) = run(
+            &["--shell", "sh", "--no-tui"],
+            "next",
+            HeadlessReason::Requested,
+        );
+        assert_eq!(stdout, "unset FLOX_DBG_TRACEPOINT\n");
+    }
+
+    #[test]
+    fn demo_output_is_still_commented_out() {
+        let (stdout, _) = run(
+            &["--shell", "zsh", "--demo"],
+            "setup",
+            HeadlessReason::Requested,
+        );
+        assert!(stdout.lines().all(|line| line.starts_with('#')), "{stdout}");
+    }
+}
@@ -0,0 +1,150 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers},
+    widgets::ListState,
+};
+
+use crate::{
+    app::{App, Event, NavEvent, key_bindings::KeyCombo},
+    config::export_key_bindings,
+};
+
+/// A full-screen overlay drawn on top of the current screen.
+#[derive(Debug, Clone, Hash)]
+pub enum Overlay {
+    /// Lists the key bindings for the current screen.
+    Help,
+    /// Lists every key binding and lets the user rebind them.
+    Bindings(BindingsEditor),
+}
+
+/// The state of the key bindings editor.
+#[derive(Debug, Clone, Hash)]
+pub struct BindingsEditor {
+    list_state: ListState,
+    capturing: bool,
+    /// The outcome of the last rebind or export, which is an error message
+    /// if it failed.
+    message: Option<Result<String, String>>,
+}
+
+impl Default for BindingsEditor {
+    fn default() -> Self {
+        let mut list_state = ListState::default();
+        list_state.select_first();
+        Self {
+            list_state,
+            capturing: false,
+            message: None,
+        }
+    }
+}
+
+impl BindingsEditor {
+    /// Returns the binding list state for stateful rendering.
+    pub fn list_state(&mut self) -> &mut ListState {
+        &mut self.list_state
+    }
+
+    /// Returns whether the next key press will be bound to the selected
+    /// action.
+    pub fn capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Returns the result of the last rebind or export, if any.
+    pub fn message(&self) -> Option<&Result<String, String>> {
+        self.message.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverlayEvent {
+    /// Close the overlay.
+    Close,
+    /// Switch from the help overlay to the bindings editor.
+    OpenBindings,
+    /// Wait for a key press to bind to the selected action.
+    StartCapture,
+    /// A key was pressed while waiting to rebind an action.
+    Capture(KeyCombo),
+    /// Write the current bindings to the config file.
+    Export,
+}
+
+/// Handles navigation and interaction while an overlay is displayed.
+pub fn handle_overlay_event(app: &mut App, event: &Event) {
+    let n_bindings = app.key_bindings().entries().len();
+    match event {
+        Event::Overlay(OverlayEvent::Close) => app.set_overlay(None),
+        Event::Overlay(OverlayEvent::OpenBindings) => {
+            app.set_overlay(Some(Overlay::Bindings(BindingsEditor::default())));
+        }
+        Event::Overlay(OverlayEvent::StartCapture) => {
+            if let Some(Overlay::Bindings(editor)) = app.overlay_mut() {
+                editor.capturing = true;
+                editor.message = None;
+            }
+        }
+        Event::Overlay(OverlayEvent::Capture(key)) => capture_key(app, *key),
+        Event::Overlay(OverlayEvent::Export) => export(app),
+        Event::Nav(NavEvent::Up) => {
+            if let Some(Overlay::Bindings(editor)) = app.overlay_mut()
+                && editor.list_state.selected().is_some_and(|idx| idx > 0)
+            {
+                editor.list_state.select_previous();
+            }
+        }
+        Event::Nav(NavEvent::Down) => {
+            if let Some(Overlay::Bindings(editor)) = app.overlay_mut()
+                && editor
+                    .list_state
+                    .selected()
+                    .is_some_and(|idx| idx + 1 < n_bindings)
+            {
+                editor.list_state.select_next();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Binds the captured key to the selected action, or reports why it can't
+/// be bound. Esc cancels the capture so there's always a way back out.
+fn capture_key(app: &mut App, key: KeyCombo) {
+    let Some(Overlay::Bindings(editor)) = app.overlay() else {
+        return;
+    };
+    let Some(idx) = editor.list_state.selected() else {
+        return;
+    };
+    let entry = app.key_bindings().entries()[idx].clone();
+    let message = if key == KeyCombo::new(KeyCode::Esc, KeyModifiers::NONE) {
+        Ok("Cancelled".to_string())
+    } else {
+        match app
+            .key_bindings_mut()
+            .rebind(entry.section, entry.action, key)
+        {
+            Ok(()) => Ok(format!("Bound '{}' to {key}", entry.description)),
+            Err(err) => Err(format!("{err:#}")),
+        }
+    };
+    if let Some(Overlay::Bindings(editor)) = app.overlay_mut() {
+        editor.capturing = false;
+        editor.message = Some(message);
+    }
+}
+
+/// Writes the current bindings to the config file.
+fn export(app: &mut App) {
+    let message = match app.config_path() {
+        Some(path) => match export_key_bindings(path, app.key_bindings()) {
+            Ok(()) => Ok(format!("Saved key bindings to {}", path.display())),
+            Err(err) => Err(format!("{err:#}")),
+        },
+        None => Err("No config file location is available".to_string()),
+    };
+    if let Some(Overlay::Bindings(editor)) = app.overlay_mut() {
+        editor.message = Some(message);
+    }
+}
@@ -0,0 +1,106 @@
+use ratatui::widgets::ListState;
+
+use crate::app::{
+    Action, App, AppEvent, Event, NavEvent, Screen, handle_event, prompt::start_prompt_command,
+};
+
+/// Something the Home screen's menu can do, in the order it's listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HomeAction {
+    InspectVars,
+    ViewCallStack,
+    SetNextTracepoint,
+    ReviewOutput,
+    Exit,
+}
+
+impl HomeAction {
+    pub const ALL: [HomeAction; 5] = [
+        HomeAction::InspectVars,
+        HomeAction::ViewCallStack,
+        HomeAction::SetNextTracepoint,
+        HomeAction::ReviewOutput,
+        HomeAction::Exit,
+    ];
+
+    /// Returns what the menu calls the action.
+    pub fn label(&self) -> &'static str {
+        match self {
+            HomeAction::InspectVars => "Inspect variables",
+            HomeAction::ViewCallStack => "View call stack",
+            HomeAction::SetNextTracepoint => "Set next tracepoint…",
+            HomeAction::ReviewOutput => "Review pending output",
+            HomeAction::Exit => "Exit",
+        }
+    }
+}
+
+/// The view state of the Home screen's menu.
+#[derive(Debug, Clone, Hash)]
+pub struct HomeState {
+    list_state: ListState,
+}
+
+impl Default for HomeState {
+    fn default() -> Self {
+        Self {
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+}
+
+impl HomeState {
+    /// Returns the menu's list state for stateful rendering.
+    pub fn list_state(&mut self) -> &mut ListState {
+        &mut self.list_state
+    }
+
+    /// Returns the highlighted action.
+    pub fn selected(&self) -> Option<HomeAction> {
+        self.list_state
+            .selected()
+            .and_then(|idx| HomeAction::ALL.get(idx))
+            .copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HomeEvent {
+    /// Do the highlighted action.
+    Activate,
+}
+
+/// Handles navigation and interaction in the Home screen.
+pub fn handle_home_event(app: &mut App, event: &Event) -> Action {
+    let last = HomeAction::ALL.len() - 1;
+    let list_state = app.home_mut().list_state();
+    let selected = list_state.selected().unwrap_or(0);
+    match event {
+        Event::Nav(NavEvent::Up) => list_state.select(Some(selected.saturating_sub(1))),
+        Event::Nav(NavEvent::Down) => list_state.select(Some((selected + 1).min(last))),
+        Event::Nav(NavEvent::Top) => list_state.select(Some(0)),
+        Event::Nav(NavEvent::Bottom) => list_state.select(Some(last)),
+        Event::Home(HomeEvent::Activate) => {
+            if let Some(action) = app.home().selected() {
+                return run_home_action(app, action);
+            }
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Does `action` the way its own key would.
+fn run_home_action(app: &mut App, action: HomeAction) -> Action {
+    let event = match action {
+        HomeAction::InspectVars => AppEvent::GoTo(Screen::Vars),
+        HomeAction::ViewCallStack => AppEvent::GoTo(Screen::Trace),
+        HomeAction::SetNextTracepoint => {
+            start_prompt_command(app, "break ");
+            return Action::Continue;
+        }
+        HomeAction::ReviewOutput => AppEvent::GoTo(Screen::Output),
+        HomeAction::Exit => AppEvent::ExitRequested,
+    };
+    handle_event(app, &Event::App(event))
+}
@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+/// How soon a second Ctrl+C has to follow the first to quit without asking.
+pub const FORCE_QUIT_WINDOW: Duration = Duration::from_secs(2);
+
+/// What pressing Ctrl+C should do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptAction {
+    /// Ask whether to exit, as if exit had been requested.
+    Confirm,
+    /// Quit right away, discarding any changes.
+    ForceQuit,
+}
+
+/// Tracks Ctrl+C presses so that a second press soon after the first quits
+/// even when the first one's question goes unanswered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum InterruptState {
+    #[default]
+    Idle,
+    /// Ctrl+C was pressed at `at`, and pressing it again before the window
+    /// closes quits.
+    Armed { at: Instant },
+}
+
+impl InterruptState {
+    /// Records a press of Ctrl+C at `now`, returning what it should do.
+    pub fn press(&mut self, now: Instant) -> InterruptAction {
+        match *self {
+            InterruptState::Armed { at } if now.duration_since(at) < FORCE_QUIT_WINDOW => {
+                *self = InterruptState::Idle;
+                InterruptAction::ForceQuit
+            }
+            _ => {
+                *self = InterruptState::Armed { at: now };
+                InterruptAction::Confirm
+            }
+        }
+    }
+
+    /// Forgets an earlier press once its window has closed, returning whether
+    /// it did.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match *self {
+            InterruptState::Armed { at } if now.duration_since(at) >= FORCE_QUIT_WINDOW => {
+                *self = InterruptState::Idle;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the next press quits right away.
+    pub fn is_armed(&self) -> bool {
+        matches!(self, InterruptState::Armed { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_press_within_the_window_force_quits() {
+        let start = Instant::now();
+        let mut state = InterruptState::default();
+        assert_eq!(state.press(start), InterruptAction::Confirm);
+        assert!(state.is_armed());
+        let soon = start + FORCE_QUIT_WINDOW / 2;
+        assert_eq!(state.press(soon), InterruptAction::ForceQuit);
+        assert_eq!(state, InterruptState::Idle);
+    }
+
+    #[test]
+    fn presses_too_far_apart_each_ask_first() {
+        let start = Instant::now();
+        let mut state = InterruptState::default();
+        assert_eq!(state.press(start), InterruptAction::Confirm);
+        let later = start + FORCE_QUIT_WINDOW;
+        assert_eq!(state.press(later), InterruptAction::Confirm);
+        // The window restarts from the latest press.
+        assert_eq!(
+            state.press(later + Duration::from_millis(10)),
+            InterruptAction::ForceQuit
+        );
+    }
+
+    #[test]
+    fn expiring_disarms_after_the_window() {
+        let start = Instant::now();
+        let mut state = InterruptState::default();
+        assert!(!state.expire(start));
+        state.press(start);
+        assert!(!state.expire(start + FORCE_QUIT_WINDOW / 2));
+        assert!(state.is_armed());
+        assert!(state.expire(start + FORCE_QUIT_WINDOW));
+        assert!(!state.is_armed());
+        assert_eq!(
+            state.press(start + FORCE_QUIT_WINDOW),
+            InterruptAction::Confirm
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, Event, ExitDisposition, Screen, prompt::PromptEvent};
+
+/// A significant action taken during a session, as written to `--json-log`.
+///
+/// Payloads are kept minimal, and never contain variable values or commands
+/// verbatim since either could hold a secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum JournalAction {
+    SessionStarted {
+        shell: String,
+        screen: String,
+    },
+    ScreenChanged {
+        from: String,
+        to: String,
+    },
+    VarChanged {
+        name: String,
+        #[serde(flatten)]
+        value: Fingerprint,
+    },
+    VarUnset {
+        name: String,
+    },
+    /// A command was added to the output, identified by its first word.
+    CommandQueued {
+        program: String,
+        #[serde(flatten)]
+        command: Fingerprint,
+    },
+    CommandRemoved {
+        #[serde(flatten)]
+        command: Fingerprint,
+    },
+    /// A command was run at the prompt, identified by its name alone since
+    /// its arguments could be anything.
+    PromptCommand {
+        command: String,
+    },
+    ExitDecision {
+        exit: bool,
+        /// Whether the queued commands were dropped on the way out.
+        discard: bool,
+    },
+}
+
+/// Identifies a value without revealing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub len: usize,
+    /// The FNV-1a hash of the value in hex, which is stable across builds so
+    /// logs from different versions can be compared.
+    pub hash: String,
+}
+
+impl Fingerprint {
+    pub fn of(value: &str) -> Self {
+        let hash = value.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+        Self {
+            len: value.len(),
+            hash: format!("{hash:016x}"),
+        }
+    }
+}
+
+/// One line of the JSON log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub action: JournalAction,
+}
+
+/// Appends [`JournalEntry`]s to the file given with `--json-log`.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+impl Journal {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open JSON log {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Writes `actions` to the log, each on its own line.
+    pub fn record(&mut self, actions: &[JournalAction]) -> Result<(), Error> {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        let mut lines = String::new();
+        for action in actions {
+            let entry = JournalEntry {
+                timestamp_ms,
+                action: action.clone(),
+            };
+            lines.push_str(&serde_json::to_string(&entry)?);
+            lines.push('\n');
+        }
+        // A single write keeps the entries for one event together.
+        self.file
+            .write_all(lines.as_bytes())
+            .with_context(|| format!("failed to write JSON log {}", self.path.display()))
+    }
+}
+
+/// The state that journaled actions are found in, taken before and after an
+/// event is handled so that any change is logged no matter which handler
+/// made it.
+#[derive(Debug, Clone)]
+pub struct JournalSnapshot {
+    screen: Screen,
+    output_lines: Vec<String>,
+    vars: HashMap<String, Fingerprint>,
+    exit_modal_shown: bool,
+    discarding: bool,
+}
+
+impl JournalSnapshot {
+    pub fn take(app: &App) -> Self {
+        Self {
+            screen: app.screen(),
+            output_lines: app.output_lines().into_iter().map(String::from).collect(),
+            vars: app
+                .env()
+                .vars()
+                .iter()
+                .zip(app.env().values())
+                .map(|(name, value)| (name.clone(), Fingerprint::of(value)))
+                .collect(),
+            exit_modal_shown: app.is_displaying_exit_modal(),
+            discarding: app.exit_disposition() == ExitDisposition::Discard,
+        }
+    }
+}
+
+/// Returns the prompt command that `event` is about to run, if any.
+pub fn submitted_prompt_command(app: &App, event: &Event) -> Option<String> {
+    if app.screen() != Screen::Prompt
+        || app.overlay().is_some()
+        || app.modal().is_some()
+        || !matches!(event, Event::Prompt(PromptEvent::Submit))
+    {
+        return None;
+    }
+    let command = app.prompt().input().split_whitespace().next()?;
+    Some(command.to_string())
+}
+
+/// Lists the actions that happened between `before` and `after`.
+pub fn diff_snapshots(
+    before: &JournalSnapshot,
+    after: &JournalSnapshot,
+    prompt_command: Option<String>,
+    should_exit: bool,
+) -> Vec<JournalAction> {
+    let mut actions = Vec::new();
+    if let Some(command) = prompt_command {
+        actions.push(JournalAction::PromptCommand { command });
+    }
+    if before.screen != after.screen {
+        actions.push(JournalAction::ScreenChanged {
+            from: before.screen.to_string(),
+            to: after.screen.to_string(),
+        });
+    }
+    let mut names = after.vars.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let value = &after.vars[name];
+        if before.vars.get(name) != Some(value) {
+            actions.push(JournalAction::VarChanged {
+                name: name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    let mut unset = before
+        .vars
+        .keys()
+        .filter(|name| !after.vars.contains_key(*name))
+        .collect::<Vec<_>>();
+    unset.sort();
+    actions.extend(
+        unset
+            .into_iter()
+            .map(|name| JournalAction::VarUnset { name: name.clone() }),
+    );
+    actions.extend(output_changes(&before.output_lines, &after.output_lines));
+    if before.exit_modal_shown && !after.exit_modal_shown {
+        actions.push(JournalAction::ExitDecision {
+            exit: should_exit,
+            discard: after.discarding,
+        });
+    }
+    actions
+}
+
+/// Lists the commands added to or removed from the output, treating
+/// everything between the lines both sides start and end with as changed.
+fn output_changes(before: &[String], after: &[String]) -> Vec<JournalAction> {
+    let prefix = before
+        .iter()
+        .zip(after)
+        .take_while(|(before, after)| before == after)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(before, after)| before == after)
+        .count();
+    let removed =
+        before[prefix..before.len() - suffix]
+            .iter()
+            .map(|line| JournalAction::CommandRemoved {
+                command: Fingerprint::of(line),
+            });
+    let queued =
+        after[prefix..after.len() - suffix]
+            .iter()
+            .map(|line| JournalAction::CommandQueued {
+                program: line
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                command: Fingerprint::of(line),
+            });
+    removed.chain(queued).collect()
+}
@@ -1,13 +1,27 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc, str::FromStr};
 
-use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use anyhow::{Error, anyhow, bail};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
-use crate::app::{AppEvent, Event, ExitState, NavEvent, Screen, vars::VarsEvent};
+use crate::app::{
+    AppEvent, Event, NavEvent, Screen,
+    help::{Overlay, OverlayEvent},
+    macros::Macro,
+    modal::{Modal, ModalEvent},
+    prompt::PromptEvent,
+    vars::DetailView,
+};
 
 pub trait DisplayKeyBindings {
     /// Returns an iterator over (keys, description) pairs
     /// for each keybinding.
     fn displayable(&self) -> Vec<(String, &'static str)>;
+
+    /// Returns the (keys, description) pairs for the keybindings that are
+    /// still usable in read-only mode.
+    fn read_only_displayable(&self) -> Vec<(String, &'static str)> {
+        self.displayable()
+    }
 }
 
 pub trait DisplayKeyCombo {
@@ -15,7 +29,82 @@ pub trait DisplayKeyCombo {
     fn display_key_combo(&self) -> String;
 }
 
-impl DisplayKeyCombo for KeyEvent {
+/// A key combination normalized down to the parts that identify a binding.
+///
+/// Terminals disagree about the extra information they attach to a key press:
+/// the kitty keyboard protocol reports `KeyEventKind::Repeat` while a key is
+/// held and sets `KeyEventState::KEYPAD` for keypad keys, and some terminals
+/// report SHIFT alongside an already-uppercase character. Bindings are stored
+/// and looked up by `KeyCombo` so that all of these resolve to the same action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    /// The modifiers that participate in matching a binding.
+    const RELEVANT_MODIFIERS: KeyModifiers = KeyModifiers::SHIFT
+        .union(KeyModifiers::CONTROL)
+        .union(KeyModifiers::ALT)
+        .union(KeyModifiers::SUPER);
+
+    /// Creates a normalized key combination.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers.intersection(Self::RELEVANT_MODIFIERS);
+        // The character (or BackTab) already encodes whether SHIFT was held,
+        // so an explicit SHIFT modifier is redundant and only some terminals
+        // report it.
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    /// Normalizes an incoming key event, returning `None` for events that
+    /// should never trigger a binding (key releases).
+    pub fn from_event(event: &KeyEvent) -> Option<Self> {
+        match event.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                Some(Self::new(event.code, event.modifiers))
+            }
+            KeyEventKind::Release => None,
+        }
+    }
+}
+
+/// Whether key presses are interpreted as bindings or typed into a text field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum InputMode {
+    #[default]
+    Normal,
+    TextEntry,
+    /// The next key press will be bound to an action.
+    KeyCapture,
+}
+
+/// Converts a key press that isn't bound to anything into text entry while a
+/// text field is focused.
+pub fn text_entry_event(key: &KeyEvent) -> Option<Event> {
+    let combo = KeyCombo::from_event(key)?;
+    match combo.code {
+        KeyCode::Char(c)
+            if !combo
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SUPER) =>
+        {
+            Some(Event::Prompt(PromptEvent::Insert(c)))
+        }
+        _ => None,
+    }
+}
+
+/// Looks up the event bound to an incoming key event in a keymap.
+pub fn resolve_key(keymap: &HashMap<KeyCombo, Event>, key: &KeyEvent) -> Option<Event> {
+    KeyCombo::from_event(key).and_then(|combo| keymap.get(&combo).cloned())
+}
+
+impl DisplayKeyCombo for KeyCombo {
     fn display_key_combo(&self) -> String {
         let mut parts = vec![];
         if self.modifiers.contains(KeyModifiers::SHIFT) {
@@ -54,6 +143,129 @@ impl DisplayKeyCombo for KeyEvent {
     }
 }
 
+/// Formats a key combination the way it is written in the config file, e.g.
+/// `Ctrl+r` or `Shift+Up`.
+impl std::fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in MODIFIER_NAMES {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => {
+                let name = KEY_NAMES
+                    .iter()
+                    .find_map(|(name, named_code)| (*named_code == code).then_some(*name))
+                    .unwrap_or("Unknown");
+                write!(f, "{name}")
+            }
+        }
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split on the last '+' so that `+` and `Ctrl++` parse as the plus key.
+        let (modifier_part, key_part) = match s.rsplit_once('+') {
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", s),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for part in modifier_part.split('+').filter(|part| !part.is_empty()) {
+            let (modifier, _) = MODIFIER_NAMES
+                .iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(part))
+                .ok_or_else(|| anyhow!("unknown modifier '{part}' in key '{s}'"))?;
+            modifiers |= *modifier;
+        }
+        let mut chars = key_part.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ if key_part.eq_ignore_ascii_case("space") => KeyCode::Char(' '),
+            _ => {
+                let function_key = key_part
+                    .strip_prefix(['F', 'f'])
+                    .and_then(|n| n.parse::<u8>().ok());
+                match function_key {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => KEY_NAMES
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(key_part))
+                        .map(|(_, code)| *code)
+                        .ok_or_else(|| anyhow!("unknown key '{key_part}' in key '{s}'"))?,
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+const MODIFIER_NAMES: [(KeyModifiers, &str); 4] = [
+    (KeyModifiers::CONTROL, "Ctrl"),
+    (KeyModifiers::ALT, "Alt"),
+    (KeyModifiers::SUPER, "Super"),
+    (KeyModifiers::SHIFT, "Shift"),
+];
+
+const KEY_NAMES: [(&str, KeyCode); 14] = [
+    ("Backspace", KeyCode::Backspace),
+    ("Enter", KeyCode::Enter),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Delete", KeyCode::Delete),
+    ("Esc", KeyCode::Esc),
+];
+
+/// Where a key binding is active, used to detect bindings that conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingScope {
+    /// Active on every screen.
+    Global,
+    /// Active on a single screen.
+    Screen(Screen),
+    /// Active while the prompt's input line is focused.
+    PromptInput,
+}
+
+impl BindingScope {
+    /// Returns whether a key could be bound in both scopes at once.
+    fn overlaps(&self, other: &BindingScope) -> bool {
+        match (self, other) {
+            (BindingScope::Global, BindingScope::Screen(_))
+            | (BindingScope::Screen(_), BindingScope::Global) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// A single rebindable action and the key it is bound to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingEntry {
+    /// The config section the binding lives under, e.g. `vars`.
+    pub section: &'static str,
+    /// The name of the action within its section, e.g. `next_var`.
+    pub action: &'static str,
+    /// A user-facing description of the action.
+    pub description: &'static str,
+    pub scope: BindingScope,
+    pub key: KeyCombo,
+}
+
 /// The complete set of configured key bindings for the application.
 #[derive(Debug, Clone, Default)]
 pub struct KeyBindings {
@@ -64,6 +276,8 @@ pub struct KeyBindings {
     vars: VarsKeyBindings,
     trace: TraceKeyBindings,
     output: OutputKeyBindings,
+    /// The macros from the config file, whose keys work on every screen.
+    macros: Vec<Macro>,
 }
 
 impl KeyBindings {
@@ -97,47 +311,98 @@ impl KeyBindings {
         self.output.clone()
     }
 
+    /// Returns the macros from the config file.
+    pub fn macros(&self) -> &[Macro] {
+        &self.macros
+    }
+
+    /// Returns the macro called `name`.
+    pub fn macro_named(&self, name: &str) -> Option<&Macro> {
+        self.macros.iter().find(|macro_def| macro_def.name == name)
+    }
+
+    /// Adds `macros` to the bindings, refusing any whose key is already
+    /// bound on some screen or to another macro.
+    pub fn set_macros(&mut self, macros: Vec<Macro>) -> Result<(), Error> {
+        for macro_def in &macros {
+            let mut conflicts = self
+                .entries()
+                .into_iter()
+                .filter(|entry| {
+                    entry.key == macro_def.key && entry.scope.overlaps(&BindingScope::Global)
+                })
+                .map(|entry| format!("{}.{}", entry.section, entry.action))
+                .collect::<Vec<_>>();
+            conflicts.extend(
+                macros
+                    .iter()
+                    .filter(|other| other.name != macro_def.name && other.key == macro_def.key)
+                    .map(|other| format!("macros.{}", other.name)),
+            );
+            conflicts.extend(self.reserved_conflicts(macro_def.key, &BindingScope::Global));
+            if !conflicts.is_empty() {
+                bail!(
+                    "macros.{}: {} is already bound to {}",
+                    macro_def.name,
+                    macro_def.key,
+                    conflicts.join(", ")
+                );
+            }
+        }
+        self.macros = macros;
+        Ok(())
+    }
+
     /// Returns a mapping from keycode to application event given the
     /// current screen.
     pub fn current_keymap(
         &self,
         screen: &Screen,
-        exit_state: &ExitState,
-    ) -> HashMap<KeyEvent, Event> {
+        modal: Option<&Modal>,
+        input_mode: InputMode,
+    ) -> HashMap<KeyCombo, Event> {
         let mut keymap = HashMap::new();
 
-        // Handle modal-specific bindings
-        if let ExitState::PresentModal { .. } = exit_state {
-            // When modal is presented, only add navigation keys (no global keys)
-            let left_arrow = KeyEvent {
-                code: KeyCode::Left,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::NONE,
-            };
-            let existing = keymap.insert(left_arrow, Event::Nav(NavEvent::Left));
-            debug_assert!(existing.is_none());
-
-            let right_arrow = KeyEvent {
-                code: KeyCode::Right,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::NONE,
-            };
-            let existing = keymap.insert(right_arrow, Event::Nav(NavEvent::Right));
-            debug_assert!(existing.is_none());
-
-            // Add Enter key for selection
-            let enter = KeyEvent {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::NONE,
-            };
-            let existing = keymap.insert(enter, Event::Nav(NavEvent::Select));
-            debug_assert!(existing.is_none());
+        // When a modal is presented, only its own keys work (no global keys)
+        if modal.is_some() {
+            for (code, event) in [
+                (KeyCode::Left, ModalEvent::Previous),
+                (KeyCode::Right, ModalEvent::Next),
+                (KeyCode::Up, ModalEvent::ScrollUp),
+                (KeyCode::Down, ModalEvent::ScrollDown),
+                (KeyCode::Enter, ModalEvent::Select),
+                (KeyCode::Esc, ModalEvent::Cancel),
+                (KeyCode::Char('y'), ModalEvent::Accept),
+                (KeyCode::Char('n'), ModalEvent::Cancel),
+            ] {
+                let existing =
+                    keymap.insert(KeyCombo::new(code, KeyModifiers::NONE), Event::Modal(event));
+                debug_assert!(existing.is_none());
+            }
+            return keymap;
+        }
 
-            // Return early, no other keys should work when modal is shown
+        // While a text field is focused every printable key is text, so only
+        // the bindings that edit or leave the field apply.
+        if input_mode == InputMode::TextEntry {
+            let PromptKeyBindings {
+                unfocus_input,
+                submit,
+                history_previous,
+                history_next,
+                backspace,
+                complete,
+                ..
+            } = self.prompt;
+            keymap.insert(unfocus_input, Event::Prompt(PromptEvent::UnfocusInput));
+            keymap.insert(submit, Event::Prompt(PromptEvent::Submit));
+            keymap.insert(
+                history_previous,
+                Event::Prompt(PromptEvent::HistoryPrevious),
+            );
+            keymap.insert(history_next, Event::Prompt(PromptEvent::HistoryNext));
+            keymap.insert(backspace, Event::Prompt(PromptEvent::Backspace));
+            keymap.insert(complete, Event::Prompt(PromptEvent::Complete));
             return keymap;
         }
 
@@ -145,163 +410,1000 @@ impl KeyBindings {
         let GlobalKeyBindings {
             exit,
             next_tab,
-            // prev_tab,
+            prev_tab,
+            last_item,
+            help,
+            dismiss_message,
+            ..
         } = self.global;
         let existing = keymap.insert(exit, Event::App(AppEvent::ExitRequested));
         debug_assert!(existing.is_none());
         let existing = keymap.insert(next_tab, Event::App(AppEvent::NextTab));
         debug_assert!(existing.is_none());
-        // let existing = keymap.insert(prev_tab, Event::App(AppEvent::PrevTab));
-        // debug_assert!(existing.is_none());
-
-        // Match on the screen and apply screen-specific keybindings once
-        // we have them
-        match screen {
-            Screen::Home => {
-                let HomeKeyBindings {} = self.home;
+        let existing = keymap.insert(prev_tab, Event::App(AppEvent::PrevTab));
+        debug_assert!(existing.is_none());
+        let existing = keymap.insert(last_item, Event::Nav(NavEvent::Bottom));
+        debug_assert!(existing.is_none());
+        let existing = keymap.insert(help, Event::App(AppEvent::ShowHelp));
+        debug_assert!(existing.is_none());
+        let existing = keymap.insert(dismiss_message, Event::App(AppEvent::DismissMessage));
+        debug_assert!(existing.is_none());
+        for macro_def in &self.macros {
+            let event = Event::App(AppEvent::RunMacro(macro_def.name.clone()));
+            let existing = keymap.insert(macro_def.key, event);
+            debug_assert!(existing.is_none());
+        }
+
+        keymap.extend(screen.controller().keymap(self));
+        keymap
+    }
+
+    /// Returns every rebindable action along with its current key.
+    pub fn entries(&self) -> Vec<BindingEntry> {
+        let on = BindingScope::Screen;
+        let global = &self.global;
+        let home = &self.home;
+        let prompt = &self.prompt;
+        let vars = &self.vars;
+        let trace = &self.trace;
+        let output = &self.output;
+        let entry = |section, action, description, scope, key| BindingEntry {
+            section,
+            action,
+            description,
+            scope,
+            key,
+        };
+        vec![
+            entry("global", "exit", "Exit", BindingScope::Global, global.exit),
+            entry(
+                "global",
+                "next_tab",
+                "Next tab",
+                BindingScope::Global,
+                global.next_tab,
+            ),
+            entry(
+                "global",
+                "prev_tab",
+                "Previous tab",
+                BindingScope::Global,
+                global.prev_tab,
+            ),
+            entry(
+                "global",
+                "last_item",
+                "Jump to bottom",
+                BindingScope::Global,
+                global.last_item,
+            ),
+            entry(
+                "global",
+                "help",
+                "Show help",
+                BindingScope::Global,
+                global.help,
+            ),
+            entry(
+                "global",
+                "dismiss_message",
+                "Dismiss message",
+                BindingScope::Global,
+                global.dismiss_message,
+            ),
+            entry(
+                "home",
+                "go_to_prompt",
+                "Go to Prompt",
+                on(Screen::Home),
+                home.go_to_prompt,
+            ),
+            entry(
+                "home",
+                "go_to_vars",
+                "Go to Vars",
+                on(Screen::Home),
+                home.go_to_vars,
+            ),
+            entry(
+                "home",
+                "go_to_trace",
+                "Go to Trace",
+                on(Screen::Home),
+                home.go_to_trace,
+            ),
+            entry(
+                "home",
+                "go_to_output",
+                "Go to Output",
+                on(Screen::Home),
+                home.go_to_output,
+            ),
+            entry(
+                "home",
+                "reload_config",
+                "Reload config",
+                on(Screen::Home),
+                home.reload_config,
+            ),
+            entry(
+                "home",
+                "previous_action",
+                "Previous action",
+                on(Screen::Home),
+                home.previous_action,
+            ),
+            entry(
+                "home",
+                "next_action",
+                "Next action",
+                on(Screen::Home),
+                home.next_action,
+            ),
+            entry(
+                "home",
+                "activate",
+                "Do the selected action",
+                on(Screen::Home),
+                home.activate,
+            ),
+            entry(
+                "prompt",
+                "focus_input",
+                "Start typing",
+                on(Screen::Prompt),
+                prompt.focus_input,
+            ),
+            entry(
+                "prompt",
+                "scroll_up",
+                "Scroll up",
+                on(Screen::Prompt),
+                prompt.scroll_up,
+            ),
+            entry(
+                "prompt",
+                "scroll_down",
+                "Scroll down",
+                on(Screen::Prompt),
+                prompt.scroll_down,
+            ),
+            entry(
+                "prompt",
+                "unfocus_input",
+                "Stop typing",
+                BindingScope::PromptInput,
+                prompt.unfocus_input,
+            ),
+            entry(
+                "prompt",
+                "submit",
+                "Run command",
+                BindingScope::PromptInput,
+                prompt.submit,
+            ),
+            entry(
+                "prompt",
+                "history_previous",
+                "Previous command",
+                BindingScope::PromptInput,
+                prompt.history_previous,
+            ),
+            entry(
+                "prompt",
+                "history_next",
+                "Next command",
+                BindingScope::PromptInput,
+                prompt.history_next,
+            ),
+            entry(
+                "prompt",
+                "backspace",
+                "Delete character",
+                BindingScope::PromptInput,
+                prompt.backspace,
+            ),
+            entry(
+                "prompt",
+                "complete",
+                "Complete",
+                BindingScope::PromptInput,
+                prompt.complete,
+            ),
+            entry(
+                "vars",
+                "next_var",
+                "Next variable",
+                on(Screen::Vars),
+                vars.next_var,
+            ),
+            entry(
+                "vars",
+                "previous_var",
+                "Previous variable",
+                on(Screen::Vars),
+                vars.previous_var,
+            ),
+            entry(
+                "vars",
+                "focus_var_list",
+                "Focus variable list",
+                on(Screen::Vars),
+                vars.focus_var_list,
+            ),
+            entry(
+                "vars",
+                "focus_var_detail",
+                "Focus variable detail",
+                on(Screen::Vars),
+                vars.focus_var_detail,
+            ),
+            entry(
+                "vars",
+                "raw_detail",
+                "Raw detail view",
+                on(Screen::Vars),
+                vars.raw_detail,
+            ),
+            entry(
+                "vars",
+                "split_detail",
+                "Split detail view",
+                on(Screen::Vars),
+                vars.split_detail,
+            ),
+            entry(
+                "vars",
+                "open_in_pager",
+                "Open value in pager",
+                on(Screen::Vars),
+                vars.open_in_pager,
+            ),
+            entry(
+                "vars",
+                "toggle_tree",
+                "Group variables by prefix",
+                on(Screen::Vars),
+                vars.toggle_tree,
+            ),
+            entry(
+                "vars",
+                "toggle_group",
+                "Expand or collapse group",
+                on(Screen::Vars),
+                vars.toggle_group,
+            ),
+            entry(
+                "vars",
+                "cycle_origin",
+                "Filter variables by origin",
+                on(Screen::Vars),
+                vars.cycle_origin,
+            ),
+            entry(
+                "vars",
+                "toggle_auto_split",
+                "Split lists automatically",
+                on(Screen::Vars),
+                vars.toggle_auto_split,
+            ),
+            entry(
+                "vars",
+                "toggle_command_preview",
+                "Show the command for the shell",
+                on(Screen::Vars),
+                vars.toggle_command_preview,
+            ),
+            entry(
+                "vars",
+                "reveal_value",
+                "Reveal a secret in the command",
+                on(Screen::Vars),
+                vars.reveal_value,
+            ),
+            entry(
+                "vars",
+                "reload_env",
+                "Reload the environment",
+                on(Screen::Vars),
+                vars.reload_env,
+            ),
+            entry(
+                "trace",
+                "next_frame",
+                "Next frame",
+                on(Screen::Trace),
+                trace.next_frame,
+            ),
+            entry(
+                "trace",
+                "previous_frame",
+                "Previous frame",
+                on(Screen::Trace),
+                trace.previous_frame,
+            ),
+            entry(
+                "trace",
+                "set_breakpoint",
+                "Set breakpoint",
+                on(Screen::Trace),
+                trace.set_breakpoint,
+            ),
+            entry(
+                "trace",
+                "show_vars",
+                "Show the frame's variables",
+                on(Screen::Trace),
+                trace.show_vars,
+            ),
+            entry(
+                "trace",
+                "open_in_pager",
+                "Open frame's file in pager",
+                on(Screen::Trace),
+                trace.open_in_pager,
+            ),
+            entry(
+                "output",
+                "next_command",
+                "Next command",
+                on(Screen::Output),
+                output.next_command,
+            ),
+            entry(
+                "output",
+                "previous_command",
+                "Previous command",
+                on(Screen::Output),
+                output.previous_command,
+            ),
+            entry(
+                "output",
+                "delete_command",
+                "Delete command",
+                on(Screen::Output),
+                output.delete_command,
+            ),
+            entry(
+                "output",
+                "edit_command",
+                "Edit command",
+                on(Screen::Output),
+                output.edit_command,
+            ),
+            entry(
+                "output",
+                "clear_commands",
+                "Clear commands",
+                on(Screen::Output),
+                output.clear_commands,
+            ),
+            entry(
+                "output",
+                "undo_command",
+                "Undo last command",
+                on(Screen::Output),
+                output.undo_command,
+            ),
+            entry(
+                "output",
+                "open_in_pager",
+                "Open commands in pager",
+                on(Screen::Output),
+                output.open_in_pager,
+            ),
+            entry(
+                "output",
+                "toggle_wrap",
+                "Wrap long commands",
+                on(Screen::Output),
+                output.toggle_wrap,
+            ),
+            entry(
+                "output",
+                "scroll_left",
+                "Scroll left",
+                on(Screen::Output),
+                output.scroll_left,
+            ),
+            entry(
+                "output",
+                "scroll_right",
+                "Scroll right",
+                on(Screen::Output),
+                output.scroll_right,
+            ),
+        ]
+    }
+
+    /// Returns a mutable reference to the key bound to an action.
+    fn binding_mut(&mut self, section: &str, action: &str) -> Option<&mut KeyCombo> {
+        let key = match (section, action) {
+            ("global", "exit") => &mut self.global.exit,
+            ("global", "next_tab") => &mut self.global.next_tab,
+            ("global", "prev_tab") => &mut self.global.prev_tab,
+            ("global", "last_item") => &mut self.global.last_item,
+            ("global", "help") => &mut self.global.help,
+            ("global", "dismiss_message") => &mut self.global.dismiss_message,
+            ("home", "go_to_prompt") => &mut self.home.go_to_prompt,
+            ("home", "go_to_vars") => &mut self.home.go_to_vars,
+            ("home", "go_to_trace") => &mut self.home.go_to_trace,
+            ("home", "go_to_output") => &mut self.home.go_to_output,
+            ("home", "reload_config") => &mut self.home.reload_config,
+            ("home", "previous_action") => &mut self.home.previous_action,
+            ("home", "next_action") => &mut self.home.next_action,
+            ("home", "activate") => &mut self.home.activate,
+            ("prompt", "focus_input") => &mut self.prompt.focus_input,
+            ("prompt", "scroll_up") => &mut self.prompt.scroll_up,
+            ("prompt", "scroll_down") => &mut self.prompt.scroll_down,
+            ("prompt", "unfocus_input") => &mut self.prompt.unfocus_input,
+            ("prompt", "submit") => &mut self.prompt.submit,
+            ("prompt", "history_previous") => &mut self.prompt.history_previous,
+            ("prompt", "history_next") => &mut self.prompt.history_next,
+            ("prompt", "backspace") => &mut self.prompt.backspace,
+            ("prompt", "complete") => &mut self.prompt.complete,
+            ("vars", "next_var") => &mut self.vars.next_var,
+            ("vars", "previous_var") => &mut self.vars.previous_var,
+            ("vars", "focus_var_list") => &mut self.vars.focus_var_list,
+            ("vars", "focus_var_detail") => &mut self.vars.focus_var_detail,
+            ("vars", "raw_detail") => &mut self.vars.raw_detail,
+            ("vars", "split_detail") => &mut self.vars.split_detail,
+            ("vars", "open_in_pager") => &mut self.vars.open_in_pager,
+            ("vars", "toggle_tree") => &mut self.vars.toggle_tree,
+            ("vars", "toggle_group") => &mut self.vars.toggle_group,
+            ("vars", "cycle_origin") => &mut self.vars.cycle_origin,
+            ("vars", "toggle_auto_split") => &mut self.vars.toggle_auto_split,
+            ("vars", "toggle_command_preview") => &mut self.vars.toggle_command_preview,
+            ("vars", "reveal_value") => &mut self.vars.reveal_value,
+            ("vars", "reload_env") => &mut self.vars.reload_env,
+            ("trace", "next_frame") => &mut self.trace.next_frame,
+            ("trace", "previous_frame") => &mut self.trace.previous_frame,
+            ("trace", "set_breakpoint") => &mut self.trace.set_breakpoint,
+            ("trace", "show_vars") => &mut self.trace.show_vars,
+            ("trace", "open_in_pager") => &mut self.trace.open_in_pager,
+            ("output", "next_command") => &mut self.output.next_command,
+            ("output", "previous_command") => &mut self.output.previous_command,
+            ("output", "delete_command") => &mut self.output.delete_command,
+            ("output", "edit_command") => &mut self.output.edit_command,
+            ("output", "clear_commands") => &mut self.output.clear_commands,
+            ("output", "undo_command") => &mut self.output.undo_command,
+            ("output", "open_in_pager") => &mut self.output.open_in_pager,
+            ("output", "toggle_wrap") => &mut self.output.toggle_wrap,
+            ("output", "scroll_left") => &mut self.output.scroll_left,
+            ("output", "scroll_right") => &mut self.output.scroll_right,
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    /// Returns a description of every binding that `key` would conflict with
+    /// if it were bound to the given action.
+    pub fn conflicts_for(&self, section: &str, action: &str, key: KeyCombo) -> Vec<String> {
+        let entries = self.entries();
+        let Some(target) = entries
+            .iter()
+            .find(|entry| entry.section == section && entry.action == action)
+        else {
+            return vec![];
+        };
+        let mut conflicts = entries
+            .iter()
+            .filter(|entry| !(entry.section == section && entry.action == action))
+            .filter(|entry| entry.key == key && entry.scope.overlaps(&target.scope))
+            .map(|entry| format!("{}.{}", entry.section, entry.action))
+            .collect::<Vec<_>>();
+        if target.scope != BindingScope::PromptInput {
+            conflicts.extend(
+                self.macros
+                    .iter()
+                    .filter(|macro_def| macro_def.key == key)
+                    .map(|macro_def| format!("macros.{}", macro_def.name)),
+            );
+        }
+        conflicts.extend(self.reserved_conflicts(key, &target.scope));
+        conflicts
+    }
+
+    /// Returns what `key` is kept for besides the bindings in `scope`:
+    /// interrupting and suspending the debugger anywhere, and starting a key
+    /// sequence outside the prompt's input line.
+    fn reserved_conflicts(&self, key: KeyCombo, scope: &BindingScope) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        if key == interrupt_key() {
+            conflicts.push("interrupting the debugger".to_string());
+        }
+        if key == suspend_key() {
+            conflicts.push("suspending the debugger".to_string());
+        }
+        // A key that starts a sequence can't also be bound on its own, since
+        // the sequence would swallow it.
+        if *scope != BindingScope::PromptInput {
+            let GlobalKeyBindings {
+                next_tab_sequence,
+                prev_tab_sequence,
+                first_item,
+                ..
+            } = self.global;
+            for sequence in [next_tab_sequence, prev_tab_sequence, first_item] {
+                if sequence[0] == key {
+                    conflicts.push(format!(
+                        "the '{}' sequence",
+                        display_key_sequence(&sequence)
+                    ));
+                }
             }
-            Screen::Vars => {
-                let VarsKeyBindings {
-                    next_var,
-                    previous_var,
-                    focus_var_list,
-                    focus_var_detail,
-                    raw_detail,
-                    split_detail,
-                } = self.vars;
-                keymap.insert(next_var, Event::Nav(NavEvent::Down));
-                keymap.insert(previous_var, Event::Nav(NavEvent::Up));
-                keymap.insert(focus_var_list, Event::Nav(NavEvent::Left));
-                keymap.insert(focus_var_detail, Event::Nav(NavEvent::Right));
-                keymap.insert(raw_detail, Event::Vars(VarsEvent::RawDetail));
-                keymap.insert(split_detail, Event::Vars(VarsEvent::SplitDetail));
+        }
+        conflicts
+    }
+
+    /// Returns a description of every pair of conflicting bindings.
+    pub fn conflicts(&self) -> Vec<String> {
+        self.entries()
+            .into_iter()
+            .flat_map(|entry| {
+                self.conflicts_for(entry.section, entry.action, entry.key)
+                    .into_iter()
+                    .map(move |other| {
+                        format!("{}.{} conflicts with {other}", entry.section, entry.action)
+                    })
+            })
+            .collect()
+    }
+
+    /// Binds an action to a new key, refusing keys that would conflict with
+    /// another binding or that can't be bound at all.
+    pub fn rebind(&mut self, section: &str, action: &str, key: KeyCombo) -> Result<(), Error> {
+        if key.display_key_combo().is_empty() || key.to_string().ends_with("Unknown") {
+            bail!("'{}' can't be bound", key.display_key_combo());
+        }
+        let conflicts = self.conflicts_for(section, action, key);
+        if !conflicts.is_empty() {
+            bail!("{key} is already bound to {}", conflicts.join(", "));
+        }
+        let binding = self
+            .binding_mut(section, action)
+            .ok_or_else(|| anyhow!("unknown key binding '{section}.{action}'"))?;
+        *binding = key;
+        Ok(())
+    }
+
+    /// Serializes the bindings into the `[keys]` table of the config file.
+    pub fn to_toml(&self) -> toml::Table {
+        let mut table = toml::Table::new();
+        for entry in self.entries() {
+            let section = table
+                .entry(entry.section)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if let toml::Value::Table(section) = section {
+                section.insert(entry.action.to_string(), entry.key.to_string().into());
             }
-            Screen::Trace => {
-                let TraceKeyBindings {
-                    next_frame,
-                    previous_frame,
-                } = self.trace;
-                keymap.insert(next_frame, Event::Nav(NavEvent::Down));
-                keymap.insert(previous_frame, Event::Nav(NavEvent::Up));
+        }
+        table
+    }
+
+    /// Applies the bindings from the `[keys]` table of the config file on top
+    /// of the current bindings.
+    pub fn apply_toml(&mut self, table: &toml::Table) -> Result<(), Error> {
+        for (section, actions) in table {
+            let actions = actions
+                .as_table()
+                .ok_or_else(|| anyhow!("keys.{section} must be a table"))?;
+            for (action, key) in actions {
+                let key = key
+                    .as_str()
+                    .ok_or_else(|| anyhow!("keys.{section}.{action} must be a string"))?;
+                let key = key
+                    .parse::<KeyCombo>()
+                    .map_err(|err| anyhow!("keys.{section}.{action}: {err}"))?;
+                let binding = self
+                    .binding_mut(section, action)
+                    .ok_or_else(|| anyhow!("unknown key binding 'keys.{section}.{action}'"))?;
+                *binding = key;
+            }
+        }
+        if let Some(conflict) = self.conflicts().first() {
+            bail!("conflicting key bindings: {conflict}");
+        }
+        Ok(())
+    }
+
+    /// Returns a mapping from keycode to application event while an overlay
+    /// is displayed. No other keys work while an overlay is shown.
+    pub fn overlay_keymap(&self, overlay: &Overlay) -> HashMap<KeyCombo, Event> {
+        let mut keymap = HashMap::new();
+        let esc = KeyCombo::new(KeyCode::Esc, KeyModifiers::NONE);
+        keymap.insert(esc, Event::Overlay(OverlayEvent::Close));
+        match overlay {
+            Overlay::Help => {
+                keymap.insert(self.global.help, Event::Overlay(OverlayEvent::Close));
+                let bindings = KeyCombo::new(KeyCode::Char('b'), KeyModifiers::NONE);
+                keymap.insert(bindings, Event::Overlay(OverlayEvent::OpenBindings));
+            }
+            Overlay::Bindings(_) => {
+                let up = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+                keymap.insert(up, Event::Nav(NavEvent::Up));
+                let down = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+                keymap.insert(down, Event::Nav(NavEvent::Down));
+                let enter = KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE);
+                keymap.insert(enter, Event::Overlay(OverlayEvent::StartCapture));
+                let export = KeyCombo::new(KeyCode::Char('x'), KeyModifiers::NONE);
+                keymap.insert(export, Event::Overlay(OverlayEvent::Export));
             }
-            _ => {}
         }
         keymap
     }
+
+    /// Returns a mapping from two-key sequences to application events given
+    /// the current state of the application.
+    ///
+    /// Sequences are unavailable while a modal is presented or a text field
+    /// is focused, since every key has a more immediate meaning there.
+    pub fn current_sequences(
+        &self,
+        modal: Option<&Modal>,
+        input_mode: InputMode,
+    ) -> HashMap<[KeyCombo; 2], Event> {
+        let mut sequences = HashMap::new();
+        if modal.is_some() || input_mode == InputMode::TextEntry {
+            return sequences;
+        }
+        let GlobalKeyBindings {
+            next_tab_sequence,
+            prev_tab_sequence,
+            first_item,
+            ..
+        } = self.global;
+        sequences.insert(next_tab_sequence, Event::App(AppEvent::NextTab));
+        sequences.insert(prev_tab_sequence, Event::App(AppEvent::PrevTab));
+        sequences.insert(first_item, Event::Nav(NavEvent::Top));
+        sequences
+    }
+}
+
+/// The (keys, description) pairs shown in a screen's footer.
+pub type FooterBindings = Rc<[(String, &'static str)]>;
+
+/// Everything besides the screen that decides which bindings its footer
+/// shows, so that the footer can be cached until one of these changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FooterContext {
+    pub read_only: bool,
+    /// The state of the Vars screen, while it's the one shown.
+    pub vars: Option<VarsFooterState>,
+}
+
+/// What the Vars screen shows, as far as its footer cares.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VarsFooterState {
+    pub detail_focused: bool,
+    pub detail_view: DetailView,
+    pub tree: bool,
+    pub command_preview: bool,
+}
+
+/// Shows several keys as one, such as `↑↓←→` for the arrows, separating
+/// them with `/` unless they're all arrows.
+fn compact_keys(combos: &[KeyCombo]) -> String {
+    let keys = combos
+        .iter()
+        .map(|combo| combo.display_key_combo())
+        .collect::<Vec<_>>();
+    if keys
+        .iter()
+        .all(|key| matches!(key.as_str(), "↑" | "↓" | "←" | "→"))
+    {
+        keys.concat()
+    } else {
+        keys.join("/")
+    }
+}
+
+/// What a keymap was built for. A keymap only depends on the bindings and
+/// these, so the same one can be used until one of them changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeymapContext {
+    screen: Screen,
+    modal_open: bool,
+    input_mode: InputMode,
+}
+
+/// The keymap and footer bindings worked out from the key bindings, kept
+/// since they're needed on every key press and frame but rarely change.
+///
+/// Anything cached has to be cleared when the bindings change.
+#[derive(Debug, Default)]
+pub struct BindingsCache {
+    keymap: Option<(KeymapContext, Rc<HashMap<KeyCombo, Event>>)>,
+    /// The global and screen bindings shown in the footer of each screen,
+    /// by what else they depend on.
+    footers: HashMap<(Screen, FooterContext), FooterBindings>,
+}
+
+impl BindingsCache {
+    /// Returns the keymap for `screen`, building it only if the last one was
+    /// for something else.
+    pub fn keymap(
+        &mut self,
+        bindings: &KeyBindings,
+        screen: &Screen,
+        modal: Option<&Modal>,
+        input_mode: InputMode,
+    ) -> Rc<HashMap<KeyCombo, Event>> {
+        let context = KeymapContext {
+            screen: screen.clone(),
+            modal_open: modal.is_some(),
+            input_mode,
+        };
+        match &self.keymap {
+            Some((cached, keymap)) if *cached == context => keymap.clone(),
+            _ => {
+                let keymap = Rc::new(bindings.current_keymap(screen, modal, input_mode));
+                self.keymap = Some((context, keymap.clone()));
+                keymap
+            }
+        }
+    }
+
+    /// Returns the (keys, description) pairs for the footer of `screen`.
+    pub fn footer(
+        &mut self,
+        bindings: &KeyBindings,
+        screen: &Screen,
+        context: FooterContext,
+    ) -> FooterBindings {
+        self.footers
+            .entry((screen.clone(), context))
+            .or_insert_with(|| {
+                let mut footer = bindings.global.displayable();
+                footer.extend(screen.controller().footer_bindings(bindings, context));
+                footer.into()
+            })
+            .clone()
+    }
+
+    /// Forgets everything, for when the bindings have changed.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Returns the key that always interrupts the debugger, whatever else is
+/// going on, so it can't be bound to anything else.
+pub fn interrupt_key() -> KeyCombo {
+    KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+}
+
+/// Returns the key that suspends the debugger, which works everywhere for the
+/// same reason as [`interrupt_key`].
+pub fn suspend_key() -> KeyCombo {
+    KeyCombo::new(KeyCode::Char('z'), KeyModifiers::CONTROL)
+}
+
+/// Returns a user-facing representation of a key sequence.
+pub fn display_key_sequence(sequence: &[KeyCombo]) -> String {
+    sequence
+        .iter()
+        .map(|combo| match combo.code {
+            // Spell out lowercase characters so `g g` isn't shown as `G G`.
+            KeyCode::Char(c) if combo.modifiers.is_empty() => c.to_string(),
+            _ => combo.display_key_combo(),
+        })
+        .collect::<Vec<_>>()
+        .join("")
 }
 
 #[derive(Debug, Clone)]
 pub struct GlobalKeyBindings {
-    exit: KeyEvent,
-    next_tab: KeyEvent,
+    exit: KeyCombo,
+    next_tab: KeyCombo,
+    prev_tab: KeyCombo,
+    next_tab_sequence: [KeyCombo; 2],
+    prev_tab_sequence: [KeyCombo; 2],
+    first_item: [KeyCombo; 2],
+    last_item: KeyCombo,
+    help: KeyCombo,
+    dismiss_message: KeyCombo,
 }
 
 impl Default for GlobalKeyBindings {
     fn default() -> Self {
-        let exit = KeyEvent {
-            code: KeyCode::Char('q'),
-            // modifiers: KeyModifiers::CONTROL,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let next_tab = KeyEvent {
-            code: KeyCode::Tab,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        // FIXME: this doesn't work for some reason, haven't looked into why
-        // let prev_tab = KeyEvent {
-        //     code: KeyCode::Tab,
-        //     modifiers: KeyModifiers::SHIFT,
-        //     kind: KeyEventKind::Press,
-        //     state: KeyEventState::NONE,
-        // };
+        let exit = KeyCombo::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        let next_tab = KeyCombo::new(KeyCode::Tab, KeyModifiers::NONE);
+        let g = KeyCombo::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        let next_tab_sequence = [g, KeyCombo::new(KeyCode::Char('t'), KeyModifiers::NONE)];
+        // Terminals send Shift+Tab as its own key rather than Tab with SHIFT.
+        let prev_tab = KeyCombo::new(KeyCode::BackTab, KeyModifiers::NONE);
+        let prev_tab_sequence = [g, KeyCombo::new(KeyCode::Char('T'), KeyModifiers::NONE)];
+        let first_item = [g, g];
+        let last_item = KeyCombo::new(KeyCode::Char('G'), KeyModifiers::NONE);
+        let help = KeyCombo::new(KeyCode::Char('?'), KeyModifiers::NONE);
+        let dismiss_message = KeyCombo::new(KeyCode::Esc, KeyModifiers::NONE);
         Self {
             exit,
             next_tab,
-            // prev_tab,
+            prev_tab,
+            next_tab_sequence,
+            prev_tab_sequence,
+            first_item,
+            last_item,
+            help,
+            dismiss_message,
         }
     }
 }
 
+impl GlobalKeyBindings {
+    /// Returns the key that dismisses the message currently shown.
+    pub fn dismiss_message(&self) -> KeyCombo {
+        self.dismiss_message
+    }
+
+    /// Returns the key that shows the help overlay.
+    pub fn help(&self) -> KeyCombo {
+        self.help
+    }
+}
+
 impl DisplayKeyBindings for GlobalKeyBindings {
     fn displayable(&self) -> Vec<(String, &'static str)> {
         vec![
             (self.exit.display_key_combo(), "Exit"),
-            (self.next_tab.display_key_combo(), "Next Tab"),
-            // (self.prev_tab.display_key_combo(), "Prev"),
+            (self.help.display_key_combo(), "Help"),
+            (
+                format!(
+                    "{}/{}",
+                    self.next_tab.display_key_combo(),
+                    self.prev_tab.display_key_combo()
+                ),
+                "Next/Prev Tab",
+            ),
+            (
+                format!(
+                    "{}/{}",
+                    display_key_sequence(&self.first_item),
+                    self.last_item.display_key_combo()
+                ),
+                "Top/Bottom",
+            ),
         ]
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct HomeKeyBindings {}
+#[derive(Debug, Clone)]
+pub struct HomeKeyBindings {
+    pub go_to_prompt: KeyCombo,
+    pub go_to_vars: KeyCombo,
+    pub go_to_trace: KeyCombo,
+    pub go_to_output: KeyCombo,
+    pub reload_config: KeyCombo,
+    pub previous_action: KeyCombo,
+    pub next_action: KeyCombo,
+    pub activate: KeyCombo,
+}
+
+impl Default for HomeKeyBindings {
+    fn default() -> Self {
+        let go_to_prompt = KeyCombo::new(KeyCode::Char('p'), KeyModifiers::NONE);
+        let go_to_vars = KeyCombo::new(KeyCode::Char('v'), KeyModifiers::NONE);
+        let go_to_trace = KeyCombo::new(KeyCode::Char('t'), KeyModifiers::NONE);
+        let go_to_output = KeyCombo::new(KeyCode::Char('o'), KeyModifiers::NONE);
+        let reload_config = KeyCombo::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        let previous_action = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+        let next_action = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+        let activate = KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE);
+        Self {
+            go_to_prompt,
+            go_to_vars,
+            go_to_trace,
+            go_to_output,
+            reload_config,
+            previous_action,
+            next_action,
+            activate,
+        }
+    }
+}
 
 impl DisplayKeyBindings for HomeKeyBindings {
     fn displayable(&self) -> Vec<(String, &'static str)> {
-        vec![]
+        vec![
+            (
+                compact_keys(&[self.previous_action, self.next_action]),
+                "Select",
+            ),
+            (self.activate.display_key_combo(), "Open"),
+            (self.go_to_prompt.display_key_combo(), "Prompt"),
+            (self.go_to_vars.display_key_combo(), "Vars"),
+            (self.go_to_trace.display_key_combo(), "Trace"),
+            (self.go_to_output.display_key_combo(), "Output"),
+            (self.reload_config.display_key_combo(), "Reload config"),
+        ]
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct PromptKeyBindings {}
+#[derive(Debug, Clone)]
+pub struct PromptKeyBindings {
+    pub focus_input: KeyCombo,
+    pub unfocus_input: KeyCombo,
+    pub submit: KeyCombo,
+    pub history_previous: KeyCombo,
+    pub history_next: KeyCombo,
+    pub backspace: KeyCombo,
+    pub complete: KeyCombo,
+    pub scroll_up: KeyCombo,
+    pub scroll_down: KeyCombo,
+}
+
+impl Default for PromptKeyBindings {
+    fn default() -> Self {
+        let focus_input = KeyCombo::new(KeyCode::Char('i'), KeyModifiers::NONE);
+        let unfocus_input = KeyCombo::new(KeyCode::Esc, KeyModifiers::NONE);
+        let submit = KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE);
+        let history_previous = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+        let history_next = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+        let backspace = KeyCombo::new(KeyCode::Backspace, KeyModifiers::NONE);
+        let complete = KeyCombo::new(KeyCode::Tab, KeyModifiers::NONE);
+        let scroll_up = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+        let scroll_down = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+        Self {
+            focus_input,
+            unfocus_input,
+            submit,
+            history_previous,
+            history_next,
+            backspace,
+            complete,
+            scroll_up,
+            scroll_down,
+        }
+    }
+}
 
 impl DisplayKeyBindings for PromptKeyBindings {
     fn displayable(&self) -> Vec<(String, &'static str)> {
-        vec![]
+        vec![
+            (self.focus_input.display_key_combo(), "Type"),
+            (self.unfocus_input.display_key_combo(), "Stop Typing"),
+            (self.submit.display_key_combo(), "Run"),
+            ("↑↓".to_string(), "Scroll/History"),
+        ]
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct VarsKeyBindings {
-    next_var: KeyEvent,
-    previous_var: KeyEvent,
-    focus_var_list: KeyEvent,
-    focus_var_detail: KeyEvent,
-    raw_detail: KeyEvent,
-    split_detail: KeyEvent,
+    pub next_var: KeyCombo,
+    pub previous_var: KeyCombo,
+    pub focus_var_list: KeyCombo,
+    pub focus_var_detail: KeyCombo,
+    pub raw_detail: KeyCombo,
+    pub split_detail: KeyCombo,
+    pub open_in_pager: KeyCombo,
+    pub toggle_tree: KeyCombo,
+    pub toggle_group: KeyCombo,
+    pub cycle_origin: KeyCombo,
+    pub toggle_auto_split: KeyCombo,
+    pub toggle_command_preview: KeyCombo,
+    pub reveal_value: KeyCombo,
+    pub reload_env: KeyCombo,
 }
 
 impl Default for VarsKeyBindings {
     fn default() -> Self {
-        let next_var = KeyEvent {
-            code: KeyCode::Down,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let previous_var = KeyEvent {
-            code: KeyCode::Up,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let focus_var_list = KeyEvent {
-            code: KeyCode::Left,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let focus_var_detail = KeyEvent {
-            code: KeyCode::Right,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let raw_detail = KeyEvent {
-            code: KeyCode::Char('r'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let split_detail = KeyEvent {
-            code: KeyCode::Char('s'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
+        let next_var = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+        let previous_var = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+        let focus_var_list = KeyCombo::new(KeyCode::Left, KeyModifiers::NONE);
+        let focus_var_detail = KeyCombo::new(KeyCode::Right, KeyModifiers::NONE);
+        let raw_detail = KeyCombo::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        let split_detail = KeyCombo::new(KeyCode::Char('s'), KeyModifiers::NONE);
+        let open_in_pager = KeyCombo::new(KeyCode::Char('p'), KeyModifiers::NONE);
+        let toggle_tree = KeyCombo::new(KeyCode::Char('t'), KeyModifiers::NONE);
+        let toggle_group = KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE);
+        let cycle_origin = KeyCombo::new(KeyCode::Char('o'), KeyModifiers::NONE);
+        let toggle_auto_split = KeyCombo::new(KeyCode::Char('a'), KeyModifiers::NONE);
+        let toggle_command_preview = KeyCombo::new(KeyCode::Char('e'), KeyModifiers::NONE);
+        let reveal_value = KeyCombo::new(KeyCode::Char('v'), KeyModifiers::NONE);
+        let reload_env = KeyCombo::new(KeyCode::F(5), KeyModifiers::NONE);
         Self {
             next_var,
             previous_var,
@@ -309,54 +1411,551 @@ impl Default for VarsKeyBindings {
             focus_var_detail,
             raw_detail,
             split_detail,
+            open_in_pager,
+            toggle_tree,
+            toggle_group,
+            cycle_origin,
+            toggle_auto_split,
+            toggle_command_preview,
+            reveal_value,
+            reload_env,
+        }
+    }
+}
+
+impl VarsKeyBindings {
+    /// Returns the footer bindings for the Vars screen in `state`. Keys that
+    /// only move around are shown together, and keys that do nothing in
+    /// `state` are left out.
+    pub fn footer(&self, state: VarsFooterState) -> Vec<(String, &'static str)> {
+        let arrows = [
+            self.previous_var,
+            self.next_var,
+            self.focus_var_list,
+            self.focus_var_detail,
+        ];
+        let mut footer = match (state.detail_focused, state.detail_view) {
+            (true, DetailView::Split) => vec![
+                (compact_keys(&arrows[..2]), "Entries"),
+                (self.focus_var_list.display_key_combo(), "Back"),
+                (self.raw_detail.display_key_combo(), "Raw"),
+            ],
+            (_, DetailView::Split) => vec![
+                (compact_keys(&arrows), "Nav"),
+                (self.raw_detail.display_key_combo(), "Raw"),
+            ],
+            (_, DetailView::Raw) => vec![
+                (compact_keys(&arrows), "Nav"),
+                (self.split_detail.display_key_combo(), "Split"),
+            ],
+        };
+        if state.tree && !state.detail_focused {
+            footer.push((self.toggle_group.display_key_combo(), "Expand"));
+        }
+        footer.extend([
+            (
+                self.toggle_tree.display_key_combo(),
+                if state.tree { "List" } else { "Tree" },
+            ),
+            (self.cycle_origin.display_key_combo(), "Origin"),
+            (self.toggle_auto_split.display_key_combo(), "Auto-split"),
+            (self.toggle_command_preview.display_key_combo(), "Command"),
+        ]);
+        if state.command_preview {
+            footer.push((self.reveal_value.display_key_combo(), "Reveal"));
         }
+        footer.push((self.open_in_pager.display_key_combo(), "Pager"));
+        footer.push((self.reload_env.display_key_combo(), "Reload"));
+        footer
     }
 }
 
 impl DisplayKeyBindings for VarsKeyBindings {
     fn displayable(&self) -> Vec<(String, &'static str)> {
-        vec![("↑↓←→".to_string(), "Nav")]
+        self.footer(VarsFooterState::default())
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct TraceKeyBindings {
-    pub next_frame: KeyEvent,
-    pub previous_frame: KeyEvent,
+    pub next_frame: KeyCombo,
+    pub previous_frame: KeyCombo,
+    pub set_breakpoint: KeyCombo,
+    pub show_vars: KeyCombo,
+    pub open_in_pager: KeyCombo,
 }
 
 impl DisplayKeyBindings for TraceKeyBindings {
     fn displayable(&self) -> Vec<(String, &'static str)> {
-        vec![("↑↓".to_string(), "Nav")]
+        vec![
+            (compact_keys(&[self.previous_frame, self.next_frame]), "Nav"),
+            (self.set_breakpoint.display_key_combo(), "Break"),
+            (self.show_vars.display_key_combo(), "Vars"),
+            (self.open_in_pager.display_key_combo(), "Pager"),
+        ]
+    }
+
+    fn read_only_displayable(&self) -> Vec<(String, &'static str)> {
+        vec![
+            (compact_keys(&[self.previous_frame, self.next_frame]), "Nav"),
+            (self.show_vars.display_key_combo(), "Vars"),
+            (self.open_in_pager.display_key_combo(), "Pager"),
+        ]
     }
 }
 
 impl Default for TraceKeyBindings {
     fn default() -> Self {
-        let next_frame = KeyEvent {
-            code: KeyCode::Down,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
-        let previous_frame = KeyEvent {
-            code: KeyCode::Up,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        };
+        let next_frame = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+        let previous_frame = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+        let set_breakpoint = KeyCombo::new(KeyCode::Char('b'), KeyModifiers::NONE);
+        let show_vars = KeyCombo::new(KeyCode::Char('v'), KeyModifiers::NONE);
+        let open_in_pager = KeyCombo::new(KeyCode::Char('o'), KeyModifiers::NONE);
         Self {
             next_frame,
             previous_frame,
+            set_breakpoint,
+            show_vars,
+            open_in_pager,
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct OutputKeyBindings {}
+#[derive(Debug, Clone)]
+pub struct OutputKeyBindings {
+    pub next_command: KeyCombo,
+    pub previous_command: KeyCombo,
+    pub delete_command: KeyCombo,
+    pub edit_command: KeyCombo,
+    pub clear_commands: KeyCombo,
+    pub undo_command: KeyCombo,
+    pub open_in_pager: KeyCombo,
+    pub toggle_wrap: KeyCombo,
+    pub scroll_left: KeyCombo,
+    pub scroll_right: KeyCombo,
+}
+
+impl Default for OutputKeyBindings {
+    fn default() -> Self {
+        let next_command = KeyCombo::new(KeyCode::Down, KeyModifiers::NONE);
+        let previous_command = KeyCombo::new(KeyCode::Up, KeyModifiers::NONE);
+        let delete_command = KeyCombo::new(KeyCode::Char('d'), KeyModifiers::NONE);
+        let edit_command = KeyCombo::new(KeyCode::Char('e'), KeyModifiers::NONE);
+        let clear_commands = KeyCombo::new(KeyCode::Char('c'), KeyModifiers::NONE);
+        let undo_command = KeyCombo::new(KeyCode::Char('u'), KeyModifiers::NONE);
+        let open_in_pager = KeyCombo::new(KeyCode::Char('p'), KeyModifiers::NONE);
+        let toggle_wrap = KeyCombo::new(KeyCode::Char('w'), KeyModifiers::NONE);
+        let scroll_left = KeyCombo::new(KeyCode::Left, KeyModifiers::NONE);
+        let scroll_right = KeyCombo::new(KeyCode::Right, KeyModifiers::NONE);
+        Self {
+            next_command,
+            previous_command,
+            delete_command,
+            edit_command,
+            clear_commands,
+            undo_command,
+            open_in_pager,
+            toggle_wrap,
+            scroll_left,
+            scroll_right,
+        }
+    }
+}
+
+impl OutputKeyBindings {
+    /// Returns the keys that scroll the commands sideways, for the footer.
+    fn scroll_keys(&self) -> String {
+        compact_keys(&[self.scroll_left, self.scroll_right])
+    }
+}
 
 impl DisplayKeyBindings for OutputKeyBindings {
     fn displayable(&self) -> Vec<(String, &'static str)> {
-        vec![]
+        vec![
+            ("↑↓".to_string(), "Select"),
+            (self.delete_command.display_key_combo(), "Delete"),
+            (self.edit_command.display_key_combo(), "Edit"),
+            (self.clear_commands.display_key_combo(), "Clear"),
+            (self.undo_command.display_key_combo(), "Undo"),
+            (self.scroll_keys(), "Scroll"),
+            (self.toggle_wrap.display_key_combo(), "Wrap"),
+            (self.open_in_pager.display_key_combo(), "Pager"),
+        ]
+    }
+
+    fn read_only_displayable(&self) -> Vec<(String, &'static str)> {
+        vec![
+            ("↑↓".to_string(), "Select"),
+            (self.scroll_keys(), "Scroll"),
+            (self.toggle_wrap.display_key_combo(), "Wrap"),
+            (self.open_in_pager.display_key_combo(), "Pager"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::KeyEventState;
+
+    use super::*;
+    use crate::app::{output::OutputEvent, trace::TraceEvent, vars::VarsEvent};
+
+    fn key_event(code: KeyCode, modifiers: KeyModifiers, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers,
+            kind,
+            state: KeyEventState::NONE,
+        }
+    }
+
+    #[test]
+    fn terminal_variants_resolve_to_same_action() {
+        let keymap = KeyBindings::default().current_keymap(&Screen::Vars, None, InputMode::Normal);
+        let variants = [
+            // Legacy terminals
+            key_event(KeyCode::Down, KeyModifiers::NONE, KeyEventKind::Press),
+            // Kitty keyboard protocol while the key is held
+            key_event(KeyCode::Down, KeyModifiers::NONE, KeyEventKind::Repeat),
+            // Keypad arrow keys under the kitty keyboard protocol
+            KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::KEYPAD,
+            },
+            // Lock keys are reported as modifiers by some terminals
+            KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK,
+            },
+        ];
+        for variant in variants {
+            assert_eq!(
+                resolve_key(&keymap, &variant),
+                Some(Event::Nav(NavEvent::Down)),
+                "{variant:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn spurious_shift_on_chars_is_ignored() {
+        let keymap = KeyBindings::default().current_keymap(&Screen::Vars, None, InputMode::Normal);
+        let variants = [
+            key_event(KeyCode::Char('s'), KeyModifiers::NONE, KeyEventKind::Press),
+            key_event(KeyCode::Char('s'), KeyModifiers::SHIFT, KeyEventKind::Press),
+        ];
+        for variant in variants {
+            assert_eq!(
+                resolve_key(&keymap, &variant),
+                Some(Event::Vars(VarsEvent::SplitDetail)),
+                "{variant:?}"
+            );
+        }
+        assert_eq!(
+            KeyCombo::new(KeyCode::Char('G'), KeyModifiers::SHIFT),
+            KeyCombo::new(KeyCode::Char('G'), KeyModifiers::NONE),
+        );
+        assert_eq!(
+            KeyCombo::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+            KeyCombo::new(KeyCode::BackTab, KeyModifiers::NONE),
+        );
+    }
+
+    #[test]
+    fn meaningful_modifiers_are_preserved() {
+        let keymap = KeyBindings::default().current_keymap(&Screen::Vars, None, InputMode::Normal);
+        let ctrl_down = key_event(KeyCode::Down, KeyModifiers::CONTROL, KeyEventKind::Press);
+        assert_eq!(resolve_key(&keymap, &ctrl_down), None);
+        assert_ne!(
+            KeyCombo::new(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            KeyCombo::new(KeyCode::Char('q'), KeyModifiers::NONE),
+        );
+    }
+
+    #[test]
+    fn releases_never_resolve() {
+        let keymap = KeyBindings::default().current_keymap(&Screen::Vars, None, InputMode::Normal);
+        let release = key_event(KeyCode::Down, KeyModifiers::NONE, KeyEventKind::Release);
+        assert_eq!(resolve_key(&keymap, &release), None);
+    }
+
+    fn keymap_for(screen: Screen, input_mode: InputMode) -> HashMap<KeyCombo, Event> {
+        KeyBindings::default().current_keymap(&screen, None, input_mode)
+    }
+
+    fn plain(code: KeyCode) -> KeyCombo {
+        KeyCombo::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn home_keymap_has_quick_actions() {
+        let keymap = keymap_for(Screen::Home, InputMode::Normal);
+        assert_eq!(keymap.len(), 14);
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('v'))),
+            Some(&Event::App(AppEvent::GoTo(Screen::Vars)))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('o'))),
+            Some(&Event::App(AppEvent::GoTo(Screen::Output)))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::BackTab)),
+            Some(&Event::App(AppEvent::PrevTab))
+        );
+    }
+
+    #[test]
+    fn prompt_keymap_depends_on_input_focus() {
+        let keymap = keymap_for(Screen::Prompt, InputMode::Normal);
+        assert_eq!(keymap.len(), 9);
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('i'))),
+            Some(&Event::Prompt(PromptEvent::FocusInput))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Up)),
+            Some(&Event::Nav(NavEvent::Up))
+        );
+
+        // Typing must not trigger global bindings such as exit.
+        let keymap = keymap_for(Screen::Prompt, InputMode::TextEntry);
+        assert_eq!(keymap.len(), 6);
+        assert_eq!(keymap.get(&plain(KeyCode::Char('q'))), None);
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Up)),
+            Some(&Event::Prompt(PromptEvent::HistoryPrevious))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Esc)),
+            Some(&Event::Prompt(PromptEvent::UnfocusInput))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Tab)),
+            Some(&Event::Prompt(PromptEvent::Complete))
+        );
+    }
+
+    #[test]
+    fn output_keymap_has_command_actions() {
+        let keymap = keymap_for(Screen::Output, InputMode::Normal);
+        assert_eq!(keymap.len(), 16);
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('w'))),
+            Some(&Event::Output(OutputEvent::ToggleWrap))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('u'))),
+            Some(&Event::Output(OutputEvent::Undo))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('p'))),
+            Some(&Event::Output(OutputEvent::OpenInPager))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('d'))),
+            Some(&Event::Output(OutputEvent::DeleteSelected))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('e'))),
+            Some(&Event::Output(OutputEvent::EditSelected))
+        );
+        assert_eq!(
+            keymap.get(&plain(KeyCode::Char('c'))),
+            Some(&Event::Output(OutputEvent::Clear))
+        );
+    }
+
+    #[test]
+    fn unbound_chars_become_text_while_typing() {
+        let key = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(
+            text_entry_event(&key),
+            Some(Event::Prompt(PromptEvent::Insert('q')))
+        );
+        let key = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(text_entry_event(&key), None);
+    }
+
+    #[test]
+    fn key_combos_round_trip_through_config_form() {
+        for text in ["q", "G", "Ctrl+r", "Alt+Shift+Up", "Space", "F5", "Esc"] {
+            let combo = text.parse::<KeyCombo>().unwrap();
+            assert_eq!(combo.to_string().parse::<KeyCombo>().unwrap(), combo);
+        }
+        assert_eq!(
+            "ctrl+R".parse::<KeyCombo>().unwrap(),
+            KeyCombo::new(KeyCode::Char('R'), KeyModifiers::CONTROL)
+        );
+        assert!("Hyper+q".parse::<KeyCombo>().is_err());
+        assert!("".parse::<KeyCombo>().is_err());
+    }
+
+    #[test]
+    fn rebinding_updates_keymap() {
+        let mut bindings = KeyBindings::default();
+        let key = KeyCombo::new(KeyCode::Char('n'), KeyModifiers::NONE);
+        bindings.rebind("global", "next_tab", key).unwrap();
+        let keymap = bindings.current_keymap(&Screen::Vars, None, InputMode::Normal);
+        assert_eq!(keymap.get(&key), Some(&Event::App(AppEvent::NextTab)));
+        assert_eq!(keymap.get(&plain(KeyCode::Tab)), None);
+    }
+
+    #[test]
+    fn conflicting_rebinds_are_rejected() {
+        let mut bindings = KeyBindings::default();
+        // Tab is already bound to the next tab on every screen.
+        assert!(
+            bindings
+                .rebind("output", "clear", plain(KeyCode::Tab))
+                .is_err()
+        );
+        // `g` starts the `gg` and `gt` sequences.
+        assert!(
+            bindings
+                .rebind("global", "exit", plain(KeyCode::Char('g')))
+                .is_err()
+        );
+        // Ctrl+C always interrupts.
+        assert!(bindings.rebind("global", "exit", interrupt_key()).is_err());
+        assert!(
+            bindings
+                .rebind("global", "missing", plain(KeyCode::F(2)))
+                .is_err()
+        );
+        assert!(bindings.conflicts().is_empty());
+    }
+
+    #[test]
+    fn bindings_on_different_screens_may_share_keys() {
+        let mut bindings = KeyBindings::default();
+        // `d` deletes on the Output screen but is free on Home.
+        bindings
+            .rebind("home", "go_to_vars", plain(KeyCode::Char('d')))
+            .unwrap();
+        assert!(bindings.conflicts().is_empty());
+    }
+
+    #[test]
+    fn keymaps_are_reused_until_their_context_changes() {
+        let mut bindings = KeyBindings::default();
+        let mut cache = BindingsCache::default();
+        let vars = cache.keymap(&bindings, &Screen::Vars, None, InputMode::Normal);
+        let again = cache.keymap(&bindings, &Screen::Vars, None, InputMode::Normal);
+        assert!(Rc::ptr_eq(&vars, &again));
+
+        let modal = Modal::exit();
+        let in_modal = cache.keymap(&bindings, &Screen::Vars, Some(&modal), InputMode::Normal);
+        assert!(!Rc::ptr_eq(&vars, &in_modal));
+        let trace = cache.keymap(&bindings, &Screen::Trace, None, InputMode::Normal);
+        assert_eq!(
+            *trace,
+            bindings.current_keymap(&Screen::Trace, None, InputMode::Normal)
+        );
+
+        // Rebinding clears the cache, so the new key is picked up.
+        bindings
+            .rebind("trace", "set_breakpoint", plain(KeyCode::Char('x')))
+            .unwrap();
+        cache.clear();
+        let rebound = cache.keymap(&bindings, &Screen::Trace, None, InputMode::Normal);
+        assert_eq!(
+            rebound.get(&plain(KeyCode::Char('x'))),
+            Some(&Event::Trace(TraceEvent::SetBreakpoint))
+        );
+    }
+
+    #[test]
+    fn footers_are_built_once_per_screen() {
+        let bindings = KeyBindings::default();
+        let mut cache = BindingsCache::default();
+        let writable = FooterContext::default();
+        let output = cache.footer(&bindings, &Screen::Output, writable);
+        assert!(Rc::ptr_eq(
+            &output,
+            &cache.footer(&bindings, &Screen::Output, writable)
+        ));
+        assert!(output.iter().any(|(_, desc)| *desc == "Delete"));
+        let read_only = FooterContext {
+            read_only: true,
+            vars: None,
+        };
+        let read_only = cache.footer(&bindings, &Screen::Output, read_only);
+        assert!(!read_only.iter().any(|(_, desc)| *desc == "Delete"));
+    }
+
+    fn footer_keys(footer: &[(String, &'static str)]) -> Vec<String> {
+        footer
+            .iter()
+            .map(|(keys, desc)| format!("{keys}: {desc}"))
+            .collect()
+    }
+
+    #[test]
+    fn vars_footer_follows_what_the_screen_shows() {
+        let vars = VarsKeyBindings::default();
+        let list = VarsFooterState::default();
+        assert_eq!(
+            footer_keys(&vars.footer(list)),
+            [
+                "↑↓←→: Nav",
+                "S: Split",
+                "T: Tree",
+                "O: Origin",
+                "A: Auto-split",
+                "E: Command",
+                "P: Pager",
+                "F5: Reload"
+            ]
+        );
+        let split_detail = VarsFooterState {
+            detail_focused: true,
+            detail_view: DetailView::Split,
+            ..list
+        };
+        assert_eq!(
+            footer_keys(&vars.footer(split_detail))[..3],
+            ["↑↓: Entries", "←: Back", "R: Raw"]
+        );
+        // Splitting isn't offered while the value is already split.
+        let split_list = VarsFooterState {
+            detail_view: DetailView::Split,
+            ..list
+        };
+        assert_eq!(footer_keys(&vars.footer(split_list))[1], "R: Raw");
+        let tree = VarsFooterState {
+            tree: true,
+            command_preview: true,
+            ..list
+        };
+        let tree = footer_keys(&vars.footer(tree));
+        assert_eq!(tree[2..4], ["Enter: Expand", "T: List"]);
+        assert!(tree.contains(&"V: Reveal".to_string()));
+    }
+
+    #[test]
+    fn footers_show_the_configured_keys() {
+        let mut bindings = KeyBindings::default();
+        bindings
+            .rebind("vars", "next_var", plain(KeyCode::Char('j')))
+            .unwrap();
+        bindings
+            .rebind("vars", "split_detail", plain(KeyCode::Char('x')))
+            .unwrap();
+        bindings
+            .rebind("trace", "previous_frame", plain(KeyCode::Char('k')))
+            .unwrap();
+        let vars = footer_keys(&bindings.vars().footer(VarsFooterState::default()));
+        assert_eq!(vars[..2], ["↑/J/←/→: Nav", "X: Split"]);
+        let trace = footer_keys(&bindings.trace().displayable());
+        assert_eq!(trace[0], "K/↓: Nav");
+        assert_eq!(
+            footer_keys(&bindings.trace().read_only_displayable()),
+            ["K/↓: Nav", "V: Vars", "O: Pager"]
+        );
     }
 }
@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::app::{Event, key_bindings::KeyCombo};
+
+/// How long the debugger waits for the second key of a sequence before
+/// forgetting the prefix.
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Tracks a partially entered two-key sequence such as `g g`.
+#[derive(Debug, Clone, Default)]
+pub struct KeySequenceState {
+    pending: Option<PendingPrefix>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingPrefix {
+    key: KeyCombo,
+    started: Instant,
+}
+
+impl KeySequenceState {
+    /// Returns the prefix key waiting for its second key, if any.
+    pub fn pending(&self) -> Option<KeyCombo> {
+        self.pending.map(|prefix| prefix.key)
+    }
+
+    /// Returns how long until the pending prefix expires, if there is one.
+    pub fn time_remaining(&self, now: Instant) -> Option<Duration> {
+        self.pending
+            .map(|prefix| SEQUENCE_TIMEOUT.saturating_sub(now.duration_since(prefix.started)))
+    }
+
+    /// Forgets the pending prefix if it has been waiting for too long,
+    /// returning whether it was forgotten.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.pending {
+            Some(prefix) if now.duration_since(prefix.started) >= SEQUENCE_TIMEOUT => {
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets the pending prefix.
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+
+    /// Resolves a key press against the sequence table, falling back to the
+    /// single-key bindings in `keymap`.
+    ///
+    /// A key that starts a sequence is held as the pending prefix and
+    /// resolves to nothing. The key after it either completes a sequence or,
+    /// if there is no such sequence, is resolved as a normal key press.
+    pub fn resolve(
+        &mut self,
+        combo: KeyCombo,
+        sequences: &HashMap<[KeyCombo; 2], Event>,
+        keymap: &HashMap<KeyCombo, Event>,
+        now: Instant,
+    ) -> Option<Event> {
+        self.expire(now);
+        if let Some(prefix) = self.pending.take()
+            && let Some(event) = sequences.get(&[prefix.key, combo])
+        {
+            return Some(event.clone());
+        }
+        if sequences.keys().any(|[first, _]| *first == combo) {
+            self.pending = Some(PendingPrefix {
+                key: combo,
+                started: now,
+            });
+            return None;
+        }
+        keymap.get(&combo).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+    use crate::app::{AppEvent, NavEvent};
+
+    fn key(c: char) -> KeyCombo {
+        KeyCombo::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn tables() -> (HashMap<[KeyCombo; 2], Event>, HashMap<KeyCombo, Event>) {
+        let sequences = HashMap::from([
+            ([key('g'), key('g')], Event::Nav(NavEvent::Top)),
+            ([key('g'), key('t')], Event::App(AppEvent::NextTab)),
+        ]);
+        let keymap = HashMap::from([
+            (key('G'), Event::Nav(NavEvent::Bottom)),
+            (key('q'), Event::App(AppEvent::ExitRequested)),
+        ]);
+        (sequences, keymap)
+    }
+
+    #[test]
+    fn completes_sequence_within_timeout() {
+        let (sequences, keymap) = tables();
+        let mut state = KeySequenceState::default();
+        let start = Instant::now();
+        assert_eq!(state.resolve(key('g'), &sequences, &keymap, start), None);
+        assert_eq!(state.pending(), Some(key('g')));
+        let event = state.resolve(
+            key('g'),
+            &sequences,
+            &keymap,
+            start + Duration::from_millis(100),
+        );
+        assert_eq!(event, Some(Event::Nav(NavEvent::Top)));
+        assert_eq!(state.pending(), None);
+    }
+
+    #[test]
+    fn prefix_times_out() {
+        let (sequences, keymap) = tables();
+        let mut state = KeySequenceState::default();
+        let start = Instant::now();
+        state.resolve(key('g'), &sequences, &keymap, start);
+        assert!(!state.expire(start + SEQUENCE_TIMEOUT / 2));
+        assert!(state.expire(start + SEQUENCE_TIMEOUT));
+        assert_eq!(state.pending(), None);
+
+        // A second key arriving after the timeout is resolved on its own.
+        state.resolve(key('g'), &sequences, &keymap, start);
+        let event = state.resolve(key('t'), &sequences, &keymap, start + SEQUENCE_TIMEOUT * 2);
+        assert_eq!(event, None);
+        assert_eq!(state.pending(), None);
+    }
+
+    #[test]
+    fn invalid_second_key_falls_back_to_normal_binding() {
+        let (sequences, keymap) = tables();
+        let mut state = KeySequenceState::default();
+        let start = Instant::now();
+        state.resolve(key('g'), &sequences, &keymap, start);
+        let event = state.resolve(key('q'), &sequences, &keymap, start);
+        assert_eq!(event, Some(Event::App(AppEvent::ExitRequested)));
+        assert_eq!(state.pending(), None);
+
+        state.resolve(key('g'), &sequences, &keymap, start);
+        assert_eq!(state.resolve(key('x'), &sequences, &keymap, start), None);
+        assert_eq!(state.pending(), None);
+    }
+}
@@ -0,0 +1,561 @@
+//! Keys that queue a canned list of commands at once, defined in the
+//! `[macros]` table of the config file, e.g.
+//!
+//! ```toml
+//! [macros.debug-hook]
+//! key = "Alt+d"
+//! actions = ["set DEBUG=1", "prepend PATH=/src/project/bin", "break post-hook"]
+//! ```
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use anyhow::{Context, Error, anyhow, bail};
+
+use crate::app::{
+    App, Shell,
+    commands::{PATH_SEPARATOR, TracepointDisposition, check_tracepoint_pattern, check_var_name},
+    key_bindings::KeyCombo,
+    messages::Severity,
+    modal::{Modal, PendingAction},
+    protected::VarMutation,
+};
+
+/// One step of a macro, written in the config file much like the prompt
+/// command that does the same.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MacroAction {
+    /// `set NAME=VALUE`
+    Set { name: String, value: String },
+    /// `prepend NAME=ENTRY`, which puts `ENTRY` at the front of a list like
+    /// `PATH`, moving it there if it's already in the list.
+    Prepend { name: String, entry: String },
+    /// `unset NAME`
+    Unset { name: String },
+    /// `raw COMMAND`, queued verbatim.
+    Raw(String),
+    /// `break TRACEPOINT`, which also takes a glob, `next` or `all`.
+    Break(String),
+    /// `continue`
+    Continue,
+    /// `step`
+    Step,
+}
+
+impl FromStr for MacroAction {
+    type Err = Error;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        // Only spaces are trimmed, so that a value can end in a newline.
+        let action = action.trim_matches(' ');
+        let (verb, arg) = action.split_once(' ').unwrap_or((action, ""));
+        let arg = arg.trim_matches(' ');
+        let assignment = || {
+            let (name, value) = arg
+                .split_once('=')
+                .ok_or_else(|| anyhow!("'{verb}' takes NAME=VALUE, found {arg:?}"))?;
+            check_var_name(name)?;
+            Ok::<_, Error>((name.to_string(), value.to_string()))
+        };
+        let parsed = match verb {
+            "set" => {
+                let (name, value) = assignment()?;
+                MacroAction::Set { name, value }
+            }
+            "prepend" => {
+                let (name, entry) = assignment()?;
+                if entry.is_empty() || entry.contains(PATH_SEPARATOR) {
+                    bail!("'prepend' takes a single entry, found {entry:?}");
+                }
+                MacroAction::Prepend { name, entry }
+            }
+            "unset" => {
+                check_var_name(arg)?;
+                MacroAction::Unset {
+                    name: arg.to_string(),
+                }
+            }
+            "raw" if arg.is_empty() => bail!("'raw' takes a command"),
+            // Each queued command is one line of the output.
+            "raw" if arg.contains(char::is_control) => {
+                bail!("'raw' takes a single line without control characters, found {arg:?}")
+            }
+            "raw" => MacroAction::Raw(arg.to_string()),
+            "break" => {
+                check_tracepoint_pattern(arg)?;
+                MacroAction::Break(arg.to_string())
+            }
+            "continue" | "step" if !arg.is_empty() => bail!("'{verb}' takes no arguments"),
+            "continue" => MacroAction::Continue,
+            "step" => MacroAction::Step,
+            _ => bail!("unknown action {action:?}"),
+        };
+        Ok(parsed)
+    }
+}
+
+/// A key and the actions it runs, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Macro {
+    pub name: String,
+    pub key: KeyCombo,
+    pub actions: Vec<MacroAction>,
+}
+
+/// Parses the `[macros]` table of the config file, in which each macro is
+/// a table with a `key` and a list of `actions`. Whether the keys conflict
+/// with other bindings is left to [`KeyBindings::set_macros`].
+///
+/// [`KeyBindings::set_macros`]: crate::app::key_bindings::KeyBindings::set_macros
+pub fn parse_macros(table: &toml::Table) -> Result<Vec<Macro>, Error> {
+    let mut macros = Vec::new();
+    for (name, definition) in table {
+        let definition = definition
+            .as_table()
+            .ok_or_else(|| anyhow!("macros.{name} must be a table"))?;
+        if let Some(field) = definition
+            .keys()
+            .find(|field| !["key", "actions"].contains(&field.as_str()))
+        {
+            bail!("unknown field 'macros.{name}.{field}'");
+        }
+        let key = definition
+            .get("key")
+            .and_then(|key| key.as_str())
+            .ok_or_else(|| anyhow!("macros.{name}.key must be a string"))?;
+        let key = key
+            .parse::<KeyCombo>()
+            .with_context(|| format!("macros.{name}.key"))?;
+        let actions = definition
+            .get("actions")
+            .and_then(|actions| actions.as_array())
+            .filter(|actions| !actions.is_empty())
+            .ok_or_else(|| anyhow!("macros.{name}.actions must be a list of actions"))?
+            .iter()
+            .enumerate()
+            .map(|(idx, action)| {
+                action
+                    .as_str()
+                    .ok_or_else(|| anyhow!("must be a string"))
+                    .and_then(str::parse)
+                    .with_context(|| format!("macros.{name}.actions[{idx}]"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        macros.push(Macro {
+            name: name.clone(),
+            key,
+            actions,
+        });
+    }
+    Ok(macros)
+}
+
+/// What running a macro comes down to in the current session. It's worked
+/// out before anything is queued so that a macro runs whole or not at all.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MacroRun {
+    pub name: String,
+    pub mutations: Vec<VarMutation>,
+    /// Where the shell pauses after exiting, if the macro changes it.
+    pub disposition: Option<TracepointDisposition>,
+}
+
+impl MacroRun {
+    /// Works out what `macro_def` does to `app`. Each action sees the
+    /// variables as the ones before it left them, on top of the commands
+    /// already queued.
+    pub fn plan(app: &App, macro_def: &Macro) -> Self {
+        let queued = app
+            .var_changes()
+            .into_iter()
+            .map(|change| (change.name, change.new))
+            .collect::<HashMap<_, _>>();
+        // What the macro's own actions have set so far, or `None` for unset.
+        let mut values: HashMap<String, Option<String>> = HashMap::new();
+        let mut mutations = Vec::new();
+        let mut disposition = None;
+        for action in &macro_def.actions {
+            match action {
+                MacroAction::Set { name, value } => {
+                    values.insert(name.clone(), Some(value.clone()));
+                    mutations.push(VarMutation::Set {
+                        name: name.clone(),
+                        value: value.clone(),
+                    });
+                }
+                MacroAction::Prepend { name, entry } => {
+                    let old = values
+                        .get(name)
+                        .or_else(|| queued.get(name))
+                        .cloned()
+                        .unwrap_or_else(|| app.env().value_of(name).map(str::to_string))
+                        .unwrap_or_default();
+                    let entries = std::iter::once(entry.as_str())
+                        .chain(
+                            old.split(PATH_SEPARATOR)
+                                .filter(|old| !old.is_empty() && old != entry),
+                        )
+                        .map(String::from)
+                        .collect::<Vec<_>>();
+                    let joined = entries.join(&PATH_SEPARATOR.to_string());
+                    values.insert(name.clone(), Some(joined));
+                    mutations.push(VarMutation::SetPath {
+                        name: name.clone(),
+                        entries,
+                    });
+                }
+                MacroAction::Unset { name } => {
+                    values.insert(name.clone(), None);
+                    mutations.push(VarMutation::Unset { name: name.clone() });
+                }
+                MacroAction::Raw(line) => mutations.push(VarMutation::Raw(line.clone())),
+                MacroAction::Break(name) => {
+                    disposition = Some(match name.as_str() {
+                        "next" => TracepointDisposition::PauseAtNext,
+                        "all" => TracepointDisposition::PauseAtAll,
+                        name => disposition
+                            .take()
+                            .unwrap_or_else(|| app.tracepoint_disposition().clone())
+                            .and_pause_at(name),
+                    });
+                }
+                MacroAction::Continue => disposition = Some(TracepointDisposition::Continue),
+                MacroAction::Step => disposition = Some(TracepointDisposition::PauseAtNext),
+            }
+        }
+        Self {
+            name: macro_def.name.clone(),
+            mutations,
+            disposition,
+        }
+    }
+
+    /// Returns the variables among `protected` that the macro changes, each
+    /// once.
+    pub fn protected_targets(&self, protected: &BTreeSet<String>) -> Vec<String> {
+        self.mutations
+            .iter()
+            .flat_map(VarMutation::targets)
+            .filter(|name| protected.contains(name))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the commands the macro queues, in order.
+    pub fn commands(&self, shell: Shell) -> Vec<String> {
+        self.mutations
+            .iter()
+            .map(|mutation| mutation.command(shell))
+            .collect()
+    }
+
+    /// Queues everything at once, once it's been confirmed or didn't need to
+    /// be.
+    pub fn apply(&self, app: &mut App) {
+        for mutation in &self.mutations {
+            app.apply_mutation(mutation);
+        }
+        if let Some(disposition) = &self.disposition {
+            app.set_tracepoint_disposition(disposition.clone());
+        }
+        app.notify(Severity::Success, self.summary());
+    }
+
+    /// Sums up what the macro did, e.g. "Macro 'debug-hook' queued 2
+    /// commands, will pause at 'post-hook' after exiting".
+    pub fn summary(&self) -> String {
+        let count = self.mutations.len();
+        let plural = if count == 1 { "" } else { "s" };
+        let mut summary = format!("Macro '{}' queued {count} command{plural}", self.name);
+        if let Some(disposition) = &self.disposition {
+            summary.push_str(", ");
+            summary.push_str(&disposition.describe());
+        }
+        summary
+    }
+}
+
+/// Runs the macro called `name`. One that changes protected variables asks
+/// once for all of its commands before queueing any of them.
+pub fn run_macro(app: &mut App, name: &str) {
+    let Some(macro_def) = app.key_bindings().macro_named(name).cloned() else {
+        return;
+    };
+    let run = MacroRun::plan(app, &macro_def);
+    if run.protected_targets(app.protected_vars()).is_empty() {
+        run.apply(app);
+    } else {
+        let modal = Modal::confirm(app, PendingAction::Macro(run));
+        app.open_modal(modal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        app::{AppEvent, Event, handle_event, modal::ModalEvent},
+        cli::Cli,
+        terminal::TerminalInfo,
+        test_dir::TestDir,
+    };
+
+    /// Returns an app with the macros in `config`, paused with a few
+    /// variables set.
+    fn app_with_macros(config: &str) -> App {
+        let dir = TestDir::new("macros");
+        let path = dir.join("config.toml");
+        std::fs::write(&path, config).unwrap();
+        let args = Cli::try_parse_from([
+            "flox-debugger",
+            "--shell",
+            "bash",
+            "--config",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+        let env = HashMap::from([
+            ("MANPATH".to_string(), "/usr/share/man:/src/man".to_string()),
+            ("PATH".to_string(), "/usr/bin:/bin".to_string()),
+            ("PAGER".to_string(), "less".to_string()),
+        ]);
+        App::new(&args, &TerminalInfo::default())
+            .unwrap()
+            .with_env(&env)
+    }
+
+    fn run(app: &mut App, name: &str) {
+        handle_event(app, &Event::App(AppEvent::RunMacro(name.to_string())));
+    }
+
+    fn parse(config: &str) -> Result<Vec<Macro>, Error> {
+        let config = config.parse::<toml::Table>().unwrap();
+        parse_macros(config["macros"].as_table().unwrap())
+    }
+
+    #[test]
+    fn actions_are_parsed_like_prompt_commands() {
+        let cases = [
+            (
+                "set DEBUG=1",
+                MacroAction::Set {
+                    name: "DEBUG".into(),
+                    value: "1".into(),
+                },
+            ),
+            (
+                "set  EMPTY=",
+                MacroAction::Set {
+                    name: "EMPTY".into(),
+                    value: "".into(),
+                },
+            ),
+            (
+                "prepend PATH=/src/bin",
+                MacroAction::Prepend {
+                    name: "PATH".into(),
+                    entry: "/src/bin".into(),
+                },
+            ),
+            (
+                "unset DEBUG",
+                MacroAction::Unset {
+                    name: "DEBUG".into(),
+                },
+            ),
+            (
+                "set MSG=two\nlines\n",
+                MacroAction::Set {
+                    name: "MSG".into(),
+                    value: "two\nlines\n".into(),
+                },
+            ),
+            ("raw echo a=b", MacroAction::Raw("echo a=b".into())),
+            ("break post-*", MacroAction::Break("post-*".into())),
+            (" continue ", MacroAction::Continue),
+            ("step", MacroAction::Step),
+        ];
+        for (action, expected) in cases {
+            assert_eq!(action.parse::<MacroAction>().unwrap(), expected, "{action}");
+        }
+        for bad in [
+            "",
+            "set DEBUG",
+            "set =1",
+            "prepend PATH=/a:/b",
+            "unset",
+            "unset A B",
+            "raw ",
+            "raw echo hi\nrm -rf ~",
+            "raw echo hi\n",
+            "raw printf '\x1b[2J'",
+            "set a;b=1",
+            "break",
+            "step now",
+            "export A=1",
+        ] {
+            assert!(bad.parse::<MacroAction>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn macros_are_parsed_from_the_config() {
+        let macros = parse(
+            r#"
+            [macros.debug]
+            key = "Alt+d"
+            actions = ["set DEBUG=1", "break post-hook"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            macros,
+            [Macro {
+                name: "debug".into(),
+                key: "Alt+d".parse().unwrap(),
+                actions: vec![
+                    MacroAction::Set {
+                        name: "DEBUG".into(),
+                        value: "1".into(),
+                    },
+                    MacroAction::Break("post-hook".into()),
+                ],
+            }]
+        );
+
+        let err = |config| format!("{:#}", parse(config).unwrap_err());
+        assert_eq!(
+            err("[macros.m]\nkey = \"Alt+d\"\nactions = [\"set DEBUG=1\", \"jump\"]"),
+            "macros.m.actions[1]: unknown action \"jump\""
+        );
+        assert_eq!(
+            err("[macros.m]\nkey = \"Alt+d\"\nactions = []"),
+            "macros.m.actions must be a list of actions"
+        );
+        assert!(err("[macros.m]\nactions = [\"step\"]").contains("macros.m.key"));
+        assert!(err("[macros.m]\nkey = \"Hyper+d\"\nactions = [\"step\"]").contains("Hyper"));
+        assert!(
+            err("[macros.m]\nkey = \"Alt+d\"\nactions = [\"step\"]\nkeys = 1")
+                .contains("macros.m.keys")
+        );
+    }
+
+    #[test]
+    fn a_macro_mixes_variable_ops_and_raw_commands() {
+        let mut app = app_with_macros(
+            r#"
+            [macros.man]
+            key = "Alt+m"
+            actions = [
+                "set DEBUG=1",
+                "prepend MANPATH=/src/man",
+                "unset PAGER",
+                "raw echo 'debugging'",
+                "break post-hook",
+            ]
+            "#,
+        );
+        let before = app.output_lines().len();
+        run(&mut app, "man");
+        assert!(app.modal().is_none());
+        assert_eq!(
+            app.output_lines()[before..],
+            [
+                "export DEBUG='1'",
+                "export MANPATH='/src/man:/usr/share/man'",
+                "unset PAGER",
+                "echo 'debugging'",
+                "export FLOX_DBG_TRACEPOINT='post-hook'",
+            ]
+        );
+        assert_eq!(
+            app.tracepoint_disposition(),
+            &TracepointDisposition::PauseAt(vec!["post-hook".to_string()])
+        );
+        let toast = app.toasts().visible().last().unwrap();
+        assert_eq!(
+            toast.text,
+            "Macro 'man' queued 4 commands, will pause at 'post-hook' after exiting"
+        );
+    }
+
+    #[test]
+    fn values_with_newlines_are_queued_on_one_line() {
+        let mut app = app_with_macros(
+            r#"
+            [macros.msg]
+            key = "Alt+m"
+            actions = ["set MSG=two\nlines\n"]
+            "#,
+        );
+        let before = app.output_lines().len();
+        run(&mut app, "msg");
+        assert_eq!(
+            app.output_lines()[before..],
+            [r"export MSG=$'two\nlines\n'"]
+        );
+    }
+
+    #[test]
+    fn prepending_builds_on_queued_values() {
+        let mut app = app_with_macros(
+            r#"
+            [macros.bins]
+            key = "Alt+b"
+            actions = ["prepend MANPATH=/a", "prepend MANPATH=/b", "prepend MANPATH=/a"]
+            "#,
+        );
+        app.queue_set_var("MANPATH", "/queued").unwrap();
+        run(&mut app, "bins");
+        let lines = app.output_lines();
+        assert_eq!(
+            lines[lines.len() - 3..],
+            [
+                "export MANPATH='/a:/queued'",
+                "export MANPATH='/b:/a:/queued'",
+                "export MANPATH='/a:/b:/queued'",
+            ]
+        );
+    }
+
+    #[test]
+    fn macros_touching_protected_vars_are_confirmed_as_a_whole() {
+        let mut app = app_with_macros(
+            r#"
+            [macros.bin]
+            key = "Alt+b"
+            actions = ["set DEBUG=1", "prepend PATH=/src/bin", "continue"]
+            "#,
+        );
+        let output = app.output();
+        run(&mut app, "bin");
+        let message = app.modal().unwrap().message().to_string();
+        assert!(
+            message.starts_with("The macro 'bin' changes PATH, which is protected"),
+            "{message}"
+        );
+        assert!(message.contains("export PATH='/src/bin:/usr/bin:/bin'"));
+        assert_eq!(app.output(), output);
+
+        // Cancelling queues none of it.
+        handle_event(&mut app, &Event::Modal(ModalEvent::Cancel));
+        assert_eq!(app.output(), output);
+
+        run(&mut app, "bin");
+        handle_event(&mut app, &Event::Modal(ModalEvent::Accept));
+        assert!(app.output_lines().contains(&"export DEBUG='1'"));
+        assert!(
+            app.output_lines()
+                .contains(&"export PATH='/src/bin:/usr/bin:/bin'")
+        );
+        assert_eq!(
+            app.tracepoint_disposition(),
+            &TracepointDisposition::Continue
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+
+/// How serious a message is, which decides how it's styled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Success,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Severity::Success => "Success",
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Something that went wrong, or that the user should know about, without
+/// ending the session.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Message {
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// The messages waiting to be read, shown one at a time in a banner until
+/// each is dismissed.
+///
+/// Unlike a status message these don't expire, since a problem that's only
+/// shown for a few seconds is easily missed.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct MessageQueue {
+    messages: VecDeque<Message>,
+}
+
+impl MessageQueue {
+    /// Adds a message behind any that are already waiting. A message that's
+    /// already waiting isn't added again, so a repeated failure doesn't bury
+    /// everything else.
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        let message = Message {
+            severity,
+            text: text.into(),
+        };
+        if !self.messages.contains(&message) {
+            self.messages.push_back(message);
+        }
+    }
+
+    /// Returns the message currently shown, if any.
+    pub fn current(&self) -> Option<&Message> {
+        self.messages.front()
+    }
+
+    /// Removes the message currently shown, returning whether there was one.
+    pub fn dismiss(&mut self) -> bool {
+        self.messages.pop_front().is_some()
+    }
+
+    /// Returns how many messages are waiting, including the one shown.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_shown_in_order_until_dismissed() {
+        let mut queue = MessageQueue::default();
+        assert!(!queue.dismiss());
+        queue.push(Severity::Warning, "first");
+        queue.push(Severity::Error, "second");
+        queue.push(Severity::Warning, "first");
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.current().unwrap().text, "first");
+        assert!(queue.dismiss());
+        assert_eq!(queue.current().unwrap().severity, Severity::Error);
+        assert!(queue.dismiss());
+        assert!(queue.is_empty());
+    }
+}
@@ -1,100 +1,576 @@
+pub mod aliases;
+pub mod changes;
+pub mod commands;
+pub mod control;
+pub mod countdown;
+pub mod demo;
+pub mod fd;
+pub mod glob;
+pub mod headless;
+pub mod help;
+pub mod home;
+pub mod interrupt;
+pub mod journal;
 pub mod key_bindings;
+pub mod key_sequence;
+pub mod macros;
+pub mod messages;
+pub mod modal;
+pub mod origins;
+pub mod output;
+pub mod prompt;
+pub mod protected;
+pub mod screens;
+pub mod secrets;
+pub mod session;
+pub mod summary;
 pub mod theme;
+pub mod toasts;
 pub mod trace;
+pub mod transcript;
+pub mod undo;
+pub mod var_refs;
+pub mod var_tree;
 pub mod vars;
+pub mod watch;
 
-use std::{collections::HashMap, io::Write, str::FromStr};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{ErrorKind, Write},
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    sync::mpsc::RecvTimeoutError,
+    time::{Duration, Instant, SystemTime},
+};
 
-use anyhow::{Context, Error, anyhow};
+use anyhow::{Context, Error, anyhow, bail};
+use clap::ValueEnum;
 use ratatui::{
     Terminal,
-    crossterm::event::{self, Event as TermEvent},
+    crossterm::event::{Event as TermEvent, KeyEvent, KeyEventKind},
     prelude::*,
 };
 
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
 use crate::{
     app::{
-        key_bindings::KeyBindings,
-        theme::Theme,
-        trace::{TraceState, handle_trace_event, load_call_stack},
-        vars::{Env, VarsEvent, handle_vars_event},
+        aliases::PromptAliases,
+        changes::{ChangeLog, EnvReload, VarChange},
+        commands::{
+            Cmd, InvalidAliasName, InvalidFunction, InvalidVarName, PATH_SEPARATOR,
+            REVERT_FUNCTION_NAME, TracepointDisposition, check_alias_name, check_function,
+            check_var_name, defines_function, entries_with_separator, resolve_dir, revert_body,
+            set_path_var_cmd, tracepoint_cmd,
+        },
+        control::{
+            ControlRequest, ControlResponse, ControlSocket, handle_control_request,
+            service_control_requests,
+        },
+        countdown::Countdown,
+        demo::{comment_out, demo_data, demo_dir},
+        help::{Overlay, OverlayEvent, handle_overlay_event},
+        home::{HomeEvent, HomeState},
+        interrupt::{InterruptAction, InterruptState},
+        journal::{
+            Journal, JournalAction, JournalSnapshot, diff_snapshots, submitted_prompt_command,
+        },
+        key_bindings::{
+            BindingsCache, FooterBindings, FooterContext, InputMode, KeyBindings, KeyCombo,
+            VarsFooterState, interrupt_key, resolve_key, suspend_key, text_entry_event,
+        },
+        key_sequence::KeySequenceState,
+        macros::run_macro,
+        messages::{MessageQueue, Severity},
+        modal::{Modal, ModalEvent, PendingAction, handle_modal_event},
+        origins::{VarOrigins, load_var_origins},
+        output::{OutputEvent, OutputState, open_output_fd},
+        prompt::{PromptEvent, PromptState},
+        protected::{MutationOutcome, VarMutation, protected_vars},
+        session::SessionState,
+        theme::{Theme, ThemeChoice, parse_accent_override},
+        toasts::ToastQueue,
+        trace::{
+            StackInput, TraceEvent, TraceState, UnsupportedStackVersion, load_call_stack,
+            normalize_tracepoint_names, read_call_stack_file,
+        },
+        undo::{QueuedCommand, QueuedKind, UndoStack},
+        vars::{Env, EnvSource, VarsEvent, resolve_env},
+        watch::WatchList,
     },
     cli::Cli,
+    config::{Config, default_config_path},
+    terminal::{
+        TerminalInfo,
+        events::{EventSource, is_end_of_input},
+        pager::{page, page_file, pager_command},
+        signals::{take_continued, take_shutdown_signal, take_suspend_request},
+        suspend::{reenter_terminal, suspend},
+    },
     ui::draw_ui,
 };
 
 pub const TRACEPOINT_VAR_NAME: &str = "FLOX_DBG_TRACEPOINT";
 
+/// Returns the value the tracepoint variable had when the shell paused.
+fn tracepoint_var_value() -> String {
+    std::env::var(TRACEPOINT_VAR_NAME).unwrap_or_default()
+}
+
 #[derive(Debug)]
 pub struct App {
     screen: Screen,
     shell: Shell,
     theme: Theme,
     key_bindings: KeyBindings,
+    /// What's worked out from `key_bindings` on every key press and frame.
+    bindings_cache: BindingsCache,
+    key_sequence: KeySequenceState,
     env: Env,
+    /// Where `env` was read from, for reloading it.
+    env_source: EnvSource,
     trace: TraceState,
+    prompt: PromptState,
     output: String,
-    exit_state: ExitState,
+    /// Where the shell pauses next, which the output ends up telling it.
+    tracepoint_disposition: TracepointDisposition,
+    output_state: OutputState,
+    home: HomeState,
+    /// Where the commands are printed on exit instead of stdout, as given by
+    /// `--output-fd`.
+    output_fd: Option<File>,
+    overlay: Option<Overlay>,
+    config_path: Option<PathBuf>,
+    /// When the config file was last modified, used to reload the theme
+    /// when the file changes.
+    config_mtime: Option<SystemTime>,
+    last_config_check: Instant,
+    theme_settings: ThemeSettings,
+    /// The dialog that has the user's attention, if any.
+    modal: Option<Modal>,
+    /// Whether destructive actions wait for the user to confirm them.
+    confirm_destructive: bool,
+    /// Whether variables are saved before they're changed, so that the
+    /// shell can put them back with the revert function.
+    revert_helpers: bool,
+    /// The variables that changes to are confirmed first.
+    protected_vars: BTreeSet<String>,
+    /// Shortcuts for prompt commands from the config file.
+    prompt_aliases: PromptAliases,
+    /// Whether variables that look like lists are split when they're
+    /// selected.
+    auto_split: bool,
+    /// The variables saved for the revert function, in the order they were
+    /// first changed.
+    saved_vars: Vec<String>,
+    /// The variable changes queued in the session, for the transcript.
+    change_log: ChangeLog,
+    /// The variables looked at on the Vars screen, for the transcript.
+    inspected_vars: BTreeSet<String>,
+    /// The variables pinned with the `watch` command.
+    watch_list: WatchList,
+    /// The commands queued verbatim in the session.
+    raw_commands: Vec<String>,
+    /// The commands the user queued, for undoing them and for telling them
+    /// apart from the debugger's own.
+    undo_stack: UndoStack,
+    /// Problems found at startup that didn't stop the debugger from running.
+    warnings: Vec<String>,
+    /// Whether actions that would change the environment or the output
+    /// commands are disabled.
+    read_only: bool,
+    /// Short notes about what just happened, shown for a few seconds.
+    toasts: ToastQueue,
+    /// Problems found while running that stay on screen until dismissed.
+    messages: MessageQueue,
+    /// When the debugger started, and so the shell paused.
+    started: Instant,
+    /// Counts down to exiting unattended when `--timeout` is given.
+    countdown: Countdown,
+    /// Whether the countdown ran out before the user pressed a key.
+    timed_out: bool,
+    /// Whether a second Ctrl+C would quit right away.
+    interrupt: InterruptState,
+    /// Whether the session was cut short by Ctrl+C or a signal, in which
+    /// case none of the changes made are printed.
+    cancelled: bool,
+    /// What happens to the queued commands on exit.
+    exit_disposition: ExitDisposition,
+    /// Whether the app is showing made up data, in which case nothing it
+    /// prints should be run.
+    demo: bool,
+    /// The working directory of the shell being debugged.
+    cwd: PathBuf,
+    /// Where actions are recorded when `--json-log` is given, until writing
+    /// to it fails.
+    journal: Option<Journal>,
+    /// Where the flox CLI sends requests, if `--control-socket` was given.
+    control: Option<ControlSocket>,
+    /// The answer to the control request being handled, waiting to be sent.
+    control_response: Option<ControlResponse>,
+    /// How long the event loop waits for input before ticking.
+    tick_rate: Duration,
+    /// Whether a tick changed what's on screen.
+    redraw_needed: bool,
+    /// What to show in the pager once the event loop gets to it.
+    paged: Option<Paged>,
+}
+
+/// What's shown in the user's pager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Paged {
+    /// Text, which is written to a private temporary file for the pager.
+    Content(String),
+    /// A file, which is opened where it is from `line`.
+    File { path: PathBuf, line: usize },
+}
+
+/// How long the event loop waits for input before ticking, unless
+/// `--tick-rate` says otherwise.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// How often the UI is redrawn when nothing has changed.
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the config file is checked for changes.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The inputs other than the config file that decide which theme is used,
+/// kept so the theme can be rebuilt when the config file changes.
+#[derive(Debug, Clone)]
+struct ThemeSettings {
+    /// The theme chosen on the command line, which wins over the config file.
+    choice: Option<ThemeChoice>,
+    no_color: bool,
+    terminal: TerminalInfo,
+}
+
+/// Builds the theme from the command line, terminal, and config file,
+/// returning any problems that were worked around along the way.
+fn build_theme(settings: &ThemeSettings, config: &Config) -> Result<(Theme, Vec<String>), Error> {
+    let mut warnings = vec![];
+    let choice = settings.choice.or(config.theme_choice).unwrap_or_default();
+    let base_theme = if settings.no_color {
+        Theme::no_color()
+    } else {
+        Theme::for_choice(choice, &settings.terminal)
+    };
+    let mut theme = Theme::from_config(base_theme, &config.theme)?;
+    if !settings.no_color {
+        // A bad accent shouldn't keep the debugger from starting since
+        // the variable may be set for every debugger the user runs.
+        match parse_accent_override(std::env::var("FLOX_DBG_ACCENT").ok().as_deref()) {
+            Ok(Some(accent)) => theme = theme.with_accent(accent),
+            Ok(None) => {}
+            Err(err) => warnings.push(err.to_string()),
+        }
+    }
+    // Colors from the config file or environment are downgraded the same
+    // way as the built-in ones.
+    let theme = theme.with_color_depth(settings.terminal.color_depth);
+    Ok((theme, warnings))
+}
+
+/// Returns when the file at `path` was last modified, if it exists.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
 }
 
 impl App {
-    pub fn new(args: &Cli) -> Result<Self, Error> {
-        let env = Env::new();
-        let call_stack = args
-            .call_stack
-            .as_ref()
-            .map(|cs| load_call_stack(cs, args.shell))
-            .transpose()
-            .context("failed to load call stack")?;
-        let app = Self {
+    pub fn new(args: &Cli, terminal: &TerminalInfo) -> Result<Self, Error> {
+        let started = Instant::now();
+        let shell = args
+            .shell
+            .ok_or_else(|| anyhow!("the --shell option is required"))?;
+        let config_path = args.config.clone().or_else(default_config_path);
+        let config = config_path
+            .as_deref()
+            .map(Config::load)
+            .transpose()?
+            .unwrap_or_default();
+        let demo = args.demo.then(|| demo_data(&demo_dir())).transpose()?;
+        let call_stack_text = match (&args.call_stack, &args.call_stack_file) {
+            (Some(call_stack), _) => Some(call_stack.clone()),
+            (None, Some(path)) => read_call_stack_file(path)?,
+            (None, None) => None,
+        };
+        let call_stack_input = match (&args.bash_source, &args.funcname, &args.bash_lineno) {
+            (Some(source), Some(funcname), Some(lineno)) => Some(StackInput::BashArrays {
+                source,
+                funcname,
+                lineno,
+            }),
+            _ => call_stack_text
+                .as_deref()
+                .filter(|cs| !cs.trim().is_empty())
+                .map(StackInput::Formatted),
+        };
+        let mut call_stack_error = None;
+        let call_stack = match &demo {
+            // The demo call stack is always in the bash format.
+            Some(demo) => Some(load_call_stack(&demo.call_stack, Shell::Bash)?),
+            None => match call_stack_input
+                .map(|input| load_call_stack(input, shell))
+                .transpose()
+            {
+                Ok(call_stack) => call_stack,
+                // A hook from a newer debugger shouldn't stop this one from
+                // being used for everything else.
+                Err(err) if err.is::<UnsupportedStackVersion>() => {
+                    call_stack_error = Some(err.to_string());
+                    None
+                }
+                Err(err) => return Err(err.context("failed to load call stack")),
+            },
+        };
+        let tracepoint = match &demo {
+            Some(demo) => Some(demo.tracepoint.clone()),
+            None => args.tracepoint.clone(),
+        };
+        let theme_settings = ThemeSettings {
+            choice: args.theme,
+            no_color: args.no_color || no_color_requested(),
+            terminal: terminal.clone(),
+        };
+        let (theme, mut warnings) = build_theme(&theme_settings, &config)?;
+        let mut messages = MessageQueue::default();
+        // A frame without its source is still worth showing, so a file that
+        // can't be read is reported rather than stopping the debugger.
+        for file in call_stack.iter().flat_map(|cs| cs.unreadable_files()) {
+            messages.push(
+                Severity::Warning,
+                format!("couldn't read the source of {}", file.display()),
+            );
+        }
+        let mut trace = TraceState::new(tracepoint, call_stack)
+            .with_known_tracepoints(normalize_tracepoint_names(&args.tracepoints));
+        if let Some(error) = call_stack_error {
+            warnings.push(error.clone());
+            trace = trace.with_call_stack_error(error);
+        }
+        warnings.extend(trace.unknown_tracepoint_warning());
+        let paused_at = trace.tracepoint().map(String::as_str);
+        let output = Self::initial_output_inner(shell, &tracepoint_var_value(), paused_at);
+        let tracepoint_disposition =
+            TracepointDisposition::on_resume(&tracepoint_var_value(), paused_at);
+        let config_mtime = config_path.as_deref().and_then(modified_time);
+        let journal = match args.json_log.as_deref().map(Journal::open) {
+            Some(Ok(journal)) => Some(journal),
+            Some(Err(err)) => {
+                warnings.push(format!("{err:#}"));
+                None
+            }
+            None => None,
+        };
+        let control = args
+            .control_socket
+            .as_deref()
+            .map(ControlSocket::bind)
+            .transpose()?;
+        if args.output_fd.is_some() && args.output_fd == args.env_fd {
+            bail!("the environment and the commands need different file descriptors");
+        }
+        let output_fd = args.output_fd.map(open_output_fd).transpose()?;
+        let var_origins = args
+            .var_origins
+            .as_deref()
+            .map(load_var_origins)
+            .transpose()?;
+        let (env_source, resolved_env) = match &demo {
+            Some(demo) => (EnvSource::Demo, Some(demo.env.clone())),
+            None => resolve_env(
+                args.env_file.as_deref(),
+                args.env_fd,
+                args.shell_pid,
+                &mut warnings,
+            )?,
+        };
+        let env = match &resolved_env {
+            Some(env) => Env::with_env(env),
+            None => Env::new(),
+        };
+        let mut app = Self {
             env,
-            trace: TraceState::new(args.tracepoint.clone(), call_stack),
+            env_source,
+            trace,
             screen: Screen::Home,
-            shell: args.shell,
-            output: Self::initial_output(args.shell),
-            theme: Theme::default(),
-            key_bindings: KeyBindings::default(),
-            exit_state: ExitState::default(),
+            shell,
+            prompt: PromptState::default(),
+            output,
+            tracepoint_disposition,
+            output_state: OutputState::default(),
+            home: HomeState::default(),
+            output_fd,
+            overlay: None,
+            config_path,
+            config_mtime,
+            last_config_check: Instant::now(),
+            theme_settings,
+            theme,
+            key_bindings: config.key_bindings,
+            bindings_cache: BindingsCache::default(),
+            key_sequence: KeySequenceState::default(),
+            modal: None,
+            confirm_destructive: !args.yes && config.confirm.unwrap_or(true),
+            revert_helpers: config.revert_helpers.unwrap_or(false),
+            protected_vars: protected_vars(&config.protected_vars),
+            prompt_aliases: config.aliases,
+            auto_split: config.auto_split.unwrap_or(true),
+            saved_vars: Vec::new(),
+            change_log: ChangeLog::default(),
+            inspected_vars: BTreeSet::new(),
+            watch_list: WatchList::default(),
+            raw_commands: Vec::new(),
+            undo_stack: UndoStack::default(),
+            warnings,
+            toasts: ToastQueue::default(),
+            messages,
+            read_only: args.read_only,
+            started,
+            countdown: args.timeout.map_or(Countdown::Disabled, |secs| {
+                Countdown::start(Duration::from_secs(secs), started)
+            }),
+            timed_out: false,
+            interrupt: InterruptState::default(),
+            cancelled: false,
+            exit_disposition: ExitDisposition::default(),
+            demo: args.demo,
+            cwd: args
+                .cwd
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default(),
+            journal,
+            control,
+            control_response: None,
+            tick_rate: args.tick_rate.map_or(TICK_RATE, Duration::from_millis),
+            redraw_needed: false,
+            paged: None,
+        };
+        app.env.update_detail_view(app.auto_split);
+        let app = match var_origins {
+            Some(origins) => app.with_var_origins(origins),
+            None => app,
         };
+        let mut app = match &args.start_screen {
+            Some(screen) => app.with_screen(screen.clone()),
+            None => app,
+        };
+        app.record_actions(&[JournalAction::SessionStarted {
+            shell: shell.to_string(),
+            screen: app.screen().to_string(),
+        }]);
         Ok(app)
     }
 
     /// Initialize the app with a specific set of environment variables.
-    #[expect(dead_code)]
-    fn with_env(mut self, env: &HashMap<String, String>) -> Self {
+    pub fn with_env(mut self, env: &HashMap<String, String>) -> Self {
         self.env = Env::with_env(env);
+        self.env.update_detail_view(self.auto_split);
+        self
+    }
+
+    /// Returns whether variables that look like lists are split when
+    /// they're selected.
+    pub fn auto_split(&self) -> bool {
+        self.auto_split
+    }
+
+    pub fn set_auto_split(&mut self, auto_split: bool) {
+        self.auto_split = auto_split;
+        self.env.update_detail_view(auto_split);
+    }
+
+    /// Labels the variables with where they came from.
+    pub fn with_var_origins(mut self, origins: VarOrigins) -> Self {
+        self.env.set_origins(origins);
+        self
+    }
+
+    /// Picks up where the previous session left off. A screen chosen with
+    /// `--start-screen` wins over the saved one.
+    pub fn restore_session(&mut self, session: &SessionState, args: &Cli) {
+        if args.start_screen.is_none()
+            && let Some(screen) = &session.screen
+        {
+            self.screen = screen.clone();
+        }
+        if let Some(view) = session.vars_detail {
+            self.env.set_detail_view(view);
+        }
+        for name in &session.watched {
+            self.watch_list.add(name, self.env.value_of(name));
+        }
+    }
+
+    /// Counts the time the shell has been paused for from `started`.
+    pub fn with_started(mut self, started: Instant) -> Self {
+        self.started = started;
         self
     }
 
     /// Initialize the app with a specific starting screen.
-    #[expect(dead_code)]
-    fn with_screen(mut self, screen: Screen) -> Self {
+    pub fn with_screen(mut self, screen: Screen) -> Self {
         self.screen = screen;
         self
     }
 
     /// Initialize the app to generate commands for a specific shell.
-    #[expect(dead_code)]
-    fn with_shell(mut self, shell: Shell) -> Self {
+    pub fn with_shell(mut self, shell: Shell) -> Self {
         self.shell = shell;
         self
     }
 
     /// Returns the initial output that will be sourced when the debugger exits.
-    fn initial_output(shell: Shell) -> String {
-        let tracepoint = std::env::var(TRACEPOINT_VAR_NAME).unwrap_or_default();
-        Self::initial_output_inner(shell, &tracepoint)
-    }
-
-    fn initial_output_inner(shell: Shell, tracepoint_var_value: &str) -> String {
-        match tracepoint_var_value {
-            "all" | "" => String::new(),
-            _other => match shell {
-                Shell::Bash => format!("unset {TRACEPOINT_VAR_NAME}\n"),
-                Shell::Zsh => format!("unset {TRACEPOINT_VAR_NAME}\n"),
-                Shell::Fish => format!("set -e {TRACEPOINT_VAR_NAME}\n"),
-            },
+    fn initial_output(&self) -> String {
+        let paused_at = self.trace.tracepoint().map(String::as_str);
+        Self::initial_output_inner(self.shell, &tracepoint_var_value(), paused_at)
+    }
+
+    /// Returns the commands that continue from pausing at `paused_at` when
+    /// the tracepoint variable was `tracepoint_var_value`.
+    fn initial_output_inner(
+        shell: Shell,
+        tracepoint_var_value: &str,
+        paused_at: Option<&str>,
+    ) -> String {
+        let disposition = TracepointDisposition::on_resume(tracepoint_var_value, paused_at);
+        tracepoint_cmd(shell, &disposition, tracepoint_var_value)
+            .map(|command| command + "\n")
+            .unwrap_or_default()
+    }
+
+    /// Returns where the shell will pause after the debugger exits.
+    pub fn tracepoint_disposition(&self) -> &TracepointDisposition {
+        &self.tracepoint_disposition
+    }
+
+    /// Changes where the shell will pause after the debugger exits, replacing
+    /// the command in the output that set the old disposition.
+    pub fn set_tracepoint_disposition(&mut self, disposition: TracepointDisposition) {
+        let current = tracepoint_var_value();
+        let old = tracepoint_cmd(self.shell, &self.tracepoint_disposition, &current);
+        if let Some(old) = old
+            && let Some(idx) = self.output_lines().iter().rposition(|line| *line == old)
+        {
+            self.remove_output_line(idx);
+        }
+        if let Some(new) = tracepoint_cmd(self.shell, &disposition, &current) {
+            self.push_output_line(&new);
         }
+        self.tracepoint_disposition = disposition;
+    }
+
+    /// Returns the shell that the output commands are generated for.
+    pub fn shell(&self) -> Shell {
+        self.shell
     }
 
     /// Returns a copy of the output commands.
@@ -102,272 +578,3487 @@ impl App {
         self.output.clone()
     }
 
-    /// Prints the commands that the user's shell should source
-    /// after the debugger exits.
-    pub fn print_output(&self) -> Result<(), Error> {
-        Self::print_cmds_inner(&self.output, &mut std::io::stdout())
-            .context("failed to write commands")?;
-        Ok(())
+    /// Returns the individual lines of the output commands.
+    pub fn output_lines(&self) -> Vec<&str> {
+        self.output.lines().collect()
     }
 
-    /// Prints the commands that the user's shell should source to the specified
-    /// buffer in the specified shell dialect.
-    fn print_cmds_inner(output: &str, stream: &mut impl Write) -> Result<(), Error> {
-        stream
-            .write_all(output.as_bytes())
-            .context("failed to write commands to buffer")?;
-        Ok(())
+    /// Returns the commands the user queued, which are the output commands
+    /// other than the ones the debugger starts with.
+    pub fn queued_commands(&self) -> Vec<&str> {
+        let initial = self.initial_output();
+        let mut initial = initial.lines().collect::<Vec<_>>();
+        self.output
+            .lines()
+            .filter(|line| match initial.iter().position(|kept| kept == line) {
+                Some(idx) => {
+                    initial.remove(idx);
+                    false
+                }
+                None => true,
+            })
+            .collect()
     }
 
-    /// Returns the current screen.
-    pub fn screen(&self) -> Screen {
-        self.screen.clone()
+    /// Writes `actions` to the JSON log, if there is one. A failed write stops
+    /// the logging rather than the session.
+    fn record_actions(&mut self, actions: &[JournalAction]) {
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+        if actions.is_empty() {
+            return;
+        }
+        if let Err(err) = journal.record(actions) {
+            self.journal = None;
+            self.push_message(Severity::Error, format!("Stopped JSON logging: {err:#}"));
+        }
     }
 
-    /// Returns a reference to the trace state.
-    pub fn trace(&self) -> &TraceState {
-        &self.trace
+    /// Queues `cmd` in the output. A toggle that repeats the last command
+    /// queued is dropped, and one that undoes it removes it instead.
+    pub fn queue(&mut self, cmd: Cmd) {
+        let command = cmd.render(self.shell);
+        if let Some(inverse) = cmd.inverse() {
+            let lines = self.output_lines();
+            let last = lines.last().copied();
+            let last_idx = lines.len().saturating_sub(1);
+            if last == Some(command.as_str()) {
+                return;
+            }
+            if last == Some(inverse.render(self.shell).as_str()) {
+                self.remove_output_line(last_idx);
+                return;
+            }
+        }
+        self.undo_stack.push(&command, QueuedKind::of(&cmd));
+        self.push_output_line(&command);
     }
 
-    /// Returns a mutable reference to the trace state.
-    pub fn trace_mut(&mut self) -> &mut TraceState {
-        &mut self.trace
+    /// Returns the shortcuts for prompt commands from the config file.
+    pub fn prompt_aliases(&self) -> &PromptAliases {
+        &self.prompt_aliases
     }
 
-    /// Returns the configured theme.
-    pub fn theme(&self) -> Theme {
-        self.theme.clone()
+    /// Returns the variables that changes to are confirmed first.
+    pub fn protected_vars(&self) -> &BTreeSet<String> {
+        &self.protected_vars
     }
 
-    /// Returns the configured key bindings.
-    pub fn key_bindings(&self) -> KeyBindings {
-        self.key_bindings.clone()
+    /// Queues `mutation`, unless it touches a protected variable. Those are
+    /// only queued once the user confirms them, and never in read-only mode.
+    ///
+    /// Every change to variables goes through here, so that nothing gets
+    /// around the check.
+    pub fn mutate(&mut self, mutation: VarMutation) -> MutationOutcome {
+        let protected = mutation
+            .targets()
+            .into_iter()
+            .filter(|name| self.protected_vars.contains(name))
+            .collect::<Vec<_>>();
+        if protected.is_empty() {
+            self.apply_mutation(&mutation);
+            return MutationOutcome::Queued;
+        }
+        let names = protected.join(", ");
+        if self.read_only {
+            self.notify(
+                Severity::Error,
+                format!("Read-only mode: {names} can't be changed"),
+            );
+            return MutationOutcome::Blocked;
+        }
+        // Unlike other confirmations, this one can't be turned off.
+        let modal = Modal::confirm(self, PendingAction::Protected(mutation));
+        self.open_modal(modal);
+        MutationOutcome::Held
     }
 
-    /// Returns the current exit status.
-    pub fn exit_state(&self) -> ExitState {
-        self.exit_state.clone()
+    /// Queues `mutation` without checking it, once it's been confirmed or
+    /// didn't need to be.
+    pub(crate) fn apply_mutation(&mut self, mutation: &VarMutation) {
+        match mutation {
+            VarMutation::Set { name, value } => self.apply_set_var(name, value),
+            VarMutation::SetPath { name, entries } => self.apply_set_path_var(name, entries),
+            VarMutation::Unset { name } => self.apply_unset_var(name),
+            VarMutation::Raw(line) => {
+                self.raw_commands.push(line.to_string());
+                self.undo_stack.push(line, QueuedKind::Raw);
+                self.push_output_line(line);
+            }
+        }
     }
 
-    /// Returns true if the application is presenting the exit modal.
-    pub fn is_displaying_exit_modal(&self) -> bool {
-        matches!(self.exit_state, ExitState::PresentModal { .. })
+    /// Queues a command that sets and exports `name` as `value`.
+    pub fn queue_set_var(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> Result<MutationOutcome, InvalidVarName> {
+        check_var_name(name)?;
+        Ok(self.mutate(VarMutation::Set {
+            name: name.to_string(),
+            value: value.to_string(),
+        }))
     }
 
-    /// Get a mutable reference to the exit state
-    pub fn exit_state_mut(&mut self) -> &mut ExitState {
-        &mut self.exit_state
+    /// Returns the command that sets the selected variable to its current
+    /// value, written the way [`App::queue_set_var`] would queue it.
+    pub fn selected_var_command(&self) -> Option<String> {
+        let env = self.env();
+        let mutation = VarMutation::Set {
+            name: env.selected_var()?,
+            value: env.selected_var_value()?.to_string(),
+        };
+        Some(mutation.command(self.shell))
     }
 
-    /// Set the exit state
-    pub fn set_exit_state(&mut self, state: ExitState) {
-        self.exit_state = state;
+    fn apply_set_var(&mut self, name: &str, value: &str) {
+        self.save_original(name);
+        let cmd = Cmd::SetVar {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        self.change_log
+            .record(name, Some(value), &cmd.render(self.shell));
+        self.env.resolve_conflict(name);
+        self.queue(cmd);
+        self.refresh_revert_function();
     }
 
-    /// Switches to the next tab
-    pub fn next_tab(&mut self) {
-        self.screen = self.screen.next_tab()
+    /// Queues a command that saves what `name` holds before it's first
+    /// changed, if revert helpers are turned on. Later changes keep the
+    /// value saved the first time.
+    fn save_original(&mut self, name: &str) {
+        if !self.revert_helpers {
+            return;
+        }
+        let save = Cmd::SaveOriginal {
+            name: name.to_string(),
+        }
+        .render(self.shell);
+        if !self.output_lines().contains(&save.as_str()) {
+            self.push_output_line(&save);
+            self.saved_vars.retain(|saved| saved != name);
+            self.saved_vars.push(name.to_string());
+        }
     }
 
-    /// Returns the current environment.
-    pub fn env(&self) -> &Env {
-        &self.env
+    /// Moves the definition of the revert function to the end of the output,
+    /// covering every variable whose original is still saved there.
+    fn refresh_revert_function(&mut self) {
+        if !self.revert_helpers {
+            return;
+        }
+        let shell = self.shell;
+        if let Some(idx) = self
+            .output_lines()
+            .iter()
+            .position(|line| defines_function(shell, line, REVERT_FUNCTION_NAME))
+        {
+            self.remove_output_line(idx);
+        }
+        let lines = self.output_lines();
+        let saved = self
+            .saved_vars
+            .iter()
+            .filter(|name| {
+                let save = Cmd::SaveOriginal {
+                    name: name.to_string(),
+                }
+                .render(shell);
+                lines.contains(&save.as_str())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if saved.is_empty() {
+            return;
+        }
+        let define = Cmd::DefineFunction {
+            name: REVERT_FUNCTION_NAME.to_string(),
+            body: revert_body(shell, &saved),
+        };
+        self.push_output_line(&define.render(shell));
     }
 
-    /// Returns a mutable reference to the current environment.
-    pub fn env_mut(&mut self) -> &mut Env {
-        &mut self.env
+    /// Queues a command that sets the PATH-style variable `name` to
+    /// `entries`. Entries containing a colon are queued as they are, but
+    /// since the shell will split them they're warned about.
+    pub fn queue_set_path_var(
+        &mut self,
+        name: &str,
+        entries: &[String],
+    ) -> Result<MutationOutcome, InvalidVarName> {
+        check_var_name(name)?;
+        Ok(self.mutate(VarMutation::SetPath {
+            name: name.to_string(),
+            entries: entries.to_vec(),
+        }))
     }
-}
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
-pub enum ExitState {
-    #[default]
-    NotExiting,
-    PresentModal {
-        highlighted_option: ExitOption,
-    },
-}
+    fn apply_set_path_var(&mut self, name: &str, entries: &[String]) {
+        let split = entries_with_separator(entries);
+        if !split.is_empty() {
+            let plural = if split.len() == 1 { "y" } else { "ies" };
+            self.push_message(
+                Severity::Warning,
+                format!(
+                    "{name} entr{plural} containing ':' will be split: {}",
+                    split.join(", ")
+                ),
+            );
+        }
+        self.save_original(name);
+        let command = set_path_var_cmd(self.shell, name, entries);
+        let value = entries.join(&PATH_SEPARATOR.to_string());
+        self.change_log.record(name, Some(&value), &command);
+        self.env.resolve_conflict(name);
+        let kind = QueuedKind::Set {
+            name: name.to_string(),
+        };
+        self.undo_stack.push(&command, kind);
+        self.push_output_line(&command);
+        self.refresh_revert_function();
+    }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
-pub enum ExitOption {
-    Ok,
-    #[default]
-    Cancel,
-}
+    /// Queues a command that defines a shell function called `name` that
+    /// runs `body`.
+    pub fn queue_function(&mut self, name: &str, body: &str) -> Result<(), InvalidFunction> {
+        check_function(self.shell, name, body)?;
+        self.queue(Cmd::DefineFunction {
+            name: name.to_string(),
+            body: body.to_string(),
+        });
+        Ok(())
+    }
 
-#[derive(Debug, Clone)]
-pub enum Screen {
-    Home,
-    Prompt,
-    Vars,
-    Trace,
-    Output,
-}
+    /// Returns the shell's working directory, which relative paths are
+    /// resolved against.
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
 
-impl Screen {
-    /// Returns the index of the current tab to determine which tab
-    /// to highlight in the UI.
-    pub fn tab_index(&self) -> usize {
-        match self {
-            Screen::Home => 0,
-            Screen::Prompt => 1,
-            Screen::Vars => 2,
-            Screen::Trace => 3,
-            Screen::Output => 4,
-        }
+    /// Queues a command that changes the shell's working directory to `dir`,
+    /// which may start with `~` or be relative to the shell's working
+    /// directory. Returns the directory it resolved to.
+    pub fn queue_cd(&mut self, dir: &str) -> PathBuf {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let dir = resolve_dir(dir, &self.cwd, home.as_deref());
+        self.queue(Cmd::ChangeDir {
+            dir: dir.to_string_lossy().into_owned(),
+        });
+        dir
     }
 
-    /// Returns the next tab
-    pub fn next_tab(&self) -> Self {
-        use Screen::*;
-        match self {
-            Home => Prompt,
-            Prompt => Vars,
-            Vars => Trace,
-            Trace => Output,
-            Output => Home,
+    /// Queues a command that defines `name` as an alias for `value`.
+    pub fn queue_alias(&mut self, name: &str, value: &str) -> Result<(), InvalidAliasName> {
+        check_alias_name(name)?;
+        self.queue(Cmd::Alias {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Queues a command that unsets `name`.
+    pub fn queue_unset_var(&mut self, name: &str) -> Result<MutationOutcome, InvalidVarName> {
+        check_var_name(name)?;
+        Ok(self.mutate(VarMutation::Unset {
+            name: name.to_string(),
+        }))
+    }
+
+    fn apply_unset_var(&mut self, name: &str) {
+        self.save_original(name);
+        let cmd = Cmd::UnsetVar {
+            name: name.to_string(),
+        };
+        self.change_log.record(name, None, &cmd.render(self.shell));
+        self.env.resolve_conflict(name);
+        self.queue(cmd);
+        self.refresh_revert_function();
+    }
+
+    /// Reads the environment again from wherever it first came from and
+    /// shows it in place of the old one, summing up what changed in a toast.
+    /// The queued commands are kept as they are, and the variables they
+    /// change that also changed underneath them are flagged as conflicts.
+    pub fn reload_env(&mut self) {
+        let environ = match self.env_source.reread() {
+            Ok(environ) => environ,
+            Err(err) => {
+                self.push_message(Severity::Warning, format!("{err:#}"));
+                return;
+            }
+        };
+        if let EnvSource::Process(pid) = self.env_source
+            && let Some(warning) = environ.skipped_warning(pid)
+        {
+            self.push_message(Severity::Warning, warning);
         }
+        let edits = self.var_changes();
+        let diff = self.env.reload(&environ.vars);
+        self.refresh_watched();
+        let reload = EnvReload::new(diff, &edits, self.env.conflicts());
+        let severity = if reload.conflicts.is_empty() {
+            Severity::Success
+        } else {
+            Severity::Warning
+        };
+        self.notify(severity, reload.summary());
+        self.env.set_conflicts(reload.conflicts);
+        self.env.selection_changed(self.auto_split);
     }
-}
 
-impl std::fmt::Display for Screen {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Screen::Home => write!(f, "Home"),
-            Screen::Prompt => write!(f, "Prompt"),
-            Screen::Vars => write!(f, "Vars"),
-            Screen::Trace => write!(f, "Trace"),
-            Screen::Output => write!(f, "Output"),
+    /// Returns how the queued commands change each variable they touch,
+    /// sorted by name.
+    pub fn var_changes(&self) -> Vec<VarChange> {
+        self.change_log
+            .changes(&self.output, |name| self.env.value_of(name))
+    }
+
+    /// Returns the variables looked at on the Vars screen, sorted by name.
+    pub fn inspected_vars(&self) -> &BTreeSet<String> {
+        &self.inspected_vars
+    }
+
+    /// Remembers the variable selected on the Vars screen, if it's showing.
+    fn note_inspected_var(&mut self) {
+        if self.screen == Screen::Vars
+            && let Some(name) = self.env.selected_var()
+        {
+            self.inspected_vars.insert(name);
         }
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub enum Shell {
-    Bash,
-    Zsh,
-    Fish,
-}
+    /// Queues `line` exactly as given.
+    pub fn queue_raw(&mut self, line: &str) -> MutationOutcome {
+        self.mutate(VarMutation::Raw(line.to_string()))
+    }
 
-impl FromStr for Shell {
-    type Err = Error;
+    /// Returns how many of the commands queued verbatim are still in the
+    /// output.
+    pub fn raw_command_count(&self) -> usize {
+        let lines = self.output_lines();
+        self.raw_commands
+            .iter()
+            .filter(|raw| lines.contains(&raw.as_str()))
+            .count()
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "bash" => Ok(Shell::Bash),
-            "zsh" => Ok(Shell::Zsh),
-            "fish" => Ok(Shell::Fish),
-            _ => Err(anyhow!("unrecognized shell: {s}")),
+    /// Appends a line to the output commands.
+    pub fn push_output_line(&mut self, line: &str) {
+        debug!(command = line, "queued command");
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    /// Removes a line from the output commands, returning it if it existed.
+    pub fn remove_output_line(&mut self, idx: usize) -> Option<String> {
+        let mut lines = self.output.lines().map(String::from).collect::<Vec<_>>();
+        if idx >= lines.len() {
+            return None;
         }
+        let removed = lines.remove(idx);
+        self.output = lines.into_iter().map(|line| line + "\n").collect();
+        Some(removed)
     }
-}
 
-/// Navigation directions for UI elements
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum NavEvent {
-    Up,
-    Down,
-    Left,
-    Right,
-    Select, // For Enter key
-}
+    /// Returns what the output command `line` does, or `None` if the
+    /// debugger queued it by itself rather than the user.
+    pub fn output_line_kind(&self, line: &str) -> Option<&QueuedKind> {
+        self.undo_stack.kind_of(line)
+    }
 
-#[derive(Debug, Clone)]
-pub enum Event {
-    App(AppEvent),
-    Nav(NavEvent),
-    Vars(VarsEvent),
-}
+    /// Removes the last command the user queued that's still in the output,
+    /// returning it. Commands the debugger queued by itself are left alone.
+    pub fn undo_last_command(&mut self) -> Option<QueuedCommand> {
+        let lines = self.output.lines().collect::<Vec<_>>();
+        let (idx, queued) = self.undo_stack.pop(&lines)?;
+        self.remove_output_line(idx);
+        Some(queued)
+    }
 
-#[derive(Debug, Clone)]
-pub enum AppEvent {
-    /// The user requested that the next tab be displayed.
-    NextTab,
-    /// The user requested that the application exit.
-    ExitRequested,
-}
+    /// Resets the output commands to the ones the debugger starts with.
+    pub fn clear_output(&mut self) {
+        self.output = self.initial_output();
+        self.saved_vars.clear();
+        self.change_log.clear();
+        self.raw_commands.clear();
+        self.undo_stack.clear();
+        let paused_at = self.trace.tracepoint().map(String::as_str);
+        self.tracepoint_disposition =
+            TracepointDisposition::on_resume(&tracepoint_var_value(), paused_at);
+    }
 
-pub fn run_app<B: Backend>(app: &mut App, terminal: &mut Terminal<B>) -> Result<(), Error> {
-    loop {
-        terminal
-            .draw(|frame| draw_ui(app, frame))
-            .context("failed to draw UI")?;
-
-        let screen = app.screen();
-        if let TermEvent::Key(key) = event::read().context("failed to read incoming events")? {
-            let keymap = app
-                .key_bindings()
-                .current_keymap(&screen, &app.exit_state());
-            if let Some(event) = keymap.get(&key) {
-                let should_exit = handle_event(app, event);
-                if should_exit {
-                    break;
-                }
-            }
+    /// Returns the view state of the Home screen's menu.
+    pub fn home(&self) -> &HomeState {
+        &self.home
+    }
+
+    pub fn home_mut(&mut self) -> &mut HomeState {
+        &mut self.home
+    }
+
+    /// Returns the view state of the output screen.
+    pub fn output_state(&self) -> &OutputState {
+        &self.output_state
+    }
+
+    /// Returns a mutable reference to the view state of the output screen.
+    pub fn output_state_mut(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    /// Prints the commands that the user's shell should source
+    /// after the debugger exits, on the file descriptor from `--output-fd` if
+    /// there was one and stdout otherwise.
+    pub fn print_output(&self) -> Result<(), Error> {
+        match self.output_fd.as_ref() {
+            Some(mut file) => self.write_output(&mut file).with_context(|| {
+                format!(
+                    "failed to write commands to file descriptor {}",
+                    file.as_raw_fd()
+                )
+            })?,
+            None => self
+                .write_output(&mut std::io::stdout())
+                .context("failed to write commands")?,
         }
+        Ok(())
     }
-    Ok(())
-}
 
-/// Modifies the application state in response to an event, returning a boolean
-/// indicating whether the application should exit.
-fn handle_event(app: &mut App, event: &Event) -> bool {
-    let should_exit = false;
-    if app.is_displaying_exit_modal() {
-        let should_exit = handle_exit_state(app, event);
-        return should_exit;
+    /// Writes the commands that would be printed on exit to `stream`, exactly
+    /// as [`App::print_output`] prints them.
+    pub fn write_output(&self, stream: &mut impl Write) -> Result<(), Error> {
+        Self::print_cmds_inner(&self.final_output(), stream)
     }
-    if let Event::App(app_event) = event {
-        match app_event {
-            AppEvent::ExitRequested => {
-                app.set_exit_state(ExitState::PresentModal {
-                    highlighted_option: ExitOption::Cancel,
-                });
-            }
-            AppEvent::NextTab => {
-                app.next_tab();
-            }
+
+    /// Returns the commands to print when the debugger exits. In read-only
+    /// mode, after timing out or when discarding changes this is only the
+    /// automatic tracepoint handling, whatever else ended up in the output,
+    /// and in demo mode every command is commented out.
+    pub fn final_output(&self) -> String {
+        let discard = self.exit_disposition == ExitDisposition::Discard;
+        let output = if self.read_only || self.timed_out || discard {
+            self.initial_output()
+        } else {
+            self.output.clone()
+        };
+        if self.demo {
+            comment_out(&output)
+        } else {
+            output
         }
-        return should_exit;
     }
-    match app.screen() {
-        Screen::Home => {}
-        Screen::Prompt => {}
-        Screen::Vars => handle_vars_event(app, event),
-        Screen::Trace => handle_trace_event(app, event),
-        Screen::Output => {}
+
+    /// Returns whether actions that change the environment or the output
+    /// commands are disabled.
+    pub fn read_only(&self) -> bool {
+        self.read_only
     }
-    should_exit
-}
 
-/// Handles events when the user is being presented the exit modal.
-fn handle_exit_state(app: &mut App, event: &Event) -> bool {
-    if let ExitState::PresentModal { highlighted_option } = app.exit_state_mut() {
-        if let Event::Nav(nav_event) = event {
-            match nav_event {
-                NavEvent::Left | NavEvent::Right => {
-                    // Toggle between Ok and Cancel
-                    *highlighted_option = match highlighted_option {
-                        ExitOption::Ok => ExitOption::Cancel,
-                        ExitOption::Cancel => ExitOption::Ok,
-                    };
-                }
-                // Ignore up/down events in the exit modal
-                NavEvent::Up | NavEvent::Down => {}
-                NavEvent::Select => {
-                    match highlighted_option {
-                        ExitOption::Ok => {
-                            app.set_exit_state(ExitState::NotExiting);
-                            return true; // Signal to exit the application
-                        }
-                        ExitOption::Cancel => {
-                            app.set_exit_state(ExitState::NotExiting);
-                        }
-                    }
-                }
+    /// Returns whether the mutating `action` is allowed, telling the user why
+    /// not if it isn't.
+    pub fn check_writable(&mut self, action: &str) -> bool {
+        if self.read_only {
+            self.notify(
+                Severity::Error,
+                format!("Read-only mode: {action} is disabled"),
+            );
+        }
+        !self.read_only
+    }
+
+    /// Prints the commands that the user's shell should source to the specified
+    /// buffer in the specified shell dialect.
+    ///
+    /// The commands always end with exactly one newline, so the last of them
+    /// is complete however the shell reads them. If whatever was reading has
+    /// gone away there's nobody left to run the commands, so that's not an
+    /// error.
+    pub(crate) fn print_cmds_inner(output: &str, stream: &mut impl Write) -> Result<(), Error> {
+        let output = output.trim_end_matches('\n');
+        let res = if output.is_empty() {
+            stream.flush()
+        } else {
+            stream
+                .write_all(output.as_bytes())
+                .and_then(|_| stream.write_all(b"\n"))
+                .and_then(|_| stream.flush())
+        };
+        match res {
+            Err(err) if err.kind() == ErrorKind::BrokenPipe => {
+                debug!("stopped printing commands, nothing is reading them");
+                Ok(())
             }
+            res => res.context("failed to write commands to buffer"),
+        }
+    }
+
+    /// Returns the current screen.
+    pub fn screen(&self) -> Screen {
+        self.screen.clone()
+    }
+
+    /// Switches to the specified screen.
+    ///
+    /// Only the screen shown changes. Where each screen's lists are scrolled
+    /// to and what they have selected is kept on the app, so switching back
+    /// finds the screen as it was left until the data it shows changes.
+    pub fn set_screen(&mut self, screen: Screen) {
+        self.screen = screen;
+    }
+
+    /// Returns whether typed keys are interpreted as bindings or text.
+    pub fn input_mode(&self) -> InputMode {
+        if let Some(Overlay::Bindings(editor)) = &self.overlay
+            && editor.capturing()
+        {
+            return InputMode::KeyCapture;
+        }
+        match self.screen {
+            Screen::Prompt if self.prompt.input_focused() => InputMode::TextEntry,
+            _ => InputMode::Normal,
         }
     }
-    false
+
+    /// Returns a reference to the prompt state.
+    pub fn prompt(&self) -> &PromptState {
+        &self.prompt
+    }
+
+    /// Returns a mutable reference to the prompt state.
+    pub fn prompt_mut(&mut self) -> &mut PromptState {
+        &mut self.prompt
+    }
+
+    /// Returns a reference to the trace state.
+    pub fn trace(&self) -> &TraceState {
+        &self.trace
+    }
+
+    /// Returns a mutable reference to the trace state.
+    pub fn trace_mut(&mut self) -> &mut TraceState {
+        &mut self.trace
+    }
+
+    /// Returns the toasts on screen and waiting to be shown.
+    pub fn toasts(&self) -> &ToastQueue {
+        &self.toasts
+    }
+
+    /// Shows a toast for a few seconds, for feedback that doesn't need to
+    /// interrupt the user.
+    pub fn notify(&mut self, severity: Severity, text: impl Into<String>) {
+        self.toasts.push(severity, text, Instant::now());
+    }
+
+    /// Returns the messages waiting to be read.
+    pub fn messages(&self) -> &MessageQueue {
+        &self.messages
+    }
+
+    /// Reports a problem that doesn't end the session, showing it until the
+    /// user dismisses it.
+    pub fn push_message(&mut self, severity: Severity, text: impl Into<String>) {
+        let text = text.into();
+        debug!(%severity, text, "showing message");
+        self.messages.push(severity, text);
+    }
+
+    /// Dismisses the message currently shown, if any.
+    pub fn dismiss_message(&mut self) {
+        if !self.messages.dismiss() {
+            self.toasts.dismiss(Instant::now());
+        }
+    }
+
+    /// Re-reads the config file and swaps in the theme it describes. If the
+    /// file can't be loaded the current theme is kept.
+    pub fn reload_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            self.notify(Severity::Error, "No config file location is available");
+            return;
+        };
+        self.config_mtime = modified_time(&path);
+        let reloaded =
+            Config::load(&path).and_then(|config| build_theme(&self.theme_settings, &config));
+        match reloaded {
+            Ok((theme, mut warnings)) => {
+                warnings.extend(self.trace.unknown_tracepoint_warning());
+                self.theme = theme;
+                self.warnings = warnings;
+                self.notify(
+                    Severity::Success,
+                    format!("Reloaded theme from {}", path.display()),
+                );
+            }
+            Err(err) => self.push_message(Severity::Error, format!("{err:#}")),
+        }
+    }
+
+    /// Performs periodic work that doesn't depend on input: counting down
+    /// the timeout, reloading the config file when it changes and expiring
+    /// old toasts and key sequences.
+    ///
+    /// Returns whether anything on screen changed.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let mut changed = self.key_sequence.expire(now);
+        changed |= self.interrupt.expire(now);
+        if !self.timed_out && self.countdown.is_expired(now) {
+            self.timed_out = true;
+            changed = true;
+        }
+        changed |= self.toasts.expire(now);
+        if now.duration_since(self.last_config_check) < CONFIG_POLL_INTERVAL {
+            return changed;
+        }
+        self.last_config_check = now;
+        if let Some(path) = &self.config_path
+            && modified_time(path) != self.config_mtime
+        {
+            self.reload_config();
+            changed = true;
+        }
+        changed
+    }
+
+    /// Returns how long the event loop waits for input before ticking.
+    pub fn tick_rate(&self) -> Duration {
+        self.tick_rate
+    }
+
+    /// Returns how long the shell has been paused for as of `now`.
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.started)
+    }
+
+    /// Returns the countdown to exiting unattended.
+    pub fn countdown(&self) -> Countdown {
+        self.countdown
+    }
+
+    /// Stops the countdown to exiting unattended, since the user is present.
+    pub fn cancel_countdown(&mut self) {
+        self.countdown.cancel();
+    }
+
+    /// Returns a hash of everything that input can change on screen. Two
+    /// equal fingerprints mean there's nothing new to draw.
+    ///
+    /// Anything left out is still drawn by the periodic redraw, just late.
+    pub fn view_fingerprint(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        self.screen.hash(&mut state);
+        self.tracepoint_disposition.hash(&mut state);
+        self.modal.hash(&mut state);
+        self.overlay.hash(&mut state);
+        self.input_mode().hash(&mut state);
+        self.key_sequence.pending().hash(&mut state);
+        self.interrupt.hash(&mut state);
+        self.countdown.hash(&mut state);
+        self.toasts.hash(&mut state);
+        self.messages.hash(&mut state);
+        self.warnings.hash(&mut state);
+        self.exit_disposition.hash(&mut state);
+        self.output.hash(&mut state);
+        self.output_state.hash(&mut state);
+        self.home.hash(&mut state);
+        self.prompt.hash(&mut state);
+        self.env.hash_view(&mut state);
+        self.watch_list.hash(&mut state);
+        self.trace.hash_view(&mut state);
+        state.finish()
+    }
+
+    /// Asks the event loop to show `content` in the user's pager.
+    pub fn page(&mut self, content: String) -> Action {
+        self.paged = Some(Paged::Content(content));
+        Action::Page
+    }
+
+    /// Asks the event loop to show the file at `path` in the user's pager,
+    /// from `line`.
+    pub fn page_file(&mut self, path: PathBuf, line: usize) -> Action {
+        self.paged = Some(Paged::File { path, line });
+        Action::Page
+    }
+
+    /// Returns what's waiting to be shown in the pager.
+    pub fn paged(&self) -> Option<&Paged> {
+        self.paged.as_ref()
+    }
+
+    /// Returns whether a tick changed what's on screen since this was last
+    /// called.
+    fn take_redraw_needed(&mut self) -> bool {
+        std::mem::take(&mut self.redraw_needed)
+    }
+
+    /// Returns whether the countdown to exiting unattended ran out.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Handles Ctrl+C: the first press asks whether to exit, and a second
+    /// one soon after quits without printing any changes.
+    pub fn interrupt(&mut self, now: Instant) -> Action {
+        match self.interrupt.press(now) {
+            InterruptAction::Confirm => {
+                if !self.is_displaying_exit_modal() {
+                    self.open_modal(Modal::exit());
+                }
+                Action::Continue
+            }
+            InterruptAction::ForceQuit => {
+                debug!("force quitting");
+                self.cancel();
+                Action::Exit
+            }
+        }
+    }
+
+    /// Returns whether pressing Ctrl+C again would quit right away.
+    pub fn interrupt_armed(&self) -> bool {
+        self.interrupt.is_armed()
+    }
+
+    /// Ends the session without printing any of the changes made in it.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.exit_disposition = ExitDisposition::Discard;
+        self.close_modal();
+    }
+
+    /// Returns whether the session was cut short without keeping changes.
+    pub fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Returns what happens to the queued commands on exit.
+    pub fn exit_disposition(&self) -> ExitDisposition {
+        self.exit_disposition
+    }
+
+    /// Sets what happens to the queued commands on exit.
+    pub fn set_exit_disposition(&mut self, disposition: ExitDisposition) {
+        self.exit_disposition = disposition;
+    }
+
+    /// Returns the problems found at startup that the user should know about.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the configured theme.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Returns the configured key bindings.
+    pub fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
+    }
+
+    /// Returns a mutable reference to the configured key bindings.
+    pub fn key_bindings_mut(&mut self) -> &mut KeyBindings {
+        // The caller is about to change them.
+        self.bindings_cache.clear();
+        &mut self.key_bindings
+    }
+
+    /// Returns the keymap for the current screen, ignoring any overlay.
+    pub fn keymap(&mut self) -> Rc<HashMap<KeyCombo, Event>> {
+        let input_mode = self.input_mode();
+        self.bindings_cache.keymap(
+            &self.key_bindings,
+            &self.screen,
+            self.modal.as_ref(),
+            input_mode,
+        )
+    }
+
+    /// Returns the (keys, description) pairs shown in the footer of the
+    /// current screen.
+    pub fn footer_bindings(&mut self) -> FooterBindings {
+        let vars = (self.screen == Screen::Vars).then(|| VarsFooterState {
+            detail_focused: !self.env.var_list_focused(),
+            detail_view: self.env.detail_view(),
+            tree: self.env.tree().is_some(),
+            command_preview: self.env.command_preview(),
+        });
+        let context = FooterContext {
+            read_only: self.read_only,
+            vars,
+        };
+        self.bindings_cache
+            .footer(&self.key_bindings, &self.screen, context)
+    }
+
+    /// Returns the location of the config file, if one could be determined.
+    pub fn config_path(&self) -> Option<&Path> {
+        self.config_path.as_deref()
+    }
+
+    /// Returns the overlay drawn on top of the current screen, if any.
+    pub fn overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    /// Returns a mutable reference to the overlay, if any.
+    pub fn overlay_mut(&mut self) -> Option<&mut Overlay> {
+        self.overlay.as_mut()
+    }
+
+    /// Displays or hides an overlay.
+    pub fn set_overlay(&mut self, overlay: Option<Overlay>) {
+        self.overlay = overlay;
+    }
+
+    /// Returns the state of any partially entered key sequence.
+    pub fn key_sequence(&self) -> &KeySequenceState {
+        &self.key_sequence
+    }
+
+    /// Returns the open modal, if any.
+    pub fn modal(&self) -> Option<&Modal> {
+        self.modal.as_ref()
+    }
+
+    /// Returns a mutable reference to the open modal, if any.
+    pub fn modal_mut(&mut self) -> Option<&mut Modal> {
+        self.modal.as_mut()
+    }
+
+    /// Opens a modal, replacing any that was already open.
+    pub fn open_modal(&mut self, modal: Modal) {
+        self.modal = Some(modal);
+    }
+
+    /// Closes the open modal, returning it.
+    pub fn close_modal(&mut self) -> Option<Modal> {
+        self.modal.take()
+    }
+
+    /// Returns true if the application is presenting the exit modal.
+    pub fn is_displaying_exit_modal(&self) -> bool {
+        matches!(self.modal, Some(Modal::Exit { .. }))
+    }
+
+    /// Asks the user to confirm `action` before doing it, or does it right
+    /// away if confirmations were turned off with `--yes` or `confirm = false`.
+    pub fn confirm(&mut self, action: PendingAction) {
+        if self.confirm_destructive {
+            let modal = Modal::confirm(self, action);
+            self.open_modal(modal);
+        } else {
+            action.apply(self);
+        }
+    }
+
+    /// Switches to the next tab
+    pub fn next_tab(&mut self) {
+        self.screen = self.screen.next_tab()
+    }
+
+    /// Switches to the previous tab
+    pub fn prev_tab(&mut self) {
+        self.screen = self.screen.prev_tab()
+    }
+
+    /// Returns the current environment.
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    /// Returns a mutable reference to the current environment.
+    pub fn env_mut(&mut self) -> &mut Env {
+        &mut self.env
+    }
+
+    /// Returns the variables pinned with the `watch` command.
+    pub fn watch_list(&self) -> &WatchList {
+        &self.watch_list
+    }
+
+    /// Watches `name`, returning false if it's already watched.
+    pub fn watch_var(&mut self, name: &str) -> bool {
+        self.watch_list.add(name, self.env.value_of(name))
+    }
+
+    /// Stops watching `name`, returning false if it wasn't watched.
+    pub fn unwatch_var(&mut self, name: &str) -> bool {
+        self.watch_list.remove(name)
+    }
+
+    /// Marks the watched variables whose values changed since the last
+    /// refresh, which happens whenever the environment is reloaded.
+    pub fn refresh_watched(&mut self) {
+        self.watch_list.refresh(|name| self.env.value_of(name));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Screen {
+    Home,
+    Prompt,
+    Vars,
+    Trace,
+    Output,
+}
+
+impl Screen {
+    /// Every screen in the order their tabs are shown and cycled through.
+    pub const TABS: [Screen; 5] = [
+        Screen::Home,
+        Screen::Prompt,
+        Screen::Vars,
+        Screen::Trace,
+        Screen::Output,
+    ];
+
+    /// Returns the index of the current tab to determine which tab
+    /// to highlight in the UI.
+    pub fn tab_index(&self) -> usize {
+        Self::TABS
+            .iter()
+            .position(|screen| screen == self)
+            .expect("every screen has a tab")
+    }
+
+    /// Returns the next tab, wrapping around after the last one.
+    pub fn next_tab(&self) -> Self {
+        Self::TABS[(self.tab_index() + 1) % Self::TABS.len()].clone()
+    }
+
+    /// Returns the previous tab, wrapping around before the first one.
+    pub fn prev_tab(&self) -> Self {
+        Self::TABS[(self.tab_index() + Self::TABS.len() - 1) % Self::TABS.len()].clone()
+    }
+}
+
+impl std::fmt::Display for Screen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Screen::Home => write!(f, "Home"),
+            Screen::Prompt => write!(f, "Prompt"),
+            Screen::Vars => write!(f, "Vars"),
+            Screen::Trace => write!(f, "Trace"),
+            Screen::Output => write!(f, "Output"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    /// Plain POSIX `sh`, such as `dash`.
+    #[value(name = "sh", alias = "posix")]
+    Posix,
+}
+
+/// Formats the shell the way it's passed to `--shell`.
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Zsh => write!(f, "zsh"),
+            Shell::Fish => write!(f, "fish"),
+            Shell::Posix => write!(f, "sh"),
+        }
+    }
+}
+
+impl FromStr for Shell {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true).map_err(|_| {
+            let names = Self::value_variants()
+                .iter()
+                .filter_map(|shell| shell.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect::<Vec<_>>();
+            anyhow!(
+                "unrecognized shell '{s}', expected one of: {}",
+                names.join(", ")
+            )
+        })
+    }
+}
+
+/// Navigation directions for UI elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavEvent {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Jump to the first item of a list.
+    Top,
+    /// Jump to the last item of a list.
+    Bottom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    App(AppEvent),
+    Nav(NavEvent),
+    Home(HomeEvent),
+    Vars(VarsEvent),
+    Prompt(PromptEvent),
+    Output(OutputEvent),
+    Trace(TraceEvent),
+    Overlay(OverlayEvent),
+    Modal(ModalEvent),
+    /// A request that arrived on the control socket.
+    Control(ControlRequest),
+    /// Time passed without any input.
+    Tick(Instant),
+}
+
+impl Event {
+    /// Returns whether holding down the key bound to this event should
+    /// trigger it repeatedly.
+    ///
+    /// Only movement and text entry repeat; holding down the key for an
+    /// action such as deleting a command or exiting triggers it once.
+    pub fn is_repeatable(&self) -> bool {
+        match self {
+            Event::Nav(_) => true,
+            Event::Prompt(prompt_event) => matches!(
+                prompt_event,
+                PromptEvent::Insert(_)
+                    | PromptEvent::Backspace
+                    | PromptEvent::HistoryPrevious
+                    | PromptEvent::HistoryNext
+            ),
+            Event::Modal(modal_event) => {
+                matches!(
+                    modal_event,
+                    ModalEvent::Previous
+                        | ModalEvent::Next
+                        | ModalEvent::ScrollUp
+                        | ModalEvent::ScrollDown
+                )
+            }
+            Event::App(_)
+            | Event::Home(_)
+            | Event::Vars(_)
+            | Event::Output(_)
+            | Event::Trace(_)
+            | Event::Overlay(_)
+            | Event::Control(_)
+            | Event::Tick(_) => false,
+        }
+    }
+
+    /// Returns a description of the change this event makes to the
+    /// environment or the output commands, or `None` if it's only for
+    /// looking around.
+    pub fn mutation(&self) -> Option<&'static str> {
+        match self {
+            Event::Output(OutputEvent::DeleteSelected) => Some("deleting commands"),
+            Event::Output(OutputEvent::EditSelected) => Some("editing commands"),
+            Event::Output(OutputEvent::Clear) => Some("clearing commands"),
+            Event::Output(OutputEvent::Undo) => Some("undoing commands"),
+            Event::Trace(TraceEvent::SetBreakpoint) => Some("setting breakpoints"),
+            Event::App(AppEvent::RunMacro(_)) => Some("running macros"),
+            _ => None,
+        }
+    }
+}
+
+/// What the event loop should do once an event has been handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Keep running.
+    Continue,
+    /// Exit, printing the output commands for the shell to run.
+    Exit,
+    /// Stop until the shell resumes the debugger, giving it the terminal in
+    /// the meantime.
+    Suspend,
+    /// Show the content handed to [`App::page`] in the user's pager, giving
+    /// it the terminal until it exits.
+    Page,
+}
+
+/// What happens to the queued commands when the debugger exits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ExitDisposition {
+    /// Print the queued commands for the shell to run.
+    #[default]
+    Apply,
+    /// Print only the automatic tracepoint handling, dropping everything the
+    /// user queued.
+    Discard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    /// The user requested that the next tab be displayed.
+    NextTab,
+    /// The user requested that the previous tab be displayed.
+    PrevTab,
+    /// The user requested that a specific screen be displayed.
+    GoTo(Screen),
+    /// The user requested the help overlay.
+    ShowHelp,
+    /// The user requested that the theme be reloaded from the config file.
+    ReloadConfig,
+    /// The user requested that the application exit.
+    ExitRequested,
+    /// The user pressed Ctrl+C.
+    Interrupt,
+    /// The user pressed Ctrl+Z.
+    Suspend,
+    /// The user dismissed the message currently shown.
+    DismissMessage,
+    /// The user pressed the key of the macro with this name.
+    RunMacro(String),
+}
+
+/// What the event loop does after handling the result of a poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoopAction {
+    /// Nothing on screen changed, so wait for the next poll.
+    Wait,
+    Redraw,
+    /// Redraw from scratch at the new size.
+    Resize {
+        width: u16,
+        height: u16,
+    },
+    /// Hand the terminal back and stop until continued.
+    Suspend,
+    /// Hand the terminal to the pager until it exits.
+    Page,
+    Exit,
+}
+
+/// Runs the event loop until the user exits, drawing to `terminal` and
+/// taking input from `events`.
+pub fn run_app<B: Backend>(
+    app: &mut App,
+    terminal: &mut Terminal<B>,
+    events: &EventSource,
+) -> Result<(), Error> {
+    let mut last_draw = None;
+    let mut last_tick = Instant::now();
+    loop {
+        let now = Instant::now();
+        // Redraw now and then even when nothing changed, which keeps the
+        // clock in the header current without redrawing on every tick.
+        if last_draw.is_none_or(|drawn: Instant| now.duration_since(drawn) >= REDRAW_INTERVAL) {
+            terminal
+                .draw(|frame| draw_ui(app, frame))
+                .context("failed to draw UI")?;
+            last_draw = Some(now);
+        }
+
+        // Wake up every tick even without input, and sooner when part of a
+        // key sequence or the countdown runs out, so the footer stops showing
+        // a stale prefix and unattended sessions exit on time.
+        let timeout = [
+            app.key_sequence.time_remaining(now),
+            app.countdown.remaining(now),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(app.tick_rate(), Duration::min);
+        let input = match events.receiver().recv_timeout(timeout) {
+            Ok(Err(err)) if is_end_of_input(&err) => {
+                debug!("input ended, exiting");
+                break;
+            }
+            Ok(input) => Some(input.context("failed to read incoming events")?),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => bail!("stopped receiving input"),
+        };
+        // Signals are only noticed here, since their handlers can't safely
+        // do anything but record them.
+        if let Some(signal) = take_shutdown_signal() {
+            debug!(signal, "shutting down on signal");
+            app.cancel();
+            break;
+        }
+        if take_suspend_request() {
+            suspend_app(terminal, events)?;
+            last_draw = None;
+        } else if take_continued() {
+            // Something stopped the debugger without letting it hand back
+            // the terminal first, such as SIGSTOP, and the shell may have
+            // changed the terminal's modes while it had it.
+            debug!("continued without suspending");
+            reenter_terminal()?;
+            terminal.clear().context("failed to clear terminal")?;
+            last_draw = None;
+        }
+        if service_control_requests(app) == Action::Exit {
+            break;
+        }
+        let now = Instant::now();
+        let mut action = step(app, input.as_ref(), now);
+        // Keep ticking while input keeps arriving.
+        if input.is_none() {
+            last_tick = now;
+        } else if action != LoopAction::Exit && now.duration_since(last_tick) >= app.tick_rate() {
+            last_tick = now;
+            if step(app, None, now) == LoopAction::Exit {
+                action = LoopAction::Exit;
+            }
+        }
+        match action {
+            LoopAction::Wait => {}
+            LoopAction::Redraw => last_draw = None,
+            LoopAction::Resize { width, height } => {
+                // Redraw from scratch so nothing drawn at the old size is
+                // left behind. Anything that depends on the size is
+                // recalculated while drawing.
+                debug!(width, height, "terminal resized");
+                terminal
+                    .resize(Rect::new(0, 0, width, height))
+                    .context("failed to resize terminal")?;
+                last_draw = None;
+            }
+            LoopAction::Suspend => {
+                suspend_app(terminal, events)?;
+                last_draw = None;
+            }
+            LoopAction::Page => {
+                page_app(app, terminal, events)?;
+                last_draw = None;
+            }
+            LoopAction::Exit => break,
+        }
+    }
+    Ok(())
+}
+
+/// Shows what's waiting to be paged in the user's pager, then redraws
+/// everything since the pager will have drawn over the TUI.
+fn page_app<B: Backend>(
+    app: &mut App,
+    terminal: &mut Terminal<B>,
+    events: &EventSource,
+) -> Result<(), Error> {
+    let Some(paged) = app.paged.take() else {
+        return Ok(());
+    };
+    let pager_var = std::env::var("PAGER").ok();
+    let pager = pager_command(pager_var.as_deref());
+    debug!(pager, "paging");
+    let result = match &paged {
+        Paged::Content(content) => page(events, pager, content),
+        Paged::File { path, line } => page_file(events, pager, path, *line),
+    };
+    if let Err(err) = result.context("failed to page")? {
+        app.notify(Severity::Error, format!("{err:#}"));
+    }
+    terminal.clear().context("failed to clear terminal")
+}
+
+/// Stops the debugger until the shell continues it, then redraws everything
+/// since the shell will have drawn over the TUI in the meantime.
+fn suspend_app<B: Backend>(terminal: &mut Terminal<B>, events: &EventSource) -> Result<(), Error> {
+    debug!("suspending");
+    suspend(events).context("failed to suspend")?;
+    // Continuing after suspending is already handled.
+    take_continued();
+    debug!("resumed");
+    terminal.clear().context("failed to clear terminal")
+}
+
+/// Handles the result of polling for input at `now`, where `None` means the
+/// poll timed out and it's time to tick.
+fn step(app: &mut App, input: Option<&TermEvent>, now: Instant) -> LoopAction {
+    let Some(term_event) = input else {
+        if handle_event(app, &Event::Tick(now)) == Action::Exit {
+            debug!("timed out waiting for input");
+            return LoopAction::Exit;
+        }
+        return if app.take_redraw_needed() {
+            LoopAction::Redraw
+        } else {
+            LoopAction::Wait
+        };
+    };
+    match term_event {
+        TermEvent::Resize(width, height) => LoopAction::Resize {
+            width: *width,
+            height: *height,
+        },
+        TermEvent::Key(key) => {
+            let before = app.view_fingerprint();
+            app.cancel_countdown();
+            if let Some(event) = resolve_event(app, key, now) {
+                trace!(?key, "resolved key");
+                match handle_event(app, &event) {
+                    Action::Continue => {}
+                    Action::Exit => return LoopAction::Exit,
+                    Action::Suspend => return LoopAction::Suspend,
+                    Action::Page => return LoopAction::Page,
+                }
+            }
+            // Keys that aren't bound, or that move past the end of a list,
+            // leave the screen as it was.
+            if app.view_fingerprint() == before {
+                LoopAction::Wait
+            } else {
+                LoopAction::Redraw
+            }
+        }
+        _ => LoopAction::Wait,
+    }
+}
+
+/// Resolves an incoming key press to an application event given the current
+/// state of the application.
+fn resolve_event(app: &mut App, key: &KeyEvent, now: Instant) -> Option<Event> {
+    let combo = KeyCombo::from_event(key)?;
+    let is_repeat = key.kind == KeyEventKind::Repeat;
+    // Ctrl+C and Ctrl+Z work everywhere, so there's always a way out.
+    let event = if combo == interrupt_key() {
+        Some(AppEvent::Interrupt)
+    } else if combo == suspend_key() {
+        Some(AppEvent::Suspend)
+    } else {
+        None
+    };
+    if let Some(event) = event {
+        app.key_sequence.reset();
+        return (!is_repeat).then_some(Event::App(event));
+    }
+    let input_mode = app.input_mode();
+    let keymap = match &app.overlay {
+        Some(overlay) if app.modal.is_none() => Rc::new(app.key_bindings.overlay_keymap(overlay)),
+        _ => app.keymap(),
+    };
+    let modal = app.modal.as_ref();
+    let event = match input_mode {
+        // Every key is a candidate binding while rebinding an action.
+        InputMode::KeyCapture => Some(Event::Overlay(OverlayEvent::Capture(combo))),
+        InputMode::TextEntry => {
+            app.key_sequence.reset();
+            resolve_key(&keymap, key).or_else(|| text_entry_event(key))
+        }
+        // A held key shouldn't start or complete a key sequence.
+        InputMode::Normal if is_repeat || app.overlay.is_some() => keymap.get(&combo).cloned(),
+        InputMode::Normal => {
+            let sequences = app.key_bindings.current_sequences(modal, input_mode);
+            app.key_sequence.resolve(combo, &sequences, &keymap, now)
+        }
+    };
+    event.filter(|event| !is_repeat || event.is_repeatable())
+}
+
+/// Modifies the application state in response to an event, returning what
+/// the event loop should do next.
+pub fn handle_event(app: &mut App, event: &Event) -> Action {
+    // Ticks are frequent and only advance time-based state, so they skip the
+    // logging and everything else below.
+    if let Event::Tick(now) = event {
+        if app.tick(*now) {
+            app.redraw_needed = true;
+        }
+        return if app.timed_out() {
+            Action::Exit
+        } else {
+            Action::Continue
+        };
+    }
+    debug!(?event, screen = %app.screen(), "handling event");
+    if app.journal.is_none() {
+        let action = dispatch_event(app, event);
+        app.note_inspected_var();
+        return action;
+    }
+    // Every change is found by comparing the state around the dispatch, so
+    // handlers don't need to know about the JSON log.
+    let before = JournalSnapshot::take(app);
+    let prompt_command = submitted_prompt_command(app, event);
+    let action = dispatch_event(app, event);
+    let after = JournalSnapshot::take(app);
+    app.record_actions(&diff_snapshots(
+        &before,
+        &after,
+        prompt_command,
+        action == Action::Exit,
+    ));
+    app.note_inspected_var();
+    action
+}
+
+/// Sends an event to whichever part of the app handles it.
+fn dispatch_event(app: &mut App, event: &Event) -> Action {
+    match event {
+        Event::App(AppEvent::Interrupt) => return app.interrupt(Instant::now()),
+        Event::App(AppEvent::Suspend) => return Action::Suspend,
+        // Whoever sent the request can't see what's on screen, so it doesn't
+        // wait for modals or overlays.
+        Event::Control(request) => return handle_control_request(app, request),
+        _ => {}
+    }
+    if app.modal.is_some() {
+        return handle_modal_event(app, event);
+    }
+    if app.overlay.is_some() {
+        handle_overlay_event(app, event);
+        return Action::Continue;
+    }
+    if let Some(mutation) = event.mutation()
+        && !app.check_writable(mutation)
+    {
+        return Action::Continue;
+    }
+    if let Event::App(app_event) = event {
+        match app_event {
+            AppEvent::ExitRequested => {
+                app.open_modal(Modal::exit());
+            }
+            AppEvent::NextTab => {
+                app.next_tab();
+            }
+            AppEvent::PrevTab => {
+                app.prev_tab();
+            }
+            AppEvent::GoTo(screen) => {
+                app.set_screen(screen.clone());
+            }
+            AppEvent::ShowHelp => {
+                app.set_overlay(Some(Overlay::Help));
+            }
+            AppEvent::ReloadConfig => app.reload_config(),
+            AppEvent::DismissMessage => app.dismiss_message(),
+            AppEvent::RunMacro(name) => run_macro(app, name),
+            // Handled first thing, since these work even in a modal.
+            AppEvent::Interrupt | AppEvent::Suspend => {}
+        }
+        return Action::Continue;
+    }
+    app.screen().controller().handle(app, event)
+}
+
+/// Returns whether the user asked for colorless output by setting `NO_COLOR`
+/// to a non-empty value, see <https://no-color.org>.
+fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+    use crate::{
+        app::{
+            commands::{set_var_cmd, tests::run_in},
+            home::HomeAction,
+            interrupt::FORCE_QUIT_WINDOW,
+            origins::INHERITED,
+            trace::{CallCtx, CallFrame, CallStack, FrameKind},
+            vars::{DetailView, MAX_SPLIT_ENTRIES},
+        },
+        cli::test_args,
+        test_dir::TestDir,
+    };
+
+    fn test_app() -> App {
+        App::new(&test_args(), &TerminalInfo::default()).unwrap()
+    }
+
+    fn press(app: &mut App, c: char) -> Option<Event> {
+        let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        resolve_event(app, &key, Instant::now())
+    }
+
+    fn key_with_kind(code: KeyCode, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(code, KeyModifiers::NONE, kind)
+    }
+
+    /// Resolves and handles a key event the way `run_app` does.
+    fn feed(app: &mut App, key: KeyEvent) {
+        if let Some(event) = resolve_event(app, &key, Instant::now()) {
+            handle_event(app, &event);
+        }
+    }
+
+    #[test]
+    fn held_down_key_advances_vars_selection() {
+        let env = (0..10)
+            .map(|i| (format!("VAR_{i}"), i.to_string()))
+            .collect::<HashMap<_, _>>();
+        let mut app = test_app().with_env(&env);
+        app.set_screen(Screen::Vars);
+        feed(&mut app, key_with_kind(KeyCode::Down, KeyEventKind::Press));
+        for _ in 0..3 {
+            feed(&mut app, key_with_kind(KeyCode::Down, KeyEventKind::Repeat));
+        }
+        feed(
+            &mut app,
+            key_with_kind(KeyCode::Down, KeyEventKind::Release),
+        );
+        assert_eq!(app.env_mut().var_list_state().selected(), Some(4));
+    }
+
+    #[test]
+    fn held_down_action_keys_trigger_once() {
+        let mut app = test_app();
+        let repeat = key_with_kind(KeyCode::Char('q'), KeyEventKind::Repeat);
+        assert_eq!(resolve_event(&mut app, &repeat, Instant::now()), None);
+
+        app.set_screen(Screen::Prompt);
+        app.prompt_mut().set_input_focused(true);
+        feed(
+            &mut app,
+            key_with_kind(KeyCode::Char('a'), KeyEventKind::Press),
+        );
+        feed(
+            &mut app,
+            key_with_kind(KeyCode::Char('a'), KeyEventKind::Repeat),
+        );
+        feed(
+            &mut app,
+            key_with_kind(KeyCode::Char('a'), KeyEventKind::Release),
+        );
+        assert_eq!(app.prompt().input(), "aa");
+        feed(
+            &mut app,
+            key_with_kind(KeyCode::Backspace, KeyEventKind::Press),
+        );
+        feed(
+            &mut app,
+            key_with_kind(KeyCode::Backspace, KeyEventKind::Repeat),
+        );
+        assert_eq!(app.prompt().input(), "");
+    }
+
+    #[test]
+    fn sequences_resolve_in_normal_mode() {
+        let mut app = test_app();
+        assert_eq!(press(&mut app, 'g'), None);
+        assert!(app.key_sequence().pending().is_some());
+        assert_eq!(press(&mut app, 't'), Some(Event::App(AppEvent::NextTab)));
+        assert_eq!(press(&mut app, 'G'), Some(Event::Nav(NavEvent::Bottom)));
+    }
+
+    #[test]
+    fn moving_around_an_empty_call_stack_does_nothing() {
+        let args = Cli {
+            bash_source: Some("run.sh".to_string()),
+            funcname: Some("main".to_string()),
+            bash_lineno: Some("0".to_string()),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Trace);
+        for key in ['G', 'j', 'k', 'g'] {
+            feed(
+                &mut app,
+                KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE),
+            );
+        }
+        assert_eq!(app.screen(), Screen::Trace);
+    }
+
+    #[test]
+    fn handle_event_returns_actions() {
+        let mut app = test_app();
+        let next_tab = Event::App(AppEvent::NextTab);
+        assert_eq!(handle_event(&mut app, &next_tab), Action::Continue);
+        assert_eq!(app.screen(), Screen::Prompt);
+
+        // Screen handlers only ever continue.
+        app.set_screen(Screen::Vars);
+        assert_eq!(
+            handle_event(&mut app, &Event::Nav(NavEvent::Down)),
+            Action::Continue
+        );
+        app.set_screen(Screen::Trace);
+        assert_eq!(
+            handle_event(&mut app, &Event::Nav(NavEvent::Bottom)),
+            Action::Continue
+        );
+
+        // Cancelling the exit modal continues, confirming it exits.
+        let exit = Event::App(AppEvent::ExitRequested);
+        let select = Event::Modal(ModalEvent::Select);
+        assert_eq!(handle_event(&mut app, &exit), Action::Continue);
+        assert_eq!(handle_event(&mut app, &select), Action::Continue);
+        assert!(!app.is_displaying_exit_modal());
+        handle_event(&mut app, &exit);
+        assert_eq!(
+            handle_event(&mut app, &Event::Modal(ModalEvent::Next)),
+            Action::Continue
+        );
+        assert_eq!(handle_event(&mut app, &select), Action::Exit);
+    }
+
+    #[test]
+    fn only_keys_that_change_the_screen_redraw() {
+        let key = |code| TermEvent::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let env = (0..3)
+            .map(|i| (format!("VAR_{i}"), i.to_string()))
+            .collect::<HashMap<_, _>>();
+        let mut app = test_app().with_env(&env).with_screen(Screen::Vars);
+        let now = Instant::now();
+
+        // Nothing is bound to `z`.
+        let before = app.view_fingerprint();
+        assert_eq!(
+            step(&mut app, Some(&key(KeyCode::Char('z'))), now),
+            LoopAction::Wait
+        );
+        assert_eq!(app.view_fingerprint(), before);
+
+        // The first variable is already selected, so there's nowhere to go.
+        assert_eq!(
+            step(&mut app, Some(&key(KeyCode::Up)), now),
+            LoopAction::Wait
+        );
+        assert_eq!(
+            step(&mut app, Some(&key(KeyCode::Down)), now),
+            LoopAction::Redraw
+        );
+        assert_eq!(
+            step(&mut app, Some(&key(KeyCode::Char('s'))), now),
+            LoopAction::Redraw
+        );
+
+        // Opening and closing a modal both redraw.
+        assert_eq!(
+            step(&mut app, Some(&key(KeyCode::Char('q'))), now),
+            LoopAction::Redraw
+        );
+        assert_eq!(
+            step(&mut app, Some(&key(KeyCode::Esc)), now),
+            LoopAction::Redraw
+        );
+        assert_eq!(app.modal(), None);
+    }
+
+    #[test]
+    fn secrets_are_only_paged_once_revealed() {
+        let key = |c| TermEvent::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let env = HashMap::from([("GITHUB_TOKEN".to_string(), "ghp_secret".to_string())]);
+        let mut app = test_app().with_env(&env).with_screen(Screen::Vars);
+        let now = Instant::now();
+        assert_eq!(step(&mut app, Some(&key('p')), now), LoopAction::Redraw);
+        assert_eq!(app.paged(), None);
+        let toast = app.toasts().visible().last().unwrap();
+        assert!(toast.text.contains("reveal it"), "{}", toast.text);
+
+        assert_eq!(step(&mut app, Some(&key('v')), now), LoopAction::Redraw);
+        assert_eq!(step(&mut app, Some(&key('p')), now), LoopAction::Page);
+        assert_eq!(app.paged(), Some(&Paged::Content("ghp_secret".to_string())));
+    }
+
+    #[test]
+    fn pager_keys_hand_content_to_the_event_loop() {
+        let key = |c| TermEvent::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let env = HashMap::from([("LS_COLORS".to_string(), "di=01;34".to_string())]);
+        let mut app = test_app().with_env(&env).with_screen(Screen::Vars);
+        let now = Instant::now();
+        assert_eq!(step(&mut app, Some(&key('p')), now), LoopAction::Page);
+        assert_eq!(app.paged(), Some(&Paged::Content("di=01;34".to_string())));
+
+        app.set_screen(Screen::Output);
+        app.push_output_line("export FOO=bar");
+        assert_eq!(step(&mut app, Some(&key('p')), now), LoopAction::Page);
+        assert_eq!(app.paged(), Some(&Paged::Content(app.final_output())));
+
+        let frame = |lines: Option<Vec<String>>| CallFrame {
+            ctx: CallCtx {
+                file: PathBuf::from("/nonexistent/my hooks.sh"),
+                line: 7,
+                function: "setup".to_string(),
+                kind: FrameKind::Func,
+            },
+            lines,
+        };
+        let frames = vec![frame(Some(vec![String::new(); 8])), frame(None)];
+        app.trace = TraceState::new(None, Some(CallStack { frames }));
+        app.set_screen(Screen::Trace);
+        assert_eq!(step(&mut app, Some(&key('o')), now), LoopAction::Page);
+        assert_eq!(
+            app.paged(),
+            Some(&Paged::File {
+                path: PathBuf::from("/nonexistent/my hooks.sh"),
+                line: 7
+            })
+        );
+        // Frames whose file couldn't be read have nothing to page.
+        app.paged = None;
+        app.trace_mut().list_state().unwrap().select(Some(1));
+        assert_eq!(step(&mut app, Some(&key('o')), now), LoopAction::Redraw);
+        assert_eq!(app.paged(), None);
+        let toast = app.toasts().visible().last().unwrap();
+        assert!(toast.text.contains("couldn't be read"), "{}", toast.text);
+    }
+
+    #[test]
+    fn step_dispatches_poll_results() {
+        let key = |c| TermEvent::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let start = Instant::now();
+        let mut app = test_app();
+        app.countdown = Countdown::start(Duration::from_secs(60), start);
+
+        // Keys are handled exactly as before, and cancel the countdown.
+        assert_eq!(step(&mut app, Some(&key('g')), start), LoopAction::Redraw);
+        assert!(app.key_sequence().pending().is_some());
+        assert_eq!(app.countdown(), Countdown::Cancelled);
+        assert_eq!(step(&mut app, Some(&key('t')), start), LoopAction::Redraw);
+        assert_eq!(app.screen(), Screen::Prompt);
+
+        // A tick with nothing to do doesn't redraw, but one that expires a
+        // pending key sequence does.
+        assert_eq!(step(&mut app, None, start), LoopAction::Wait);
+        step(&mut app, Some(&key('g')), start);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(step(&mut app, None, later), LoopAction::Redraw);
+        assert!(app.key_sequence().pending().is_none());
+        assert_eq!(step(&mut app, None, later), LoopAction::Wait);
+
+        assert_eq!(
+            step(&mut app, Some(&TermEvent::Resize(80, 24)), later),
+            LoopAction::Resize {
+                width: 80,
+                height: 24
+            }
+        );
+        assert_eq!(
+            step(&mut app, Some(&TermEvent::FocusGained), later),
+            LoopAction::Wait
+        );
+
+        // Ticking past the deadline exits an unattended session.
+        let mut app = test_app();
+        app.countdown = Countdown::start(Duration::from_secs(5), start);
+        assert_eq!(step(&mut app, None, start), LoopAction::Wait);
+        assert_eq!(
+            step(&mut app, None, start + Duration::from_secs(5)),
+            LoopAction::Exit
+        );
+    }
+
+    /// Returns a source of `events` that fails once they run out.
+    fn scripted_events(events: Vec<TermEvent>) -> EventSource {
+        let mut events = events.into_iter();
+        EventSource::spawn(move |_timeout| {
+            events
+                .next()
+                .map(Some)
+                .ok_or_else(|| std::io::Error::other("out of input"))
+        })
+    }
+
+    #[test]
+    fn run_app_takes_scripted_input() {
+        let keys = [
+            KeyCode::Char('g'),
+            KeyCode::Char('t'),
+            KeyCode::Char('q'),
+            KeyCode::Left,
+            KeyCode::Enter,
+        ];
+        let script = std::iter::once(TermEvent::Resize(90, 30))
+            .chain(
+                keys.into_iter()
+                    .map(|code| TermEvent::Key(KeyEvent::new(code, KeyModifiers::NONE))),
+            )
+            .collect();
+        let mut app = test_app();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+        run_app(&mut app, &mut terminal, &scripted_events(script)).unwrap();
+        assert_eq!(app.screen(), Screen::Prompt);
+
+        // Running out of input is an error rather than a hang.
+        let mut app = test_app();
+        assert!(run_app(&mut app, &mut terminal, &scripted_events(vec![])).is_err());
+    }
+
+    #[test]
+    fn sequences_are_typed_as_text_while_entering_text() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        app.prompt_mut().set_input_focused(true);
+        assert_eq!(
+            press(&mut app, 'g'),
+            Some(Event::Prompt(PromptEvent::Insert('g')))
+        );
+        assert!(app.key_sequence().pending().is_none());
+        assert_eq!(
+            press(&mut app, 'g'),
+            Some(Event::Prompt(PromptEvent::Insert('g')))
+        );
+    }
+
+    #[test]
+    fn bindings_editor_rebinds_captured_key() {
+        let mut app = test_app();
+        feed(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE),
+        );
+        assert!(matches!(app.overlay(), Some(Overlay::Help)));
+        // Keys bound on the screen below don't leak through the overlay.
+        assert_eq!(press(&mut app, 'q'), None);
+        feed(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('b'), KeyModifiers::NONE),
+        );
+        // The first entry is the exit binding.
+        feed(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.input_mode(), InputMode::KeyCapture);
+        feed(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.input_mode(), InputMode::Normal);
+        feed(&mut app, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(app.overlay().is_none());
+        assert_eq!(
+            press(&mut app, 'Q'),
+            Some(Event::App(AppEvent::ExitRequested))
+        );
+        assert_eq!(press(&mut app, 'q'), None);
+    }
+
+    #[test]
+    fn theme_reloads_when_config_changes() {
+        let dir = TestDir::new("reload");
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "[theme]\nfg = \"red\"\n").unwrap();
+        let args = Cli {
+            config: Some(path.clone()),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        assert_eq!(app.theme().fg.fg, Some(Color::Red));
+
+        std::fs::write(&path, "[theme]\nfg = \"blue\"\n").unwrap();
+        // Pretend the previous check saw an older version of the file.
+        app.config_mtime = None;
+        app.tick(Instant::now() + CONFIG_POLL_INTERVAL);
+        assert_eq!(app.theme().fg.fg, Some(Color::Blue));
+        let toast = app.toasts().visible().last().unwrap();
+        assert_eq!(toast.severity, Severity::Success);
+
+        // A broken config keeps the previous theme.
+        std::fs::write(&path, "[theme]\nfg = \"bleu\"\n").unwrap();
+        app.reload_config();
+        assert_eq!(app.theme().fg.fg, Some(Color::Blue));
+        let message = app.messages().current().unwrap();
+        assert!(
+            message.severity == Severity::Error && message.text.contains("bleu"),
+            "{message:?}"
+        );
+    }
+
+    #[test]
+    fn undo_removes_the_last_command_the_user_queued() {
+        let env = HashMap::from([
+            ("FOO".to_string(), "bar".to_string()),
+            ("GONE".to_string(), "x".to_string()),
+        ]);
+        let mut app = test_app().with_env(&env).with_screen(Screen::Output);
+        app.queue_set_var("FOO", "baz").unwrap();
+        app.queue_unset_var("GONE").unwrap();
+        // Where the shell pauses next is the debugger's to manage.
+        app.set_tracepoint_disposition(TracepointDisposition::PauseAtAll);
+        let tracepoint = app.output_lines().last().unwrap().to_string();
+        let entries = ["/a".to_string(), "/b".to_string()];
+        app.queue_set_path_var("MANPATH", &entries).unwrap();
+        app.queue_raw("echo hi");
+        assert_eq!(app.var_changes().len(), 3);
+
+        let undo = Event::Output(OutputEvent::Undo);
+        for (expected, changes) in [
+            ("Undid raw command: echo hi", 3),
+            ("Undid setting MANPATH", 2),
+            ("Undid unsetting GONE", 1),
+            ("Undid setting FOO", 0),
+        ] {
+            app.toasts = ToastQueue::default();
+            handle_event(&mut app, &undo);
+            let toast = app.toasts().visible().last().unwrap();
+            assert_eq!(toast.text, expected);
+            assert_eq!(app.var_changes().len(), changes, "{expected}");
+        }
+        assert_eq!(app.output_lines(), [tracepoint.as_str()]);
+
+        handle_event(&mut app, &undo);
+        assert_eq!(app.output_lines(), [tracepoint.as_str()]);
+        let message = app.messages().current().unwrap();
+        assert_eq!(message.text, "Nothing to undo");
+
+        // Commands deleted by hand are skipped.
+        app.queue_raw("echo one");
+        app.queue_raw("echo two");
+        app.remove_output_line(2);
+        handle_event(&mut app, &undo);
+        assert_eq!(app.output_lines(), [tracepoint.as_str()]);
+    }
+
+    #[test]
+    fn read_only_mode_blocks_mutations() {
+        let env = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+        let mut app = test_app().with_env(&env);
+        app.read_only = true;
+        app.push_output_line("echo queued before");
+        let output = app.output();
+
+        app.set_screen(Screen::Output);
+        app.output_state_mut().list_state().select_first();
+        for event in [
+            OutputEvent::DeleteSelected,
+            OutputEvent::EditSelected,
+            OutputEvent::Clear,
+            OutputEvent::Undo,
+        ] {
+            handle_event(&mut app, &Event::Output(event));
+            assert_eq!(app.output(), output);
+            let toast = app.toasts().visible().last().unwrap();
+            assert_eq!(toast.severity, Severity::Error);
+        }
+        assert_eq!(app.screen(), Screen::Output);
+
+        app.set_screen(Screen::Prompt);
+        app.prompt_mut().set_input_focused(true);
+        app.prompt_mut().set_input("raw echo hi");
+        handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        assert_eq!(app.output(), output);
+
+        assert_eq!(app.env().vars(), ["FOO"]);
+        assert_eq!(app.final_output(), app.initial_output());
+    }
+
+    #[test]
+    fn log_file_records_session_events() {
+        let dir = TestDir::new("log");
+        let path = dir.join("debugger.log");
+        let subscriber = crate::logging::file_subscriber(&path).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut app = test_app();
+            app.set_screen(Screen::Prompt);
+            app.prompt_mut().set_input_focused(true);
+            app.prompt_mut().set_input("raw echo hi");
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        });
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        for marker in ["loading config", "handling event", "queued command"] {
+            assert!(log.contains(marker), "missing {marker:?} in:\n{log}");
+        }
+        assert!(log.contains("echo hi"), "{log}");
+    }
+
+    #[test]
+    fn frames_jump_to_the_variables_they_use() {
+        let env = [("FLOX_ENV", "/env"), ("HOME", "/home"), ("PATH", "/bin")]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .into();
+        let mut app = test_app().with_env(&env);
+        let frame = |line, lines: Option<&[&str]>| CallFrame {
+            ctx: CallCtx {
+                file: PathBuf::from("/nonexistent/hooks.sh"),
+                line,
+                function: "setup".to_string(),
+                kind: FrameKind::Func,
+            },
+            lines: lines.map(|lines| lines.iter().map(|line| line.to_string()).collect()),
+        };
+        let source: &[&str] = &[
+            "setup() {",
+            "  export PATH=\"$FLOX_ENV/bin:$PATH\"",
+            "  fdb_tracepoint",
+            "}",
+            "",
+            "",
+            "echo \"$UNSET_VAR\"",
+            "setup",
+        ];
+        app.trace = TraceState::new(
+            None,
+            Some(CallStack {
+                frames: vec![
+                    frame(3, Some(source)),
+                    frame(8, Some(source)),
+                    frame(1, None),
+                ],
+            }),
+        );
+        app.set_screen(Screen::Trace);
+        handle_event(&mut app, &Event::Trace(TraceEvent::ShowVars));
+        assert_eq!(app.screen(), Screen::Vars);
+        assert_eq!(app.env().filter_label(), Some("hooks.sh:3"));
+        assert_eq!(app.env().origin_filter(), None);
+        let (shown, _) = app.env_mut().filtered_vars().unwrap();
+        assert_eq!(shown, [0, 2]);
+        assert_eq!(app.env().selected_var().as_deref(), Some("FLOX_ENV"));
+
+        // Frames that use nothing that's set stay where they are.
+        for (frame, message) in [
+            (1, "None of the variables used around hooks.sh:8 are set"),
+            (2, "The source of /nonexistent/hooks.sh couldn't be read"),
+        ] {
+            app.set_screen(Screen::Trace);
+            app.trace_mut().list_state().unwrap().select(Some(frame));
+            handle_event(&mut app, &Event::Trace(TraceEvent::ShowVars));
+            assert_eq!(app.screen(), Screen::Trace);
+            let toast = app.toasts().visible().last().unwrap();
+            assert_eq!(toast.text, message);
+        }
+
+        // Cycling origins clears the filter when there are no origins.
+        app.set_screen(Screen::Vars);
+        handle_event(&mut app, &Event::Vars(VarsEvent::CycleOrigin));
+        assert_eq!(app.env().filter_label(), None);
+    }
+
+    #[test]
+    fn break_completes_and_queues_known_tracepoints() {
+        let mut app = test_app();
+        app.trace = TraceState::new(Some("setup".to_string()), None)
+            .with_known_tracepoints(vec!["setup".to_string(), "teardown".to_string()]);
+        app.set_screen(Screen::Trace);
+        handle_event(&mut app, &Event::Trace(TraceEvent::SetBreakpoint));
+        assert_eq!(app.screen(), Screen::Prompt);
+        assert_eq!(app.input_mode(), InputMode::TextEntry);
+        assert_eq!(app.prompt().input(), "break ");
+        assert_eq!(
+            app.prompt().scrollback().last().unwrap(),
+            "setup  teardown  next  all"
+        );
+
+        handle_event(&mut app, &Event::Prompt(PromptEvent::Insert('t')));
+        handle_event(&mut app, &Event::Prompt(PromptEvent::Complete));
+        assert_eq!(app.prompt().input(), "break teardown ");
+        handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        assert!(
+            app.output()
+                .ends_with("export FLOX_DBG_TRACEPOINT='teardown'\n")
+        );
+
+        let output = app.output();
+        app.prompt_mut().set_input("break activate");
+        handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        assert_eq!(app.output(), output);
+        assert!(
+            app.prompt()
+                .scrollback()
+                .last()
+                .unwrap()
+                .contains("unknown tracepoint")
+        );
+    }
+
+    #[test]
+    fn break_accepts_globs_that_match_known_tracepoints() {
+        let mut app = test_app();
+        let known = ["setup", "py-build", "py-test"].map(String::from).to_vec();
+        app.trace = TraceState::new(Some("setup".to_string()), None).with_known_tracepoints(known);
+        app.set_screen(Screen::Prompt);
+        let submit = |app: &mut App, input: &str| {
+            app.prompt_mut().set_input(input);
+            handle_event(app, &Event::Prompt(PromptEvent::Submit));
+            app.prompt().scrollback().last().unwrap().clone()
+        };
+
+        assert!(submit(&mut app, "break 'node-*'").contains("no known tracepoint matches"));
+        assert!(submit(&mut app, "break py-[bt]*").contains("'['"));
+        assert_eq!(
+            app.tracepoint_disposition(),
+            &TracepointDisposition::Continue
+        );
+        submit(&mut app, "break 'py-*'");
+        assert_eq!(app.queued_commands(), ["export FLOX_DBG_TRACEPOINT='py-*'"]);
+
+        submit(&mut app, "tracepoints");
+        let scrollback = app.prompt().scrollback();
+        assert_eq!(
+            scrollback[scrollback.len() - 3..],
+            [
+                "  setup",
+                "* py-build (matches 'py-*')",
+                "* py-test (matches 'py-*')"
+            ]
+        );
+    }
+
+    #[test]
+    fn tracepoint_commands_replace_each_other() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        app.push_output_line("echo queued");
+        let submit = |app: &mut App, input: &str| {
+            app.prompt_mut().set_input(input);
+            handle_event(app, &Event::Prompt(PromptEvent::Submit));
+        };
+
+        submit(&mut app, "break setup");
+        submit(&mut app, "step");
+        assert_eq!(
+            app.queued_commands(),
+            ["echo queued", "export FLOX_DBG_TRACEPOINT='next'"]
+        );
+        assert_eq!(
+            app.tracepoint_disposition(),
+            &TracepointDisposition::PauseAtNext
+        );
+
+        // Continuing is what the shell would do anyway, so no stale export is
+        // left behind and nothing replaces it.
+        submit(&mut app, "continue");
+        assert_eq!(app.queued_commands(), ["echo queued"]);
+
+        submit(&mut app, "break all");
+        app.clear_output();
+        assert_eq!(
+            app.tracepoint_disposition(),
+            &TracepointDisposition::Continue
+        );
+        submit(&mut app, "continue");
+        assert_eq!(app.output(), app.initial_output());
+    }
+
+    #[test]
+    fn tracing_toggles_collapse() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        let submit = |app: &mut App, input: &str| {
+            app.prompt_mut().set_input(input);
+            handle_event(app, &Event::Prompt(PromptEvent::Submit));
+        };
+
+        submit(&mut app, "trace on");
+        submit(&mut app, "trace on");
+        assert_eq!(app.queued_commands(), ["set -x"]);
+        assert!(
+            app.prompt()
+                .scrollback()
+                .last()
+                .unwrap()
+                .contains("after exiting")
+        );
+        submit(&mut app, "trace off");
+        assert!(app.queued_commands().is_empty());
+
+        submit(&mut app, "trace off");
+        submit(&mut app, "raw echo hi");
+        submit(&mut app, "trace on");
+        assert_eq!(app.queued_commands(), ["set +x", "echo hi", "set -x"]);
+
+        submit(&mut app, "trace maybe");
+        assert_eq!(
+            app.prompt().scrollback().last().unwrap(),
+            "usage: trace <on|off>"
+        );
+    }
+
+    #[test]
+    fn functions_are_checked_before_being_queued() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        for input in [
+            "function up cd ..; ls",
+            "function down }",
+            "function nobody",
+        ] {
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        }
+        assert_eq!(app.queued_commands(), ["up() { cd ..; ls; }"]);
+        let scrollback = app.prompt().scrollback();
+        assert_eq!(scrollback[1], "will define 'up' after exiting");
+        assert_eq!(
+            scrollback[3],
+            "function: function body has unbalanced blocks"
+        );
+        assert_eq!(scrollback[5], "usage: function <name> <body>");
+    }
+
+    #[test]
+    fn cd_resolves_against_the_shells_working_directory() {
+        let dir = TestDir::new("cwd");
+        let cwd = dir.path().to_path_buf();
+        let args = Cli {
+            cwd: Some(cwd.clone()),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Prompt);
+        for input in ["cd .", "cd no such dir"] {
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        }
+        let missing = cwd.join("no such dir");
+        assert_eq!(
+            app.queued_commands(),
+            [
+                Shell::Bash.quote(&cwd.join(".").to_string_lossy(), |dir| format!("cd {dir}")),
+                Shell::Bash.quote(&missing.to_string_lossy(), |dir| format!("cd {dir}")),
+            ]
+        );
+        let scrollback = app.prompt().scrollback();
+        assert!(scrollback[1].starts_with("will change to"));
+        assert_eq!(
+            scrollback[3],
+            format!("cd: warning: {} doesn't exist", missing.display())
+        );
+    }
+
+    #[test]
+    fn huge_lists_are_only_split_once_confirmed() {
+        let entries = vec!["x"; MAX_SPLIT_ENTRIES + 1].join(":");
+        let env = HashMap::from([("HUGE_PATH".to_string(), entries)]);
+        let mut app = test_app().with_env(&env).with_screen(Screen::Vars);
+        assert_eq!(app.env().detail_view(), DetailView::Raw);
+
+        handle_event(&mut app, &Event::Vars(VarsEvent::SplitDetail));
+        assert_eq!(app.env().detail_view(), DetailView::Raw);
+        let Some(Modal::Confirm { message, .. }) = app.modal() else {
+            panic!("expected a confirmation, found {:?}", app.modal());
+        };
+        assert!(
+            message.starts_with("The value has 10001 entries,"),
+            "{message}"
+        );
+        handle_event(&mut app, &Event::Modal(ModalEvent::Accept));
+        assert_eq!(app.env().detail_view(), DetailView::Split);
+        assert_eq!(app.env().selected_var_split_value().unwrap().len(), 10_001);
+
+        // A saved session doesn't split it either.
+        let mut app = test_app().with_env(&env);
+        app.env_mut().set_detail_view(DetailView::Split);
+        assert_eq!(app.env().detail_view(), DetailView::Raw);
+    }
+
+    #[test]
+    fn lists_are_split_when_selected() {
+        let env = HashMap::from([
+            ("EDITOR".to_string(), "vim".to_string()),
+            ("MANPATH".to_string(), "/usr/share/man".to_string()),
+            ("TIMES".to_string(), "1:2:3".to_string()),
+        ]);
+        let mut app = test_app().with_env(&env).with_screen(Screen::Vars);
+        let down = Event::Nav(NavEvent::Down);
+        assert_eq!(app.env().detail_view(), DetailView::Raw);
+        handle_event(&mut app, &down);
+        assert_eq!(app.env().detail_view(), DetailView::Split);
+        assert_eq!(
+            app.env().selected_detail_item().as_deref(),
+            Some("/usr/share/man")
+        );
+
+        // The view can still be changed by hand.
+        handle_event(&mut app, &Event::Vars(VarsEvent::RawDetail));
+        assert_eq!(app.env().detail_view(), DetailView::Raw);
+        handle_event(&mut app, &down);
+        assert_eq!(app.env().detail_view(), DetailView::Split);
+        assert_eq!(app.env().selected_detail_item().as_deref(), Some("1"));
+
+        // Without it, the view stays the same from one variable to the next.
+        handle_event(&mut app, &Event::Vars(VarsEvent::ToggleAutoSplit));
+        assert!(!app.auto_split());
+        handle_event(&mut app, &Event::Nav(NavEvent::Top));
+        assert_eq!(app.env().detail_view(), DetailView::Split);
+        assert_eq!(app.env().selected_detail_item().as_deref(), Some("vim"));
+    }
+
+    #[test]
+    fn var_origins_are_loaded_before_starting() {
+        let dir = TestDir::new("origins");
+        let env_file = dir.join("env");
+        std::fs::write(&env_file, "EDITOR=vim\nHOME=/home/user\n").unwrap();
+        let good = dir.join("origins.toml");
+        std::fs::write(&good, "EDITOR = \"manifest\"\n").unwrap();
+        let bad = dir.join("origins");
+        std::fs::write(&bad, "EDITOR manifest\n").unwrap();
+
+        let args = Cli {
+            env_file: Some(env_file.clone()),
+            var_origins: Some(good),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        assert_eq!(app.env().selected_var_origin(), Some("manifest"));
+        app.set_screen(Screen::Vars);
+        handle_event(&mut app, &Event::Nav(NavEvent::Down));
+        assert_eq!(app.env().selected_var_origin(), Some(INHERITED));
+
+        let args = Cli {
+            env_file: Some(env_file),
+            var_origins: Some(bad.clone()),
+            ..test_args()
+        };
+        let err = App::new(&args, &TerminalInfo::default()).err().unwrap();
+        assert_eq!(
+            format!("{err:#}"),
+            format!(
+                "invalid variable origins {}: line 1: expected NAME=origin, found 'EDITOR manifest'",
+                bad.display()
+            )
+        );
+    }
+
+    #[test]
+    fn reloading_the_env_keeps_queued_changes() {
+        let dir = TestDir::new("reload");
+        let env_file = dir.join("env");
+        std::fs::write(&env_file, "EDITOR=vim\nHOME=/home/user\nPAGER=less\n").unwrap();
+        let args = Cli {
+            env_file: Some(env_file.clone()),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Vars);
+        app.queue_set_var("EDITOR", "nano").unwrap();
+        app.queue_set_var("PAGER", "most").unwrap();
+        let output = app.output();
+
+        std::fs::write(
+            &env_file,
+            "EDITOR=emacs\nHOME=/home/user\nPAGER=less\nTERM=xterm\n",
+        )
+        .unwrap();
+        feed(&mut app, KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(app.env().value_of("EDITOR"), Some("emacs"));
+        assert_eq!(app.env().value_of("TERM"), Some("xterm"));
+        assert_eq!(app.output(), output);
+        let toast = app.toasts().visible().last().unwrap();
+        assert_eq!(
+            toast.text,
+            "Reloaded the environment: 1 changed, 1 new, 0 removed, 1 conflicting with queued changes"
+        );
+        assert_eq!(toast.severity, Severity::Warning);
+        assert_eq!(
+            app.env().conflicts(),
+            &BTreeSet::from(["EDITOR".to_string()])
+        );
+        let changes = app.var_changes();
+        assert_eq!(changes[0].old.as_deref(), Some("emacs"));
+        assert_eq!(changes[0].new.as_deref(), Some("nano"));
+
+        // The conflict stands until the variable is changed again.
+        feed(&mut app, KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(app.env().conflicts().len(), 1);
+        app.queue_set_var("EDITOR", "vi").unwrap();
+        assert!(app.env().conflicts().is_empty());
+
+        // A file descriptor can't be read twice.
+        app.env_source = EnvSource::Fd(3);
+        app.reload_env();
+        let message = app.messages().current().unwrap();
+        assert!(message.text.contains("can't be read again"), "{message:?}");
+    }
+
+    #[test]
+    fn watched_variables_are_marked_when_a_reload_changes_them() {
+        let dir = TestDir::new("watch");
+        let env_file = dir.join("env");
+        std::fs::write(&env_file, "EDITOR=vim\nHOME=/home/user\nAPI_TOKEN=abc\n").unwrap();
+        let args = Cli {
+            env_file: Some(env_file.clone()),
+            read_only: true,
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Prompt);
+        let run = |app: &mut App, input: &str| {
+            let start = app.prompt().scrollback().len() + 1;
+            app.prompt_mut().set_input(input);
+            handle_event(app, &Event::Prompt(PromptEvent::Submit));
+            app.prompt().scrollback()[start..].to_vec()
+        };
+
+        assert_eq!(run(&mut app, "watch"), ["usage: watch <name>..."]);
+        assert_eq!(
+            run(&mut app, "watch EDITOR HOME EDITOR API_TOKEN"),
+            [
+                "watching EDITOR",
+                "watching HOME",
+                "watch: already watching EDITOR",
+                "watching API_TOKEN",
+            ]
+        );
+        assert_eq!(
+            run(&mut app, "watch"),
+            ["EDITOR=vim", "HOME=/home/user", "API_TOKEN=<hidden>"]
+        );
+        assert_eq!(
+            run(&mut app, "unwatch HOME PAGER"),
+            ["stopped watching HOME", "unwatch: not watching PAGER"]
+        );
+        assert_eq!(app.watch_list().names(), ["EDITOR", "API_TOKEN"]);
+
+        std::fs::write(&env_file, "EDITOR=emacs\nHOME=/elsewhere\n").unwrap();
+        app.reload_env();
+        let changed = |app: &App| {
+            app.watch_list()
+                .iter()
+                .filter(|watched| watched.changed)
+                .map(|watched| watched.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(changed(&app), ["EDITOR", "API_TOKEN"]);
+        assert_eq!(
+            run(&mut app, "watch"),
+            ["EDITOR=emacs", "API_TOKEN=<unset>"]
+        );
+        app.reload_env();
+        assert!(changed(&app).is_empty());
+
+        // The next session watches the same variables.
+        let session = SessionState::of(&app);
+        assert_eq!(session.watched, ["EDITOR", "API_TOKEN"]);
+        let mut restored = App::new(&args, &TerminalInfo::default()).unwrap();
+        restored.restore_session(&session, &args);
+        assert_eq!(restored.watch_list().names(), ["EDITOR", "API_TOKEN"]);
+    }
+
+    #[test]
+    fn macro_keys_run_their_macros_unless_read_only() {
+        let dir = TestDir::new("macro-keys");
+        let config = dir.join("config.toml");
+        std::fs::write(
+            &config,
+            "[macros.debug]\nkey = \"Alt+d\"\nactions = [\"set DEBUG=1\", \"step\"]\n",
+        )
+        .unwrap();
+        let alt_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::ALT);
+        for screen in [Screen::Home, Screen::Output] {
+            let args = Cli {
+                config: Some(config.clone()),
+                ..test_args()
+            };
+            let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+            app.set_screen(screen);
+            feed(&mut app, alt_d);
+            assert!(app.output_lines().contains(&"export DEBUG='1'"));
+            assert_eq!(
+                app.tracepoint_disposition(),
+                &TracepointDisposition::PauseAtNext
+            );
+        }
+
+        let args = Cli {
+            config: Some(config),
+            read_only: true,
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        let output = app.output();
+        feed(&mut app, alt_d);
+        assert_eq!(app.output(), output);
+        let toast = app.toasts().visible().last().unwrap();
+        assert_eq!(toast.text, "Read-only mode: running macros is disabled");
+    }
+
+    #[test]
+    fn transcripts_are_written_relative_to_the_shells_directory() {
+        let cwd = TestDir::new("transcript");
+        let args = Cli {
+            cwd: Some(cwd.path().to_path_buf()),
+            read_only: true,
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Prompt);
+        for input in ["transcript", "transcript notes.md"] {
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        }
+        let path = cwd.join("notes.md");
+        let scrollback = app.prompt().scrollback();
+        assert_eq!(scrollback[1], "usage: transcript <path>");
+        assert_eq!(
+            scrollback[3],
+            format!("wrote the transcript to {}", path.display())
+        );
+        let transcript = std::fs::read_to_string(&path).unwrap();
+        assert!(transcript.starts_with("# flox-debugger session"));
+    }
+
+    #[test]
+    fn prompt_aliases_from_the_config_expand_before_running() {
+        let dir = TestDir::new("aliases");
+        let config = dir.join("config.toml");
+        std::fs::write(
+            &config,
+            "[aliases]\nb = \"break $1\"\nloop = \"loop\"\ntr = \"trace\"\n",
+        )
+        .unwrap();
+        let args = Cli {
+            config: Some(config),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Prompt);
+        let mut run = |input: &str| {
+            let start = app.prompt().scrollback().len() + 1;
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+            app.prompt().scrollback()[start..].to_vec()
+        };
+
+        assert_eq!(run("b setup"), ["will pause at 'setup' after exiting"]);
+        assert_eq!(
+            run("tr on"),
+            ["your shell will print each command it runs after exiting"]
+        );
+        assert_eq!(
+            run("loop"),
+            ["the alias 'loop' expands more than 8 aliases deep"]
+        );
+        let help = run("help");
+        assert!(help[0].starts_with("commands: alias break"), "{help:?}");
+        assert_eq!(
+            help[1..],
+            [
+                "aliases:",
+                "  b = break $1",
+                "  loop = loop",
+                "  tr = trace"
+            ]
+        );
+    }
+
+    #[test]
+    fn env_prints_the_variables_matching_a_glob() {
+        let env = [
+            (
+                "NIX_PATH",
+                "nixpkgs=/nix/var/nix/profiles/per-user/root/channels",
+            ),
+            ("NIX_PROFILES", "/nix/var/nix/profiles/default"),
+            ("NIX_TOKEN", "hunter2"),
+            ("MULTI", "one\ntwo"),
+            ("HOME", "/home/user"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .into();
+        let mut app = test_app().with_env(&env);
+        app.set_screen(Screen::Prompt);
+        app.prompt_mut().set_viewport_width(40);
+        let mut run = |input: &str| {
+            let start = app.prompt().scrollback().len() + 1;
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+            app.prompt().scrollback()[start..].to_vec()
+        };
+
+        assert_eq!(
+            run("env 'NIX_*'"),
+            [
+                "NIX_PATH=nixpkgs=/nix/var/nix/profiles/…",
+                "NIX_PROFILES=/nix/var/nix/profiles/defa…",
+                "NIX_TOKEN=<hidden>",
+                "3 variables match NIX_*",
+            ]
+        );
+        assert_eq!(
+            run("env --reveal NIX_[!P]*"),
+            ["NIX_TOKEN=hunter2", "1 variable matches NIX_[!P]*"]
+        );
+        assert_eq!(
+            run("env M?LTI"),
+            ["MULTI=one\\ntwo", "1 variable matches M?LTI"]
+        );
+        assert_eq!(run("env nope*"), ["0 variables match nope*"]);
+        assert_eq!(
+            run("env"),
+            [
+                "HOME",
+                "MULTI",
+                "NIX_PATH",
+                "NIX_PROFILES",
+                "NIX_TOKEN",
+                "5 variables"
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_compares_the_environment_against_a_file() {
+        let cwd = TestDir::new("diff");
+        std::fs::write(
+            cwd.join("before.env"),
+            format!("KEPT=1\nGONE=2\nEDITED=old\nLONG={}\n", "x".repeat(50)),
+        )
+        .unwrap();
+        std::fs::write(cwd.join("bad.env"), "KEPT=1\nNOT AN ENTRY\n").unwrap();
+        let args = Cli {
+            cwd: Some(cwd.path().to_path_buf()),
+            ..test_args()
+        };
+        let env = [
+            ("KEPT", "1"),
+            ("EDITED", "new\nlines"),
+            ("NEW", "it's"),
+            ("LONG", "y"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .into();
+        let mut app = App::new(&args, &TerminalInfo::default())
+            .unwrap()
+            .with_env(&env);
+        app.set_screen(Screen::Prompt);
+        let mut run = |input: &str| {
+            let start = app.prompt().scrollback().len() + 1;
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+            app.prompt().scrollback()[start..].to_vec()
+        };
+
+        let before = cwd.join("before.env");
+        assert_eq!(
+            run("diff before.env"),
+            [
+                format!(
+                    "1 added, 1 removed, 2 changed, 1 unchanged since {}",
+                    before.display()
+                ),
+                "+ NEW=\"it's\"".to_string(),
+                "- GONE=\"2\"".to_string(),
+                "~ EDITED: \"old\" → \"new\\nlines\"".to_string(),
+                format!("~ LONG: \"{}\"… → \"y\"", "x".repeat(39)),
+            ]
+        );
+        assert_eq!(
+            run("diff --names-only before.env")[1..],
+            ["+ NEW", "- GONE", "~ EDITED", "~ LONG"]
+        );
+        assert_eq!(run("diff"), ["usage: diff [--names-only] <env-file>"]);
+        let bad = run("diff bad.env");
+        assert_eq!(
+            bad,
+            [format!(
+                "diff: invalid environment file {}: line 2: expected NAME=VALUE, found 'NOT AN ENTRY'",
+                cwd.join("bad.env").display()
+            )]
+        );
+    }
+
+    #[test]
+    fn summaries_describe_the_session_without_values() {
+        use crate::app::summary::{ExitSummary, SessionSummary, TracepointSummary};
+
+        let env = HashMap::from([
+            ("FOO".to_string(), "bar".to_string()),
+            ("OLD".to_string(), "1".to_string()),
+        ]);
+        let mut app = test_app().with_env(&env);
+        app.queue_set_var("FOO", "baz").unwrap();
+        app.queue_unset_var("OLD").unwrap();
+        app.queue_set_var("NEW", "x").unwrap();
+        app.queue_raw("echo hi");
+        app.queue_raw("echo bye");
+        let last = app.output_lines().len() - 1;
+        app.remove_output_line(last);
+        app.set_tracepoint_disposition(TracepointDisposition::Continue);
+
+        let summary = SessionSummary::of(&app);
+        assert_eq!(summary.exit, ExitSummary::Applied);
+        assert_eq!(summary.tracepoint, TracepointSummary::Continue);
+        assert_eq!(summary.added_vars.names, ["NEW"]);
+        assert_eq!(summary.modified_vars.names, ["FOO"]);
+        assert_eq!(summary.unset_vars.count, 1);
+        assert_eq!(summary.raw_commands, 1);
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(!json.contains("baz"), "{json}");
+
+        app.cancel();
+        assert_eq!(SessionSummary::of(&app).exit, ExitSummary::Cancelled);
+    }
+
+    #[test]
+    fn aliases_are_queued_from_the_prompt() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        for input in ["alias ll=ls -l", "alias l l=ls", "alias ll"] {
+            app.prompt_mut().set_input(input);
+            handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        }
+        assert_eq!(app.queued_commands(), ["alias ll='ls -l'"]);
+        let scrollback = app.prompt().scrollback();
+        assert_eq!(scrollback[3], r#"alias: invalid alias name "l l""#);
+        assert_eq!(scrollback[5], "usage: alias <name>=<value>");
+    }
+
+    #[test]
+    fn initial_output_keeps_the_tracepoints_not_yet_reached() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let unset = format!(
+                "{}\n",
+                Cmd::UnsetVar {
+                    name: TRACEPOINT_VAR_NAME.to_string()
+                }
+                .render(shell)
+            );
+            let export = |value: &str| {
+                let cmd = Cmd::SetVar {
+                    name: TRACEPOINT_VAR_NAME.to_string(),
+                    value: value.to_string(),
+                };
+                format!("{}\n", cmd.render(shell))
+            };
+            let cases = [
+                ("setup", Some("setup"), unset.clone()),
+                ("setup,build,test", Some("build"), export("setup,test")),
+                ("setup,test", Some("build"), String::new()),
+                ("all", Some("build"), String::new()),
+                ("next", Some("build"), unset.clone()),
+                ("", None, String::new()),
+            ];
+            for (value, paused_at, expected) in cases {
+                assert_eq!(
+                    App::initial_output_inner(shell, value, paused_at),
+                    expected,
+                    "{shell}: {value:?} at {paused_at:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sh_dialect_is_supported() {
+        for name in ["sh", "posix", "SH"] {
+            assert!(matches!(name.parse::<Shell>(), Ok(Shell::Posix)));
+        }
+        assert_eq!(Shell::Posix.to_string(), "sh");
+        assert_eq!(Shell::Posix.quote("a'b", str::to_string), r"'a'\''b'");
+        assert_eq!(
+            App::initial_output_inner(Shell::Posix, "setup", Some("setup")),
+            "unset FLOX_DBG_TRACEPOINT\n"
+        );
+        assert_eq!(App::initial_output_inner(Shell::Posix, "all", None), "");
+
+        let stack = load_call_stack("/tmp/script.sh:12:<script>", Shell::Posix).unwrap();
+        assert_eq!(stack.frames.len(), 1);
+        assert_eq!(stack.frames[0].ctx.line, 12);
+    }
+
+    #[test]
+    fn var_commands_are_queued_on_one_line() {
+        let mut app = test_app();
+        app.queue_set_var("FOO", "two\nlines").unwrap();
+        app.queue_unset_var("BAR").unwrap();
+        assert_eq!(
+            app.queued_commands(),
+            [r"export FOO=$'two\nlines'", "unset BAR"]
+        );
+        assert!(app.queue_set_var("FOO BAR", "x").is_err());
+        assert!(app.queue_unset_var("FOO=BAR").is_err());
+        assert!(app.queue_unset_var("a;b").is_err());
+        assert!(app.queue_set_path_var("$(id)", &[]).is_err());
+        assert_eq!(app.queued_commands().len(), 2);
+    }
+
+    #[test]
+    fn protected_vars_are_only_changed_once_confirmed() {
+        let mut app = test_app();
+        let before = app.final_output();
+        assert_eq!(app.queue_unset_var("HOME").unwrap(), MutationOutcome::Held);
+        assert_eq!(app.final_output(), before);
+        assert!(app.var_changes().is_empty());
+        let Some(Modal::Confirm { message, .. }) = app.modal() else {
+            panic!("expected a confirmation, found {:?}", app.modal());
+        };
+        assert!(message.starts_with("HOME is protected,"), "{message}");
+        assert!(message.contains("\nunset HOME\n"), "{message}");
+
+        // Cancelling leaves everything as it was.
+        handle_event(&mut app, &Event::Modal(ModalEvent::Select));
+        assert_eq!(app.modal(), None);
+        assert_eq!(app.final_output(), before);
+
+        app.queue_unset_var("HOME").unwrap();
+        handle_event(&mut app, &Event::Modal(ModalEvent::Accept));
+        assert_eq!(app.queued_commands(), ["unset HOME"]);
+
+        // Raw commands are checked for the variables they obviously change.
+        assert_eq!(app.queue_raw("export FOO=1"), MutationOutcome::Queued);
+        assert_eq!(app.queue_raw("export PATH=/bin"), MutationOutcome::Held);
+        app.close_modal();
+        assert_eq!(app.queued_commands(), ["unset HOME", "export FOO=1"]);
+    }
+
+    #[test]
+    fn command_previews_match_the_queued_commands() {
+        let value = "it's \"quoted\"\nhéllo 🦀";
+        let env = HashMap::from([("MOTD".to_string(), value.to_string())]);
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let mut app = test_app().with_shell(shell).with_env(&env);
+            let preview = app.selected_var_command().unwrap();
+            app.queue_set_var("MOTD", value).unwrap();
+            assert!(app.output().contains(&preview), "{shell}: {preview}");
+        }
+        assert_eq!(
+            test_app().with_env(&HashMap::new()).selected_var_command(),
+            None
+        );
+    }
+
+    #[test]
+    fn protected_vars_are_blocked_in_read_only_mode() {
+        let dir = TestDir::new("protected");
+        let config = dir.join("config.toml");
+        std::fs::write(&config, "protected_vars = [\"KUBECONFIG\"]\n").unwrap();
+        let args = Cli {
+            config: Some(config),
+            read_only: true,
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        assert_eq!(
+            app.queue_set_var("KUBECONFIG", "/tmp/config").unwrap(),
+            MutationOutcome::Blocked
+        );
+        assert_eq!(app.modal(), None);
+        assert!(
+            app.output_lines()
+                .iter()
+                .all(|line| !line.contains("KUBECONFIG"))
+        );
+        assert_eq!(
+            app.queue_set_var("FOO", "bar").unwrap(),
+            MutationOutcome::Queued
+        );
+    }
+
+    #[test]
+    fn path_vars_warn_about_entries_containing_colons() {
+        let mut app = test_app();
+        let entries = ["/usr/bin".to_string(), "/odd:dir".to_string()];
+        // PATH is protected, so it's only queued once it's confirmed.
+        let accept = Event::Modal(ModalEvent::Accept);
+        app.queue_set_path_var("PATH", &entries).unwrap();
+        handle_event(&mut app, &accept);
+        assert_eq!(app.queued_commands(), ["export PATH='/usr/bin:/odd:dir'"]);
+        let message = app.messages().current().unwrap();
+        assert_eq!(message.severity, Severity::Warning);
+        assert!(message.text.contains("/odd:dir"), "{}", message.text);
+
+        let mut app = test_app();
+        app.queue_set_path_var("PATH", &entries[..1]).unwrap();
+        handle_event(&mut app, &accept);
+        assert!(app.messages().current().is_none());
+        assert!(app.queue_set_path_var("", &entries).is_err());
+    }
+
+    #[test]
+    fn printed_commands_end_with_exactly_one_newline() {
+        let print = |output: &str| {
+            let mut stream = Vec::new();
+            App::print_cmds_inner(output, &mut stream).unwrap();
+            String::from_utf8(stream).unwrap()
+        };
+        assert_eq!(print("echo one\necho two"), "echo one\necho two\n");
+        assert_eq!(print("echo one\n"), "echo one\n");
+        assert_eq!(print("echo one\n\n\n"), "echo one\n");
+        assert_eq!(print(""), "");
+        assert_eq!(print("\n"), "");
+    }
+
+    /// A stream whose reader has gone away, like a pipe to a process that
+    /// was interrupted.
+    struct ClosedPipe {
+        flushed: bool,
+    }
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn broken_pipes_are_not_errors() {
+        let mut stream = ClosedPipe { flushed: false };
+        App::print_cmds_inner("echo queued\n", &mut stream).unwrap();
+        // An empty output is still flushed.
+        App::print_cmds_inner("", &mut stream).unwrap();
+        assert!(stream.flushed);
+
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(ErrorKind::StorageFull.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(App::print_cmds_inner("echo queued\n", &mut Full).is_err());
+    }
+
+    #[test]
+    fn revert_helpers_save_each_variable_once() {
+        let mut app = test_app();
+        app.revert_helpers = true;
+        app.queue_set_var("FOO", "new").unwrap();
+        app.queue_set_var("FOO", "newer").unwrap();
+        app.queue_unset_var("BAR").unwrap();
+        let save = |name: &str| Cmd::SaveOriginal { name: name.into() }.render(Shell::Bash);
+        let queued = app.queued_commands();
+        assert_eq!(
+            queued[..5],
+            [
+                save("FOO").as_str(),
+                "export FOO='new'",
+                "export FOO='newer'",
+                &save("BAR"),
+                "unset BAR",
+            ]
+        );
+        assert_eq!(queued.len(), 6);
+        assert!(defines_function(
+            Shell::Bash,
+            queued[5],
+            REVERT_FUNCTION_NAME
+        ));
+
+        // Without its saved original a variable can't be reverted.
+        app.remove_output_line(
+            app.output_lines()
+                .iter()
+                .position(|line| *line == save("BAR"))
+                .unwrap(),
+        );
+        app.queue_set_var("FOO", "newest").unwrap();
+        let revert = *app.output_lines().last().unwrap();
+        assert!(
+            revert.contains("FLOX_DBG_ORIG_FOO") && !revert.contains("BAR"),
+            "{revert}"
+        );
+    }
+
+    #[test]
+    fn reverting_restores_the_original_environment() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let mut app = test_app().with_shell(shell);
+            app.clear_output();
+            app.revert_helpers = true;
+            app.queue_set_var("FOO", "new").unwrap();
+            app.queue_set_var("FOO", "it's\nnewer").unwrap();
+            app.queue_unset_var("BAR").unwrap();
+            app.queue_set_var("NEW", "x").unwrap();
+            app.queue_set_path_var("MYPATH", &["/c".to_string()])
+                .unwrap();
+
+            let print = ["FOO", "BAR", "NEW", "MYPATH"]
+                .map(|name| format!("printenv {name} || echo '<unset>'\n"))
+                .concat();
+            let script = format!(
+                "{}\n{}\n{}\n{}{print}flox_dbg_revert\n{print}command -v flox_dbg_revert || echo gone\n",
+                set_var_cmd(shell, "FOO", "orig"),
+                set_var_cmd(shell, "BAR", "bar"),
+                set_var_cmd(shell, "MYPATH", "/a:/b"),
+                app.final_output(),
+            );
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(
+                    printed, "it's\nnewer\n<unset>\nx\n/c\norig\nbar\n<unset>\n/a:/b\ngone\n",
+                    "{shell}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn countdown_exits_unattended_sessions() {
+        let start = Instant::now();
+        let mut app = test_app();
+        app.countdown = Countdown::start(Duration::from_secs(10), start);
+        app.push_output_line("echo queued");
+
+        app.tick(start + Duration::from_secs(5));
+        assert!(!app.timed_out());
+        app.tick(start + Duration::from_secs(10));
+        assert!(app.timed_out());
+        assert_eq!(app.final_output(), app.initial_output());
+    }
+
+    #[test]
+    fn home_menu_entries_do_their_actions() {
+        let activate = |steps: usize| {
+            let mut app = test_app();
+            for _ in 0..steps {
+                feed(&mut app, KeyEvent::from(KeyCode::Down));
+            }
+            feed(&mut app, KeyEvent::from(KeyCode::Enter));
+            app
+        };
+        assert_eq!(activate(0).screen(), Screen::Vars);
+        assert_eq!(activate(1).screen(), Screen::Trace);
+        let tracepoint = activate(2);
+        assert_eq!(tracepoint.screen(), Screen::Prompt);
+        assert_eq!(tracepoint.prompt().input(), "break ");
+        assert!(tracepoint.prompt().input_focused());
+        assert_eq!(activate(3).screen(), Screen::Output);
+        let exit = activate(4);
+        assert_eq!(exit.screen(), Screen::Home);
+        assert!(matches!(exit.modal(), Some(Modal::Exit { .. })));
+        // The selection stops at the last entry.
+        assert!(matches!(activate(9).modal(), Some(Modal::Exit { .. })));
+
+        let mut app = test_app();
+        feed(&mut app, KeyEvent::from(KeyCode::Down));
+        feed(&mut app, KeyEvent::from(KeyCode::Up));
+        feed(&mut app, KeyEvent::from(KeyCode::Up));
+        assert_eq!(app.home().selected(), Some(HomeAction::InspectVars));
+    }
+
+    #[test]
+    fn key_press_cancels_countdown() {
+        let start = Instant::now();
+        let mut app = test_app();
+        app.countdown = Countdown::start(Duration::from_secs(10), start);
+        app.cancel_countdown();
+        app.tick(start + Duration::from_secs(60));
+        assert!(!app.timed_out());
+        assert_eq!(app.countdown().remaining(start), None);
+    }
+
+    #[test]
+    fn json_log_records_session_actions() {
+        use crate::app::journal::{Fingerprint, JournalEntry};
+
+        let dir = TestDir::new("json-log");
+        let path = dir.join("session.jsonl");
+        let args = Cli {
+            json_log: Some(path.clone()),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Prompt)));
+        app.prompt_mut().set_input_focused(true);
+        app.prompt_mut().set_input("raw export TOKEN=hunter2");
+        handle_event(&mut app, &Event::Prompt(PromptEvent::Submit));
+        handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+        handle_event(&mut app, &Event::Modal(ModalEvent::Select));
+        handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+        assert_eq!(
+            handle_event(&mut app, &Event::Modal(ModalEvent::Accept)),
+            Action::Exit
+        );
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(!log.contains("hunter2"), "{log}");
+        let actions = log
+            .lines()
+            .map(|line| serde_json::from_str::<JournalEntry>(line).unwrap().action)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            [
+                JournalAction::SessionStarted {
+                    shell: "bash".to_string(),
+                    screen: "Home".to_string(),
+                },
+                JournalAction::ScreenChanged {
+                    from: "Home".to_string(),
+                    to: "Prompt".to_string(),
+                },
+                JournalAction::PromptCommand {
+                    command: "raw".to_string(),
+                },
+                JournalAction::CommandQueued {
+                    program: "export".to_string(),
+                    command: Fingerprint::of("export TOKEN=hunter2"),
+                },
+                JournalAction::ExitDecision {
+                    exit: false,
+                    discard: false,
+                },
+                JournalAction::ExitDecision {
+                    exit: true,
+                    discard: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn json_log_write_failures_dont_stop_the_session() {
+        let args = Cli {
+            json_log: Some(PathBuf::from("/dev/full")),
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        assert!(app.journal.is_none());
+        assert_eq!(app.messages().current().unwrap().severity, Severity::Error);
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Vars)));
+        assert_eq!(app.screen(), Screen::Vars);
+    }
+
+    fn ctrl_c() -> KeyEvent {
+        KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn ctrl_c_asks_first_then_force_quits_without_changes() {
+        let mut app = test_app();
+        app.push_output_line("export FOO=bar");
+        let start = Instant::now();
+
+        assert_eq!(
+            step(&mut app, Some(&TermEvent::Key(ctrl_c())), start),
+            LoopAction::Redraw
+        );
+        assert!(app.is_displaying_exit_modal());
+        assert!(app.interrupt_armed());
+        assert!(!app.cancelled());
+
+        assert_eq!(
+            step(&mut app, Some(&TermEvent::Key(ctrl_c())), start),
+            LoopAction::Exit
+        );
+        assert!(app.cancelled());
+        assert!(!app.is_displaying_exit_modal());
+        assert_eq!(app.final_output(), app.initial_output());
+    }
+
+    #[test]
+    fn ctrl_c_works_while_typing() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        app.prompt_mut().set_input_focused(true);
+        feed(&mut app, ctrl_c());
+        assert_eq!(app.prompt().input(), "");
+        assert!(app.is_displaying_exit_modal());
+
+        // Backing out of the modal leaves the next Ctrl+C asking again once
+        // the window has passed.
+        handle_event(&mut app, &Event::Modal(ModalEvent::Select));
+        assert!(!app.is_displaying_exit_modal());
+        assert!(app.tick(Instant::now() + FORCE_QUIT_WINDOW));
+        assert!(!app.interrupt_armed());
+        assert_eq!(app.interrupt(Instant::now()), Action::Continue);
+        assert!(app.is_displaying_exit_modal());
+    }
+
+    #[test]
+    fn ctrl_z_suspends_from_anywhere() {
+        let ctrl_z = TermEvent::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        let mut app = test_app();
+        assert_eq!(
+            step(&mut app, Some(&ctrl_z), Instant::now()),
+            LoopAction::Suspend
+        );
+
+        app.set_screen(Screen::Prompt);
+        app.prompt_mut().set_input_focused(true);
+        assert_eq!(
+            step(&mut app, Some(&ctrl_z), Instant::now()),
+            LoopAction::Suspend
+        );
+        assert_eq!(app.prompt().input(), "");
+
+        handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+        assert_eq!(
+            step(&mut app, Some(&ctrl_z), Instant::now()),
+            LoopAction::Suspend
+        );
+        assert!(app.is_displaying_exit_modal());
+    }
+
+    #[test]
+    fn modals_keep_every_key_from_the_screen_behind_them() {
+        let mut app = test_app();
+        app.push_output_line("export FOO=bar");
+        app.set_screen(Screen::Output);
+        app.output_state_mut().list_state().select_first();
+        let output = app.output();
+        feed(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE),
+        );
+        assert!(matches!(app.modal(), Some(Modal::Confirm { .. })));
+        assert_eq!(
+            app.modal().unwrap().message(),
+            "Clear 1 command?\nexport FOO=bar"
+        );
+
+        // Deleting, switching tabs, asking for help and exiting are all bound
+        // on the output screen, but none of them get past the modal.
+        for code in [
+            KeyCode::Char('d'),
+            KeyCode::Char('c'),
+            KeyCode::Tab,
+            KeyCode::BackTab,
+            KeyCode::Char('1'),
+            KeyCode::Char('?'),
+            KeyCode::Char('q'),
+            KeyCode::Down,
+        ] {
+            feed(&mut app, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        assert!(matches!(app.modal(), Some(Modal::Confirm { .. })));
+        assert_eq!(app.screen(), Screen::Output);
+        assert!(app.overlay().is_none());
+        assert_eq!(app.output(), output);
+        assert_eq!(app.output_state().selected(), Some(0));
+
+        // Events that are already resolved are dropped too.
+        handle_event(&mut app, &Event::Output(OutputEvent::DeleteSelected));
+        handle_event(&mut app, &Event::App(AppEvent::NextTab));
+        assert_eq!(app.screen(), Screen::Output);
+        assert_eq!(app.output(), output);
+
+        feed(&mut app, KeyEvent::new(KeyCode::Left, KeyModifiers::NONE));
+        feed(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(app.modal().is_none());
+        assert_eq!(app.output(), app.initial_output());
+        assert_eq!(app.output_state().selected(), None);
+    }
+
+    #[test]
+    fn output_scrolls_sideways_unless_wrapped() {
+        let mut app = test_app();
+        app.set_screen(Screen::Output);
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        // Left stops at the start of the commands.
+        feed(&mut app, key(KeyCode::Left));
+        assert_eq!(app.output_state().h_offset(), 0);
+        feed(&mut app, key(KeyCode::Right));
+        feed(&mut app, key(KeyCode::Right));
+        assert_eq!(app.output_state().h_offset(), 2 * output::H_SCROLL_STEP);
+        feed(&mut app, key(KeyCode::Left));
+        assert_eq!(app.output_state().h_offset(), output::H_SCROLL_STEP);
+
+        // Wrapped commands are shown whole, so they don't scroll.
+        feed(&mut app, key(KeyCode::Char('w')));
+        assert!(app.output_state().wrap());
+        assert_eq!(app.output_state().h_offset(), 0);
+        feed(&mut app, key(KeyCode::Right));
+        assert_eq!(app.output_state().h_offset(), 0);
+        feed(&mut app, key(KeyCode::Char('w')));
+        assert!(!app.output_state().wrap());
+    }
+
+    #[test]
+    fn confirmations_apply_the_pending_action_exactly_once() {
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut app = test_app();
+        for line in ["export A=1", "export B=2", "export C=3"] {
+            app.push_output_line(line);
+        }
+        app.set_screen(Screen::Output);
+        app.output_state_mut().list_state().select(Some(1));
+        let output = app.output();
+
+        feed(&mut app, key('d'));
+        assert_eq!(
+            app.modal().unwrap().message(),
+            "Delete 1 command?\nexport B=2"
+        );
+        feed(&mut app, key('n'));
+        assert!(app.modal().is_none());
+        assert_eq!(app.output(), output);
+        // Enter presses Cancel, which is highlighted to begin with.
+        feed(&mut app, key('d'));
+        feed(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.output(), output);
+
+        feed(&mut app, key('d'));
+        feed(&mut app, key('y'));
+        assert_eq!(app.output_lines(), ["export A=1", "export C=3"]);
+        // The modal is gone, so pressing Ok again has nothing to apply.
+        handle_event(&mut app, &Event::Modal(ModalEvent::Select));
+        handle_event(&mut app, &Event::Modal(ModalEvent::Accept));
+        assert_eq!(app.output_lines(), ["export A=1", "export C=3"]);
+
+        for n in 0..5 {
+            app.push_output_line(&format!("echo {n}"));
+        }
+        feed(&mut app, key('c'));
+        assert_eq!(
+            app.modal().unwrap().message(),
+            "Clear 7 commands?\nexport A=1\nexport C=3\necho 0\necho 1\necho 2\n…and 2 more"
+        );
+        feed(&mut app, KeyEvent::new(KeyCode::Right, KeyModifiers::NONE));
+        feed(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.output(), app.initial_output());
+        assert_eq!(app.output_state().selected(), None);
+    }
+
+    #[test]
+    fn yes_skips_confirmations() {
+        let args = Cli {
+            yes: true,
+            ..test_args()
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.push_output_line("export A=1");
+        app.push_output_line("export B=2");
+        app.set_screen(Screen::Output);
+        app.output_state_mut().list_state().select_first();
+
+        handle_event(&mut app, &Event::Output(OutputEvent::DeleteSelected));
+        assert!(app.modal().is_none());
+        assert_eq!(app.output_lines(), ["export B=2"]);
+        handle_event(&mut app, &Event::Output(OutputEvent::Clear));
+        assert!(app.modal().is_none());
+        assert_eq!(app.output(), app.initial_output());
+    }
+
+    #[test]
+    fn discarding_on_exit_drops_queued_commands() {
+        let stdout_after = |moves: &[ModalEvent]| {
+            let mut app = test_app();
+            app.push_output_line("export FOO=bar");
+            handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+            for event in moves {
+                handle_event(&mut app, &Event::Modal(event.clone()));
+            }
+            assert_eq!(
+                handle_event(&mut app, &Event::Modal(ModalEvent::Select)),
+                Action::Exit
+            );
+            let mut stdout = Vec::new();
+            app.write_output(&mut stdout).unwrap();
+            (String::from_utf8(stdout).unwrap(), app.exit_disposition())
+        };
+
+        // Ok is to the right of Cancel after wrapping around, and Discard is
+        // to its left.
+        let (confirmed, disposition) = stdout_after(&[ModalEvent::Next]);
+        assert_eq!(confirmed, "export FOO=bar\n");
+        assert_eq!(disposition, ExitDisposition::Apply);
+        let (discarded, disposition) = stdout_after(&[ModalEvent::Previous]);
+        assert_eq!(discarded, test_app().initial_output());
+        assert_eq!(disposition, ExitDisposition::Discard);
+        assert_ne!(confirmed, discarded);
+    }
+
+    #[test]
+    fn dismissing_clears_the_banner_before_toasts() {
+        let dismiss = Event::App(AppEvent::DismissMessage);
+        let mut app = test_app();
+        app.push_message(Severity::Warning, "something happened");
+        app.notify(Severity::Success, "done");
+        handle_event(&mut app, &dismiss);
+        assert!(app.messages().is_empty());
+        assert!(!app.toasts().is_empty());
+        handle_event(&mut app, &dismiss);
+        assert!(app.toasts().is_empty());
+    }
+
+    #[test]
+    fn esc_cancels_whichever_modal_is_open() {
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let mut app = test_app();
+        app.push_output_line("export FOO=bar");
+        app.push_message(Severity::Warning, "something happened");
+        let output = app.output();
+
+        // Esc cancels even with Ok highlighted.
+        handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+        handle_event(&mut app, &Event::Modal(ModalEvent::Next));
+        assert_eq!(
+            resolve_event(&mut app, &esc, Instant::now()),
+            Some(Event::Modal(ModalEvent::Cancel))
+        );
+        feed(&mut app, esc);
+        assert!(app.modal().is_none());
+        // The message behind the modal is still there for the next Esc.
+        assert_eq!(app.messages().len(), 1);
+
+        app.set_screen(Screen::Output);
+        handle_event(&mut app, &Event::Output(OutputEvent::Clear));
+        handle_event(&mut app, &Event::Modal(ModalEvent::Next));
+        feed(&mut app, esc);
+        assert!(app.modal().is_none());
+        assert_eq!(app.output(), output);
+
+        // Closing a modal opened over the help overlay goes back to the
+        // overlay.
+        app.set_overlay(Some(Overlay::Help));
+        handle_event(&mut app, &Event::App(AppEvent::Interrupt));
+        assert!(app.is_displaying_exit_modal());
+        feed(&mut app, esc);
+        assert!(app.modal().is_none());
+        assert!(app.overlay().is_some());
+        assert_eq!(app.messages().len(), 1);
+        feed(&mut app, esc);
+        assert!(app.overlay().is_none());
+    }
+
+    #[test]
+    fn prev_tab_is_the_inverse_of_next_tab() {
+        for screen in Screen::TABS {
+            assert_eq!(screen.next_tab().prev_tab(), screen);
+            assert_eq!(screen.prev_tab().next_tab(), screen);
+            assert_eq!(Screen::TABS[screen.tab_index()], screen);
+        }
+        assert_eq!(Screen::Home.prev_tab(), Screen::Output);
+        assert_eq!(Screen::Output.next_tab(), Screen::Home);
+    }
+
+    #[test]
+    fn tabs_cycle_both_ways_from_keys() {
+        let mut app = test_app();
+        assert_eq!(press(&mut app, 'g'), None);
+        assert_eq!(press(&mut app, 'T'), Some(Event::App(AppEvent::PrevTab)));
+        feed(
+            &mut app,
+            KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+        );
+        assert_eq!(app.screen(), Screen::Output);
+        feed(&mut app, KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(app.screen(), Screen::Home);
+    }
+
+    #[test]
+    fn sessions_restore_views_without_saving_values() {
+        let env = HashMap::from([("PATH".to_string(), "/secret/bin:/usr/bin".to_string())]);
+        let mut app = test_app().with_env(&env);
+        app.set_screen(Screen::Vars);
+        handle_event(&mut app, &Event::Vars(VarsEvent::SplitDetail));
+        let session = SessionState::of(&app);
+        let contents = toml::to_string(&session).unwrap();
+        assert!(!contents.contains("secret"), "{contents}");
+
+        let mut restored = test_app().with_env(&env);
+        restored.restore_session(&session, &test_args());
+        assert_eq!(restored.screen(), Screen::Vars);
+        assert_eq!(
+            restored.env().selected_detail_item().unwrap(),
+            "/secret/bin"
+        );
+
+        // The screen given on the command line wins.
+        let args = Cli {
+            start_screen: Some(Screen::Trace),
+            ..test_args()
+        };
+        let mut restored = App::new(&args, &TerminalInfo::default()).unwrap();
+        restored.restore_session(&session, &args);
+        assert_eq!(restored.screen(), Screen::Trace);
+        assert_eq!(restored.env().detail_view(), DetailView::Split);
+    }
+
+    #[test]
+    fn demo_never_runs_its_commands() {
+        let mut app = test_app();
+        app.demo = true;
+        app.push_output_line("export FOO=bar");
+        let output = app.final_output();
+        assert!(output.lines().all(|line| line.starts_with('#')), "{output}");
+        assert!(output.contains("# export FOO=bar"));
+    }
 }
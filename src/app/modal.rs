@@ -0,0 +1,336 @@
+use crate::app::{
+    Action, App, Event, ExitDisposition, macros::MacroRun, output::clamp_selection,
+    protected::VarMutation,
+};
+
+/// A dialog drawn on top of everything else. While one is open it gets every
+/// key press, so nothing behind it can be changed by accident.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Modal {
+    /// Asks whether to exit, summarizing what will be printed for the shell.
+    Exit {
+        highlighted: ExitOption,
+        /// How many lines of the summary are scrolled past.
+        scroll: usize,
+    },
+    /// Asks before doing something that can't be taken back.
+    Confirm {
+        message: String,
+        action: PendingAction,
+        highlighted: ConfirmOption,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ExitOption {
+    Ok,
+    /// Exit without running any of the queued commands.
+    Discard,
+    #[default]
+    Cancel,
+}
+
+impl ExitOption {
+    /// The exit modal's buttons from left to right.
+    const ALL: [ExitOption; 3] = [ExitOption::Ok, ExitOption::Discard, ExitOption::Cancel];
+    const LABELS: [&str; 3] = ["[   Ok   ]", "[ Discard & Exit ]", "[ Cancel ]"];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ConfirmOption {
+    Ok,
+    #[default]
+    Cancel,
+}
+
+impl ConfirmOption {
+    /// A confirmation's buttons from left to right.
+    const ALL: [ConfirmOption; 2] = [ConfirmOption::Ok, ConfirmOption::Cancel];
+    const LABELS: [&str; 2] = ["[   Ok   ]", "[ Cancel ]"];
+}
+
+/// Returns the option `forward` or backward from `current` in `options`,
+/// wrapping around at either end.
+fn step<T: Copy + PartialEq>(options: &[T], current: T, forward: bool) -> T {
+    let idx = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or_default();
+    let idx = if forward {
+        (idx + 1) % options.len()
+    } else {
+        (idx + options.len() - 1) % options.len()
+    };
+    options[idx]
+}
+
+/// Returns the index of `current` in `options`.
+fn position<T: PartialEq>(options: &[T], current: &T) -> usize {
+    options
+        .iter()
+        .position(|option| option == current)
+        .unwrap_or_default()
+}
+
+/// The most commands listed in a confirmation before the rest are counted
+/// instead, so the modal fits on screen.
+const MAX_LISTED_COMMANDS: usize = 5;
+
+/// Something the user asked for that only happens once they confirm it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PendingAction {
+    /// Reset the output to the commands the debugger starts with.
+    ClearOutput,
+    /// Remove the output command at this index.
+    DeleteOutputLine(usize),
+    /// Change a protected variable.
+    Protected(VarMutation),
+    /// Run a macro that changes protected variables.
+    Macro(MacroRun),
+    /// Split the selected variable into this many entries.
+    SplitLargeValue(usize),
+}
+
+impl PendingAction {
+    /// Returns the question to ask before doing this to `app`, spelling out
+    /// what will be lost.
+    fn describe(&self, app: &App) -> String {
+        let removed = match self {
+            // Clearing keeps the commands the debugger starts with.
+            PendingAction::ClearOutput => app.queued_commands(),
+            PendingAction::DeleteOutputLine(idx) => {
+                app.output_lines().get(*idx).into_iter().copied().collect()
+            }
+            PendingAction::Protected(mutation) => return describe_protected(app, mutation),
+            PendingAction::Macro(run) => return describe_macro(app, run),
+            PendingAction::SplitLargeValue(len) => {
+                return format!(
+                    "The value has {len} entries,\n\
+                     and showing them all can be slow.\n\
+                     Split it anyway?"
+                );
+            }
+        };
+        let verb = match self {
+            PendingAction::ClearOutput => "Clear",
+            PendingAction::DeleteOutputLine(_) => "Delete",
+            PendingAction::Protected(_)
+            | PendingAction::Macro(_)
+            | PendingAction::SplitLargeValue(_) => {
+                unreachable!("described above")
+            }
+        };
+        let plural = if removed.len() == 1 { "" } else { "s" };
+        let mut message = format!("{verb} {} command{plural}?", removed.len());
+        for line in removed.iter().take(MAX_LISTED_COMMANDS) {
+            message.push('\n');
+            message.push_str(line);
+        }
+        if removed.len() > MAX_LISTED_COMMANDS {
+            let n_more = removed.len() - MAX_LISTED_COMMANDS;
+            message.push_str(&format!("\n…and {n_more} more"));
+        }
+        message
+    }
+
+    /// Does the action, which has either been confirmed or didn't need to be.
+    pub fn apply(&self, app: &mut App) {
+        match self {
+            PendingAction::ClearOutput => {
+                app.clear_output();
+            }
+            PendingAction::DeleteOutputLine(idx) => {
+                app.remove_output_line(*idx);
+            }
+            PendingAction::Protected(mutation) => app.apply_mutation(mutation),
+            PendingAction::Macro(run) => run.apply(app),
+            PendingAction::SplitLargeValue(_) => app.env_mut().split(),
+        }
+        clamp_selection(app);
+    }
+}
+
+/// Spells out which protected variables `mutation` changes and how, and
+/// what could go wrong.
+fn describe_protected(app: &App, mutation: &VarMutation) -> String {
+    let names = mutation
+        .targets()
+        .into_iter()
+        .filter(|name| app.protected_vars().contains(name))
+        .collect::<Vec<_>>();
+    let (verb, pronoun) = if names.len() == 1 {
+        ("is", "it")
+    } else {
+        ("are", "them")
+    };
+    format!(
+        "{} {verb} protected, and changing {pronoun}\n\
+         can break the activation in confusing ways.\n\
+         {}\n\
+         Queue this anyway?",
+        names.join(", "),
+        mutation.command(app.shell())
+    )
+}
+
+/// Spells out which protected variables the macro `run` changes, along
+/// with every command it would queue.
+fn describe_macro(app: &App, run: &MacroRun) -> String {
+    let names = run.protected_targets(app.protected_vars());
+    let (verb, pronoun) = if names.len() == 1 {
+        ("is", "it")
+    } else {
+        ("are", "them")
+    };
+    let mut message = format!(
+        "The macro '{}' changes {}, which {verb} protected,\n\
+         and changing {pronoun} can break the activation in confusing ways.",
+        run.name,
+        names.join(", ")
+    );
+    let commands = run.commands(app.shell());
+    for command in commands.iter().take(MAX_LISTED_COMMANDS) {
+        message.push('\n');
+        message.push_str(command);
+    }
+    if commands.len() > MAX_LISTED_COMMANDS {
+        let n_more = commands.len() - MAX_LISTED_COMMANDS;
+        message.push_str(&format!("\n…and {n_more} more"));
+    }
+    message.push_str("\nRun it anyway?");
+    message
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModalEvent {
+    /// Highlight the button to the left, wrapping around.
+    Previous,
+    /// Highlight the button to the right, wrapping around.
+    Next,
+    /// Press the highlighted button.
+    Select,
+    /// Scroll the modal's content up a line.
+    ScrollUp,
+    /// Scroll the modal's content down a line.
+    ScrollDown,
+    /// Close the modal as if Ok had been pressed, whichever button is
+    /// highlighted.
+    Accept,
+    /// Close the modal as if Cancel had been pressed.
+    Cancel,
+}
+
+impl Modal {
+    /// Returns the modal asking whether to exit, with Cancel highlighted.
+    pub fn exit() -> Self {
+        Modal::Exit {
+            highlighted: ExitOption::default(),
+            scroll: 0,
+        }
+    }
+
+    /// Returns a modal asking whether to go ahead with `action`, with Cancel
+    /// highlighted.
+    pub fn confirm(app: &App, action: PendingAction) -> Self {
+        Modal::Confirm {
+            message: action.describe(app),
+            action,
+            highlighted: ConfirmOption::default(),
+        }
+    }
+
+    /// Returns the question the modal asks, which may span several lines.
+    pub fn message(&self) -> &str {
+        match self {
+            Modal::Exit { .. } => "Exit?",
+            Modal::Confirm { message, .. } => message,
+        }
+    }
+
+    /// Returns the labels of the buttons from left to right, along with
+    /// which of them is highlighted.
+    pub fn buttons(&self) -> (&'static [&'static str], usize) {
+        match self {
+            Modal::Exit { highlighted, .. } => {
+                (&ExitOption::LABELS, position(&ExitOption::ALL, highlighted))
+            }
+            Modal::Confirm { highlighted, .. } => (
+                &ConfirmOption::LABELS,
+                position(&ConfirmOption::ALL, highlighted),
+            ),
+        }
+    }
+
+    /// Moves the highlight one button to the right, or to the left if not
+    /// `forward`, wrapping around at either end.
+    fn cycle(&mut self, forward: bool) {
+        match self {
+            Modal::Exit { highlighted, .. } => {
+                *highlighted = step(&ExitOption::ALL, *highlighted, forward);
+            }
+            Modal::Confirm { highlighted, .. } => {
+                *highlighted = step(&ConfirmOption::ALL, *highlighted, forward);
+            }
+        }
+    }
+}
+
+/// Handles events while a modal is open. Anything that isn't meant for the
+/// modal is dropped rather than reaching the screen behind it.
+pub fn handle_modal_event(app: &mut App, event: &Event) -> Action {
+    let Event::Modal(modal_event) = event else {
+        return Action::Continue;
+    };
+    let summary_len = app.final_output().lines().count();
+    let Some(modal) = app.modal_mut() else {
+        return Action::Continue;
+    };
+    match modal_event {
+        ModalEvent::Previous => modal.cycle(false),
+        ModalEvent::Next => modal.cycle(true),
+        ModalEvent::ScrollUp => {
+            if let Modal::Exit { scroll, .. } = modal {
+                *scroll = scroll.saturating_sub(1);
+            }
+        }
+        ModalEvent::ScrollDown => {
+            // How much of the summary fits is up to the renderer, so this
+            // only stops at its last line.
+            if let Modal::Exit { scroll, .. } = modal
+                && *scroll + 1 < summary_len
+            {
+                *scroll += 1;
+            }
+        }
+        ModalEvent::Cancel => {
+            app.close_modal();
+        }
+        ModalEvent::Accept => {
+            match modal {
+                Modal::Exit { highlighted, .. } => *highlighted = ExitOption::Ok,
+                Modal::Confirm { highlighted, .. } => *highlighted = ConfirmOption::Ok,
+            }
+            return handle_modal_event(app, &Event::Modal(ModalEvent::Select));
+        }
+        ModalEvent::Select => match app.close_modal() {
+            Some(Modal::Exit {
+                highlighted: ExitOption::Ok,
+                ..
+            }) => return Action::Exit,
+            Some(Modal::Exit {
+                highlighted: ExitOption::Discard,
+                ..
+            }) => {
+                app.set_exit_disposition(ExitDisposition::Discard);
+                return Action::Exit;
+            }
+            Some(Modal::Confirm {
+                action,
+                highlighted: ConfirmOption::Ok,
+                ..
+            }) => action.apply(app),
+            _ => {}
+        },
+    }
+    Action::Continue
+}
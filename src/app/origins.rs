@@ -0,0 +1,139 @@
+//! Where variables came from, as told by a `--var-origins` file.
+//!
+//! The file maps names to origin labels, such as `manifest` or `hook`, either
+//! as `NAME=origin` lines or, when its name ends in `.toml`, as a TOML table
+//! of `NAME = "origin"` entries.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use anyhow::{Context, Error, anyhow, bail};
+
+/// The origin of variables the file doesn't mention, which came from the
+/// user's shell.
+pub const INHERITED: &str = "inherited";
+
+/// The origin labels of variables, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VarOrigins {
+    origins: HashMap<String, String>,
+}
+
+impl VarOrigins {
+    /// Returns the origin of the variable `name`, which is [`INHERITED`] for
+    /// variables that weren't listed.
+    pub fn origin_of(&self, name: &str) -> &str {
+        self.origins.get(name).map_or(INHERITED, String::as_str)
+    }
+
+    /// Returns whether no origins were listed, in which case there's nothing
+    /// worth showing.
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+
+    /// Returns the origins of `names` in order, each only once.
+    pub fn labels<'a>(&'a self, names: &'a [String]) -> Vec<&'a str> {
+        names
+            .iter()
+            .map(|name| self.origin_of(name))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Reads the origins file at `path`, see the [module docs](self).
+pub fn load_var_origins(path: &Path) -> Result<VarOrigins, Error> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read variable origins {}", path.display()))?;
+    let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+    parse_var_origins(&contents, is_toml)
+        .with_context(|| format!("invalid variable origins {}", path.display()))
+}
+
+/// Parses an origins file as TOML or as `NAME=origin` lines, where blank
+/// lines and lines starting with `#` are skipped.
+pub fn parse_var_origins(contents: &str, is_toml: bool) -> Result<VarOrigins, Error> {
+    let mut origins = HashMap::new();
+    if is_toml {
+        let table = contents.parse::<toml::Table>()?;
+        for (name, origin) in table {
+            let Some(origin) = origin.as_str() else {
+                bail!("the origin of {name} must be a string");
+            };
+            origins.insert(name, check_origin(origin)?);
+        }
+        return Ok(VarOrigins { origins });
+    }
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected NAME=origin, found '{line}'"))
+            .and_then(|(name, origin)| {
+                let name = name.trim();
+                if name.is_empty() {
+                    bail!("missing variable name in '{line}'");
+                }
+                Ok((name.to_string(), check_origin(origin.trim())?))
+            })
+            .with_context(|| format!("line {}", idx + 1))?;
+        origins.insert(entry.0, entry.1);
+    }
+    Ok(VarOrigins { origins })
+}
+
+fn check_origin(origin: &str) -> Result<String, Error> {
+    if origin.is_empty() {
+        bail!("origins can't be empty");
+    }
+    Ok(origin.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins_parse_from_lines_and_toml() {
+        let lines = "# from flox\nFOO=manifest\n\n BAR = hook \nURL=a=b\n";
+        let origins = parse_var_origins(lines, false).unwrap();
+        assert_eq!(origins.origin_of("FOO"), "manifest");
+        assert_eq!(origins.origin_of("BAR"), "hook");
+        assert_eq!(origins.origin_of("URL"), "a=b");
+        assert_eq!(origins.origin_of("HOME"), INHERITED);
+
+        let toml = "FOO = \"manifest\"\n\"weird name\" = \"hook\"\n";
+        let origins = parse_var_origins(toml, true).unwrap();
+        assert_eq!(origins.origin_of("FOO"), "manifest");
+        assert_eq!(origins.origin_of("weird name"), "hook");
+        assert!(parse_var_origins("", false).unwrap().is_empty());
+
+        let names = ["BAR", "FOO", "HOME"].map(String::from);
+        let origins = parse_var_origins(lines, false).unwrap();
+        assert_eq!(origins.labels(&names), ["hook", INHERITED, "manifest"]);
+    }
+
+    #[test]
+    fn origin_errors_are_reported() {
+        let err =
+            |contents, is_toml| format!("{:#}", parse_var_origins(contents, is_toml).unwrap_err());
+        assert_eq!(
+            err("FOO=manifest\nBAR\n", false),
+            "line 2: expected NAME=origin, found 'BAR'"
+        );
+        assert_eq!(
+            err("=hook", false),
+            "line 1: missing variable name in '=hook'"
+        );
+        assert_eq!(err("FOO=", false), "line 1: origins can't be empty");
+        assert_eq!(err("FOO = 1", true), "the origin of FOO must be a string");
+        assert!(parse_var_origins("FOO=manifest", true).is_err());
+    }
+}
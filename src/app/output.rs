@@ -0,0 +1,209 @@
+use std::{fs::File, os::fd::RawFd};
+
+use anyhow::Error;
+use ratatui::widgets::ListState;
+
+use crate::app::{
+    Action, App, Event, NavEvent, Screen,
+    fd::{Access, take_fd},
+    messages::Severity,
+    modal::PendingAction,
+};
+
+/// How many columns the commands move with each press of Left or Right.
+pub const H_SCROLL_STEP: usize = 8;
+
+/// The view state of the output screen.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct OutputState {
+    list_state: ListState,
+    /// Whether long commands wrap onto more rows rather than being cut off
+    /// at the edge.
+    wrap: bool,
+    /// How many columns the commands are scrolled to the right by, when
+    /// they don't wrap.
+    h_offset: usize,
+}
+
+impl OutputState {
+    /// Returns the command list state for stateful rendering.
+    pub fn list_state(&mut self) -> &mut ListState {
+        &mut self.list_state
+    }
+
+    /// Returns the index of the selected command, if any.
+    pub fn selected(&self) -> Option<usize> {
+        self.list_state.selected()
+    }
+
+    /// Returns whether long commands wrap.
+    pub fn wrap(&self) -> bool {
+        self.wrap
+    }
+
+    /// Returns how many columns the commands are scrolled to the right by.
+    pub fn h_offset(&self) -> usize {
+        self.h_offset
+    }
+
+    /// Keeps the horizontal scroll from going past where the end of the
+    /// widest command, `widest` columns wide, is in view in `width` columns,
+    /// returning the offset to draw with.
+    ///
+    /// It's only known how wide the commands are drawn when they are, so
+    /// this is done then rather than as the scroll keys are pressed.
+    pub fn clamp_h_offset(&mut self, widest: usize, width: usize) -> usize {
+        self.h_offset = self.h_offset.min(widest.saturating_sub(width));
+        self.h_offset
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputEvent {
+    /// Remove the selected command from the output.
+    DeleteSelected,
+    /// Move the selected command into the prompt so it can be edited and
+    /// queued again.
+    EditSelected,
+    /// Reset the output to the commands the debugger starts with.
+    Clear,
+    /// Remove the last command the user queued.
+    Undo,
+    /// Show everything that will be printed on exit in the user's pager.
+    OpenInPager,
+    /// Switch between wrapping long commands and scrolling them sideways.
+    ToggleWrap,
+}
+
+/// Handles navigation and interaction in the Output screen.
+pub fn handle_output_event(app: &mut App, event: &Event) -> Action {
+    let len = app.output_lines().len();
+    match event {
+        Event::Nav(NavEvent::Up) => {
+            let list_state = app.output_state_mut().list_state();
+            match list_state.selected() {
+                Some(idx) if idx > 0 => list_state.select_previous(),
+                None if len > 0 => list_state.select_first(),
+                _ => {}
+            }
+        }
+        Event::Nav(NavEvent::Down) => {
+            let list_state = app.output_state_mut().list_state();
+            match list_state.selected() {
+                Some(idx) if idx + 1 < len => list_state.select_next(),
+                None if len > 0 => list_state.select_first(),
+                _ => {}
+            }
+        }
+        Event::Nav(NavEvent::Top) if len > 0 => {
+            app.output_state_mut().list_state().select_first();
+        }
+        Event::Nav(NavEvent::Bottom) if len > 0 => {
+            app.output_state_mut().list_state().select(Some(len - 1));
+        }
+        // Wrapped commands are already in view from end to end.
+        Event::Nav(NavEvent::Left) if !app.output_state().wrap => {
+            let state = app.output_state_mut();
+            state.h_offset = state.h_offset.saturating_sub(H_SCROLL_STEP);
+        }
+        Event::Nav(NavEvent::Right) if !app.output_state().wrap => {
+            let state = app.output_state_mut();
+            state.h_offset = state.h_offset.saturating_add(H_SCROLL_STEP);
+        }
+        Event::Output(OutputEvent::ToggleWrap) => {
+            let state = app.output_state_mut();
+            state.wrap = !state.wrap;
+            state.h_offset = 0;
+        }
+        Event::Output(OutputEvent::DeleteSelected) => {
+            if let Some(idx) = app.output_state().selected()
+                && idx < len
+            {
+                app.confirm(PendingAction::DeleteOutputLine(idx));
+            }
+        }
+        Event::Output(OutputEvent::EditSelected) => {
+            if let Some(idx) = app.output_state().selected()
+                && let Some(line) = app.remove_output_line(idx)
+            {
+                clamp_selection(app);
+                let prompt = app.prompt_mut();
+                prompt.set_input(format!("raw {line}"));
+                prompt.set_input_focused(true);
+                app.set_screen(Screen::Prompt);
+            }
+        }
+        Event::Output(OutputEvent::Clear) => app.confirm(PendingAction::ClearOutput),
+        Event::Output(OutputEvent::Undo) => match app.undo_last_command() {
+            Some(queued) => {
+                clamp_selection(app);
+                app.notify(Severity::Success, queued.describe_undo());
+            }
+            None => app.push_message(Severity::Warning, "Nothing to undo"),
+        },
+        Event::Output(OutputEvent::OpenInPager) => return app.page(app.final_output()),
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Keeps the selection within the bounds of the output after lines have been
+/// removed.
+pub fn clamp_selection(app: &mut App) {
+    let len = app.output_lines().len();
+    let list_state = app.output_state_mut().list_state();
+    match list_state.selected() {
+        _ if len == 0 => list_state.select(None),
+        Some(idx) if idx >= len => list_state.select(Some(len - 1)),
+        _ => {}
+    }
+}
+
+/// Takes over the file descriptor `fd` for printing the commands on exit,
+/// checking that it's open for writing first so that a mistake is reported
+/// before the TUI starts rather than once the user is done.
+pub fn open_output_fd(fd: RawFd) -> Result<File, Error> {
+    take_fd(fd, Access::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd};
+
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn horizontal_scroll_stays_within_the_commands() {
+        let mut state = OutputState {
+            h_offset: 3,
+            ..OutputState::default()
+        };
+        // Commands that fit can't be scrolled at all.
+        assert_eq!(state.clamp_h_offset(10, 40), 0);
+        state.h_offset = 100;
+        assert_eq!(state.clamp_h_offset(50, 40), 10);
+        assert_eq!(state.h_offset(), 10);
+        // An offset that's in range is left alone.
+        state.h_offset = 4;
+        assert_eq!(state.clamp_h_offset(50, 40), 4);
+    }
+
+    #[test]
+    fn output_fds_must_be_open_for_writing() {
+        let message = |fd| open_output_fd(fd).unwrap_err().to_string();
+        assert!(message(1).contains("standard streams"));
+        assert!(message(-1).contains("isn't open"));
+
+        let dir = TestDir::new("output-fd");
+        let path = dir.join("output");
+        File::create(&path).unwrap();
+        let read_only = File::open(&path).unwrap().into_raw_fd();
+        assert!(message(read_only).contains("isn't open for writing"));
+        // SAFETY: The descriptor was only checked, so it's still ours to close.
+        drop(unsafe { File::from_raw_fd(read_only) });
+
+        let writable = File::create(&path).unwrap().into_raw_fd();
+        assert_eq!(open_output_fd(writable).unwrap().as_raw_fd(), writable);
+    }
+}
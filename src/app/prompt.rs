@@ -0,0 +1,774 @@
+use std::path::PathBuf;
+
+use crate::app::{
+    App, Event, NavEvent, Screen,
+    changes::{VarChangeKind, diff_envs},
+    commands::{
+        Cmd, TracepointDisposition, check_tracepoint_pattern, check_var_name, is_glob, resolve_dir,
+    },
+    glob::glob_match,
+    secrets::looks_secret,
+    transcript::write_transcript,
+    vars::load_env_file,
+    watch::shown_value,
+};
+use crate::ui::text::truncate_to_width;
+
+/// The commands built into the prompt, which aliases can't be named after.
+pub const COMMANDS: [&str; 15] = [
+    "alias",
+    "break",
+    "cd",
+    "continue",
+    "diff",
+    "env",
+    "help",
+    "function",
+    "raw",
+    "step",
+    "trace",
+    "tracepoints",
+    "transcript",
+    "unwatch",
+    "watch",
+];
+
+/// Tracepoint names that the shell side treats specially rather than as the
+/// name of a tracepoint.
+const SPECIAL_TRACEPOINTS: [&str; 2] = ["next", "all"];
+
+/// The state of the prompt screen: an input line, the history of submitted
+/// commands, and a scrollback of everything the prompt has printed.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct PromptState {
+    input: String,
+    input_focused: bool,
+    history: Vec<String>,
+    /// The history entry currently recalled into the input, if any.
+    history_idx: Option<usize>,
+    scrollback: Vec<String>,
+    /// How many lines the scrollback is scrolled up from the bottom.
+    scroll_offset: usize,
+    /// How many lines of scrollback fit on screen, as of the last time it
+    /// was drawn.
+    viewport_height: Option<usize>,
+    /// How many columns of scrollback fit on screen, as of the last time it
+    /// was drawn.
+    viewport_width: Option<usize>,
+}
+
+impl PromptState {
+    /// Returns the current contents of the input line.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Replaces the contents of the input line.
+    pub fn set_input(&mut self, input: impl Into<String>) {
+        self.input = input.into();
+        self.history_idx = None;
+    }
+
+    /// Returns whether the input line is focused and receiving typed text.
+    pub fn input_focused(&self) -> bool {
+        self.input_focused
+    }
+
+    /// Focuses or unfocuses the input line.
+    pub fn set_input_focused(&mut self, focused: bool) {
+        self.input_focused = focused;
+    }
+
+    /// Returns the lines printed by the prompt, oldest first.
+    pub fn scrollback(&self) -> &[String] {
+        &self.scrollback
+    }
+
+    /// Returns how many lines the scrollback is scrolled up from the bottom.
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    /// Returns how far the scrollback can be scrolled up, which is as far as
+    /// keeps the viewport full.
+    fn max_scroll_offset(&self) -> usize {
+        let visible = self.viewport_height.unwrap_or(1).max(1);
+        self.scrollback.len().saturating_sub(visible)
+    }
+
+    /// Records how many lines of scrollback fit on screen, pulling the view
+    /// back down if the viewport grew past the top of the scrollback.
+    pub fn set_viewport_height(&mut self, height: usize) {
+        self.viewport_height = Some(height);
+        self.scroll_offset = self.scroll_offset.min(self.max_scroll_offset());
+    }
+
+    /// Records how many columns of scrollback fit on screen.
+    pub fn set_viewport_width(&mut self, width: usize) {
+        self.viewport_width = Some(width);
+    }
+
+    /// Appends a line to the scrollback, snapping the view to the bottom.
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.scrollback.push(line.into());
+        self.scroll_offset = 0;
+    }
+
+    /// Takes the input line, recording it in the history and echoing it to
+    /// the scrollback.
+    fn take_input(&mut self) -> String {
+        let input = std::mem::take(&mut self.input);
+        self.history_idx = None;
+        if !input.trim().is_empty() {
+            self.history.push(input.clone());
+        }
+        self.print(format!("> {input}"));
+        input
+    }
+
+    /// Recalls the previous (older) history entry into the input line.
+    fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let idx = match self.history_idx {
+            None => self.history.len() - 1,
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.history_idx = Some(idx);
+        self.input = self.history[idx].clone();
+    }
+
+    /// Recalls the next (newer) history entry into the input line, clearing
+    /// it once the newest entry has been passed.
+    fn history_next(&mut self) {
+        match self.history_idx {
+            Some(idx) if idx + 1 < self.history.len() => {
+                self.history_idx = Some(idx + 1);
+                self.input = self.history[idx + 1].clone();
+            }
+            Some(_) => {
+                self.history_idx = None;
+                self.input.clear();
+            }
+            None => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptEvent {
+    /// Focus the input line so that typed keys are entered as text.
+    FocusInput,
+    /// Return focus to the scrollback so that keys act as bindings again.
+    UnfocusInput,
+    /// Run the command in the input line.
+    Submit,
+    /// Recall the previous command from the history.
+    HistoryPrevious,
+    /// Recall the next command from the history.
+    HistoryNext,
+    /// Type a character into the input line.
+    Insert(char),
+    /// Delete the last character of the input line.
+    Backspace,
+    /// Complete the command or argument being typed.
+    Complete,
+}
+
+/// Handles navigation and interaction in the Prompt screen.
+pub fn handle_prompt_event(app: &mut App, event: &Event) {
+    match event {
+        Event::Nav(NavEvent::Up) => {
+            let prompt = app.prompt_mut();
+            prompt.scroll_offset = (prompt.scroll_offset + 1).min(prompt.max_scroll_offset());
+        }
+        Event::Nav(NavEvent::Down) => {
+            let prompt = app.prompt_mut();
+            prompt.scroll_offset = prompt.scroll_offset.saturating_sub(1);
+        }
+        Event::Nav(NavEvent::Top) => {
+            let prompt = app.prompt_mut();
+            prompt.scroll_offset = prompt.max_scroll_offset();
+        }
+        Event::Nav(NavEvent::Bottom) => app.prompt_mut().scroll_offset = 0,
+        Event::Prompt(prompt_event) => match prompt_event {
+            PromptEvent::FocusInput => app.prompt_mut().input_focused = true,
+            PromptEvent::UnfocusInput => app.prompt_mut().input_focused = false,
+            PromptEvent::Submit => {
+                let input = app.prompt_mut().take_input();
+                run_prompt_command(app, &input);
+            }
+            PromptEvent::HistoryPrevious => app.prompt_mut().history_previous(),
+            PromptEvent::HistoryNext => app.prompt_mut().history_next(),
+            PromptEvent::Insert(c) => app.prompt_mut().input.push(*c),
+            PromptEvent::Backspace => {
+                app.prompt_mut().input.pop();
+            }
+            PromptEvent::Complete => complete_input(app),
+        },
+        _ => {}
+    }
+}
+
+/// Parses and runs a single line entered at the prompt, once any alias it
+/// starts with is expanded.
+fn run_prompt_command(app: &mut App, input: &str) {
+    let input = match app.prompt_aliases().expand(input) {
+        Ok(input) => input,
+        Err(err) => {
+            app.prompt_mut().print(err.to_string());
+            return;
+        }
+    };
+    let input = input.as_str();
+    let (command, args) = input.split_once(' ').unwrap_or((input, ""));
+    match command {
+        "" => {}
+        "break" => run_break(app, args.trim()),
+        "continue" | "step" if !args.trim().is_empty() => {
+            app.prompt_mut().print(format!("usage: {command}"));
+        }
+        "continue" => set_disposition(app, "continue", TracepointDisposition::Continue),
+        "step" => set_disposition(app, "step", TracepointDisposition::PauseAtNext),
+        "raw" => {
+            if args.trim().is_empty() {
+                app.prompt_mut().print("usage: raw <command>");
+            } else if !app.check_writable("raw") {
+                app.prompt_mut().print("raw: disabled in read-only mode");
+            } else {
+                app.queue_raw(args.trim());
+            }
+        }
+        "alias" => run_alias(app, args.trim()),
+        "cd" => run_cd(app, args.trim()),
+        "diff" => run_diff(app, args.trim()),
+        "env" => run_env(app, args.trim()),
+        "help" if !args.trim().is_empty() => app.prompt_mut().print("usage: help"),
+        "help" => run_help(app),
+        "function" => run_function(app, args.trim()),
+        "trace" => run_trace(app, args.trim()),
+        "tracepoints" if !args.trim().is_empty() => {
+            app.prompt_mut().print("usage: tracepoints");
+        }
+        "tracepoints" => run_tracepoints(app),
+        "transcript" => run_transcript(app, args.trim()),
+        "unwatch" => run_unwatch(app, args.trim()),
+        "watch" => run_watch(app, args.trim()),
+        other => app.prompt_mut().print(format!("unknown command: {other}")),
+    }
+}
+
+/// Sets the tracepoint the shell will pause at after the debugger exits. A
+/// glob pauses at every tracepoint it matches, and may be quoted as it would
+/// be in the shell.
+fn run_break(app: &mut App, name: &str) {
+    let known = app.trace().known_tracepoints().to_vec();
+    if name.is_empty() {
+        app.prompt_mut()
+            .print("usage: break <tracepoint|glob|next|all>");
+        if !known.is_empty() {
+            let list = known.join(", ");
+            app.prompt_mut().print(format!("known tracepoints: {list}"));
+        }
+        return;
+    }
+    let name = unquote(name);
+    if let Err(err) = check_tracepoint_pattern(name) {
+        app.prompt_mut().print(format!("break: {err}"));
+        return;
+    }
+    if !known.is_empty()
+        && !known.iter().any(|known| glob_match(name, known))
+        && !SPECIAL_TRACEPOINTS.contains(&name)
+    {
+        let what = if is_glob(name) {
+            "no known tracepoint matches"
+        } else {
+            "unknown tracepoint"
+        };
+        app.prompt_mut().print(format!("break: {what} '{name}'"));
+        return;
+    }
+    let disposition = match name {
+        "next" => TracepointDisposition::PauseAtNext,
+        "all" => TracepointDisposition::PauseAtAll,
+        name => app.tracepoint_disposition().clone().and_pause_at(name),
+    };
+    set_disposition(app, "break", disposition);
+}
+
+/// Strips one pair of matching quotes from around `arg`.
+fn unquote(arg: &str) -> &str {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = arg
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    arg
+}
+
+/// Lists the known tracepoints, marking the ones the shell will pause at
+/// after exiting and the glob that matches each.
+fn run_tracepoints(app: &mut App) {
+    let known = app.trace().known_tracepoints().to_vec();
+    if known.is_empty() {
+        app.prompt_mut()
+            .print("no known tracepoints, pass them with --tracepoints");
+        return;
+    }
+    let disposition = app.tracepoint_disposition().clone();
+    for name in &known {
+        let line = match disposition.pauses_at(name) {
+            None => format!("  {name}"),
+            Some("next") => format!("* {name} (if reached first)"),
+            Some(pattern) if is_glob(pattern) => format!("* {name} (matches '{pattern}')"),
+            Some(_) => format!("* {name}"),
+        };
+        app.prompt_mut().print(line);
+    }
+}
+
+/// Defines an alias once the debugger exits, e.g. `alias ll=ls -l`. The
+/// value is taken as typed, without quotes.
+fn run_alias(app: &mut App, args: &str) {
+    let Some((name, value)) = args.split_once('=') else {
+        app.prompt_mut().print("usage: alias <name>=<value>");
+        return;
+    };
+    if !app.check_writable("alias") {
+        app.prompt_mut().print("alias: disabled in read-only mode");
+        return;
+    }
+    match app.queue_alias(name, value) {
+        Ok(()) => app
+            .prompt_mut()
+            .print(format!("will alias '{name}' after exiting")),
+        Err(err) => app.prompt_mut().print(format!("alias: {err}")),
+    }
+}
+
+/// Changes the shell's working directory once the debugger exits. A missing
+/// directory is only warned about, since it may be created by then.
+fn run_cd(app: &mut App, dir: &str) {
+    if dir.is_empty() {
+        app.prompt_mut().print("usage: cd <dir>");
+        return;
+    }
+    if !app.check_writable("cd") {
+        app.prompt_mut().print("cd: disabled in read-only mode");
+        return;
+    }
+    let dir = app.queue_cd(dir);
+    let shown = dir.display();
+    if !dir.exists() {
+        app.prompt_mut()
+            .print(format!("cd: warning: {shown} doesn't exist"));
+    } else if !dir.is_dir() {
+        app.prompt_mut()
+            .print(format!("cd: warning: {shown} isn't a directory"));
+    }
+    app.prompt_mut()
+        .print(format!("will change to {shown} after exiting"));
+}
+
+/// Defines a shell function once the debugger exits, e.g.
+/// `function up cd ..; ls`.
+fn run_function(app: &mut App, args: &str) {
+    let Some((name, body)) = args.split_once(' ') else {
+        app.prompt_mut().print("usage: function <name> <body>");
+        return;
+    };
+    if !app.check_writable("function") {
+        app.prompt_mut()
+            .print("function: disabled in read-only mode");
+        return;
+    }
+    match app.queue_function(name, body.trim()) {
+        Ok(()) => app
+            .prompt_mut()
+            .print(format!("will define '{name}' after exiting")),
+        Err(err) => app.prompt_mut().print(format!("function: {err}")),
+    }
+}
+
+/// Turns the shell's tracing of the commands it runs on or off once the
+/// debugger exits. Nothing the debugger itself does is traced.
+fn run_trace(app: &mut App, arg: &str) {
+    let on = match arg {
+        "on" => true,
+        "off" => false,
+        _ => {
+            app.prompt_mut().print("usage: trace <on|off>");
+            return;
+        }
+    };
+    if !app.check_writable("trace") {
+        app.prompt_mut().print("trace: disabled in read-only mode");
+        return;
+    }
+    app.queue(Cmd::Xtrace { on });
+    let done = if on {
+        "your shell will print each command it runs after exiting"
+    } else {
+        "your shell will stop printing the commands it runs after exiting"
+    };
+    app.prompt_mut().print(done);
+}
+
+/// Writes a Markdown summary of the session to a file, for writing up what
+/// was found. This doesn't change anything, so it works in read-only mode.
+fn run_transcript(app: &mut App, path: &str) {
+    if path.is_empty() {
+        app.prompt_mut().print("usage: transcript <path>");
+        return;
+    }
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let path = resolve_dir(path, app.cwd(), home.as_deref());
+    let done = match write_transcript(app, &path) {
+        Ok(()) => format!("wrote the transcript to {}", path.display()),
+        Err(err) => format!("transcript: {err:#}"),
+    };
+    app.prompt_mut().print(done);
+}
+
+/// Watches each variable named, or lists the watched variables and their
+/// values when none are. Watching only reads the environment, so it's
+/// allowed in read-only mode.
+fn run_watch(app: &mut App, names: &str) {
+    if names.is_empty() {
+        let width = app
+            .prompt()
+            .viewport_width
+            .unwrap_or(DEFAULT_SCROLLBACK_WIDTH);
+        let lines = app
+            .watch_list()
+            .iter()
+            .map(|watched| {
+                let line = format!("{}={}", watched.name, shown_value(app.env(), &watched.name));
+                truncate_to_width(&line, width).into_owned()
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            app.prompt_mut().print("usage: watch <name>...");
+        }
+        for line in lines {
+            app.prompt_mut().print(line);
+        }
+        return;
+    }
+    for name in names.split_whitespace() {
+        let done = if let Err(err) = check_var_name(name) {
+            format!("watch: {err}")
+        } else if app.watch_var(name) {
+            format!("watching {name}")
+        } else {
+            format!("watch: already watching {name}")
+        };
+        app.prompt_mut().print(done);
+    }
+}
+
+/// Stops watching each variable named.
+fn run_unwatch(app: &mut App, names: &str) {
+    if names.is_empty() {
+        app.prompt_mut().print("usage: unwatch <name>...");
+        return;
+    }
+    for name in names.split_whitespace() {
+        let done = if app.unwatch_var(name) {
+            format!("stopped watching {name}")
+        } else {
+            format!("unwatch: not watching {name}")
+        };
+        app.prompt_mut().print(done);
+    }
+}
+
+/// Lists the built-in commands, then the aliases from the config file along
+/// with what they run.
+fn run_help(app: &mut App) {
+    let mut lines = vec![format!("commands: {}", COMMANDS.join(" "))];
+    let aliases = app
+        .prompt_aliases()
+        .iter()
+        .map(|(name, command)| format!("  {name} = {command}"))
+        .collect::<Vec<_>>();
+    if !aliases.is_empty() {
+        lines.push("aliases:".to_string());
+        lines.extend(aliases);
+    }
+    for line in lines {
+        app.prompt_mut().print(line);
+    }
+}
+
+/// How wide `env` assumes the scrollback is before it's been drawn.
+const DEFAULT_SCROLLBACK_WIDTH: usize = 80;
+
+/// Prints the variables whose names match a glob, see [`glob_match`], as
+/// `NAME=value` lines cut to the width of the scrollback. Without a pattern
+/// only the names are printed, since every value at once would bury
+/// everything else. Values of variables that look like secrets are hidden
+/// unless `--reveal` is given.
+fn run_env(app: &mut App, args: &str) {
+    let reveal = args.split_whitespace().any(|arg| arg == "--reveal");
+    let pattern = args
+        .split_whitespace()
+        .filter(|arg| *arg != "--reveal")
+        .collect::<Vec<_>>()
+        .join(" ");
+    let pattern = unquote(&pattern);
+    let width = app
+        .prompt()
+        .viewport_width
+        .unwrap_or(DEFAULT_SCROLLBACK_WIDTH);
+    let env = app.env();
+    let mut lines = Vec::new();
+    if pattern.is_empty() {
+        lines.extend(env.vars().iter().cloned());
+    } else {
+        for (name, value) in env.vars().iter().zip(env.values()) {
+            if !glob_match(pattern, name) {
+                continue;
+            }
+            let line = if !reveal && looks_secret(name) {
+                format!("{name}=<hidden>")
+            } else {
+                format!("{name}={}", value.replace('\n', "\\n"))
+            };
+            lines.push(truncate_to_width(&line, width).into_owned());
+        }
+    }
+    let count = match (lines.len(), pattern.is_empty()) {
+        (1, true) => "1 variable".to_string(),
+        (count, true) => format!("{count} variables"),
+        (1, false) => format!("1 variable matches {pattern}"),
+        (count, false) => format!("{count} variables match {pattern}"),
+    };
+    lines.push(count);
+    for line in lines {
+        app.prompt_mut().print(line);
+    }
+}
+
+/// The most characters of a value that `diff` prints.
+const DIFF_VALUE_CHARS: usize = 40;
+
+/// Prints how the environment differs from the one captured in an env file,
+/// in any of the formats `--env-file` reads. With `--names-only`, values
+/// are left out, since they may hold secrets.
+fn run_diff(app: &mut App, args: &str) {
+    let names_only = args.split_whitespace().any(|arg| arg == "--names-only");
+    let path = args
+        .split_whitespace()
+        .filter(|arg| *arg != "--names-only")
+        .collect::<Vec<_>>()
+        .join(" ");
+    if path.is_empty() {
+        app.prompt_mut()
+            .print("usage: diff [--names-only] <env-file>");
+        return;
+    }
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let path = resolve_dir(unquote(&path), app.cwd(), home.as_deref());
+    let baseline = match load_env_file(&path) {
+        Ok(baseline) => baseline,
+        Err(err) => {
+            app.prompt_mut().print(format!("diff: {err:#}"));
+            return;
+        }
+    };
+    let env = app.env();
+    let diff = diff_envs(
+        &baseline,
+        env.vars()
+            .iter()
+            .zip(env.values())
+            .map(|(name, value)| (name.as_str(), value.as_str())),
+    );
+    let mut lines = vec![format!(
+        "{} added, {} removed, {} changed, {} unchanged since {}",
+        diff.count(VarChangeKind::Added),
+        diff.count(VarChangeKind::Unset),
+        diff.count(VarChangeKind::Modified),
+        diff.unchanged,
+        path.display()
+    )];
+    for kind in [
+        VarChangeKind::Added,
+        VarChangeKind::Unset,
+        VarChangeKind::Modified,
+    ] {
+        for change in diff.changes.iter().filter(|change| change.kind() == kind) {
+            let name = &change.name;
+            let line = match (kind, names_only) {
+                (VarChangeKind::Added, true) => format!("+ {name}"),
+                (VarChangeKind::Unset, true) => format!("- {name}"),
+                (VarChangeKind::Modified, true) => format!("~ {name}"),
+                (VarChangeKind::Added, false) => {
+                    format!("+ {name}={}", shorten(change.new.as_deref()))
+                }
+                (VarChangeKind::Unset, false) => {
+                    format!("- {name}={}", shorten(change.old.as_deref()))
+                }
+                (VarChangeKind::Modified, false) => format!(
+                    "~ {name}: {} → {}",
+                    shorten(change.old.as_deref()),
+                    shorten(change.new.as_deref())
+                ),
+            };
+            lines.push(line);
+        }
+    }
+    for line in lines {
+        app.prompt_mut().print(line);
+    }
+}
+
+/// Quotes a value for `diff` so that newlines and the like stay on one line,
+/// keeping only the first [`DIFF_VALUE_CHARS`] characters.
+fn shorten(value: Option<&str>) -> String {
+    let value = value.unwrap_or_default();
+    if value.chars().count() <= DIFF_VALUE_CHARS {
+        return format!("{value:?}");
+    }
+    let kept = value.chars().take(DIFF_VALUE_CHARS - 1).collect::<String>();
+    format!("{kept:?}…")
+}
+
+/// Changes where the shell pauses after exiting, reporting where once it
+/// has.
+fn set_disposition(app: &mut App, command: &str, disposition: TracepointDisposition) {
+    if !app.check_writable(command) {
+        app.prompt_mut()
+            .print(format!("{command}: disabled in read-only mode"));
+        return;
+    }
+    let done = disposition.describe();
+    app.set_tracepoint_disposition(disposition);
+    app.prompt_mut().print(done);
+}
+
+/// Switches to the prompt with `input` already typed, offering completions
+/// for it.
+pub fn start_prompt_command(app: &mut App, input: &str) {
+    app.set_screen(Screen::Prompt);
+    let prompt = app.prompt_mut();
+    prompt.set_input(input);
+    prompt.input_focused = true;
+    complete_input(app);
+}
+
+/// Completes the command name, tracepoint or variable being typed. A unique match is
+/// filled in, otherwise the input is extended as far as the matches agree
+/// and they're listed in the scrollback.
+fn complete_input(app: &mut App) {
+    let input = app.prompt().input().to_string();
+    let (prefix, word, candidates) = match input.split_once(' ') {
+        None => {
+            let aliases = app.prompt_aliases().iter().map(|(name, _)| name);
+            let mut names = COMMANDS.into_iter().chain(aliases).collect::<Vec<_>>();
+            names.sort_unstable();
+            (
+                "",
+                input.as_str(),
+                names.into_iter().map(String::from).collect(),
+            )
+        }
+        Some(("break", arg)) if !arg.contains(' ') => {
+            let mut names = app.trace().known_tracepoints().to_vec();
+            names.extend(SPECIAL_TRACEPOINTS.map(String::from));
+            ("break ", arg, names)
+        }
+        Some(("watch", arg)) if !arg.contains(' ') => ("watch ", arg, app.env().vars().to_vec()),
+        Some(("unwatch", arg)) if !arg.contains(' ') => ("unwatch ", arg, app.watch_list().names()),
+        _ => return,
+    };
+    let matches = candidates
+        .iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [] => {}
+        [only] => app.prompt_mut().set_input(format!("{prefix}{only} ")),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.as_str(), |common, candidate| {
+                let len = common
+                    .char_indices()
+                    .zip(candidate.chars())
+                    .take_while(|((_, a), b)| a == b)
+                    .last()
+                    .map_or(0, |((i, a), _)| i + a.len_utf8());
+                &common[..len]
+            });
+            let listing = matches
+                .iter()
+                .map(|candidate| candidate.as_str())
+                .collect::<Vec<_>>()
+                .join("  ");
+            let completed = format!("{prefix}{common}");
+            let prompt = app.prompt_mut();
+            prompt.set_input(completed);
+            prompt.print(listing);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{Shell, commands::tracepoint_cmd};
+
+    #[test]
+    fn history_recall_walks_back_and_forth() {
+        let mut prompt = PromptState::default();
+        prompt.set_input("first");
+        prompt.take_input();
+        prompt.set_input("second");
+        prompt.take_input();
+
+        prompt.history_previous();
+        assert_eq!(prompt.input(), "second");
+        prompt.history_previous();
+        assert_eq!(prompt.input(), "first");
+        prompt.history_previous();
+        assert_eq!(prompt.input(), "first");
+        prompt.history_next();
+        assert_eq!(prompt.input(), "second");
+        prompt.history_next();
+        assert_eq!(prompt.input(), "");
+    }
+
+    #[test]
+    fn break_commands_use_each_shells_syntax() {
+        let break_command = |shell, name| {
+            let disposition = TracepointDisposition::from_var_value(name);
+            tracepoint_cmd(shell, &disposition, "").unwrap()
+        };
+        assert_eq!(
+            break_command(Shell::Bash, "it's"),
+            r"export FLOX_DBG_TRACEPOINT='it'\''s'"
+        );
+        assert_eq!(
+            break_command(Shell::Fish, "it's"),
+            r"set -gx FLOX_DBG_TRACEPOINT 'it\'s'"
+        );
+        assert_eq!(
+            break_command(Shell::Posix, "setup"),
+            "FLOX_DBG_TRACEPOINT='setup'; export FLOX_DBG_TRACEPOINT"
+        );
+    }
+
+    #[test]
+    fn blank_input_is_not_recorded_in_history() {
+        let mut prompt = PromptState::default();
+        prompt.set_input("   ");
+        prompt.take_input();
+        assert!(prompt.history.is_empty());
+        assert_eq!(prompt.scrollback().len(), 1);
+    }
+}
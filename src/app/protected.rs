@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+
+use crate::app::{
+    Shell,
+    commands::{Cmd, is_name, set_path_var_cmd},
+};
+
+/// Variables that changing from the debugger is almost sure to break the
+/// activation with, so changes to them are confirmed first. The config file
+/// can add to these with `protected_vars`.
+pub const DEFAULT_PROTECTED_VARS: [&str; 4] = ["FLOX_ENV", "FLOX_ENV_DIRS", "HOME", "PATH"];
+
+/// Returns the default protected variables along with `extra` ones.
+pub fn protected_vars(extra: &[String]) -> BTreeSet<String> {
+    DEFAULT_PROTECTED_VARS
+        .iter()
+        .map(|name| name.to_string())
+        .chain(extra.iter().cloned())
+        .collect()
+}
+
+/// A change to the environment that's held back until it's confirmed if it
+/// touches a protected variable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VarMutation {
+    Set {
+        name: String,
+        value: String,
+    },
+    SetPath {
+        name: String,
+        entries: Vec<String>,
+    },
+    Unset {
+        name: String,
+    },
+    /// A command queued verbatim, which may set or unset anything.
+    Raw(String),
+}
+
+/// What became of a change to the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationOutcome {
+    Queued,
+    /// It touches a protected variable, so it waits for the user to confirm
+    /// it.
+    Held,
+    /// It touches a protected variable in read-only mode.
+    Blocked,
+}
+
+impl VarMutation {
+    /// Returns the command the mutation queues.
+    pub fn command(&self, shell: Shell) -> String {
+        match self {
+            VarMutation::Set { name, value } => Cmd::SetVar {
+                name: name.clone(),
+                value: value.clone(),
+            }
+            .render(shell),
+            VarMutation::SetPath { name, entries } => set_path_var_cmd(shell, name, entries),
+            VarMutation::Unset { name } => Cmd::UnsetVar { name: name.clone() }.render(shell),
+            VarMutation::Raw(line) => line.clone(),
+        }
+    }
+
+    /// Returns the variables the mutation sets or unsets. For raw commands
+    /// that's only a best guess, see [`assigned_vars`].
+    pub fn targets(&self) -> Vec<String> {
+        match self {
+            VarMutation::Set { name, .. }
+            | VarMutation::SetPath { name, .. }
+            | VarMutation::Unset { name } => vec![name.clone()],
+            VarMutation::Raw(line) => assigned_vars(line),
+        }
+    }
+}
+
+/// Commands whose arguments are `NAME` or `NAME=VALUE` to set.
+const ASSIGNING_COMMANDS: [&str; 5] = ["export", "declare", "typeset", "local", "readonly"];
+
+/// Returns the variables a shell command line sets or unsets, going by its
+/// words alone: assignments on their own, `export`, `declare` and the like,
+/// `unset`, and fish's `set`. Quoting isn't understood, so this is only
+/// meant for spotting the obvious.
+pub fn assigned_vars(line: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for command in line.split([';', '&', '|', '\n']) {
+        let words = command.split_whitespace().collect::<Vec<_>>();
+        let assignments = words
+            .iter()
+            .take_while(|word| assignment_name(word).is_some())
+            .count();
+        let Some((first, args)) = words[assignments..].split_first() else {
+            // Only assignments, which set the variables in the shell itself.
+            names.extend(words.iter().filter_map(|word| assignment_name(word)));
+            continue;
+        };
+        let args = args.iter().filter(|arg| !arg.starts_with('-'));
+        match *first {
+            "unset" => names.extend(args),
+            // fish only takes the variable's name, with its value after it.
+            "set" => names.extend(args.take(1)),
+            first if ASSIGNING_COMMANDS.contains(&first) => {
+                names.extend(args.map(|arg| assignment_name(arg).unwrap_or(arg)));
+            }
+            _ => {}
+        }
+    }
+    names
+        .into_iter()
+        .filter(|name| is_name(name))
+        .map(String::from)
+        .collect()
+}
+
+/// Returns the name assigned by a `NAME=VALUE` word.
+fn assignment_name(word: &str) -> Option<&str> {
+    let (name, _) = word.split_once('=')?;
+    is_name(name).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assigned_vars_are_found_in_simple_commands() {
+        let cases = [
+            ("export PATH=/bin", vec!["PATH"]),
+            ("export -n HOME FOO=1", vec!["HOME", "FOO"]),
+            ("unset -v HOME; echo hi", vec!["HOME"]),
+            ("HOME=/tmp", vec!["HOME"]),
+            ("A=1 B=2", vec!["A", "B"]),
+            ("set -gx PATH /bin $PATH", vec!["PATH"]),
+            ("set -e FLOX_ENV", vec!["FLOX_ENV"]),
+            ("true && declare -x HOME=/", vec!["HOME"]),
+            // Assignments before a command only apply to that command.
+            ("HOME=/tmp ls", vec![]),
+            ("echo export PATH=/bin", vec![]),
+            ("cd /tmp", vec![]),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(assigned_vars(line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn extra_protected_vars_add_to_the_defaults() {
+        let protected = protected_vars(&["KUBECONFIG".to_string()]);
+        assert!(protected.contains("HOME"));
+        assert!(protected.contains("KUBECONFIG"));
+        assert_eq!(protected.len(), DEFAULT_PROTECTED_VARS.len() + 1);
+    }
+}
@@ -0,0 +1,278 @@
+use ratatui::{Frame, layout::Rect};
+
+use crate::{
+    app::{
+        Action, App, AppEvent, Event, NavEvent, Screen,
+        home::{HomeEvent, handle_home_event},
+        key_bindings::{DisplayKeyBindings, FooterContext, KeyBindings, KeyCombo},
+        output::{OutputEvent, handle_output_event},
+        prompt::{PromptEvent, handle_prompt_event},
+        trace::{TraceEvent, handle_trace_event},
+        vars::{VarsEvent, handle_vars_event},
+    },
+    ui::{
+        home::render_home_screen, output::render_output_screen, prompt::render_prompt_screen,
+        trace::render_trace_screen, vars::render_vars_screen,
+    },
+};
+
+/// Everything that differs between screens: which keys they bind, how they
+/// handle the events those keys produce, how they're drawn, and which of
+/// their bindings the footer advertises.
+///
+/// Keeping these together means a new screen is one more implementation
+/// rather than a new arm in matches spread across the app and the UI.
+pub trait ScreenController {
+    /// Returns the screen's own bindings. They're added on top of the global
+    /// ones, so they win if both use the same key.
+    fn keymap(&self, bindings: &KeyBindings) -> Vec<(KeyCombo, Event)>;
+
+    /// Handles an event that isn't meant for a modal, an overlay, or the app
+    /// as a whole.
+    fn handle(&self, app: &mut App, event: &Event) -> Action;
+
+    /// Draws the screen's body in `area`.
+    fn render(&self, app: &mut App, frame: &mut Frame, area: Rect);
+
+    /// Returns the (keys, description) pairs shown in the footer after the
+    /// global ones.
+    fn footer_bindings(
+        &self,
+        bindings: &KeyBindings,
+        context: FooterContext,
+    ) -> Vec<(String, &'static str)>;
+}
+
+impl Screen {
+    /// Returns what handles and draws this screen.
+    pub fn controller(&self) -> &'static dyn ScreenController {
+        match self {
+            Screen::Home => &HomeScreen,
+            Screen::Prompt => &PromptScreen,
+            Screen::Vars => &VarsScreen,
+            Screen::Trace => &TraceScreen,
+            Screen::Output => &OutputScreen,
+        }
+    }
+}
+
+/// Picks the footer bindings for `read_only` mode from `bindings`.
+fn displayable(bindings: &impl DisplayKeyBindings, read_only: bool) -> Vec<(String, &'static str)> {
+    if read_only {
+        bindings.read_only_displayable()
+    } else {
+        bindings.displayable()
+    }
+}
+
+pub struct HomeScreen;
+
+impl ScreenController for HomeScreen {
+    fn keymap(&self, bindings: &KeyBindings) -> Vec<(KeyCombo, Event)> {
+        let home = bindings.home();
+        vec![
+            (
+                home.go_to_prompt,
+                Event::App(AppEvent::GoTo(Screen::Prompt)),
+            ),
+            (home.go_to_vars, Event::App(AppEvent::GoTo(Screen::Vars))),
+            (home.go_to_trace, Event::App(AppEvent::GoTo(Screen::Trace))),
+            (
+                home.go_to_output,
+                Event::App(AppEvent::GoTo(Screen::Output)),
+            ),
+            (home.reload_config, Event::App(AppEvent::ReloadConfig)),
+            (home.previous_action, Event::Nav(NavEvent::Up)),
+            (home.next_action, Event::Nav(NavEvent::Down)),
+            (home.activate, Event::Home(HomeEvent::Activate)),
+        ]
+    }
+
+    fn handle(&self, app: &mut App, event: &Event) -> Action {
+        handle_home_event(app, event)
+    }
+
+    fn render(&self, app: &mut App, frame: &mut Frame, area: Rect) {
+        render_home_screen(app, frame, area);
+    }
+
+    fn footer_bindings(
+        &self,
+        bindings: &KeyBindings,
+        context: FooterContext,
+    ) -> Vec<(String, &'static str)> {
+        displayable(&bindings.home(), context.read_only)
+    }
+}
+
+pub struct PromptScreen;
+
+impl ScreenController for PromptScreen {
+    fn keymap(&self, bindings: &KeyBindings) -> Vec<(KeyCombo, Event)> {
+        let prompt = bindings.prompt();
+        vec![
+            (prompt.focus_input, Event::Prompt(PromptEvent::FocusInput)),
+            (prompt.scroll_up, Event::Nav(NavEvent::Up)),
+            (prompt.scroll_down, Event::Nav(NavEvent::Down)),
+        ]
+    }
+
+    fn handle(&self, app: &mut App, event: &Event) -> Action {
+        handle_prompt_event(app, event);
+        Action::Continue
+    }
+
+    fn render(&self, app: &mut App, frame: &mut Frame, area: Rect) {
+        render_prompt_screen(app, frame, area);
+    }
+
+    fn footer_bindings(
+        &self,
+        bindings: &KeyBindings,
+        context: FooterContext,
+    ) -> Vec<(String, &'static str)> {
+        displayable(&bindings.prompt(), context.read_only)
+    }
+}
+
+pub struct VarsScreen;
+
+impl ScreenController for VarsScreen {
+    fn keymap(&self, bindings: &KeyBindings) -> Vec<(KeyCombo, Event)> {
+        let vars = bindings.vars();
+        vec![
+            (vars.next_var, Event::Nav(NavEvent::Down)),
+            (vars.previous_var, Event::Nav(NavEvent::Up)),
+            (vars.focus_var_list, Event::Nav(NavEvent::Left)),
+            (vars.focus_var_detail, Event::Nav(NavEvent::Right)),
+            (vars.raw_detail, Event::Vars(VarsEvent::RawDetail)),
+            (vars.split_detail, Event::Vars(VarsEvent::SplitDetail)),
+            (vars.open_in_pager, Event::Vars(VarsEvent::OpenInPager)),
+            (vars.toggle_tree, Event::Vars(VarsEvent::ToggleTree)),
+            (vars.toggle_group, Event::Vars(VarsEvent::ToggleGroup)),
+            (vars.cycle_origin, Event::Vars(VarsEvent::CycleOrigin)),
+            (
+                vars.toggle_auto_split,
+                Event::Vars(VarsEvent::ToggleAutoSplit),
+            ),
+            (
+                vars.toggle_command_preview,
+                Event::Vars(VarsEvent::ToggleCommandPreview),
+            ),
+            (vars.reveal_value, Event::Vars(VarsEvent::RevealValue)),
+            (vars.reload_env, Event::Vars(VarsEvent::ReloadEnv)),
+        ]
+    }
+
+    fn handle(&self, app: &mut App, event: &Event) -> Action {
+        handle_vars_event(app, event)
+    }
+
+    fn render(&self, app: &mut App, frame: &mut Frame, area: Rect) {
+        render_vars_screen(app, frame, area);
+    }
+
+    fn footer_bindings(
+        &self,
+        bindings: &KeyBindings,
+        context: FooterContext,
+    ) -> Vec<(String, &'static str)> {
+        bindings.vars().footer(context.vars.unwrap_or_default())
+    }
+}
+
+pub struct TraceScreen;
+
+impl ScreenController for TraceScreen {
+    fn keymap(&self, bindings: &KeyBindings) -> Vec<(KeyCombo, Event)> {
+        let trace = bindings.trace();
+        vec![
+            (trace.next_frame, Event::Nav(NavEvent::Down)),
+            (trace.previous_frame, Event::Nav(NavEvent::Up)),
+            (
+                trace.set_breakpoint,
+                Event::Trace(TraceEvent::SetBreakpoint),
+            ),
+            (trace.show_vars, Event::Trace(TraceEvent::ShowVars)),
+            (trace.open_in_pager, Event::Trace(TraceEvent::OpenInPager)),
+        ]
+    }
+
+    fn handle(&self, app: &mut App, event: &Event) -> Action {
+        handle_trace_event(app, event)
+    }
+
+    fn render(&self, app: &mut App, frame: &mut Frame, area: Rect) {
+        render_trace_screen(app, frame, area);
+    }
+
+    fn footer_bindings(
+        &self,
+        bindings: &KeyBindings,
+        context: FooterContext,
+    ) -> Vec<(String, &'static str)> {
+        displayable(&bindings.trace(), context.read_only)
+    }
+}
+
+pub struct OutputScreen;
+
+impl ScreenController for OutputScreen {
+    fn keymap(&self, bindings: &KeyBindings) -> Vec<(KeyCombo, Event)> {
+        let output = bindings.output();
+        vec![
+            (output.next_command, Event::Nav(NavEvent::Down)),
+            (output.previous_command, Event::Nav(NavEvent::Up)),
+            (
+                output.delete_command,
+                Event::Output(OutputEvent::DeleteSelected),
+            ),
+            (
+                output.edit_command,
+                Event::Output(OutputEvent::EditSelected),
+            ),
+            (output.clear_commands, Event::Output(OutputEvent::Clear)),
+            (output.undo_command, Event::Output(OutputEvent::Undo)),
+            (
+                output.open_in_pager,
+                Event::Output(OutputEvent::OpenInPager),
+            ),
+            (output.scroll_left, Event::Nav(NavEvent::Left)),
+            (output.scroll_right, Event::Nav(NavEvent::Right)),
+            (output.toggle_wrap, Event::Output(OutputEvent::ToggleWrap)),
+        ]
+    }
+
+    fn handle(&self, app: &mut App, event: &Event) -> Action {
+        handle_output_event(app, event)
+    }
+
+    fn render(&self, app: &mut App, frame: &mut Frame, area: Rect) {
+        render_output_screen(app, frame, area);
+    }
+
+    fn footer_bindings(
+        &self,
+        bindings: &KeyBindings,
+        context: FooterContext,
+    ) -> Vec<(String, &'static str)> {
+        displayable(&bindings.output(), context.read_only)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn screen_keymaps_dont_bind_a_key_twice() {
+        let bindings = KeyBindings::default();
+        for screen in Screen::TABS {
+            let keymap = screen.controller().keymap(&bindings);
+            let keys = keymap.iter().map(|(key, _)| key).collect::<HashSet<_>>();
+            assert_eq!(keys.len(), keymap.len(), "{screen}");
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Recognizing variables whose values shouldn't be shown or shared.
+
+/// Parts of variable names that suggest the value is a secret.
+const SECRET_NAME_PARTS: [&str; 7] = [
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "API_KEY",
+    "PRIVATE_KEY",
+];
+
+/// Returns whether the value of `name` should be kept out of anything the
+/// user might share.
+pub fn looks_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_names_are_recognized() {
+        assert!(looks_secret("GITHUB_TOKEN"));
+        assert!(looks_secret("aws_secret_access_key"));
+        assert!(!looks_secret("PATH"));
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::app::{App, Screen, vars::DetailView};
+
+/// The name of the directory holding the debugger's state.
+const STATE_DIR_NAME: &str = "flox-debugger";
+
+/// The name of the session file inside the state directory.
+const SESSION_FILE_NAME: &str = "session.toml";
+
+/// Returns where the session is saved, which is
+/// `$XDG_STATE_HOME/flox-debugger/session.toml` or
+/// `~/.local/state/flox-debugger/session.toml` if `XDG_STATE_HOME` is unset.
+pub fn default_session_path() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join(STATE_DIR_NAME).join(SESSION_FILE_NAME))
+}
+
+/// Where the user left the debugger last time, so the next session can pick
+/// up in the same place.
+///
+/// Only how things were viewed is kept, never anything from the environment
+/// itself, since the file outlives the session. Watched variables are saved
+/// by name alone, without their values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub screen: Option<Screen>,
+    pub vars_detail: Option<DetailView>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub watched: Vec<String>,
+}
+
+impl SessionState {
+    /// Loads the session saved at `path`. A missing or unreadable file is
+    /// the same as no session, since it's only a convenience.
+    pub fn load(path: &Path) -> Self {
+        let state = std::fs::read_to_string(path)
+            .map_err(Error::from)
+            .and_then(|contents| Self::parse(&contents));
+        match state {
+            Ok(state) => state,
+            Err(err) => {
+                debug!(path = %path.display(), %err, "ignoring saved session");
+                Self::default()
+            }
+        }
+    }
+
+    /// Parses the contents of a session file.
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        toml::from_str(contents).context("failed to parse session")
+    }
+
+    /// Saves the session to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let contents = toml::to_string(self).context("failed to serialize session")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write session file {}", path.display()))
+    }
+
+    /// Returns the session to save for `app`.
+    pub fn of(app: &App) -> Self {
+        Self {
+            screen: Some(app.screen()),
+            vars_detail: Some(app.env().detail_view()),
+            watched: app.watch_list().names(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn sessions_round_trip() {
+        let state = SessionState {
+            screen: Some(Screen::Vars),
+            vars_detail: Some(DetailView::Split),
+            watched: Vec::new(),
+        };
+        let contents = toml::to_string(&state).unwrap();
+        assert_eq!(contents, "screen = \"vars\"\nvars_detail = \"split\"\n");
+        assert_eq!(SessionState::parse(&contents).unwrap(), state);
+
+        // Fields added later are missing from older files.
+        assert_eq!(
+            SessionState::parse("screen = \"trace\"\n").unwrap(),
+            SessionState {
+                screen: Some(Screen::Trace),
+                vars_detail: None,
+                watched: Vec::new(),
+            }
+        );
+
+        // Watched variables are kept by name only.
+        let state = SessionState {
+            watched: vec!["PATH".to_string(), "EDITOR".to_string()],
+            ..state
+        };
+        let contents = toml::to_string(&state).unwrap();
+        assert_eq!(
+            contents,
+            "screen = \"vars\"\nvars_detail = \"split\"\nwatched = [\"PATH\", \"EDITOR\"]\n"
+        );
+        assert_eq!(SessionState::parse(&contents).unwrap(), state);
+    }
+
+    #[test]
+    fn missing_and_corrupt_sessions_are_ignored() {
+        let dir = TestDir::new("session");
+        let path = dir.join("nested").join(SESSION_FILE_NAME);
+        assert_eq!(SessionState::load(&path), SessionState::default());
+
+        let state = SessionState {
+            screen: Some(Screen::Output),
+            vars_detail: Some(DetailView::Raw),
+            watched: vec!["HOME".to_string()],
+        };
+        state.save(&path).unwrap();
+        assert_eq!(SessionState::load(&path), state);
+
+        for corrupt in ["screen = \"nowhere\"\n", "screen = [", "\u{0}\u{1}"] {
+            std::fs::write(&path, corrupt).unwrap();
+            assert_eq!(SessionState::load(&path), SessionState::default());
+        }
+    }
+}
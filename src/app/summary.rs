@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    App, ExitDisposition,
+    changes::{VarChange, VarChangeKind},
+    commands::TracepointDisposition,
+};
+
+/// The version of the summary's layout. It goes up whenever a field changes
+/// meaning or goes away, but not when one is added.
+pub const SUMMARY_SCHEMA_VERSION: u32 = 1;
+
+/// What the user did in the session, as written to `--summary-file` for the
+/// program that ran the debugger.
+///
+/// Variable values are left out, since any of them could be a secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub schema_version: u32,
+    pub exit: ExitSummary,
+    pub tracepoint: TracepointSummary,
+    pub added_vars: VarNames,
+    pub modified_vars: VarNames,
+    pub unset_vars: VarNames,
+    /// How many commands were queued verbatim, which could do anything.
+    pub raw_commands: usize,
+}
+
+/// How the session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitSummary {
+    /// The queued commands were printed for the shell to run.
+    Applied,
+    /// The user chose to exit without the queued commands.
+    Discarded,
+    /// The debugger was interrupted, dropping the queued commands.
+    Cancelled,
+    /// No one answered before `--timeout` ran out, dropping the queued
+    /// commands.
+    TimedOut,
+}
+
+/// Where the shell pauses next.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "disposition", rename_all = "snake_case")]
+pub enum TracepointSummary {
+    Continue,
+    PauseAtNext,
+    PauseAtAll,
+    PauseAt { tracepoints: Vec<String> },
+}
+
+impl From<&TracepointDisposition> for TracepointSummary {
+    fn from(disposition: &TracepointDisposition) -> Self {
+        match disposition {
+            TracepointDisposition::Continue => TracepointSummary::Continue,
+            TracepointDisposition::PauseAtNext => TracepointSummary::PauseAtNext,
+            TracepointDisposition::PauseAtAll => TracepointSummary::PauseAtAll,
+            TracepointDisposition::PauseAt(names) => TracepointSummary::PauseAt {
+                tracepoints: names.clone(),
+            },
+        }
+    }
+}
+
+/// The names of some variables, along with how many there are so readers
+/// don't have to count.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VarNames {
+    pub count: usize,
+    pub names: Vec<String>,
+}
+
+impl VarNames {
+    fn of_kind(changes: &[VarChange], kind: VarChangeKind) -> Self {
+        let names = changes
+            .iter()
+            .filter(|change| change.kind() == kind)
+            .map(|change| change.name.clone())
+            .collect::<Vec<_>>();
+        Self {
+            count: names.len(),
+            names,
+        }
+    }
+}
+
+impl SessionSummary {
+    /// Summarizes the session `app` ran, once it's over.
+    pub fn of(app: &App) -> Self {
+        let exit = if app.cancelled() {
+            ExitSummary::Cancelled
+        } else if app.timed_out() {
+            ExitSummary::TimedOut
+        } else if app.exit_disposition() == ExitDisposition::Discard {
+            ExitSummary::Discarded
+        } else {
+            ExitSummary::Applied
+        };
+        let changes = app.var_changes();
+        Self {
+            schema_version: SUMMARY_SCHEMA_VERSION,
+            exit,
+            tracepoint: app.tracepoint_disposition().into(),
+            added_vars: VarNames::of_kind(&changes, VarChangeKind::Added),
+            modified_vars: VarNames::of_kind(&changes, VarChangeKind::Modified),
+            unset_vars: VarNames::of_kind(&changes, VarChangeKind::Unset),
+            raw_commands: app.raw_command_count(),
+        }
+    }
+
+    /// Writes the summary to `path` as JSON. It's written to a temporary
+    /// file next to `path` and moved into place, so a reader never sees half
+    /// of it.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut json = serde_json::to_vec_pretty(self).context("failed to serialize summary")?;
+        json.push(b'\n');
+        let tmp = temp_path(path);
+        let written = fs::File::create(&tmp)
+            .and_then(|mut file| file.write_all(&json).and_then(|_| file.sync_all()))
+            .with_context(|| format!("failed to write {}", tmp.display()))
+            .and_then(|_| {
+                fs::rename(&tmp, path)
+                    .with_context(|| format!("failed to move summary to {}", path.display()))
+            });
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        written
+    }
+}
+
+/// Returns a hidden path in the same directory as `path`, so that renaming
+/// it over `path` doesn't cross file systems.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn summary() -> SessionSummary {
+        SessionSummary {
+            schema_version: SUMMARY_SCHEMA_VERSION,
+            exit: ExitSummary::Discarded,
+            tracepoint: TracepointSummary::PauseAt {
+                tracepoints: vec!["setup".to_string(), "hook-*".to_string()],
+            },
+            added_vars: VarNames::default(),
+            modified_vars: VarNames {
+                count: 1,
+                names: vec!["PATH".to_string()],
+            },
+            unset_vars: VarNames::default(),
+            raw_commands: 2,
+        }
+    }
+
+    #[test]
+    fn summaries_round_trip_through_json() {
+        let summary = summary();
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["schema_version"], SUMMARY_SCHEMA_VERSION);
+        assert_eq!(json["exit"], "discarded");
+        assert_eq!(json["tracepoint"]["disposition"], "pause_at");
+        assert_eq!(json["modified_vars"]["names"][0], "PATH");
+        let parsed = serde_json::from_value::<SessionSummary>(json).unwrap();
+        assert_eq!(parsed, summary);
+
+        for tracepoint in [
+            TracepointSummary::Continue,
+            TracepointSummary::PauseAtNext,
+            TracepointSummary::PauseAtAll,
+        ] {
+            let json = serde_json::to_string(&tracepoint).unwrap();
+            assert_eq!(
+                serde_json::from_str::<TracepointSummary>(&json).unwrap(),
+                tracepoint
+            );
+        }
+    }
+
+    #[test]
+    fn summaries_replace_the_file_whole() {
+        let dir = TestDir::new("summary");
+        let path = dir.join("summary.json");
+        fs::write(&path, "stale").unwrap();
+        summary().write(&path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SessionSummary>(&written).unwrap(),
+            summary()
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A missing directory fails without leaving anything behind.
+        assert!(summary().write(&dir.join("missing/summary.json")).is_err());
+    }
+}
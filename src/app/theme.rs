@@ -1,5 +1,101 @@
+use std::str::FromStr;
+
+use anyhow::{Error, anyhow, bail};
 use ratatui::style::{Color, Modifier, Style};
 
+use crate::terminal::{Background, ColorDepth, TerminalInfo};
+
+/// A built-in theme that can be selected by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemePreset {
+    /// The theme the debugger has always used, tuned for dark backgrounds.
+    Default,
+    /// A higher contrast theme for dark backgrounds.
+    Dark,
+    Light,
+    Solarized,
+    /// Shades of gray only, for users who find the accent color distracting.
+    Monochrome,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 5] = [
+        ThemePreset::Default,
+        ThemePreset::Dark,
+        ThemePreset::Light,
+        ThemePreset::Solarized,
+        ThemePreset::Monochrome,
+    ];
+
+    /// Returns the name used to select the preset.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThemePreset::Default => "default",
+            ThemePreset::Dark => "dark",
+            ThemePreset::Light => "light",
+            ThemePreset::Solarized => "solarized",
+            ThemePreset::Monochrome => "monochrome",
+        }
+    }
+}
+
+/// Which theme the user asked for on the command line or in the config file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThemeChoice {
+    /// Pick the theme matching the terminal's background.
+    #[default]
+    Auto,
+    Preset(ThemePreset),
+}
+
+impl FromStr for ThemeChoice {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(ThemeChoice::Auto);
+        }
+        ThemePreset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .map(ThemeChoice::Preset)
+            .ok_or_else(|| {
+                let names = ThemePreset::ALL.map(|preset| preset.name()).join(", ");
+                anyhow!("unknown theme '{s}', available themes are: auto, {names}")
+            })
+    }
+}
+
+/// The config names of every style in a [Theme].
+const STYLE_NAMES: [&str; 12] = [
+    "flox_purple",
+    "fg",
+    "fg_dim",
+    "selected_tab",
+    "highlighted_text",
+    "selected_option",
+    "border",
+    "border_focused",
+    "error",
+    "warning",
+    "success",
+    "stripe",
+];
+
+/// Hand-picked stand-ins for the theme's RGB colors, used instead of the
+/// nearest palette color when the terminal can't display them.
+///
+/// Entries are `(rgb, 256-color, 16-color)`.
+const COLOR_FALLBACKS: [(Color, Color, Color); 3] = [
+    (
+        Color::Rgb(175, 135, 255),
+        Color::Indexed(141),
+        Color::Magenta,
+    ),
+    (Color::Rgb(135, 95, 215), Color::Indexed(98), Color::Magenta),
+    (Color::Rgb(175, 95, 0), Color::Indexed(130), Color::Yellow),
+];
+
 #[derive(Debug, Clone)]
 pub struct Theme {
     pub flox_purple: Style,
@@ -7,6 +103,22 @@ pub struct Theme {
     pub fg_dim: Style,
     pub selected_tab: Style,
     pub highlighted_text: Style,
+    /// The active option in a title that lists several, e.g. Raw / Split.
+    pub selected_option: Style,
+    /// The border of a pane that doesn't have focus.
+    pub border: Style,
+    /// The border of the pane that has focus.
+    pub border_focused: Style,
+    pub error: Style,
+    pub warning: Style,
+    pub success: Style,
+    /// The style of every other row of a list, a subtle background that
+    /// keeps the eye on the row it's reading across. The default style
+    /// leaves the rows as they are.
+    pub stripe: Style,
+    /// A marker placed at the start of the focused pane's title, for themes
+    /// where the focused border alone may not stand out.
+    pub focus_marker: Option<&'static str>,
 }
 
 impl Theme {
@@ -20,6 +132,249 @@ impl Theme {
                 .add_modifier(Modifier::UNDERLINED)
                 .add_modifier(Modifier::BOLD),
             highlighted_text: Style::default().bg(Color::White).fg(Color::Black),
+            selected_option: Style::default().add_modifier(Modifier::UNDERLINED),
+            border: Style::default(),
+            border_focused: Style::default().fg(Color::Rgb(175, 135, 255)),
+            error: Style::default().fg(Color::LightRed),
+            warning: Style::default().fg(Color::Yellow),
+            success: Style::default().fg(Color::Green),
+            stripe: Style::default().bg(Color::Rgb(28, 28, 28)),
+            focus_marker: None,
+        }
+    }
+
+    /// Returns the built-in theme with the given name.
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Default => Self::new(),
+            ThemePreset::Dark => Self::dark(),
+            ThemePreset::Light => Self::light(),
+            ThemePreset::Solarized => Self::solarized(),
+            ThemePreset::Monochrome => Self::monochrome(),
+        }
+    }
+
+    /// Returns a higher contrast variant of the default theme that uses the
+    /// accent color for selections.
+    fn dark() -> Self {
+        let purple = Color::Rgb(175, 135, 255);
+        Theme {
+            flox_purple: Style::default().fg(purple),
+            fg: Style::default().fg(Color::White),
+            fg_dim: Style::default().fg(Color::DarkGray),
+            selected_tab: Style::default()
+                .fg(purple)
+                .add_modifier(Modifier::UNDERLINED)
+                .add_modifier(Modifier::BOLD),
+            highlighted_text: Style::default().bg(purple).fg(Color::Black),
+            selected_option: Style::default()
+                .fg(purple)
+                .add_modifier(Modifier::UNDERLINED),
+            border: Style::default().fg(Color::DarkGray),
+            border_focused: Style::default().fg(purple),
+            error: Style::default().fg(Color::LightRed),
+            warning: Style::default().fg(Color::LightYellow),
+            success: Style::default().fg(Color::LightGreen),
+            // Striping would take away from the contrast.
+            stripe: Style::default(),
+            focus_marker: None,
+        }
+    }
+
+    /// Returns a theme suited to a terminal with a light background, where
+    /// white highlights would be nearly invisible.
+    fn light() -> Self {
+        Theme {
+            flox_purple: Style::default().fg(Color::Rgb(135, 95, 215)),
+            fg: Style::default(),
+            fg_dim: Style::default().add_modifier(Modifier::DIM),
+            selected_tab: Style::default()
+                .fg(Color::Black)
+                .add_modifier(Modifier::UNDERLINED)
+                .add_modifier(Modifier::BOLD),
+            highlighted_text: Style::default().bg(Color::Black).fg(Color::White),
+            selected_option: Style::default().add_modifier(Modifier::UNDERLINED),
+            border: Style::default(),
+            border_focused: Style::default().fg(Color::Rgb(135, 95, 215)),
+            error: Style::default().fg(Color::Red),
+            warning: Style::default().fg(Color::Rgb(175, 95, 0)),
+            success: Style::default().fg(Color::Green),
+            stripe: Style::default().bg(Color::Rgb(238, 238, 238)),
+            focus_marker: None,
+        }
+    }
+
+    /// Returns a theme using the Solarized palette.
+    fn solarized() -> Self {
+        let violet = Color::Rgb(108, 113, 196);
+        let base01 = Color::Rgb(88, 110, 117);
+        let base1 = Color::Rgb(147, 161, 161);
+        Theme {
+            flox_purple: Style::default().fg(violet),
+            fg: Style::default().fg(base1),
+            fg_dim: Style::default().fg(base01),
+            selected_tab: Style::default()
+                .fg(Color::Rgb(38, 139, 210))
+                .add_modifier(Modifier::UNDERLINED)
+                .add_modifier(Modifier::BOLD),
+            highlighted_text: Style::default()
+                .bg(Color::Rgb(7, 54, 66))
+                .fg(Color::Rgb(238, 232, 213)),
+            selected_option: Style::default()
+                .fg(Color::Rgb(42, 161, 152))
+                .add_modifier(Modifier::UNDERLINED),
+            border: Style::default().fg(base01),
+            border_focused: Style::default().fg(violet),
+            error: Style::default().fg(Color::Rgb(220, 50, 47)),
+            warning: Style::default().fg(Color::Rgb(181, 137, 0)),
+            success: Style::default().fg(Color::Rgb(133, 153, 0)),
+            stripe: Style::default().bg(Color::Rgb(0, 50, 62)),
+            focus_marker: None,
+        }
+    }
+
+    /// Returns a theme that only uses shades of gray.
+    fn monochrome() -> Self {
+        Theme {
+            flox_purple: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            fg: Style::default().fg(Color::Gray),
+            fg_dim: Style::default().fg(Color::DarkGray),
+            selected_tab: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::UNDERLINED)
+                .add_modifier(Modifier::BOLD),
+            highlighted_text: Style::default().bg(Color::Gray).fg(Color::Black),
+            selected_option: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::UNDERLINED),
+            border: Style::default().fg(Color::DarkGray),
+            border_focused: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+            warning: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            success: Style::default().fg(Color::Gray),
+            stripe: Style::default().bg(Color::Rgb(28, 28, 28)),
+            focus_marker: Some("▶"),
+        }
+    }
+
+    /// Returns the theme variant for the given terminal background.
+    pub fn for_background(background: Background) -> Self {
+        match background {
+            Background::Dark => Self::preset(ThemePreset::Default),
+            Background::Light => Self::preset(ThemePreset::Light),
+        }
+    }
+
+    /// Returns the theme the user chose, or the one for the terminal's
+    /// background when they left it up to us, limited to the colors the
+    /// terminal can display.
+    pub fn for_choice(choice: ThemeChoice, caps: &TerminalInfo) -> Self {
+        match choice {
+            ThemeChoice::Auto => Self::for_capabilities(caps),
+            ThemeChoice::Preset(preset) => Self::preset(preset).with_color_depth(caps.color_depth),
+        }
+    }
+
+    /// Returns the theme for the terminal's background, limited to the
+    /// colors it can display.
+    pub fn for_capabilities(caps: &TerminalInfo) -> Self {
+        caps.background
+            .map(Self::for_background)
+            .unwrap_or_default()
+            .with_color_depth(caps.color_depth)
+    }
+
+    /// Replaces any colors the terminal can't display with the closest ones
+    /// it can. Lists aren't striped with 16 colors, none of which is subtle
+    /// enough.
+    pub fn with_color_depth(mut self, depth: ColorDepth) -> Self {
+        if depth == ColorDepth::Ansi16 {
+            self.stripe = Style::default();
+        }
+        for name in STYLE_NAMES {
+            let style = self.style_mut(name).expect("style names are valid");
+            style.fg = style.fg.map(|color| downgrade_color(color, depth));
+            style.bg = style.bg.map(|color| downgrade_color(color, depth));
+        }
+        self
+    }
+
+    /// Replaces the accent color everywhere it's used, e.g. the tab names,
+    /// the header, and focused borders.
+    pub fn with_accent(mut self, accent: Color) -> Self {
+        self.flox_purple = self.flox_purple.fg(accent);
+        self.border_focused = self.border_focused.fg(accent);
+        self
+    }
+
+    /// Returns a theme that leaves the terminal's default colors alone and
+    /// relies on modifiers to set elements apart. Used when the user has
+    /// asked for no color via `NO_COLOR` or `--no-color`.
+    pub fn no_color() -> Self {
+        Theme {
+            flox_purple: Style::default().add_modifier(Modifier::BOLD),
+            fg: Style::default(),
+            fg_dim: Style::default().add_modifier(Modifier::DIM),
+            selected_tab: Style::default()
+                .add_modifier(Modifier::UNDERLINED)
+                .add_modifier(Modifier::BOLD),
+            highlighted_text: Style::default().add_modifier(Modifier::REVERSED),
+            selected_option: Style::default().add_modifier(Modifier::UNDERLINED),
+            border: Style::default(),
+            border_focused: Style::default().add_modifier(Modifier::BOLD),
+            error: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+            warning: Style::default().add_modifier(Modifier::BOLD),
+            success: Style::default(),
+            stripe: Style::default(),
+            focus_marker: Some("▶"),
+        }
+    }
+
+    /// Builds a theme from the `[theme]` table of the config file, using the
+    /// style from `base` for any field the table doesn't mention.
+    ///
+    /// Each field is either a style string such as `"#af87ff bold"` or
+    /// `"black on white"`, or a table with optional `fg`, `bg`, and
+    /// `modifiers` keys. A field given in the config replaces the default
+    /// style entirely rather than being layered on top of it.
+    pub fn from_config(base: Theme, table: &toml::Table) -> Result<Self, Error> {
+        let mut theme = base;
+        for (name, value) in table {
+            let style = theme
+                .style_mut(name)
+                .ok_or_else(|| anyhow!("unknown theme style 'theme.{name}'"))?;
+            *style = parse_style_value(value).map_err(|err| anyhow!("theme.{name}: {err}"))?;
+        }
+        Ok(theme)
+    }
+
+    /// Returns the style with the given config name.
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        match name {
+            "flox_purple" => Some(&mut self.flox_purple),
+            "fg" => Some(&mut self.fg),
+            "fg_dim" => Some(&mut self.fg_dim),
+            "selected_tab" => Some(&mut self.selected_tab),
+            "highlighted_text" => Some(&mut self.highlighted_text),
+            "selected_option" => Some(&mut self.selected_option),
+            "border" => Some(&mut self.border),
+            "border_focused" => Some(&mut self.border_focused),
+            "error" => Some(&mut self.error),
+            "warning" => Some(&mut self.warning),
+            "success" => Some(&mut self.success),
+            "stripe" => Some(&mut self.stripe),
+            _ => None,
         }
     }
 }
@@ -29,3 +384,485 @@ impl Default for Theme {
         Theme::new()
     }
 }
+
+/// Parses the value of the `FLOX_DBG_ACCENT` variable, which lets users
+/// running several debuggers side by side tell them apart. An unset or empty
+/// variable means no override.
+pub fn parse_accent_override(value: Option<&str>) -> Result<Option<Color>, Error> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => parse_color(value)
+            .map(Some)
+            .map_err(|err| anyhow!("ignoring FLOX_DBG_ACCENT: {err}")),
+    }
+}
+
+/// Converts `color` to one that can be displayed at the given color depth.
+fn downgrade_color(color: Color, depth: ColorDepth) -> Color {
+    let fallback = COLOR_FALLBACKS
+        .iter()
+        .find(|(rgb, _, _)| *rgb == color)
+        .map(|(_, indexed, ansi)| (*indexed, *ansi));
+    match (depth, color) {
+        (ColorDepth::TrueColor, _) => color,
+        (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => match fallback {
+            Some((indexed, _)) => indexed,
+            None => Color::Indexed(nearest_256(r, g, b)),
+        },
+        (ColorDepth::Ansi16, Color::Rgb(..) | Color::Indexed(16..)) => match fallback {
+            Some((_, ansi)) => ansi,
+            None => {
+                let (r, g, b) = to_rgb(color).expect("color is RGB or indexed");
+                nearest_ansi16(r, g, b)
+            }
+        },
+        _ => color,
+    }
+}
+
+/// The channel values of the xterm 6x6x6 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The xterm default values of the 16 ANSI colors.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Returns the index of the closest color in the xterm 256-color palette,
+/// considering the color cube and the grayscale ramp.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(c))
+            .expect("levels aren't empty")
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_idx = 16 + 36 * ri + 6 * gi + bi;
+    let avg = ((u16::from(r) + u16::from(g) + u16::from(b)) / 3) as u8;
+    let gray_step = (avg.saturating_sub(8) / 10).min(23);
+    let gray_value = 8 + 10 * gray_step;
+    let gray = (gray_value, gray_value, gray_value);
+    if distance(gray, (r, g, b)) < distance(cube, (r, g, b)) {
+        232 + gray_step
+    } else {
+        cube_idx as u8
+    }
+}
+
+/// Returns the closest of the 16 ANSI colors.
+fn nearest_ansi16(r: u8, g: u8, b: u8) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .expect("palette isn't empty")
+}
+
+/// Returns the RGB value of an RGB or 256-color palette color.
+fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(idx @ 0..=15) => Some(ANSI16[idx as usize].1),
+        Color::Indexed(idx @ 16..=231) => {
+            let idx = idx - 16;
+            Some((
+                CUBE_LEVELS[(idx / 36) as usize],
+                CUBE_LEVELS[((idx / 6) % 6) as usize],
+                CUBE_LEVELS[(idx % 6) as usize],
+            ))
+        }
+        Color::Indexed(idx) => {
+            let value = 8 + 10 * (idx - 232);
+            Some((value, value, value))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a style from either a style string or a `{ fg, bg, modifiers }`
+/// table.
+fn parse_style_value(value: &toml::Value) -> Result<Style, Error> {
+    match value {
+        toml::Value::String(spec) => parse_style(spec),
+        toml::Value::Table(table) => {
+            let mut style = Style::default();
+            for (key, value) in table {
+                match key.as_str() {
+                    "fg" => style = style.fg(parse_color(expect_str(key, value)?)?),
+                    "bg" => style = style.bg(parse_color(expect_str(key, value)?)?),
+                    "modifiers" => {
+                        let modifiers = value
+                            .as_array()
+                            .ok_or_else(|| anyhow!("modifiers must be a list of strings"))?;
+                        for modifier in modifiers {
+                            let modifier = expect_str(key, modifier)?;
+                            let modifier = parse_modifier(modifier)
+                                .ok_or_else(|| anyhow!("invalid modifier '{modifier}'"))?;
+                            style = style.add_modifier(modifier);
+                        }
+                    }
+                    _ => bail!("unknown style key '{key}'"),
+                }
+            }
+            Ok(style)
+        }
+        _ => bail!("expected a style string or table, found '{value}'"),
+    }
+}
+
+fn expect_str<'a>(key: &str, value: &'a toml::Value) -> Result<&'a str, Error> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow!("{key} must be a string, found '{value}'"))
+}
+
+/// Parses a whitespace-separated style string.
+///
+/// Each word is a color or a modifier. The first color sets the foreground,
+/// and a color after the word `on` sets the background, e.g.
+/// `"bold black on white"`.
+pub fn parse_style(spec: &str) -> Result<Style, Error> {
+    let mut style = Style::default();
+    let mut words = spec.split_whitespace().peekable();
+    if words.peek().is_none() {
+        bail!("empty style");
+    }
+    while let Some(word) = words.next() {
+        if word.eq_ignore_ascii_case("on") {
+            let color = words
+                .next()
+                .ok_or_else(|| anyhow!("expected a background color after 'on' in '{spec}'"))?;
+            style = style.bg(parse_color(color)?);
+        } else if let Some(modifier) = parse_modifier(word) {
+            style = style.add_modifier(modifier);
+        } else if style.fg.is_none() {
+            style = style.fg(parse_color(word)?);
+        } else {
+            bail!("unexpected '{word}' in '{spec}'");
+        }
+    }
+    Ok(style)
+}
+
+/// Parses a color given as `#rrggbb`, a 256-color index, or the name of one of
+/// the 16 ANSI colors.
+pub fn parse_color(color: &str) -> Result<Color, Error> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("invalid hex color '{color}', expected '#rrggbb'");
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("validated hex");
+        return Ok(Color::Rgb(channel(0), channel(2), channel(4)));
+    }
+    if let Ok(idx) = color.parse::<u8>() {
+        return Ok(Color::Indexed(idx));
+    }
+    let normalized = color.to_ascii_lowercase().replace(['-', '_', ' '], "");
+    let color = match normalized.as_str() {
+        "default" | "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" | "brightblack" => Color::DarkGray,
+        "lightred" | "brightred" => Color::LightRed,
+        "lightgreen" | "brightgreen" => Color::LightGreen,
+        "lightyellow" | "brightyellow" => Color::LightYellow,
+        "lightblue" | "brightblue" => Color::LightBlue,
+        "lightmagenta" | "brightmagenta" => Color::LightMagenta,
+        "lightcyan" | "brightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => bail!("unknown color '{color}'"),
+    };
+    Ok(color)
+}
+
+/// Parses the name of a text modifier such as `bold` or `dim`.
+fn parse_modifier(modifier: &str) -> Option<Modifier> {
+    let modifier = match modifier.to_ascii_lowercase().as_str() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underline" | "underlined" => Modifier::UNDERLINED,
+        "reverse" | "reversed" => Modifier::REVERSED,
+        "strikethrough" | "crossed_out" => Modifier::CROSSED_OUT,
+        "blink" => Modifier::SLOW_BLINK,
+        "hidden" => Modifier::HIDDEN,
+        _ => return None,
+    };
+    Some(modifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::detect_color_depth;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#af87ff").unwrap(), Color::Rgb(175, 135, 255));
+        assert_eq!(parse_color("#AF87FF").unwrap(), Color::Rgb(175, 135, 255));
+        assert_eq!(parse_color("magenta").unwrap(), Color::Magenta);
+        assert_eq!(parse_color("Bright-Black").unwrap(), Color::DarkGray);
+        assert_eq!(parse_color("141").unwrap(), Color::Indexed(141));
+        assert!(parse_color("#af87f").is_err());
+        assert!(parse_color("#gg87ff").is_err());
+        assert!(parse_color("purpleish").is_err());
+        assert!(parse_color("256").is_err());
+    }
+
+    #[test]
+    fn parses_style_strings() {
+        assert_eq!(
+            parse_style("dim").unwrap(),
+            Style::default().add_modifier(Modifier::DIM)
+        );
+        assert_eq!(
+            parse_style("bold #af87ff").unwrap(),
+            Style::default()
+                .fg(Color::Rgb(175, 135, 255))
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            parse_style("black on white").unwrap(),
+            Style::default().fg(Color::Black).bg(Color::White)
+        );
+        assert!(parse_style("").is_err());
+        assert!(parse_style("red green").is_err());
+        assert!(parse_style("red on").is_err());
+    }
+
+    #[test]
+    fn config_overrides_only_given_fields() {
+        let table = r##"
+            flox_purple = "#ff0000"
+            highlighted_text = { fg = "white", bg = "blue", modifiers = ["bold"] }
+        "##
+        .parse::<toml::Table>()
+        .unwrap();
+        let theme = Theme::from_config(Theme::default(), &table).unwrap();
+        assert_eq!(
+            theme.flox_purple,
+            Style::default().fg(Color::Rgb(255, 0, 0))
+        );
+        assert_eq!(
+            theme.highlighted_text,
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(theme.fg_dim, Theme::default().fg_dim);
+    }
+
+    #[test]
+    fn config_errors_name_the_offending_key() {
+        let err = |toml: &str| {
+            let table = toml.parse::<toml::Table>().unwrap();
+            format!(
+                "{:#}",
+                Theme::from_config(Theme::default(), &table).unwrap_err()
+            )
+        };
+        let msg = err(r#"fg = "purpl""#);
+        assert!(msg.contains("theme.fg") && msg.contains("purpl"), "{msg}");
+        let msg = err(r#"accent = "red""#);
+        assert!(msg.contains("theme.accent"), "{msg}");
+        let msg = err(r#"fg_dim = { modifiers = ["sparkly"] }"#);
+        assert!(
+            msg.contains("theme.fg_dim") && msg.contains("sparkly"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn presets_are_complete_and_distinct() {
+        let presets = ThemePreset::ALL.map(|preset| (preset, styles(&Theme::preset(preset))));
+        for (preset, styles) in &presets {
+            assert_eq!(styles.len(), STYLE_NAMES.len());
+            // Focus and selection must be visible in every preset.
+            let theme = Theme::preset(*preset);
+            assert_ne!(theme.border, theme.border_focused, "{preset:?}");
+            assert_ne!(theme.highlighted_text, theme.fg, "{preset:?}");
+        }
+        for (i, (a, a_styles)) in presets.iter().enumerate() {
+            for (b, b_styles) in &presets[i + 1..] {
+                assert_ne!(a_styles, b_styles, "{a:?} and {b:?} are identical");
+            }
+        }
+    }
+
+    #[test]
+    fn stripes_are_left_out_where_they_would_hurt() {
+        assert_ne!(Theme::default().stripe, Style::default());
+        assert_eq!(Theme::preset(ThemePreset::Dark).stripe, Style::default());
+        assert_eq!(Theme::no_color().stripe, Style::default());
+        let ansi16 = Theme::default().with_color_depth(ColorDepth::Ansi16);
+        assert_eq!(ansi16.stripe, Style::default());
+        let ansi256 = Theme::default().with_color_depth(ColorDepth::Ansi256);
+        assert_eq!(ansi256.stripe.bg, Some(Color::Indexed(234)));
+    }
+
+    #[test]
+    fn accent_override_accepts_hex_and_named_colors() {
+        let accent = parse_accent_override(Some("#ff8800")).unwrap().unwrap();
+        assert_eq!(accent, Color::Rgb(255, 136, 0));
+        let theme = Theme::default().with_accent(accent);
+        assert_eq!(theme.flox_purple.fg, Some(accent));
+        assert_eq!(theme.border_focused.fg, Some(accent));
+        // Everything else is left alone.
+        assert_eq!(theme.highlighted_text, Theme::default().highlighted_text);
+
+        assert_eq!(
+            parse_accent_override(Some(" cyan ")).unwrap(),
+            Some(Color::Cyan)
+        );
+        assert_eq!(parse_accent_override(Some("")).unwrap(), None);
+        assert_eq!(parse_accent_override(None).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_accent_override_is_reported() {
+        let err = parse_accent_override(Some("#12345"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("FLOX_DBG_ACCENT") && err.contains("#12345"),
+            "{err}"
+        );
+        assert!(parse_accent_override(Some("chartreuse-ish")).is_err());
+    }
+
+    #[test]
+    fn parses_theme_choices() {
+        assert_eq!("auto".parse::<ThemeChoice>().unwrap(), ThemeChoice::Auto);
+        for preset in ThemePreset::ALL {
+            assert_eq!(
+                preset.name().parse::<ThemeChoice>().unwrap(),
+                ThemeChoice::Preset(preset)
+            );
+        }
+        let err = "neon".parse::<ThemeChoice>().unwrap_err().to_string();
+        assert!(err.contains("neon") && err.contains("solarized"), "{err}");
+    }
+
+    #[test]
+    fn light_theme_highlights_are_visible() {
+        let theme = Theme::for_background(Background::Light);
+        assert_ne!(theme.highlighted_text.bg, Some(Color::White));
+        assert_eq!(
+            Theme::for_background(Background::Dark).highlighted_text,
+            Theme::default().highlighted_text
+        );
+    }
+
+    /// Returns every style in the theme.
+    fn styles(theme: &Theme) -> Vec<Style> {
+        let mut theme = theme.clone();
+        STYLE_NAMES
+            .iter()
+            .map(|name| *theme.style_mut(name).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn no_color_theme_has_no_colors() {
+        let theme = Theme::no_color();
+        for style in styles(&theme) {
+            assert!(matches!(style.fg, None | Some(Color::Reset)), "{style:?}");
+            assert!(matches!(style.bg, None | Some(Color::Reset)), "{style:?}");
+        }
+        // Selection must still stand out from regular text.
+        assert_ne!(theme.highlighted_text, theme.fg);
+    }
+
+    fn caps(colorterm: Option<&str>, term: &str) -> TerminalInfo {
+        TerminalInfo {
+            background: None,
+            color_depth: detect_color_depth(colorterm, Some(term)),
+        }
+    }
+
+    #[test]
+    fn accent_falls_back_by_color_depth() {
+        let purple = |caps: &TerminalInfo| Theme::for_capabilities(caps).flox_purple.fg;
+        assert_eq!(
+            purple(&caps(Some("truecolor"), "xterm-256color")),
+            Some(Color::Rgb(175, 135, 255))
+        );
+        assert_eq!(
+            purple(&caps(None, "screen-256color")),
+            Some(Color::Indexed(141))
+        );
+        assert_eq!(purple(&caps(None, "xterm")), Some(Color::Magenta));
+        let light = TerminalInfo {
+            background: Some(Background::Light),
+            color_depth: ColorDepth::Ansi16,
+        };
+        assert_eq!(purple(&light), Some(Color::Magenta));
+    }
+
+    #[test]
+    fn downgraded_themes_only_use_supported_colors() {
+        for term in ["screen-256color", "xterm"] {
+            let caps = caps(None, term);
+            for style in styles(&Theme::for_capabilities(&caps)) {
+                for color in [style.fg, style.bg].into_iter().flatten() {
+                    assert!(!matches!(color, Color::Rgb(..)), "{term}: {color:?}");
+                    if caps.color_depth == ColorDepth::Ansi16 {
+                        assert!(!matches!(color, Color::Indexed(16..)), "{term}: {color:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_colors_map_to_nearest_palette_entry() {
+        assert_eq!(
+            downgrade_color(Color::Rgb(255, 0, 0), ColorDepth::Ansi256),
+            Color::Indexed(196)
+        );
+        assert_eq!(
+            downgrade_color(Color::Rgb(128, 128, 128), ColorDepth::Ansi256),
+            Color::Indexed(244)
+        );
+        assert_eq!(
+            downgrade_color(Color::Rgb(250, 10, 10), ColorDepth::Ansi16),
+            Color::LightRed
+        );
+        assert_eq!(
+            downgrade_color(Color::Indexed(21), ColorDepth::Ansi16),
+            Color::Blue
+        );
+        assert_eq!(
+            downgrade_color(Color::Cyan, ColorDepth::Ansi16),
+            Color::Cyan
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::app::messages::Severity;
+
+/// How long a toast stays on screen once it's shown.
+pub const TOAST_TTL: Duration = Duration::from_secs(5);
+
+/// The most toasts stacked on screen at once. Any more wait their turn.
+pub const MAX_VISIBLE_TOASTS: usize = 3;
+
+/// A short note about something that just happened, shown in a corner for a
+/// few seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Toast {
+    pub severity: Severity,
+    pub text: String,
+    /// When the toast appeared on screen, or `None` while it's waiting
+    /// behind others.
+    shown_at: Option<Instant>,
+}
+
+/// The toasts on screen and waiting to be shown, oldest first.
+///
+/// Toasts are shown in the order they arrive. A toast's time on screen only
+/// starts once it's shown, so one that had to wait isn't gone before anyone
+/// could read it.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+}
+
+impl ToastQueue {
+    /// Adds a toast behind any that are already queued. A toast that says
+    /// the same as one that's already queued restarts that one instead, so
+    /// repeating an action doesn't fill the screen with copies.
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>, now: Instant) {
+        let text = text.into();
+        if let Some(idx) = self
+            .toasts
+            .iter()
+            .position(|toast| toast.severity == severity && toast.text == text)
+        {
+            self.toasts.remove(idx);
+        }
+        self.toasts.push_back(Toast {
+            severity,
+            text,
+            shown_at: None,
+        });
+        self.show_waiting(now);
+    }
+
+    /// Returns the toasts on screen, oldest first.
+    pub fn visible(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter().take(MAX_VISIBLE_TOASTS)
+    }
+
+    /// Removes the oldest toast on screen, returning whether there was one.
+    pub fn dismiss(&mut self, now: Instant) -> bool {
+        let dismissed = self.toasts.pop_front().is_some();
+        self.show_waiting(now);
+        dismissed
+    }
+
+    /// Removes the toasts that have been on screen for their whole time,
+    /// returning whether any were.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.toasts.len();
+        self.toasts.retain(|toast| {
+            toast
+                .shown_at
+                .is_none_or(|shown_at| now.duration_since(shown_at) < TOAST_TTL)
+        });
+        let expired = self.toasts.len() != before;
+        self.show_waiting(now);
+        expired
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    /// Starts the clock for the toasts that now have room on screen.
+    fn show_waiting(&mut self, now: Instant) {
+        for toast in self.toasts.iter_mut().take(MAX_VISIBLE_TOASTS) {
+            toast.shown_at.get_or_insert(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(queue: &ToastQueue) -> Vec<&str> {
+        queue.visible().map(|toast| toast.text.as_str()).collect()
+    }
+
+    #[test]
+    fn toasts_expire_after_their_time_on_screen() {
+        let start = Instant::now();
+        let mut queue = ToastQueue::default();
+        queue.push(Severity::Success, "first", start);
+        queue.push(Severity::Warning, "second", start + TOAST_TTL / 2);
+
+        assert!(!queue.expire(start + TOAST_TTL / 2));
+        assert!(queue.expire(start + TOAST_TTL));
+        assert_eq!(texts(&queue), ["second"]);
+        assert!(!queue.expire(start + TOAST_TTL));
+        assert!(queue.expire(start + TOAST_TTL / 2 + TOAST_TTL));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn toasts_queue_in_order_once_the_screen_is_full() {
+        let start = Instant::now();
+        let mut queue = ToastQueue::default();
+        for text in ["one", "two", "three", "four", "five"] {
+            queue.push(Severity::Success, text, start);
+        }
+        assert_eq!(texts(&queue), ["one", "two", "three"]);
+
+        // A waiting toast's time starts when it's shown.
+        let later = start + TOAST_TTL;
+        assert!(queue.expire(later));
+        assert_eq!(texts(&queue), ["four", "five"]);
+        assert!(!queue.expire(later + TOAST_TTL / 2));
+        assert!(queue.expire(later + TOAST_TTL));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn dismissing_removes_the_oldest_toast() {
+        let start = Instant::now();
+        let mut queue = ToastQueue::default();
+        assert!(!queue.dismiss(start));
+        for text in ["one", "two", "three", "four"] {
+            queue.push(Severity::Error, text, start);
+        }
+        let later = start + TOAST_TTL / 2;
+        assert!(queue.dismiss(later));
+        assert_eq!(texts(&queue), ["two", "three", "four"]);
+        // "four" was shown on dismissal, so it outlasts the rest.
+        assert!(queue.expire(start + TOAST_TTL));
+        assert_eq!(texts(&queue), ["four"]);
+    }
+
+    #[test]
+    fn repeated_toasts_move_to_the_back() {
+        let start = Instant::now();
+        let mut queue = ToastQueue::default();
+        queue.push(Severity::Error, "Read-only mode", start);
+        queue.push(Severity::Success, "other", start);
+        queue.push(Severity::Error, "Read-only mode", start + TOAST_TTL / 2);
+        assert_eq!(texts(&queue), ["other", "Read-only mode"]);
+        assert!(queue.expire(start + TOAST_TTL));
+        assert_eq!(texts(&queue), ["Read-only mode"]);
+    }
+}
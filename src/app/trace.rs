@@ -1,9 +1,22 @@
-use std::path::PathBuf;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    io::{IsTerminal, Read},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Error, anyhow, bail};
 use ratatui::widgets::ListState;
+use tracing::{debug, trace};
 
-use crate::app::{App, Event, NavEvent, Shell};
+use crate::app::{
+    Action, App, Event, NavEvent, Screen, Severity, Shell, prompt::start_prompt_command,
+    var_refs::referenced_vars,
+};
+
+/// How many lines on either side of a call site are searched for the
+/// variables it refers to.
+pub const CALL_SITE_CONTEXT: usize = 2;
 
 /// Represents the call site for a function in the shell's execution trace.
 #[derive(Debug, Clone)]
@@ -12,8 +25,48 @@ pub struct CallCtx {
     pub file: PathBuf,
     /// The line number in the file at which the function was called.
     pub line: usize,
-    /// The function in which the function was called.
+    /// The function in which the function was called, or for a sourced
+    /// frame, the file that was sourced.
     pub function: String,
+    pub kind: FrameKind,
+}
+
+/// What put a frame on the call stack. Shells list the files being sourced
+/// on their stacks alongside the functions being called, but a sourced
+/// profile script isn't a function named `source`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameKind {
+    /// A function was called.
+    #[default]
+    Func,
+    /// A file was sourced with `source` or `.`.
+    Source,
+}
+
+impl FrameKind {
+    /// Parses the kind field of version 3 of the stack trace format.
+    fn parse(field: &str) -> Result<Self, Error> {
+        match field {
+            "func" => Ok(FrameKind::Func),
+            "source" => Ok(FrameKind::Source),
+            other => bail!("unknown frame kind {other:?}"),
+        }
+    }
+}
+
+impl CallCtx {
+    /// Describes what the frame is for: the function's name, or `source`
+    /// and the name of the file that was sourced.
+    pub fn label(&self) -> String {
+        match self.kind {
+            FrameKind::Func => self.function.clone(),
+            FrameKind::Source => {
+                let path = Path::new(&self.function);
+                let name = path.file_name().unwrap_or(path.as_os_str());
+                format!("source {}", name.to_string_lossy())
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -27,10 +80,52 @@ pub struct CallStack {
     pub frames: Vec<CallFrame>,
 }
 
+impl CallFrame {
+    /// Returns where the call site is, as the file's name and the line.
+    pub fn location(&self) -> String {
+        let file = self
+            .ctx
+            .file
+            .file_name()
+            .unwrap_or(self.ctx.file.as_os_str());
+        format!("{}:{}", file.to_string_lossy(), self.ctx.line)
+    }
+
+    /// Returns the call site's line along with up to `context` lines on
+    /// either side of it, or `None` if the source couldn't be read.
+    pub fn call_site_source(&self, context: usize) -> Option<String> {
+        let lines = self.lines.as_ref()?;
+        let idx = self.ctx.line.checked_sub(1)?;
+        let start = idx.saturating_sub(context).min(lines.len());
+        let end = (idx + context + 1).min(lines.len());
+        Some(lines[start..end].join("\n"))
+    }
+}
+
+impl CallStack {
+    /// Returns each file that frames point into but that couldn't be read,
+    /// in the order the frames are in.
+    pub fn unreadable_files(&self) -> Vec<&Path> {
+        let mut files: Vec<&Path> = Vec::new();
+        for frame in self.frames.iter().filter(|frame| frame.lines.is_none()) {
+            if !files.contains(&frame.ctx.file.as_path()) {
+                files.push(&frame.ctx.file);
+            }
+        }
+        files
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct TraceState {
     tracepoint: Option<String>,
+    /// Every tracepoint the debugger could pause at, as given by
+    /// `--tracepoints`.
+    known_tracepoints: Vec<String>,
     call_stack: Option<CallStack>,
+    /// Why the call stack couldn't be shown, if one was given that the
+    /// debugger can't read.
+    call_stack_error: Option<String>,
     list_state: Option<ListState>,
 }
 
@@ -45,22 +140,130 @@ impl TraceState {
         };
         Self {
             tracepoint,
+            known_tracepoints: Vec::new(),
             call_stack,
+            call_stack_error: None,
             list_state,
         }
     }
 
+    /// Sets the names of every tracepoint the debugger could pause at.
+    pub fn with_known_tracepoints(mut self, names: Vec<String>) -> Self {
+        self.known_tracepoints = names;
+        self
+    }
+
+    /// Records why the call stack that was given couldn't be shown.
+    pub fn with_call_stack_error(mut self, error: String) -> Self {
+        self.call_stack_error = Some(error);
+        self
+    }
+
     pub fn tracepoint(&self) -> Option<&String> {
         self.tracepoint.as_ref()
     }
 
+    /// Returns the names of every tracepoint the debugger could pause at,
+    /// which is empty if they weren't provided.
+    pub fn known_tracepoints(&self) -> &[String] {
+        &self.known_tracepoints
+    }
+
+    /// Returns a warning if the current tracepoint isn't one of the known
+    /// tracepoints, which usually means the list is out of date.
+    pub fn unknown_tracepoint_warning(&self) -> Option<String> {
+        let tracepoint = self.tracepoint.as_ref()?;
+        if self.known_tracepoints.is_empty() || self.known_tracepoints.contains(tracepoint) {
+            return None;
+        }
+        Some(format!(
+            "the current tracepoint '{tracepoint}' isn't one of the known tracepoints"
+        ))
+    }
+
     pub fn call_stack(&self) -> Option<&CallStack> {
         self.call_stack.as_ref()
     }
 
+    pub fn call_stack_error(&self) -> Option<&str> {
+        self.call_stack_error.as_deref()
+    }
+
     pub fn list_state(&mut self) -> Option<&mut ListState> {
         self.list_state.as_mut()
     }
+
+    /// Returns the selected frame, if there's a call stack.
+    pub fn selected_frame(&self) -> Option<&CallFrame> {
+        let idx = self.list_state.as_ref()?.selected()?;
+        self.call_stack.as_ref()?.frames.get(idx)
+    }
+
+    /// Feeds what the trace screen shows into `state`, leaving out the call
+    /// stack since nothing changes it once it's loaded.
+    pub fn hash_view(&self, state: &mut impl Hasher) {
+        self.tracepoint.hash(state);
+        self.list_state.hash(state);
+    }
+}
+
+/// Cleans up the tracepoint names passed on the command line, trimming
+/// whitespace and dropping empty and duplicate names while keeping the
+/// order they were given in.
+pub fn normalize_tracepoint_names(names: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for name in names.iter().map(|name| name.trim()) {
+        if !name.is_empty() && !normalized.iter().any(|existing| existing == name) {
+            normalized.push(name.to_string());
+        }
+    }
+    normalized
+}
+
+/// The start of the line that can open a call stack to say which version of
+/// the format it's in, e.g. `#flox-dbg-stack-v2`. A call stack without one is
+/// in version 1.
+pub const STACK_VERSION_HEADER: &str = "#flox-dbg-stack-v";
+
+/// The newest call stack format this debugger reads, and the one the hooks
+/// from `print-hook` write.
+pub const CALL_STACK_VERSION: u32 = 3;
+
+/// A call stack in a version of the format that this debugger doesn't read,
+/// most likely written by the hook of a newer debugger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedStackVersion {
+    /// The version named in the header, as it was written.
+    pub version: String,
+}
+
+impl fmt::Display for UnsupportedStackVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the call stack is in format v{}, but this debugger only reads v1 to v{CALL_STACK_VERSION}",
+            self.version
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedStackVersion {}
+
+/// Splits the version header off of `input`, returning the version and the
+/// rest of the call stack.
+fn split_version_header(input: &str) -> Result<(u32, &str), UnsupportedStackVersion> {
+    let trimmed = input.trim_start();
+    let Some(header) = trimmed.strip_prefix(STACK_VERSION_HEADER) else {
+        return Ok((1, input));
+    };
+    let (version, rest) = header.split_once('\n').unwrap_or((header, ""));
+    let version = version.trim();
+    match version.parse::<u32>() {
+        Ok(parsed @ 1..=CALL_STACK_VERSION) => Ok((parsed, rest)),
+        _ => Err(UnsupportedStackVersion {
+            version: version.to_string(),
+        }),
+    }
 }
 
 /// Parses our custom stack trace format for Bash and Zsh.
@@ -93,18 +296,109 @@ fn parse_bash_or_zsh_stack_trace(input: &str) -> Result<Vec<CallCtx>, Error> {
                 .parse::<usize>()
                 .context("failed to parse line number as usize")?,
             function: parts[2].to_string(),
+            kind: FrameKind::Func,
         };
         frames.push(cf);
     }
     Ok(frames)
 }
 
+/// Parses versions 2 and 3 of the stack trace format for Bash, Zsh and sh.
+/// Version 2 is version 1 with fields separated by tabs rather than colons
+/// so that paths can contain colons:
+/// <file abs path>\t<line number of call site>\t<function called>
+///
+/// Version 3, read `with_kind`, adds a last field with the [`FrameKind`],
+/// `func` or `source`, in which case the function is the file sourced:
+/// <file abs path>\t<line number of call site>\t<function called>\t<kind>
+fn parse_v2_stack_trace(input: &str, with_kind: bool) -> Result<Vec<CallCtx>, Error> {
+    let mut frames = Vec::new();
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        // Split from the end, since only the path could contain a tab.
+        let (line, kind) = if with_kind {
+            let (rest, kind) = line
+                .rsplit_once('\t')
+                .context("failed to parse stack trace")?;
+            (rest, FrameKind::parse(kind.trim())?)
+        } else {
+            (line, FrameKind::Func)
+        };
+        let mut parts = line.rsplitn(3, '\t');
+        let (Some(function), Some(line_number), Some(file)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            bail!("failed to parse stack trace");
+        };
+        frames.push(CallCtx {
+            file: PathBuf::from(file),
+            line: line_number
+                .trim()
+                .parse::<usize>()
+                .context("failed to parse line number as usize")?,
+            function: function.trim().to_string(),
+            kind,
+        });
+    }
+    Ok(frames)
+}
+
+/// Parses versions 2 and 3 of the stack trace format for Fish, which are
+/// Fish's own stack trace as is, one line per line, so that paths can contain
+/// `;`. Fish lists a sourced file as `from sourcing file <path>` rather than
+/// as a function, so those are the [`FrameKind::Source`] frames.
+fn parse_v2_fish_stack_trace(input: &str) -> Result<Vec<CallCtx>, Error> {
+    let lines = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let mut frames = Vec::new();
+    for chunk in lines.chunks(2) {
+        let [func_line, callsite_line] = chunk else {
+            bail!("uneven number of lines in fish stack trace")
+        };
+        // The path is everything after the line number, spaces and all.
+        let call_site = callsite_line
+            .strip_prefix("called on line ")
+            .and_then(|rest| rest.split_once(" of file "));
+        let (function, kind) = match func_line.strip_prefix("from sourcing file ") {
+            Some(sourced) => {
+                // Files fish sources as it starts up weren't sourced from a
+                // line of any file, so there's no call site to show.
+                if call_site.is_none() {
+                    continue;
+                }
+                let sourced =
+                    std::path::absolute(sourced).context("failed to get absolute path of file")?;
+                (sourced.to_string_lossy().into_owned(), FrameKind::Source)
+            }
+            None => {
+                let function = func_line
+                    .split('\'')
+                    .nth(1)
+                    .ok_or(anyhow!("failed to extract function name"))?;
+                (function.to_string(), FrameKind::Func)
+            }
+        };
+        let (line, file) = call_site.ok_or(anyhow!("failed to extract call site"))?;
+        frames.push(CallCtx {
+            file: std::path::absolute(file).context("failed to get absolute path of file")?,
+            line: line
+                .parse::<usize>()
+                .context("failed to parse line number")?,
+            function,
+            kind,
+        });
+    }
+    Ok(frames)
+}
+
 /// Parses our custom Fish stack trace format, which is just the Fish
 /// built-in stack trace format with newlines swapped for ';' characters
 /// to avoid needing to deal with joining the list with newlines in Fish.
 ///
 /// This is an example of the format:
-/// ```
+/// ```text
 /// in function 'otherfunc';        called on line 8 of file ./run.fish;in function 'myfunction';        called on line 19 of file ./run.fish
 /// ```
 fn parse_fish_stack_trace(input: &str) -> Result<Vec<CallCtx>, Error> {
@@ -152,19 +446,131 @@ fn parse_fish_stack_trace(input: &str) -> Result<Vec<CallCtx>, Error> {
             file,
             line,
             function: function.to_string(),
+            kind: FrameKind::Func,
         };
         frames.push(cf);
     }
     Ok(frames)
 }
 
-/// Loads a call stack from a stack trace and a shell type.
-pub fn load_call_stack(input: &str, shell: Shell) -> Result<CallStack, Error> {
-    let callsites = match shell {
-        Shell::Bash => parse_bash_or_zsh_stack_trace(input),
-        Shell::Zsh => parse_bash_or_zsh_stack_trace(input),
-        Shell::Fish => parse_fish_stack_trace(input),
+/// What a call stack is loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackInput<'a> {
+    /// A stack trace in one of the formats the hooks write, as given by
+    /// `--call-stack`.
+    Formatted(&'a str),
+    /// Bash's own `BASH_SOURCE`, `FUNCNAME` and `BASH_LINENO` arrays as they
+    /// were where the shell paused, each joined with NULs or newlines.
+    BashArrays {
+        source: &'a str,
+        funcname: &'a str,
+        lineno: &'a str,
+    },
+}
+
+impl<'a, S: AsRef<str> + ?Sized> From<&'a S> for StackInput<'a> {
+    fn from(input: &'a S) -> Self {
+        StackInput::Formatted(input.as_ref())
+    }
+}
+
+/// Splits one of bash's arrays joined with NULs, or with newlines if there
+/// aren't any NULs.
+fn split_bash_array(joined: &str) -> Vec<&str> {
+    let sep = if joined.contains('\0') { '\0' } else { '\n' };
+    let joined = joined.strip_suffix(sep).unwrap_or(joined);
+    if joined.is_empty() {
+        Vec::new()
+    } else {
+        joined.split(sep).collect()
+    }
+}
+
+/// Builds the call stack from bash's call stack arrays.
+///
+/// `FUNCNAME[i]` was called from line `BASH_LINENO[i]` of `BASH_SOURCE[i + 1]`,
+/// inside `FUNCNAME[i + 1]`, so each frame pairs a line number with the file
+/// and function one further up the arrays. The last entry is the bottom of the
+/// stack, which wasn't called from anywhere.
+///
+/// A file being sourced is on the stack as a call of `source`, so a line
+/// inside `source` is a line of the sourced file.
+fn parse_bash_arrays(source: &str, funcname: &str, lineno: &str) -> Result<Vec<CallCtx>, Error> {
+    let source = split_bash_array(source);
+    let funcname = split_bash_array(funcname);
+    let lineno = split_bash_array(lineno);
+    if source.len() != funcname.len() || funcname.len() != lineno.len() {
+        bail!(
+            "BASH_SOURCE, FUNCNAME and BASH_LINENO have different lengths ({}, {} and {})",
+            source.len(),
+            funcname.len(),
+            lineno.len()
+        );
+    }
+    let mut frames = Vec::new();
+    for i in 0..lineno.len().saturating_sub(1) {
+        // Code run with `bash -c` isn't in a file, and its source is empty.
+        let file = match source[i + 1] {
+            "" => PathBuf::new(),
+            source => std::path::absolute(source).context("failed to get absolute path of file")?,
+        };
+        let (function, kind) = match funcname[i + 1] {
+            "main" => ("<script>".to_string(), FrameKind::Func),
+            "source" => (file.to_string_lossy().into_owned(), FrameKind::Source),
+            function => (function.to_string(), FrameKind::Func),
+        };
+        frames.push(CallCtx {
+            file,
+            line: lineno[i]
+                .parse::<usize>()
+                .with_context(|| format!("failed to parse line number {:?}", lineno[i]))?,
+            function,
+            kind,
+        });
+    }
+    Ok(frames)
+}
+
+/// Parses a stack trace in any version of the format for `shell`.
+fn parse_stack_trace(input: &str, shell: Shell) -> Result<Vec<CallCtx>, Error> {
+    let (version, input) = split_version_header(input)?;
+    let callsites = match (version, shell) {
+        (1, Shell::Bash | Shell::Zsh) => parse_bash_or_zsh_stack_trace(input),
+        // sh has no arrays to build a stack from, so its hook writes the same
+        // format as bash and zsh with a single frame.
+        (1, Shell::Posix) => parse_bash_or_zsh_stack_trace(input),
+        (1, Shell::Fish) => parse_fish_stack_trace(input),
+        (2, Shell::Bash | Shell::Zsh | Shell::Posix) => parse_v2_stack_trace(input, false),
+        (_, Shell::Bash | Shell::Zsh | Shell::Posix) => parse_v2_stack_trace(input, true),
+        (_, Shell::Fish) => parse_v2_fish_stack_trace(input),
     }?;
+    debug!(version, "read call stack format");
+    Ok(callsites)
+}
+
+/// Loads a call stack for a shell type, either from a stack trace in the
+/// version of the format its header names or from bash's own arrays.
+///
+/// Fails with [`UnsupportedStackVersion`] if the header names a version this
+/// debugger doesn't read.
+pub fn load_call_stack<'a>(
+    input: impl Into<StackInput<'a>>,
+    shell: Shell,
+) -> Result<CallStack, Error> {
+    let callsites = match input.into() {
+        StackInput::Formatted(input) => parse_stack_trace(input, shell)?,
+        StackInput::BashArrays {
+            source,
+            funcname,
+            lineno,
+        } => {
+            if !matches!(shell, Shell::Bash) {
+                bail!("bash's call stack arrays can't be used with {shell}");
+            }
+            parse_bash_arrays(source, funcname, lineno)?
+        }
+    };
+    debug!(frames = callsites.len(), "parsed call stack");
     let mut frames = Vec::new();
     for call in callsites {
         let lines = std::fs::read_to_string(&call.file)
@@ -174,41 +580,151 @@ pub fn load_call_stack(input: &str, shell: Shell) -> Result<CallStack, Error> {
                     .collect::<Vec<_>>()
             })
             .ok();
+        if lines.is_none() {
+            trace!(file = %call.file.display(), "couldn't read source for frame");
+        }
         let frame = CallFrame { ctx: call, lines };
         frames.push(frame);
     }
     Ok(CallStack { frames })
 }
 
-/// Handles navigation and interaction in the Vars screen.
-pub fn handle_trace_event(app: &mut App, event: &Event) {
+/// Reads a call stack from a file, or from stdin if `path` is `-`. Returns
+/// `None` if there's nothing but whitespace to read.
+///
+/// Stdin is only read when it isn't a terminal since the debugger reads its
+/// own input from the terminal.
+pub fn read_call_stack_file(path: &Path) -> Result<Option<String>, Error> {
+    let contents = if path == Path::new("-") {
+        let mut stdin = std::io::stdin();
+        if stdin.is_terminal() {
+            bail!("can't read the call stack from stdin because it's a terminal");
+        }
+        let mut contents = String::new();
+        stdin
+            .read_to_string(&mut contents)
+            .context("failed to read call stack from stdin")?;
+        contents
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read call stack file {}", path.display()))?
+    };
+    if contents.trim().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(contents))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// Choose the tracepoint to pause at next from the prompt.
+    SetBreakpoint,
+    /// Show the variables the selected frame's call site refers to on the
+    /// Vars screen.
+    ShowVars,
+    /// Show the selected frame's file in the user's pager, at the call site.
+    OpenInPager,
+}
+
+/// Returns the selected frame, or says that there isn't one or that its
+/// source couldn't be read.
+fn readable_frame(app: &mut App) -> Option<CallFrame> {
+    let Some(frame) = app.trace().selected_frame() else {
+        app.notify(Severity::Warning, "No stack frame is selected");
+        return None;
+    };
+    if frame.lines.is_none() {
+        let message = format!(
+            "The source of {} couldn't be read",
+            frame.ctx.file.display()
+        );
+        app.notify(Severity::Warning, message);
+        return None;
+    }
+    Some(frame.clone())
+}
+
+/// Switches to the Vars screen filtered to the variables used around the
+/// selected frame's call site, or says why it can't.
+fn show_frame_vars(app: &mut App) {
+    let Some(frame) = readable_frame(app) else {
+        return;
+    };
+    let location = frame.location();
+    let source = frame
+        .call_site_source(CALL_SITE_CONTEXT)
+        .expect("the frame's source was read");
+    let names = referenced_vars(&source);
+    if names.is_empty() {
+        app.notify(
+            Severity::Warning,
+            format!("No variables are used around {location}"),
+        );
+        return;
+    }
+    if !app.env_mut().set_frame_filter(location.clone(), &names) {
+        app.notify(
+            Severity::Warning,
+            format!("None of the variables used around {location} are set"),
+        );
+        return;
+    }
+    let auto_split = app.auto_split();
+    app.env_mut().selection_changed(auto_split);
+    app.set_screen(Screen::Vars);
+}
+
+/// Handles navigation and interaction in the Trace screen.
+pub fn handle_trace_event(app: &mut App, event: &Event) -> Action {
+    if let Event::Trace(TraceEvent::SetBreakpoint) = event {
+        start_prompt_command(app, "break ");
+        return Action::Continue;
+    }
+    if let Event::Trace(TraceEvent::ShowVars) = event {
+        show_frame_vars(app);
+        return Action::Continue;
+    }
+    if let Event::Trace(TraceEvent::OpenInPager) = event {
+        return match readable_frame(app) {
+            Some(frame) => app.page_file(frame.ctx.file, frame.ctx.line.max(1)),
+            None => Action::Continue,
+        };
+    }
     let trace = app.trace_mut();
     let len = trace.call_stack().map(|cs| cs.frames.len());
     if let Some(list_state) = trace.list_state() {
-        let len = len.unwrap();
+        // A stack with no frames has nothing to move between.
+        let Some(last) = len.unwrap().checked_sub(1) else {
+            return Action::Continue;
+        };
         match event {
             Event::Nav(NavEvent::Up) => {
-                if let Some(idx) = list_state.selected() {
-                    if idx > 0 {
-                        list_state.select_previous();
-                    }
+                if let Some(idx) = list_state.selected()
+                    && idx > 0
+                {
+                    list_state.select_previous();
                 }
             }
             Event::Nav(NavEvent::Down) => {
-                if let Some(idx) = list_state.selected() {
-                    if idx < len - 1 {
-                        list_state.select_next();
-                    }
+                if let Some(idx) = list_state.selected()
+                    && idx < last
+                {
+                    list_state.select_next();
                 }
             }
+            Event::Nav(NavEvent::Top) => list_state.select_first(),
+            Event::Nav(NavEvent::Bottom) => list_state.select(Some(last)),
             _ => {}
         }
     }
+    Action::Continue
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{app::commands::tests::run_in, test_dir::TestDir};
 
     #[test]
     fn parses_bash_stack_trace() {
@@ -222,6 +738,54 @@ mod tests {
         assert_eq!(frames[1].function, "func2".to_string());
     }
 
+    #[test]
+    fn reads_call_stack_files() {
+        let dir = TestDir::new("stack");
+        let path = dir.join("trace.txt");
+        std::fs::write(&path, "foo:1:func1\nbar:2:func2\n").unwrap();
+        let contents = read_call_stack_file(&path).unwrap().unwrap();
+        let stack = load_call_stack(&contents, Shell::Bash).unwrap();
+        assert_eq!(stack.frames.len(), 2);
+
+        std::fs::write(&path, "\n  \n").unwrap();
+        assert!(read_call_stack_file(&path).unwrap().is_none());
+
+        assert!(read_call_stack_file(&dir.join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn normalizes_tracepoint_names() {
+        let names = [" setup", "teardown", "", "setup", "  ", "activate "]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            normalize_tracepoint_names(&names),
+            ["setup", "teardown", "activate"]
+        );
+        assert!(normalize_tracepoint_names(&[]).is_empty());
+    }
+
+    #[test]
+    fn warns_about_unknown_current_tracepoint() {
+        let known = vec!["setup".to_string(), "teardown".to_string()];
+        let trace =
+            TraceState::new(Some("setup".to_string()), None).with_known_tracepoints(known.clone());
+        assert_eq!(trace.unknown_tracepoint_warning(), None);
+
+        let trace =
+            TraceState::new(Some("activate".to_string()), None).with_known_tracepoints(known);
+        assert!(
+            trace
+                .unknown_tracepoint_warning()
+                .unwrap()
+                .contains("activate")
+        );
+
+        // There's nothing to check against without a list.
+        let trace = TraceState::new(Some("activate".to_string()), None);
+        assert_eq!(trace.unknown_tracepoint_warning(), None);
+    }
+
     #[test]
     fn parses_fish_stack_trace() {
         let st = "in function 'otherfunc';        called on line 8 of file ./run.fish;in function 'myfunction';        called on line 19 of file ./run.fish";
@@ -232,4 +796,192 @@ mod tests {
         assert_eq!(frames[1].function, "myfunction".to_string());
         assert_eq!(frames[1].line, 19);
     }
+
+    /// A call stack with two frames in each version of the format that
+    /// `shell`'s hook could write, without a header for version 1.
+    fn stacks(shell: Shell) -> [String; 4] {
+        let (v1, v2, v3) = match shell {
+            Shell::Bash | Shell::Zsh | Shell::Posix => (
+                "/nonexistent/run.sh:3:setup\n/nonexistent/run.sh:9:main\n",
+                "/nonexistent/a:b/run.sh\t3\tsetup\n/nonexistent/a:b/run.sh\t9\tmain\n",
+                "/nonexistent/a:b/run.sh\t3\tsetup\tfunc\n/nonexistent/a:b/run.sh\t9\tmain\tfunc\n",
+            ),
+            Shell::Fish => {
+                let v2 = "in function 'setup'\n  called on line 3 of file /nonexistent/a;b/run.fish\nin function 'main'\n  called on line 9 of file /nonexistent/a;b/run.fish\n";
+                (
+                    "in function 'setup';  called on line 3 of file /nonexistent/run.fish;in function 'main';  called on line 9 of file /nonexistent/run.fish",
+                    v2,
+                    v2,
+                )
+            }
+        };
+        [
+            v1.to_string(),
+            format!("{STACK_VERSION_HEADER}1\n{v1}"),
+            format!("{STACK_VERSION_HEADER}2\n{v2}"),
+            format!("{STACK_VERSION_HEADER}3\n{v3}"),
+        ]
+    }
+
+    #[test]
+    fn reads_every_call_stack_version_for_each_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            for input in stacks(shell) {
+                let stack = load_call_stack(&input, shell).unwrap();
+                let frames = stack
+                    .frames
+                    .iter()
+                    .map(|frame| (frame.ctx.function.as_str(), frame.ctx.line))
+                    .collect::<Vec<_>>();
+                assert_eq!(frames, [("setup", 3), ("main", 9)], "{shell}: {input:?}");
+            }
+            // The v2 formats leave room for characters that v1 used to
+            // separate fields.
+            let v2 = load_call_stack(&stacks(shell)[2], shell).unwrap();
+            assert!(
+                v2.frames[0].ctx.file.to_string_lossy().contains("/a"),
+                "{shell}"
+            );
+        }
+    }
+
+    #[test]
+    fn tells_sourced_files_from_function_calls() {
+        let v3 = format!(
+            "{STACK_VERSION_HEADER}3\n/nonexistent/lib.sh\t2\tsetup\tfunc\n/nonexistent/run.sh\t5\t/nonexistent/lib.sh\tsource\n/nonexistent/run.sh\t9\tmain\tfunc\n"
+        );
+        let fish = format!(
+            "{STACK_VERSION_HEADER}3\nin function 'setup'\n  called on line 2 of file /nonexistent/lib.fish\nfrom sourcing file /nonexistent/lib.fish\n  called on line 5 of file /nonexistent/run.fish\nfrom sourcing file /nonexistent/config.fish\n  (called during startup)\n"
+        );
+        let cases = [
+            (Shell::Bash, v3.as_str(), "/nonexistent/lib.sh"),
+            (Shell::Zsh, v3.as_str(), "/nonexistent/lib.sh"),
+            (Shell::Posix, v3.as_str(), "/nonexistent/lib.sh"),
+            (Shell::Fish, fish.as_str(), "/nonexistent/lib.fish"),
+        ];
+        for (shell, input, sourced) in cases {
+            let stack = load_call_stack(input, shell).unwrap();
+            let frames = stack
+                .frames
+                .iter()
+                .map(|frame| (frame.ctx.kind, frame.ctx.function.as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(frames[0], (FrameKind::Func, "setup"), "{shell}");
+            assert_eq!(frames[1], (FrameKind::Source, sourced), "{shell}");
+            let name = Path::new(sourced).file_name().unwrap().to_string_lossy();
+            assert_eq!(stack.frames[1].ctx.label(), format!("source {name}"));
+        }
+
+        // Older formats only had function calls.
+        for input in &stacks(Shell::Bash)[..3] {
+            let stack = load_call_stack(input, Shell::Bash).unwrap();
+            assert!(
+                stack
+                    .frames
+                    .iter()
+                    .all(|frame| frame.ctx.kind == FrameKind::Func)
+            );
+        }
+        let unknown = format!("{STACK_VERSION_HEADER}3\n/nonexistent/run.sh\t3\tsetup\teval\n");
+        let err = load_call_stack(&unknown, Shell::Bash).unwrap_err();
+        assert!(format!("{err:#}").contains("unknown frame kind"));
+    }
+
+    fn bash_arrays(source: &str, funcname: &str, lineno: &str) -> Vec<(PathBuf, usize, String)> {
+        let input = StackInput::BashArrays {
+            source,
+            funcname,
+            lineno,
+        };
+        load_call_stack(input, Shell::Bash)
+            .unwrap()
+            .frames
+            .into_iter()
+            .map(|frame| (frame.ctx.file, frame.ctx.line, frame.ctx.function))
+            .collect()
+    }
+
+    #[test]
+    fn pairs_bash_line_numbers_with_the_caller() {
+        // `run.sh` calls `setup` on line 20, which calls `fdb_tracepoint` on
+        // line 7 of `lib.sh`.
+        let expected = [
+            (PathBuf::from("/nonexistent/lib.sh"), 7, "setup".to_string()),
+            (
+                PathBuf::from("/nonexistent/run.sh"),
+                20,
+                "<script>".to_string(),
+            ),
+        ];
+        let frames = bash_arrays(
+            "/nonexistent/hooks.sh\n/nonexistent/lib.sh\n/nonexistent/run.sh\n",
+            "fdb_tracepoint\nsetup\nmain\n",
+            "7\n20\n0\n",
+        );
+        assert_eq!(frames, expected);
+        let frames = bash_arrays(
+            "/nonexistent/hooks.sh\0/nonexistent/lib.sh\0/nonexistent/run.sh",
+            "fdb_tracepoint\0setup\0main",
+            "7\x0020\x000",
+        );
+        assert_eq!(frames, expected);
+        assert!(bash_arrays("", "", "").is_empty());
+        // Functions defined with `bash -c` have no file.
+        assert_eq!(
+            bash_arrays("\n\n", "fdb_tracepoint\nsetup\n", "3\n5\n"),
+            [(PathBuf::new(), 3, "setup".to_string())]
+        );
+
+        let err = |source, funcname, lineno, shell| {
+            let input = StackInput::BashArrays {
+                source,
+                funcname,
+                lineno,
+            };
+            load_call_stack(input, shell).unwrap_err().to_string()
+        };
+        assert!(err("a\nb", "f\nmain", "1", Shell::Bash).contains("different lengths"));
+        assert!(err("a\nb", "f\nmain", "x\n0", Shell::Bash).contains("line number"));
+        assert!(err("a\nb", "f\nmain", "1\n0", Shell::Zsh).contains("zsh"));
+    }
+
+    #[test]
+    fn reads_the_arrays_bash_sets() {
+        let dir = TestDir::new("arrays");
+        let lib = dir.join("lib.sh");
+        let run = dir.join("run.sh");
+        std::fs::write(&lib, "setup() {\n\tfdb_tracepoint\n}\n").unwrap();
+        std::fs::write(
+            &run,
+            format!(
+                "source {}\nfdb_tracepoint() {{\n\tprintf '%s\\n' \"${{BASH_SOURCE[@]}}\" -- \"${{FUNCNAME[@]}}\" -- \"${{BASH_LINENO[@]}}\"\n}}\nsetup\n",
+                lib.display()
+            ),
+        )
+        .unwrap();
+        if let Some(printed) = run_in(Shell::Bash, &format!("bash {}", run.display())) {
+            let arrays = printed.split("--\n").collect::<Vec<_>>();
+            let frames = bash_arrays(arrays[0], arrays[1], arrays[2]);
+            assert_eq!(
+                frames,
+                [
+                    (lib.clone(), 2, "setup".to_string()),
+                    (run.clone(), 5, "<script>".to_string()),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_call_stack_versions() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            for (header, version) in [("4", "4"), ("0", "0"), ("two", "two")] {
+                let input = format!("{STACK_VERSION_HEADER}{header}\n/nonexistent/run.sh:3:main\n");
+                let err = load_call_stack(&input, shell).unwrap_err();
+                let err = err.downcast_ref::<UnsupportedStackVersion>().unwrap();
+                assert_eq!(err.version, version, "{shell}");
+                assert!(err.to_string().contains(&format!("v{version}")));
+            }
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::{Context, Error};
+
+use crate::app::{App, changes::VarChange, secrets::looks_secret};
+
+/// Writes the Markdown transcript of `app`'s session to `path`.
+pub fn write_transcript(app: &App, path: &Path) -> Result<(), Error> {
+    std::fs::write(path, render_transcript(app))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Returns a Markdown summary of the session so far: where the shell paused,
+/// the call stack, the variables looked at and changed, and the commands
+/// that will be printed on exit.
+pub fn render_transcript(app: &App) -> String {
+    let mut doc = String::from("# flox-debugger session\n\n");
+    let tracepoint = app
+        .trace()
+        .tracepoint()
+        .map_or_else(|| "none".to_string(), |name| code(name));
+    let _ = writeln!(doc, "- Shell: {}", app.shell());
+    let _ = writeln!(doc, "- Tracepoint: {tracepoint}");
+
+    doc.push_str("\n## Call stack\n\n");
+    match app.trace().call_stack() {
+        Some(stack) if !stack.frames.is_empty() => {
+            doc.push_str("| # | Function | File | Line |\n");
+            doc.push_str("|---|----------|------|------|\n");
+            for (idx, frame) in stack.frames.iter().enumerate() {
+                let _ = writeln!(
+                    doc,
+                    "| {idx} | {} | {} | {} |",
+                    cell(&code(&frame.ctx.label())),
+                    cell(&code(&frame.ctx.file.display().to_string())),
+                    frame.ctx.line,
+                );
+            }
+        }
+        _ => doc.push_str("No call stack was provided.\n"),
+    }
+
+    doc.push_str("\n## Variables inspected\n\n");
+    if app.inspected_vars().is_empty() {
+        doc.push_str("None.\n");
+    }
+    for name in app.inspected_vars() {
+        let _ = writeln!(doc, "- {}", code(name));
+    }
+
+    doc.push_str("\n## Variables changed\n\n");
+    let changes = app.var_changes();
+    if changes.is_empty() {
+        doc.push_str("None.\n");
+    }
+    for change in &changes {
+        let _ = writeln!(doc, "- {}", describe_change(change));
+    }
+
+    doc.push_str("\n## Output\n\n");
+    let output = hide_secrets(&app.final_output(), &changes);
+    let fence = "`".repeat(longest_backtick_run(&output).max(2) + 1);
+    let _ = writeln!(doc, "{fence}{}", app.shell());
+    doc.push_str(&output);
+    if !output.is_empty() && !output.ends_with('\n') {
+        doc.push('\n');
+    }
+    let _ = writeln!(doc, "{fence}");
+    doc
+}
+
+/// Replaces the new values of secrets in `output`, wherever the commands
+/// setting them spell them out as is.
+fn hide_secrets(output: &str, changes: &[VarChange]) -> String {
+    changes
+        .iter()
+        .filter(|change| looks_secret(&change.name))
+        .filter_map(|change| change.new.as_deref())
+        .filter(|value| !value.is_empty())
+        .fold(output.to_string(), |output, value| {
+            output.replace(value, "<hidden>")
+        })
+}
+
+/// Describes a change as `NAME: old → new`, hiding the values of secrets.
+fn describe_change(change: &VarChange) -> String {
+    let secret = looks_secret(&change.name);
+    let value = |value: &Option<String>| match value {
+        None => "*unset*".to_string(),
+        Some(_) if secret => "*hidden*".to_string(),
+        Some(value) => code(value),
+    };
+    format!(
+        "{}: {} → {}",
+        code(&change.name),
+        value(&change.old),
+        value(&change.new)
+    )
+}
+
+/// Formats `text` as inline code on a single line.
+fn code(text: &str) -> String {
+    let text = text.replace('\n', "\\n");
+    let fence = "`".repeat(longest_backtick_run(&text) + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{pad}{text}{pad}{fence}")
+}
+
+/// Escapes what would end a table cell early.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        app::{Event, NavEvent, Screen, handle_event},
+        cli::Cli,
+        terminal::TerminalInfo,
+    };
+
+    /// A session paused in a made up hook, with a few variables looked at
+    /// and changed.
+    fn synthetic_session() -> App {
+        let args = Cli::try_parse_from([
+            "flox-debugger",
+            "--shell",
+            "bash",
+            "--config",
+            "/nonexistent/config.toml",
+            "--tracepoint",
+            "setup",
+            "--call-stack",
+            "/nonexistent/hooks.sh:14:fdb_tracepoint\n/nonexistent/profile:3:main",
+        ])
+        .unwrap();
+        let env = HashMap::from([
+            ("EDITOR".to_string(), "vim".to_string()),
+            ("FOO".to_string(), "bar".to_string()),
+            ("GITHUB_TOKEN".to_string(), "ghp_old".to_string()),
+            ("OLD".to_string(), "1".to_string()),
+        ]);
+        let mut app = App::new(&args, &TerminalInfo::default())
+            .unwrap()
+            .with_env(&env)
+            .with_screen(Screen::Vars);
+        handle_event(&mut app, &Event::Nav(NavEvent::Down));
+        app.queue_set_var("FOO", "it's `new`").unwrap();
+        app.queue_set_var("GITHUB_TOKEN", "ghp_new").unwrap();
+        app.queue_unset_var("OLD").unwrap();
+        app.queue_set_var("ADDED", "x").unwrap();
+        app
+    }
+
+    #[test]
+    fn transcript_matches_snapshot() {
+        let transcript = render_transcript(&synthetic_session());
+        assert_eq!(transcript, include_str!("snapshots/transcript_bash.md"));
+        assert!(!transcript.contains("ghp_old"));
+        assert!(!transcript.contains("ghp_new"));
+    }
+
+    #[test]
+    fn inline_code_survives_backticks_and_newlines() {
+        assert_eq!(code("plain"), "`plain`");
+        assert_eq!(code("a `b` c"), "``a `b` c``");
+        assert_eq!(code("`edge"), "`` `edge ``");
+        assert_eq!(code("one\ntwo"), "`one\\ntwo`");
+    }
+}
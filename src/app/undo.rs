@@ -0,0 +1,105 @@
+use crate::app::commands::Cmd;
+
+/// What a command the user queued does, and so what undoing it puts back.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QueuedKind {
+    /// Sets the variable, so undoing it restores the value it had before.
+    Set { name: String },
+    /// Unsets the variable, so undoing it sets it again.
+    Unset { name: String },
+    /// Queued verbatim, so undoing it only removes it.
+    Raw,
+    /// Anything else, such as a function, an alias or a `cd`.
+    Other,
+}
+
+impl QueuedKind {
+    /// Returns the kind of `cmd`.
+    pub fn of(cmd: &Cmd) -> Self {
+        match cmd {
+            Cmd::SetVar { name, .. } => QueuedKind::Set { name: name.clone() },
+            Cmd::UnsetVar { name } => QueuedKind::Unset { name: name.clone() },
+            _ => QueuedKind::Other,
+        }
+    }
+}
+
+/// A command the user queued, as it appears in the output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueuedCommand {
+    pub command: String,
+    pub kind: QueuedKind,
+}
+
+impl QueuedCommand {
+    /// Describes what undoing the command did.
+    pub fn describe_undo(&self) -> String {
+        match &self.kind {
+            QueuedKind::Set { name } => format!("Undid setting {name}"),
+            QueuedKind::Unset { name } => format!("Undid unsetting {name}"),
+            QueuedKind::Raw => format!("Undid raw command: {}", self.command),
+            QueuedKind::Other => format!("Undid {}", self.command),
+        }
+    }
+}
+
+/// The commands the user queued, oldest first.
+///
+/// The commands the debugger queues by itself, such as the one that sets
+/// where the shell pauses next, are never recorded, so they can't be undone.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct UndoStack {
+    queued: Vec<QueuedCommand>,
+}
+
+impl UndoStack {
+    pub fn push(&mut self, command: &str, kind: QueuedKind) {
+        self.queued.push(QueuedCommand {
+            command: command.to_string(),
+            kind,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.queued.clear();
+    }
+
+    /// Returns the kind of the command queued most recently as `command`, or
+    /// `None` if the user didn't queue it.
+    pub fn kind_of(&self, command: &str) -> Option<&QueuedKind> {
+        self.queued
+            .iter()
+            .rev()
+            .find(|queued| queued.command == command)
+            .map(|queued| &queued.kind)
+    }
+
+    /// Takes the most recent command that's still among the output `lines`,
+    /// along with the index of its line. Commands deleted or edited on the
+    /// Output screen since they were queued are skipped.
+    pub fn pop(&mut self, lines: &[&str]) -> Option<(usize, QueuedCommand)> {
+        while let Some(queued) = self.queued.pop() {
+            if let Some(idx) = lines.iter().rposition(|line| *line == queued.command) {
+                return Some((idx, queued));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_no_longer_in_the_output_are_skipped() {
+        let mut stack = UndoStack::default();
+        stack.push("export A=1", QueuedKind::Set { name: "A".into() });
+        stack.push("echo gone", QueuedKind::Raw);
+        let lines = ["unset FLOX_TRACEPOINT", "export A=1"];
+        let (idx, queued) = stack.pop(&lines).unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(queued.describe_undo(), "Undid setting A");
+        assert_eq!(stack.pop(&lines), None);
+    }
+}
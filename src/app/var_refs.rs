@@ -0,0 +1,127 @@
+//! Finding the variables a piece of shell script refers to.
+
+/// Returns the variables `script` expands or assigns, each once, in the order
+/// they first come up: `$NAME`, `${NAME}` along with forms like
+/// `${NAME:-default}` and `${#NAME}`, and `NAME=` or `NAME+=` at the start of
+/// a word, as after `export` or `local`.
+///
+/// Nothing is expanded inside single quotes or comments, so those are
+/// skipped, as are special parameters like `$1` and `$@`. This is only a
+/// scan of the text rather than a parse, which is enough for the line or two
+/// around a call site.
+pub fn referenced_vars(script: &str) -> Vec<String> {
+    let chars = script.chars().collect::<Vec<_>>();
+    let mut names: Vec<String> = Vec::new();
+    let mut push = |name: String| {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    };
+    let mut in_double_quotes = false;
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let word_start = idx == 0 || is_word_break(chars[idx - 1]);
+        match c {
+            '\\' => idx += 1,
+            '"' => in_double_quotes = !in_double_quotes,
+            '\'' if !in_double_quotes => {
+                idx += chars[idx + 1..]
+                    .iter()
+                    .position(|c| *c == '\'')
+                    .map_or(chars.len(), |end| end + 1);
+            }
+            '#' if !in_double_quotes && word_start => {
+                idx += chars[idx..]
+                    .iter()
+                    .position(|c| *c == '\n')
+                    .unwrap_or(chars.len());
+            }
+            '$' => {
+                let mut start = idx + 1;
+                if chars.get(start) == Some(&'{') {
+                    start += 1;
+                    // `${#NAME}` is the length and `${!NAME}` the indirection.
+                    if matches!(chars.get(start), Some('#' | '!')) {
+                        start += 1;
+                    }
+                }
+                let name = name_at(&chars, start);
+                idx = start + name.len().saturating_sub(1);
+                if !name.is_empty() {
+                    push(name);
+                }
+            }
+            c if word_start && !in_double_quotes && is_name_start(c) => {
+                let name = name_at(&chars, idx);
+                let after = idx + name.len();
+                let assigns = match chars.get(after) {
+                    Some('=') => true,
+                    Some('+') => chars.get(after + 1) == Some(&'='),
+                    _ => false,
+                };
+                idx = after - 1;
+                if assigns {
+                    push(name);
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+    names
+}
+
+/// Returns the name starting at `start` of `chars`, which is empty if there
+/// isn't one.
+fn name_at(chars: &[char], start: usize) -> String {
+    if !chars.get(start).copied().is_some_and(is_name_start) {
+        return String::new();
+    }
+    chars[start..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+        .collect()
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+/// Returns whether a word can start after `c`.
+fn is_word_break(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')' | '{' | '}' | '`')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_are_found_in_shell_snippets() {
+        let cases = [
+            ("echo $HOME", vec!["HOME"]),
+            ("cd \"${FLOX_ENV}/bin\"", vec!["FLOX_ENV"]),
+            (
+                "export PATH=\"$FLOX_ENV/bin:$PATH\"",
+                vec!["PATH", "FLOX_ENV"],
+            ),
+            (
+                "local dir=${XDG_DATA_HOME:-$HOME/.local}",
+                vec!["dir", "XDG_DATA_HOME", "HOME"],
+            ),
+            ("[ ${#NAME} -gt 0 ] && FOO+=bar", vec!["NAME", "FOO"]),
+            ("A=1 B=$A; echo $A$B", vec!["A", "B"]),
+            ("set -gx PATH $FLOX_ENV/bin $PATH", vec!["FLOX_ENV", "PATH"]),
+            ("if [ -n \"$1\" ]; then shift; fi; echo \"$@\" $?", vec![]),
+            ("echo '$NOT_EXPANDED' \"it's $HOME\"", vec!["HOME"]),
+            ("echo \\$ESCAPED # and $COMMENTED\nrun $NEXT", vec!["NEXT"]),
+            ("echo --opt=$X path#$Y", vec!["X", "Y"]),
+            ("echo \"FOO=bar\"", vec![]),
+            ("", vec![]),
+        ];
+        for (script, expected) in cases {
+            assert_eq!(referenced_vars(script), expected, "{script}");
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::BTreeSet,
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+use ratatui::widgets::ListState;
+
+/// The fewest variables that share a prefix for them to be grouped.
+pub const MIN_GROUP_SIZE: usize = 3;
+
+/// Variables that share a prefix, such as `LC_`, as a range of the sorted
+/// variable names.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VarGroup {
+    pub prefix: String,
+    pub vars: Range<usize>,
+}
+
+/// One row of the variable list when it's shown as a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VarRow {
+    /// The group at this index of the groups, and whether its variables are
+    /// shown beneath it.
+    Group { group: usize, expanded: bool },
+    /// The variable at this index of the sorted names, and whether it's
+    /// shown inside a group.
+    Var { idx: usize, nested: bool },
+}
+
+/// Returns the prefix `name` is grouped by: everything up to and including
+/// the first `_` after any leading ones, so that `__fish_color_cwd` is
+/// grouped by `__fish_`.
+fn group_prefix(name: &str) -> Option<&str> {
+    let start = name.len() - name.trim_start_matches('_').len();
+    let end = start + name[start..].find('_')?;
+    // A name like `_` or `__` has nothing before its underscore to go by.
+    (end > start).then(|| &name[..=end])
+}
+
+/// Groups the sorted variable `names` by [`group_prefix`], keeping only the
+/// groups with at least `min_size` variables.
+///
+/// Names sharing a prefix are next to each other once sorted, so each group
+/// is a single range of `names`.
+pub fn group_vars(names: &[String], min_size: usize) -> Vec<VarGroup> {
+    let mut groups = Vec::new();
+    let mut idx = 0;
+    while idx < names.len() {
+        let Some(prefix) = group_prefix(&names[idx]) else {
+            idx += 1;
+            continue;
+        };
+        let len = names[idx..]
+            .iter()
+            .take_while(|name| group_prefix(name) == Some(prefix))
+            .count();
+        if len >= min_size.max(1) {
+            groups.push(VarGroup {
+                prefix: prefix.to_string(),
+                vars: idx..idx + len,
+            });
+        }
+        idx += len;
+    }
+    groups
+}
+
+/// Lays out the rows of the tree for `len` sorted variables, with the
+/// variables of the groups whose prefixes are in `expanded` beneath them.
+///
+/// When `shown` is given, only the variables it's true for are laid out,
+/// along with the groups that still have any.
+pub fn tree_rows(
+    len: usize,
+    groups: &[VarGroup],
+    expanded: &BTreeSet<String>,
+    shown: Option<&[bool]>,
+) -> Vec<VarRow> {
+    let is_shown = |idx: usize| shown.is_none_or(|shown| shown[idx]);
+    let mut rows = Vec::new();
+    let mut groups = groups.iter().enumerate().peekable();
+    let mut idx = 0;
+    while idx < len {
+        match groups.next_if(|(_, group)| group.vars.start == idx) {
+            Some((group_idx, group)) => {
+                idx = group.vars.end;
+                if !group.vars.clone().any(is_shown) {
+                    continue;
+                }
+                let is_expanded = expanded.contains(&group.prefix);
+                rows.push(VarRow::Group {
+                    group: group_idx,
+                    expanded: is_expanded,
+                });
+                if is_expanded {
+                    rows.extend(
+                        group
+                            .vars
+                            .clone()
+                            .filter(|idx| is_shown(*idx))
+                            .map(|idx| VarRow::Var { idx, nested: true }),
+                    );
+                }
+            }
+            None => {
+                if is_shown(idx) {
+                    rows.push(VarRow::Var { idx, nested: false });
+                }
+                idx += 1;
+            }
+        }
+    }
+    rows
+}
+
+/// The state of the variable list while it's shown as a tree.
+#[derive(Debug, Clone, Default)]
+pub struct VarTree {
+    groups: Vec<VarGroup>,
+    /// The prefixes of the groups that are expanded.
+    expanded: BTreeSet<String>,
+    /// Which variables are shown, if some are filtered out.
+    shown: Option<Vec<bool>>,
+    row_state: ListState,
+}
+
+impl VarTree {
+    /// Groups the sorted variable `names`, with every group collapsed.
+    pub fn new(names: &[String]) -> Self {
+        Self {
+            groups: group_vars(names, MIN_GROUP_SIZE),
+            expanded: BTreeSet::new(),
+            shown: None,
+            row_state: ListState::default(),
+        }
+    }
+
+    /// Groups the sorted variable `names` again after they've changed,
+    /// keeping the groups that are still there expanded or collapsed. Every
+    /// variable is shown until [`VarTree::set_shown`] says otherwise.
+    pub fn regroup(&mut self, names: &[String]) {
+        self.groups = group_vars(names, MIN_GROUP_SIZE);
+        let groups = &self.groups;
+        self.expanded
+            .retain(|prefix| groups.iter().any(|group| group.prefix == *prefix));
+        self.shown = None;
+    }
+
+    pub fn rows(&self, len: usize) -> Vec<VarRow> {
+        tree_rows(len, &self.groups, &self.expanded, self.shown.as_deref())
+    }
+
+    pub fn group(&self, idx: usize) -> &VarGroup {
+        &self.groups[idx]
+    }
+
+    /// Shows only the variables `shown` is true for, or all of them.
+    pub fn set_shown(&mut self, shown: Option<Vec<bool>>) {
+        self.shown = shown;
+    }
+
+    /// Returns the variables of the group at `idx` that are shown.
+    pub fn shown_vars(&self, idx: usize) -> Vec<usize> {
+        self.groups[idx]
+            .vars
+            .clone()
+            .filter(|idx| self.shown.as_ref().is_none_or(|shown| shown[*idx]))
+            .collect()
+    }
+
+    /// Returns the selected row for `len` variables.
+    pub fn selected_row(&self, len: usize) -> Option<VarRow> {
+        let row = self.row_state.selected()?;
+        self.rows(len).get(row).copied()
+    }
+
+    pub fn row_state(&mut self) -> &mut ListState {
+        &mut self.row_state
+    }
+
+    /// Returns the index of the group the variable at `idx` is in, if any.
+    pub fn group_of(&self, idx: usize) -> Option<usize> {
+        self.groups
+            .iter()
+            .position(|group| group.vars.contains(&idx))
+    }
+
+    /// Expands or collapses the group at `idx`.
+    pub fn set_expanded(&mut self, idx: usize, expanded: bool) {
+        let prefix = &self.groups[idx].prefix;
+        if expanded {
+            self.expanded.insert(prefix.clone());
+        } else {
+            self.expanded.remove(prefix);
+        }
+    }
+
+    pub fn hash_view(&self, state: &mut impl Hasher) {
+        self.expanded.hash(state);
+        self.shown.hash(state);
+        self.row_state.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        let mut names = names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn prefixes(groups: &[VarGroup]) -> Vec<(&str, usize)> {
+        groups
+            .iter()
+            .map(|group| (group.prefix.as_str(), group.vars.len()))
+            .collect()
+    }
+
+    #[test]
+    fn vars_are_grouped_by_their_first_underscore() {
+        let names = names(&[
+            "LC_ALL",
+            "LC_CTYPE",
+            "LC_TIME",
+            "LCX",
+            "HOME",
+            "XDG_DATA_HOME",
+            "XDG_CONFIG_HOME",
+            "__fish_color_cwd",
+            "__fish_color_host",
+            "__fish_initialized",
+            "_",
+            "__",
+            "NIX_PATH",
+            "NIX_PROFILES",
+        ]);
+        let groups = group_vars(&names, 3);
+        assert_eq!(prefixes(&groups), [("LC_", 3), ("__fish_", 3)]);
+        for group in &groups {
+            assert!(
+                names[group.vars.clone()]
+                    .iter()
+                    .all(|name| name.starts_with(&group.prefix))
+            );
+        }
+        assert_eq!(
+            prefixes(&group_vars(&names, 2)),
+            [("LC_", 3), ("NIX_", 2), ("XDG_", 2), ("__fish_", 3)]
+        );
+        assert!(group_vars(&names, 4).is_empty());
+        assert!(group_vars(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn rows_nest_the_expanded_groups() {
+        let names = names(&["A_1", "A_2", "A_3", "B", "C_1", "C_2", "C_3"]);
+        let groups = group_vars(&names, 3);
+        let collapsed = tree_rows(names.len(), &groups, &BTreeSet::new(), None);
+        assert_eq!(
+            collapsed,
+            [
+                VarRow::Group {
+                    group: 0,
+                    expanded: false
+                },
+                VarRow::Var {
+                    idx: 3,
+                    nested: false
+                },
+                VarRow::Group {
+                    group: 1,
+                    expanded: false
+                },
+            ]
+        );
+
+        let expanded = BTreeSet::from(["C_".to_string()]);
+        let rows = tree_rows(names.len(), &groups, &expanded, None);
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows[2..],
+            [
+                VarRow::Group {
+                    group: 1,
+                    expanded: true
+                },
+                VarRow::Var {
+                    idx: 4,
+                    nested: true
+                },
+                VarRow::Var {
+                    idx: 5,
+                    nested: true
+                },
+                VarRow::Var {
+                    idx: 6,
+                    nested: true
+                },
+            ]
+        );
+
+        // Groups with nothing left to show are dropped.
+        let shown = [false, false, false, true, false, true, false];
+        let rows = tree_rows(names.len(), &groups, &expanded, Some(&shown));
+        assert_eq!(
+            rows,
+            [
+                VarRow::Var {
+                    idx: 3,
+                    nested: false
+                },
+                VarRow::Group {
+                    group: 1,
+                    expanded: true
+                },
+                VarRow::Var {
+                    idx: 5,
+                    nested: true
+                },
+            ]
+        );
+    }
+}
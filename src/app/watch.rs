@@ -0,0 +1,144 @@
+//! The variables pinned with the `watch` command, which are shown on every
+//! screen so they can be kept an eye on.
+
+use crate::app::{secrets::looks_secret, vars::Env};
+
+/// A watched variable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Watched {
+    pub name: String,
+    /// The value as of the last refresh, or `None` if it wasn't set.
+    seen: Option<String>,
+    /// Whether the value changed at the last refresh.
+    pub changed: bool,
+}
+
+/// The watched variables, in the order they were watched.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct WatchList {
+    watched: Vec<Watched>,
+}
+
+impl WatchList {
+    /// Watches `name`, whose value is `value` for now, returning false if
+    /// it's already watched.
+    pub fn add(&mut self, name: &str, value: Option<&str>) -> bool {
+        if self.contains(name) {
+            return false;
+        }
+        self.watched.push(Watched {
+            name: name.to_string(),
+            seen: value.map(str::to_string),
+            changed: false,
+        });
+        true
+    }
+
+    /// Stops watching `name`, returning false if it wasn't watched.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.watched.len();
+        self.watched.retain(|watched| watched.name != name);
+        self.watched.len() != before
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.watched.iter().any(|watched| watched.name == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.watched.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watched.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Watched> {
+        self.watched.iter()
+    }
+
+    /// Returns the watched names, in the order they were watched.
+    pub fn names(&self) -> Vec<String> {
+        self.watched
+            .iter()
+            .map(|watched| watched.name.clone())
+            .collect()
+    }
+
+    /// Compares each variable against its value at the last refresh, with
+    /// `value_of` looking up its value now, and marks the ones that changed.
+    /// Returns how many did.
+    pub fn refresh<'a>(&mut self, value_of: impl Fn(&str) -> Option<&'a str>) -> usize {
+        let mut changed = 0;
+        for watched in &mut self.watched {
+            let value = value_of(&watched.name);
+            watched.changed = watched.seen.as_deref() != value;
+            if watched.changed {
+                watched.seen = value.map(str::to_string);
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+/// Returns how a watched variable's value is shown: `<unset>` if it isn't
+/// set, `<hidden>` if it looks like a secret, and otherwise with its newlines
+/// escaped so it fits on one line.
+pub fn shown_value(env: &Env, name: &str) -> String {
+    match env.value_of(name) {
+        None => "<unset>".to_string(),
+        Some(_) if looks_secret(name) => "<hidden>".to_string(),
+        Some(value) => value.replace('\n', "\\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_are_watched_once_in_order() {
+        let mut list = WatchList::default();
+        assert!(list.add("PATH", Some("/bin")));
+        assert!(list.add("EDITOR", None));
+        assert!(!list.add("PATH", Some("/usr/bin")));
+        assert_eq!(list.names(), ["PATH", "EDITOR"]);
+
+        assert!(list.remove("PATH"));
+        assert!(!list.remove("PATH"));
+        assert!(!list.remove("HOME"));
+        assert_eq!(list.names(), ["EDITOR"]);
+        assert!(list.add("PATH", None));
+        assert_eq!(list.names(), ["EDITOR", "PATH"]);
+    }
+
+    #[test]
+    fn refreshes_mark_the_values_that_changed() {
+        let mut list = WatchList::default();
+        list.add("KEPT", Some("1"));
+        list.add("EDITED", Some("vim"));
+        list.add("UNSET", Some("x"));
+        list.add("LATER", None);
+        let changed = |list: &WatchList| {
+            list.iter()
+                .filter(|watched| watched.changed)
+                .map(|watched| watched.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert!(changed(&list).is_empty());
+
+        let reloaded = |name: &str| match name {
+            "KEPT" => Some("1"),
+            "EDITED" => Some("nano"),
+            "LATER" => Some(""),
+            _ => None,
+        };
+        assert_eq!(list.refresh(reloaded), 3);
+        assert_eq!(changed(&list), ["EDITED", "UNSET", "LATER"]);
+
+        // The marks only last until the next refresh.
+        assert_eq!(list.refresh(reloaded), 0);
+        assert!(changed(&list).is_empty());
+    }
+}
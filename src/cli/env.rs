@@ -0,0 +1,166 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{Context, Error, anyhow};
+use clap::ValueEnum;
+
+use crate::{
+    app::{Screen, Shell, TRACEPOINT_VAR_NAME, commands::is_glob},
+    cli::Cli,
+};
+
+pub const SHELL_VAR: &str = "FLOX_DBG_SHELL";
+pub const CALL_STACK_VAR: &str = "FLOX_DBG_CALL_STACK";
+pub const START_SCREEN_VAR: &str = "FLOX_DBG_START_SCREEN";
+pub const LOG_FILE_VAR: &str = "FLOX_DBG_LOG";
+
+/// Returns the process's environment, skipping variables that aren't valid
+/// UTF-8 since none of the debugger's own variables can be.
+pub fn process_env() -> HashMap<String, String> {
+    std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+/// Fills in the options that weren't given on the command line from the
+/// `FLOX_DBG_*` variables in `env`, so that a flag always beats its variable
+/// and the variable beats the default.
+///
+/// The shell is required, so an error is returned if it's missing from both.
+pub fn resolve_env(mut args: Cli, env: &HashMap<String, String>) -> Result<Cli, Error> {
+    let var = |name: &str| env.get(name).filter(|value| !value.is_empty());
+
+    if args.shell.is_none() {
+        let value = var(SHELL_VAR)
+            .ok_or_else(|| anyhow!("the --shell option is required unless {SHELL_VAR} is set"))?;
+        let shell = value
+            .parse::<Shell>()
+            .with_context(|| format!("invalid {SHELL_VAR}"))?;
+        args.shell = Some(shell);
+    }
+    // The variable holds the tracepoints the shell was told to pause at, which
+    // only names the current one if it's a single name rather than a glob.
+    if args.tracepoint.is_none() {
+        args.tracepoint = var(TRACEPOINT_VAR_NAME)
+            .filter(|value| {
+                !matches!(value.as_str(), "all" | "next") && !value.contains(',') && !is_glob(value)
+            })
+            .cloned();
+    }
+    if args.call_stack.is_none() && args.call_stack_file.is_none() {
+        args.call_stack = var(CALL_STACK_VAR).cloned();
+    }
+    if args.start_screen.is_none()
+        && let Some(value) = var(START_SCREEN_VAR)
+    {
+        let screen = <Screen as ValueEnum>::from_str(value, true)
+            .map_err(|_| anyhow!("invalid {START_SCREEN_VAR} '{value}'"))?;
+        args.start_screen = Some(screen);
+    }
+    if args.log_file.is_none() {
+        args.log_file = var(LOG_FILE_VAR).map(PathBuf::from);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("flox-debugger").chain(args.iter().copied())).unwrap()
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn flags_beat_variables() {
+        let env = vars(&[
+            (SHELL_VAR, "fish"),
+            (TRACEPOINT_VAR_NAME, "from-env"),
+            (CALL_STACK_VAR, "env.sh:1:main"),
+            (START_SCREEN_VAR, "vars"),
+            (LOG_FILE_VAR, "/tmp/env.log"),
+        ]);
+        let args = parse(&[
+            "--shell",
+            "zsh",
+            "--tracepoint",
+            "from-flag",
+            "--call-stack-file",
+            "stack.txt",
+            "--start-screen",
+            "trace",
+            "--log-file",
+            "/tmp/flag.log",
+        ]);
+        let args = resolve_env(args, &env).unwrap();
+        assert!(matches!(args.shell, Some(Shell::Zsh)));
+        assert_eq!(args.tracepoint.as_deref(), Some("from-flag"));
+        // A call stack file on the command line rules out the variable.
+        assert_eq!(args.call_stack, None);
+        assert_eq!(args.start_screen, Some(Screen::Trace));
+        assert_eq!(args.log_file, Some(PathBuf::from("/tmp/flag.log")));
+    }
+
+    #[test]
+    fn variables_beat_defaults() {
+        let env = vars(&[
+            (SHELL_VAR, "Fish"),
+            (TRACEPOINT_VAR_NAME, "setup"),
+            (CALL_STACK_VAR, "env.sh:1:main"),
+            (START_SCREEN_VAR, "Vars"),
+            (LOG_FILE_VAR, "/tmp/env.log"),
+        ]);
+        let args = resolve_env(parse(&[]), &env).unwrap();
+        assert!(matches!(args.shell, Some(Shell::Fish)));
+        assert_eq!(args.tracepoint.as_deref(), Some("setup"));
+        assert_eq!(args.call_stack.as_deref(), Some("env.sh:1:main"));
+        assert_eq!(args.start_screen, Some(Screen::Vars));
+        assert_eq!(args.log_file, Some(PathBuf::from("/tmp/env.log")));
+    }
+
+    #[test]
+    fn defaults_apply_without_flags_or_variables() {
+        let args = resolve_env(parse(&["--shell", "bash"]), &HashMap::new()).unwrap();
+        assert_eq!(args.tracepoint, None);
+        assert_eq!(args.call_stack, None);
+        assert_eq!(args.start_screen, None);
+        assert_eq!(args.log_file, None);
+
+        // Wildcards and empty values don't count as being set.
+        let env = vars(&[(TRACEPOINT_VAR_NAME, "next"), (LOG_FILE_VAR, "")]);
+        let args = resolve_env(parse(&["--shell", "bash"]), &env).unwrap();
+        assert_eq!(args.tracepoint, None);
+        assert_eq!(args.log_file, None);
+
+        // Nor does a list or a glob, since they don't say which one was
+        // reached.
+        for value in ["setup,teardown", "py-*"] {
+            let env = vars(&[(TRACEPOINT_VAR_NAME, value)]);
+            let args = resolve_env(parse(&["--shell", "bash"]), &env).unwrap();
+            assert_eq!(args.tracepoint, None, "{value}");
+        }
+    }
+
+    #[test]
+    fn shell_is_required_from_somewhere() {
+        let err = resolve_env(parse(&[]), &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains(SHELL_VAR), "{err}");
+
+        let env = vars(&[(SHELL_VAR, "tcsh")]);
+        let err = resolve_env(parse(&[]), &env).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("unrecognized shell 'tcsh'"),
+            "{err:#}"
+        );
+
+        let env = vars(&[(SHELL_VAR, "bash"), (START_SCREEN_VAR, "debug")]);
+        assert!(resolve_env(parse(&[]), &env).is_err());
+    }
+}
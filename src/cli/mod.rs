@@ -1,16 +1,351 @@
-use clap::Parser;
+pub mod env;
 
-use crate::app::Shell;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::app::{Screen, Shell, theme::ThemeChoice};
 
 #[derive(Debug, Clone, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
-    #[arg(long = "shell", help = "Which shell the debugger was invoked from")]
-    pub shell: Shell,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Always present once `env::resolve_env` has run.
+    #[arg(
+        long = "shell",
+        ignore_case = true,
+        help = "Which shell the debugger was invoked from [env: FLOX_DBG_SHELL]"
+    )]
+    pub shell: Option<Shell>,
     #[arg(
         long = "tracepoint",
-        help = "The name of the tracepoint the debugger paused at"
+        help = "The name of the tracepoint the debugger paused at [env: FLOX_DBG_TRACEPOINT]"
     )]
     pub tracepoint: Option<String>,
-    #[arg(long = "call-stack", help = "A call stack of shell execution")]
+    #[arg(
+        long = "tracepoints",
+        value_delimiter = ',',
+        help = "Comma-separated names of every tracepoint the debugger could pause at"
+    )]
+    pub tracepoints: Vec<String>,
+    #[arg(
+        long = "call-stack",
+        help = "A call stack of shell execution [env: FLOX_DBG_CALL_STACK]"
+    )]
     pub call_stack: Option<String>,
+    #[arg(
+        long = "call-stack-file",
+        conflicts_with = "call_stack",
+        help = "Read the call stack from a file instead, or from stdin if the path is '-'"
+    )]
+    pub call_stack_file: Option<PathBuf>,
+    #[arg(
+        long = "bash-source",
+        value_name = "FILES",
+        requires_all = ["funcname", "bash_lineno"],
+        conflicts_with_all = ["call_stack", "call_stack_file"],
+        help = "Build the call stack from bash's BASH_SOURCE array, joined with newlines or NULs, along with --funcname and --bash-lineno"
+    )]
+    pub bash_source: Option<String>,
+    #[arg(
+        long = "funcname",
+        value_name = "FUNCTIONS",
+        requires_all = ["bash_source", "bash_lineno"],
+        help = "Bash's FUNCNAME array, joined with newlines or NULs"
+    )]
+    pub funcname: Option<String>,
+    #[arg(
+        long = "bash-lineno",
+        value_name = "LINES",
+        requires_all = ["bash_source", "funcname"],
+        help = "Bash's BASH_LINENO array, joined with newlines or NULs"
+    )]
+    pub bash_lineno: Option<String>,
+    #[arg(
+        long = "start-screen",
+        ignore_case = true,
+        help = "The screen to show first [env: FLOX_DBG_START_SCREEN] [default: home]"
+    )]
+    pub start_screen: Option<Screen>,
+    #[arg(
+        long = "config",
+        help = "Path to the config file [default: $XDG_CONFIG_HOME/flox-debugger/config.toml]"
+    )]
+    pub config: Option<PathBuf>,
+    #[arg(
+        long = "env-file",
+        help = "Load the environment from a file of NAME=VALUE lines or `env -0` output instead of the debugger's own environment"
+    )]
+    pub env_file: Option<PathBuf>,
+    #[arg(
+        long = "env-fd",
+        value_name = "FD",
+        conflicts_with = "env_file",
+        help = "Read the environment from this file descriptor, as `env -0` output or a JSON object, instead of the debugger's own environment"
+    )]
+    pub env_fd: Option<i32>,
+    #[arg(
+        long = "shell-pid",
+        value_name = "PID",
+        help = "Read the environment the shell with this process ID started with from /proc/PID/environ, unless --env-file or --env-fd is given (Linux only)"
+    )]
+    pub shell_pid: Option<u32>,
+    #[arg(
+        long = "var-origins",
+        value_name = "PATH",
+        help = "Label variables with where they came from, given as NAME=origin lines or, for a .toml file, a table of NAME = \"origin\" entries"
+    )]
+    pub var_origins: Option<PathBuf>,
+    #[arg(
+        long = "output-fd",
+        value_name = "FD",
+        help = "Print the commands for the shell to this file descriptor instead of stdout"
+    )]
+    pub output_fd: Option<i32>,
+    #[arg(
+        long = "read-only",
+        help = "Inspect the environment without allowing any changes to it"
+    )]
+    pub read_only: bool,
+    #[arg(
+        long = "yes",
+        help = "Don't ask for confirmation before clearing or deleting commands (also `confirm = false` in the config file)"
+    )]
+    pub yes: bool,
+    #[arg(
+        long = "timeout",
+        value_name = "SECONDS",
+        help = "Exit on its own, making no changes, if no key is pressed within this many seconds"
+    )]
+    pub timeout: Option<u64>,
+    #[arg(
+        long = "no-tui",
+        help = "Continue without showing the TUI, as happens when stderr isn't a terminal"
+    )]
+    pub no_tui: bool,
+    #[arg(
+        long = "tick-rate",
+        value_name = "MILLISECONDS",
+        hide = true,
+        help = "How long to wait for input before updating timers [default: 250]"
+    )]
+    pub tick_rate: Option<u64>,
+    #[arg(
+        long = "demo",
+        conflicts_with_all = ["tracepoint", "call_stack", "call_stack_file", "bash_source", "env_file", "env_fd", "shell_pid"],
+        help = "Show made up data instead of the real environment, and comment out the commands printed on exit"
+    )]
+    pub demo: bool,
+    #[arg(
+        long = "no-color",
+        help = "Don't use color in the UI (also enabled by setting NO_COLOR)"
+    )]
+    pub no_color: bool,
+    #[arg(
+        long = "theme",
+        help = "The theme to use: auto, default, dark, light, solarized, or monochrome [default: auto]"
+    )]
+    pub theme: Option<ThemeChoice>,
+    #[arg(
+        long = "log-file",
+        help = "Write debug logs to this file [env: FLOX_DBG_LOG]"
+    )]
+    pub log_file: Option<PathBuf>,
+    #[arg(
+        long = "json-log",
+        value_name = "PATH",
+        help = "Append a JSON line for each action taken in the session to this file"
+    )]
+    pub json_log: Option<PathBuf>,
+    #[arg(
+        long = "control-socket",
+        value_name = "PATH",
+        help = "Listen on a Unix socket at this path for JSON requests from another program, one per line"
+    )]
+    pub control_socket: Option<PathBuf>,
+    #[arg(
+        long = "summary-file",
+        value_name = "PATH",
+        help = "Write a JSON summary of what was done in the session to this file on exit"
+    )]
+    pub summary_file: Option<PathBuf>,
+    #[arg(
+        long = "replay",
+        value_name = "FILE",
+        help = "Feed the key presses listed in this file to the debugger instead of reading the keyboard, exiting once they run out"
+    )]
+    pub replay: Option<PathBuf>,
+    #[arg(
+        long = "cwd",
+        value_name = "DIR",
+        help = "The working directory of the shell being debugged, for resolving relative paths [default: the debugger's]"
+    )]
+    pub cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Print shell code that defines `fdb_tracepoint` for use with
+    /// `eval "$(flox-debugger print-hook --shell bash)"`
+    PrintHook {
+        #[arg(
+            long = "shell",
+            ignore_case = true,
+            help = "Which shell to print the integration for"
+        )]
+        shell: Shell,
+        #[arg(
+            long = "output-fd",
+            help = "Have the debugger hand the commands back on a file descriptor of their own, sending its stdout to stderr"
+        )]
+        output_fd: bool,
+    },
+}
+
+/// The arguments tests build on: bash, a config file that doesn't exist so
+/// the user's own isn't read, and nothing else.
+#[cfg(test)]
+pub(crate) fn test_args() -> Cli {
+    Cli {
+        command: None,
+        shell: Some(Shell::Bash),
+        tracepoint: None,
+        tracepoints: Vec::new(),
+        call_stack: None,
+        call_stack_file: None,
+        bash_source: None,
+        funcname: None,
+        bash_lineno: None,
+        start_screen: None,
+        config: Some(PathBuf::from("/nonexistent/config.toml")),
+        env_file: None,
+        env_fd: None,
+        shell_pid: None,
+        var_origins: None,
+        control_socket: None,
+        summary_file: None,
+        replay: None,
+        output_fd: None,
+        read_only: false,
+        yes: false,
+        timeout: None,
+        no_tui: false,
+        tick_rate: None,
+        demo: false,
+        no_color: false,
+        theme: None,
+        log_file: None,
+        json_log: None,
+        cwd: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, error::ErrorKind};
+
+    use super::*;
+
+    #[test]
+    fn tracepoints_are_comma_separated_and_repeatable() {
+        let args = Cli::try_parse_from([
+            "flox-debugger",
+            "--shell",
+            "bash",
+            "--tracepoints",
+            "setup,teardown",
+            "--tracepoints",
+            "activate",
+        ])
+        .unwrap();
+        assert_eq!(args.tracepoints, ["setup", "teardown", "activate"]);
+
+        let args = Cli::try_parse_from(["flox-debugger", "--shell", "bash"]).unwrap();
+        assert!(args.tracepoints.is_empty());
+    }
+
+    #[test]
+    fn call_stack_sources_are_exclusive() {
+        let args =
+            Cli::try_parse_from(["flox-debugger", "--shell", "bash", "--call-stack-file", "-"])
+                .unwrap();
+        assert_eq!(args.call_stack_file, Some(PathBuf::from("-")));
+
+        let err = Cli::try_parse_from([
+            "flox-debugger",
+            "--shell",
+            "bash",
+            "--call-stack",
+            "foo:1:func",
+            "--call-stack-file",
+            "trace.txt",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+
+        // Bash's arrays only make a call stack together, and instead of any
+        // other call stack.
+        let bash = ["flox-debugger", "--shell", "bash"];
+        let arrays = ["--bash-source", "run.sh", "--funcname", "main"];
+        let err = Cli::try_parse_from(bash.iter().chain(&arrays)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        let arrays = [&arrays[..], &["--bash-lineno", "0"]].concat();
+        let args = Cli::try_parse_from(bash.iter().chain(&arrays)).unwrap();
+        assert_eq!(args.bash_lineno.as_deref(), Some("0"));
+        let err = Cli::try_parse_from(bash.iter().chain(&arrays).chain(&["--call-stack", "x"]))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn shells_are_matched_case_insensitively() {
+        for (name, expected) in [("Bash", "bash"), ("ZSH", "zsh"), ("posix", "sh")] {
+            let args = Cli::try_parse_from(["flox-debugger", "--shell", name]).unwrap();
+            assert_eq!(args.shell.unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn unknown_shells_list_the_valid_ones() {
+        let err = Cli::try_parse_from(["flox-debugger", "--shell", "zshell"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+        let message = err.to_string();
+        for name in ["bash", "zsh", "fish", "sh"] {
+            assert!(message.contains(name), "{message}");
+        }
+
+        let err = "zshell".parse::<Shell>().unwrap_err().to_string();
+        assert!(err.contains("bash, zsh, fish, sh"), "{err}");
+
+        let help = Cli::command().render_help().to_string();
+        assert!(
+            help.contains("[possible values: bash, zsh, fish, sh]"),
+            "{help}"
+        );
+    }
+
+    #[test]
+    fn subcommands_exclude_debugger_options() {
+        let args = Cli::try_parse_from(["flox-debugger", "print-hook", "--shell", "fish"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::PrintHook {
+                shell: Shell::Fish,
+                output_fd: false
+            })
+        ));
+        assert!(args.shell.is_none());
+
+        let err = Cli::try_parse_from([
+            "flox-debugger",
+            "--shell",
+            "bash",
+            "print-hook",
+            "--shell",
+            "bash",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
 }
@@ -0,0 +1,365 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error, anyhow, bail};
+use tracing::debug;
+
+use crate::app::{
+    aliases::PromptAliases,
+    key_bindings::KeyBindings,
+    macros::parse_macros,
+    prompt::COMMANDS,
+    theme::{Theme, ThemeChoice},
+};
+
+/// The name of the directory holding the debugger's config file.
+const CONFIG_DIR_NAME: &str = "flox-debugger";
+
+/// The name of the config file inside the config directory.
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Returns the default location of the config file, which is
+/// `$XDG_CONFIG_HOME/flox-debugger/config.toml` or
+/// `~/.config/flox-debugger/config.toml` if `XDG_CONFIG_HOME` is unset.
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// The user's configuration, with defaults for anything the config file
+/// doesn't specify.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub key_bindings: KeyBindings,
+    /// The theme chosen with `theme = "name"` or `theme.preset = "name"`.
+    pub theme_choice: Option<ThemeChoice>,
+    /// Overrides for individual theme styles, applied on top of whichever
+    /// base theme was chosen.
+    pub theme: toml::Table,
+    /// Whether destructive actions ask for confirmation, set with
+    /// `confirm = false`. Asking is the default.
+    pub confirm: Option<bool>,
+    /// Whether every variable the debugger changes is saved first, with a
+    /// `flox_dbg_revert` function to put them back, set with
+    /// `revert_helpers = true`. Off by default.
+    pub revert_helpers: Option<bool>,
+    /// Whether variables that look like lists of paths are split when
+    /// they're selected, turned off with `auto_split = false`.
+    pub auto_split: Option<bool>,
+    /// Variables to confirm changes to on top of the default ones, set with
+    /// `protected_vars = ["NAME", ...]`.
+    pub protected_vars: Vec<String>,
+    /// Shortcuts for prompt commands, from the `[aliases]` table.
+    pub aliases: PromptAliases,
+}
+
+impl Config {
+    /// Loads the config file at `path`, falling back to the defaults if the
+    /// file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        debug!(path = %path.display(), "loading config");
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read config file {}", path.display()));
+            }
+        };
+        Self::parse(&contents).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// Parses the contents of a config file.
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        let table = contents
+            .parse::<toml::Table>()
+            .context("failed to parse TOML")?;
+        let mut config = Self::default();
+        if let Some(keys) = table.get("keys") {
+            let keys = keys.as_table().ok_or(anyhow!("keys must be a table"))?;
+            config.key_bindings.apply_toml(keys)?;
+        }
+        match table.get("theme") {
+            Some(toml::Value::String(name)) => {
+                config.theme_choice = Some(name.parse().context("theme")?);
+            }
+            Some(toml::Value::Table(theme)) => {
+                let mut theme = theme.clone();
+                if let Some(preset) = theme.remove("preset") {
+                    let name = preset
+                        .as_str()
+                        .ok_or(anyhow!("theme.preset must be a string"))?;
+                    config.theme_choice = Some(name.parse().context("theme.preset")?);
+                }
+                // Validate the overrides now so mistakes are reported at startup.
+                Theme::from_config(Theme::default(), &theme)?;
+                config.theme = theme;
+            }
+            Some(_) => bail!("theme must be a theme name or a table"),
+            None => {}
+        }
+        if let Some(confirm) = table.get("confirm") {
+            let confirm = confirm
+                .as_bool()
+                .ok_or(anyhow!("confirm must be true or false"))?;
+            config.confirm = Some(confirm);
+        }
+        if let Some(revert_helpers) = table.get("revert_helpers") {
+            let revert_helpers = revert_helpers
+                .as_bool()
+                .ok_or(anyhow!("revert_helpers must be true or false"))?;
+            config.revert_helpers = Some(revert_helpers);
+        }
+        if let Some(auto_split) = table.get("auto_split") {
+            let auto_split = auto_split
+                .as_bool()
+                .ok_or(anyhow!("auto_split must be true or false"))?;
+            config.auto_split = Some(auto_split);
+        }
+        if let Some(protected_vars) = table.get("protected_vars") {
+            config.protected_vars = protected_vars
+                .as_array()
+                .and_then(|names| {
+                    names
+                        .iter()
+                        .map(|name| name.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or(anyhow!("protected_vars must be a list of variable names"))?;
+        }
+        if let Some(aliases) = table.get("aliases") {
+            let aliases = aliases
+                .as_table()
+                .ok_or(anyhow!("aliases must be a table"))?
+                .iter()
+                .map(|(name, command)| match command.as_str() {
+                    Some(command) => Ok((name.clone(), command.to_string())),
+                    None => Err(anyhow!("aliases.{name} must be a prompt command")),
+                })
+                .collect::<Result<BTreeMap<_, _>, _>>()?;
+            config.aliases = PromptAliases::new(aliases, &COMMANDS).context("aliases")?;
+        }
+        if let Some(macros) = table.get("macros") {
+            let macros = macros.as_table().ok_or(anyhow!("macros must be a table"))?;
+            config.key_bindings.set_macros(parse_macros(macros)?)?;
+        }
+        Ok(config)
+    }
+}
+
+/// Writes the key bindings into the `[keys]` table of the config file at
+/// `path`, preserving the rest of the file's settings.
+pub fn export_key_bindings(path: &Path, bindings: &KeyBindings) -> Result<(), Error> {
+    let mut table = match std::fs::read_to_string(path) {
+        Ok(contents) => contents
+            .parse::<toml::Table>()
+            .with_context(|| format!("failed to parse config file {}", path.display()))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read config file {}", path.display()));
+        }
+    };
+    table.insert("keys".to_string(), toml::Value::Table(bindings.to_toml()));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+    let contents = toml::to_string_pretty(&table).context("failed to serialize config")?;
+    std::fs::write(path, contents)
+        .with_context(|| format!("failed to write config file {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+    use crate::{
+        app::{key_bindings::KeyCombo, theme::ThemePreset},
+        test_dir::TestDir,
+    };
+
+    #[test]
+    fn parses_key_overrides() {
+        let config = Config::parse(
+            r#"
+            [keys.global]
+            exit = "Ctrl+q"
+            "#,
+        )
+        .unwrap();
+        let exit = config
+            .key_bindings
+            .entries()
+            .into_iter()
+            .find(|entry| entry.action == "exit")
+            .unwrap();
+        assert_eq!(
+            exit.key,
+            KeyCombo::new(KeyCode::Char('q'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn parses_confirm() {
+        assert_eq!(Config::parse("").unwrap().confirm, None);
+        assert_eq!(
+            Config::parse("confirm = false").unwrap().confirm,
+            Some(false)
+        );
+        let err = Config::parse("confirm = \"no\"").unwrap_err();
+        assert!(format!("{err:#}").contains("confirm"));
+    }
+
+    #[test]
+    fn parses_revert_helpers() {
+        assert_eq!(Config::parse("").unwrap().revert_helpers, None);
+        assert_eq!(
+            Config::parse("revert_helpers = true")
+                .unwrap()
+                .revert_helpers,
+            Some(true)
+        );
+        let err = Config::parse("revert_helpers = 1").unwrap_err();
+        assert!(format!("{err:#}").contains("revert_helpers"));
+    }
+
+    #[test]
+    fn parses_auto_split() {
+        assert_eq!(Config::parse("").unwrap().auto_split, None);
+        assert_eq!(
+            Config::parse("auto_split = false").unwrap().auto_split,
+            Some(false)
+        );
+        let err = Config::parse("auto_split = \"off\"").unwrap_err();
+        assert!(format!("{err:#}").contains("auto_split"));
+    }
+
+    #[test]
+    fn parses_protected_vars() {
+        assert!(Config::parse("").unwrap().protected_vars.is_empty());
+        assert_eq!(
+            Config::parse("protected_vars = [\"KUBECONFIG\"]")
+                .unwrap()
+                .protected_vars,
+            ["KUBECONFIG"]
+        );
+        let err = Config::parse("protected_vars = [1]").unwrap_err();
+        assert!(format!("{err:#}").contains("protected_vars"));
+    }
+
+    #[test]
+    fn parses_aliases() {
+        let config = Config::parse("[aliases]\nb = \"break $1\"\n").unwrap();
+        assert_eq!(
+            config.aliases.iter().collect::<Vec<_>>(),
+            [("b", "break $1")]
+        );
+        let err = Config::parse("[aliases]\nbreak = \"trace on\"").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "aliases: the alias 'break' would hide the built-in command"
+        );
+        let err = Config::parse("[aliases]\nb = 1").unwrap_err();
+        assert!(format!("{err:#}").contains("aliases.b"));
+        assert!(Config::parse("aliases = \"b\"").is_err());
+    }
+
+    #[test]
+    fn macro_keys_are_checked_against_the_other_bindings() {
+        let macros = "[macros.debug]\nkey = \"Alt+d\"\nactions = [\"set DEBUG=1\"]\n";
+        let config = Config::parse(macros).unwrap();
+        let debug = config.key_bindings.macro_named("debug").unwrap();
+        assert_eq!(
+            debug.key,
+            KeyCombo::new(KeyCode::Char('d'), KeyModifiers::ALT)
+        );
+
+        let err = |config: &str| format!("{:#}", Config::parse(config).unwrap_err());
+        assert_eq!(
+            err("[macros.quit]\nkey = \"q\"\nactions = [\"continue\"]"),
+            "macros.quit: q is already bound to global.exit"
+        );
+        assert!(
+            err("[macros.r]\nkey = \"s\"\nactions = [\"continue\"]").contains("vars.split_detail"),
+        );
+        assert!(
+            err("[macros.c]\nkey = \"Ctrl+c\"\nactions = [\"continue\"]")
+                .contains("interrupting the debugger")
+        );
+        assert!(
+            err(&format!(
+                "{macros}[macros.other]\nkey = \"Alt+d\"\nactions = [\"step\"]"
+            ))
+            .contains("macros.other")
+        );
+        // Rebinding a key onto a macro's is caught too.
+        let mut bindings = config.key_bindings.clone();
+        let rebind = bindings.rebind("global", "help", debug.key).unwrap_err();
+        assert_eq!(rebind.to_string(), "Alt+d is already bound to macros.debug");
+        assert!(
+            err(&format!("{macros}[keys.global]\nhelp = \"Alt+d\"\n")).contains("macros.debug")
+        );
+        assert!(Config::parse("macros = [\"set A=1\"]").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_and_conflicting_keys() {
+        let err = Config::parse("[keys.global]\nexit = \"Hyper+q\"").unwrap_err();
+        assert!(format!("{err:#}").contains("keys.global.exit"));
+        let err = Config::parse("[keys.global]\nfly = \"f\"").unwrap_err();
+        assert!(format!("{err:#}").contains("keys.global.fly"));
+        let err = Config::parse("[keys.global]\nexit = \"Tab\"").unwrap_err();
+        assert!(format!("{err:#}").contains("conflicting"));
+    }
+
+    #[test]
+    fn selects_theme_presets() {
+        let config = Config::parse(r#"theme = "solarized""#).unwrap();
+        assert_eq!(
+            config.theme_choice,
+            Some(ThemeChoice::Preset(ThemePreset::Solarized))
+        );
+        let config = Config::parse("[theme]\npreset = \"light\"\nfg = \"red\"").unwrap();
+        assert_eq!(
+            config.theme_choice,
+            Some(ThemeChoice::Preset(ThemePreset::Light))
+        );
+        assert!(config.theme.contains_key("fg"));
+        assert!(!config.theme.contains_key("preset"));
+
+        let err = Config::parse(r#"theme = "neon""#).unwrap_err();
+        assert!(format!("{err:#}").contains("monochrome"), "{err:#}");
+    }
+
+    #[test]
+    fn exported_bindings_round_trip() {
+        let dir = TestDir::new("config");
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "[other]\nkept = true\n").unwrap();
+
+        let mut bindings = KeyBindings::default();
+        bindings
+            .rebind(
+                "global",
+                "exit",
+                KeyCombo::new(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            )
+            .unwrap();
+        export_key_bindings(&path, &bindings).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("kept = true"));
+        let config = Config::parse(&contents).unwrap();
+        assert_eq!(config.key_bindings.entries(), bindings.entries());
+    }
+}
@@ -0,0 +1,392 @@
+use std::path::Path;
+
+use crate::app::{
+    Shell,
+    trace::{CALL_STACK_VERSION, STACK_VERSION_HEADER},
+};
+
+const BASH_TEMPLATE: &str = include_str!("templates/bash.sh");
+const ZSH_TEMPLATE: &str = include_str!("templates/zsh.zsh");
+const FISH_TEMPLATE: &str = include_str!("templates/fish.fish");
+const SH_TEMPLATE: &str = include_str!("templates/sh.sh");
+
+/// The placeholder in each template that's replaced by the command that runs
+/// the debugger.
+const INVOCATION_PLACEHOLDER: &str = "@INVOCATION@";
+
+/// The placeholder in each template that's replaced by the header naming the
+/// version of the call stack format the hook writes.
+const STACK_HEADER_PLACEHOLDER: &str = "@STACK_HEADER@";
+
+/// The file descriptor the hooks write the shell's environment to for the
+/// debugger.
+const ENV_FD: u32 = 3;
+
+/// The file descriptor the debugger writes the commands to for the hooks
+/// generated with `--output-fd`.
+const OUTPUT_FD: u32 = 5;
+
+/// Generates the shell code that defines `fdb_tracepoint` for `shell`,
+/// invoking the debugger at `debugger`. With `use_output_fd`, the debugger
+/// hands the commands back on [`OUTPUT_FD`] rather than its stdout.
+///
+/// The templates hold everything that's fixed for a dialect. The invocation
+/// is generated here because it needs the path to the debugger quoted for
+/// that shell.
+pub fn render_hook(shell: Shell, debugger: &Path, use_output_fd: bool) -> String {
+    let template = match shell {
+        Shell::Bash => BASH_TEMPLATE,
+        Shell::Zsh => ZSH_TEMPLATE,
+        Shell::Fish => FISH_TEMPLATE,
+        Shell::Posix => SH_TEMPLATE,
+    };
+    template
+        .replace(
+            INVOCATION_PLACEHOLDER,
+            &invocation(shell, debugger, use_output_fd),
+        )
+        .replace(
+            STACK_HEADER_PLACEHOLDER,
+            &format!("{STACK_VERSION_HEADER}{CALL_STACK_VERSION}"),
+        )
+}
+
+/// Returns the command that runs the debugger from inside `fdb_tracepoint`.
+///
+/// The shell's environment is handed over as `env -0` output on
+/// [`ENV_FD`], rather than left for the debugger to inherit, so that whatever
+/// runs the debugger can't add to it.
+///
+/// With `use_output_fd`, the commands are captured from [`OUTPUT_FD`] and
+/// the debugger's stdout goes to stderr, so that nothing else it prints can
+/// end up run by the shell.
+fn invocation(shell: Shell, debugger: &Path, use_output_fd: bool) -> String {
+    let (name, call_stack) = match shell {
+        // bash hands over its own call stack arrays, so that they're paired
+        // up into frames by the debugger alone.
+        Shell::Bash => (
+            "$name",
+            "--bash-source \"$bash_source\" --funcname \"$funcname\" --bash-lineno \"$bash_lineno\"",
+        ),
+        Shell::Zsh | Shell::Fish => ("$name", "--call-stack \"$call_stack\""),
+        // sh has no local variables, so the template uses prefixed globals.
+        Shell::Posix => ("$__fdb_name", "--call-stack \"$__fdb_call_stack\""),
+    };
+    let mut args =
+        format!("--shell {shell} --tracepoint \"{name}\" {call_stack} --env-fd {ENV_FD}");
+    if use_output_fd {
+        args.push_str(&format!(" --output-fd {OUTPUT_FD} {OUTPUT_FD}>&1 >&2"));
+    }
+    shell.quote(&debugger.to_string_lossy(), |debugger| match shell {
+        Shell::Bash | Shell::Zsh => format!("{debugger} {args} {ENV_FD}< <(env -0)"),
+        Shell::Fish => format!("{debugger} {args} {ENV_FD}< (env -0 | psub)"),
+        // sh has no process substitution, so the environment is piped in and
+        // the debugger gets the shell's stdin back, kept aside on fd 4.
+        Shell::Posix => {
+            format!("{{ env -0 | {debugger} {args} {ENV_FD}<&0 <&4 4<&-; }} 4<&0")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::Permissions, os::unix::fs::PermissionsExt, path::PathBuf};
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        app::{
+            TRACEPOINT_VAR_NAME,
+            commands::{TracepointDisposition, set_var_cmd, tests::run_in},
+            trace::{CallCtx, FrameKind, StackInput, load_call_stack},
+        },
+        cli::Cli,
+        test_dir::TestDir,
+    };
+
+    /// A path that needs quoting in every shell.
+    const DEBUGGER: &str = "/opt/flox tools/it's/flox-debugger";
+
+    /// Writes a script into `dir` that stands in for the debugger, running
+    /// `body` with the arguments the hook passes, and returns its path.
+    fn fake_debugger(dir: &TestDir, body: &str) -> PathBuf {
+        let debugger = dir.join("fake-debugger");
+        std::fs::write(&debugger, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&debugger, Permissions::from_mode(0o755)).unwrap();
+        debugger
+    }
+
+    #[test]
+    fn bash_hook_matches_snapshot() {
+        let hook = render_hook(Shell::Bash, Path::new(DEBUGGER), false);
+        assert_eq!(hook, include_str!("snapshots/bash.sh"));
+    }
+
+    #[test]
+    fn bash_hook_with_output_fd_matches_snapshot() {
+        let hook = render_hook(Shell::Bash, Path::new(DEBUGGER), true);
+        assert_eq!(hook, include_str!("snapshots/bash_output_fd.sh"));
+    }
+
+    #[test]
+    fn zsh_hook_matches_snapshot() {
+        let hook = render_hook(Shell::Zsh, Path::new(DEBUGGER), false);
+        assert_eq!(hook, include_str!("snapshots/zsh.zsh"));
+    }
+
+    #[test]
+    fn fish_hook_matches_snapshot() {
+        let hook = render_hook(Shell::Fish, Path::new(DEBUGGER), false);
+        assert_eq!(hook, include_str!("snapshots/fish.fish"));
+    }
+
+    #[test]
+    fn sh_hook_matches_snapshot() {
+        let hook = render_hook(Shell::Posix, Path::new(DEBUGGER), false);
+        assert_eq!(hook, include_str!("snapshots/sh.sh"));
+    }
+
+    #[test]
+    fn hooks_pause_at_any_tracepoint_in_a_list() {
+        let dir = TestDir::new("hook");
+        // Stands in for the debugger, telling the shell which tracepoint it
+        // paused at.
+        let debugger = fake_debugger(
+            &dir,
+            "while [ \"$1\" != --tracepoint ]; do shift; done\necho \"echo $2\"",
+        );
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let hook = render_hook(shell, &debugger, false);
+            let set = |value| set_var_cmd(shell, TRACEPOINT_VAR_NAME, value);
+            let script = [
+                hook,
+                set("setup,build"),
+                "fdb_tracepoint setup".to_string(),
+                "fdb_tracepoint test".to_string(),
+                "fdb_tracepoint build".to_string(),
+                set("next"),
+                "fdb_tracepoint test".to_string(),
+                set("py-*"),
+                "fdb_tracepoint py-build".to_string(),
+                "fdb_tracepoint build".to_string(),
+            ]
+            .join("\n");
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(printed, "setup\nbuild\ntest\npy-build\n", "{shell}");
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_hand_the_environment_over_on_a_file_descriptor() {
+        let dir = TestDir::new("env-hook");
+        // Stands in for the debugger, telling the shell what it read for one
+        // variable.
+        let debugger = fake_debugger(
+            &dir,
+            "value=\"$(tr '\\0' '\\n' <&3 | grep '^FDB_MARK=')\"\necho \"echo '$value'\"",
+        );
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let script = [
+                render_hook(shell, &debugger, false),
+                set_var_cmd(shell, "FDB_MARK", "it is here"),
+                set_var_cmd(shell, TRACEPOINT_VAR_NAME, "all"),
+                "fdb_tracepoint setup".to_string(),
+            ]
+            .join("\n");
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(printed, "FDB_MARK=it is here\n", "{shell}");
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_can_take_the_commands_from_a_file_descriptor() {
+        let dir = TestDir::new("out-hook");
+        // Stands in for the debugger, printing one command on stdout and
+        // another on the file descriptor it's given.
+        let debugger = fake_debugger(
+            &dir,
+            "while [ \"$1\" != --output-fd ]; do shift; done\necho 'echo from stdout'\necho 'echo from fd' >&$2",
+        );
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let script = [
+                render_hook(shell, &debugger, true),
+                set_var_cmd(shell, TRACEPOINT_VAR_NAME, "all"),
+                "fdb_tracepoint setup".to_string(),
+            ]
+            .join("\n");
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(printed, "from fd\n", "{shell}");
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_write_the_current_call_stack_version() {
+        let dir = TestDir::new("stack-hook");
+        // Stands in for the debugger, telling the shell the first line of the
+        // call stack it was given.
+        let debugger = fake_debugger(
+            &dir,
+            "while [ \"$1\" != --call-stack ]; do shift; done\necho \"echo '$(printf '%s\\n' \"$2\" | head -n 1)'\"",
+        );
+        // bash hands over its arrays instead.
+        for shell in [Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let script = [
+                render_hook(shell, &debugger, false),
+                set_var_cmd(shell, TRACEPOINT_VAR_NAME, "all"),
+                // sh only has a call stack when it's given the line number.
+                "fdb_tracepoint setup 3".to_string(),
+            ]
+            .join("\n");
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(
+                    printed,
+                    format!("{STACK_VERSION_HEADER}{CALL_STACK_VERSION}\n"),
+                    "{shell}"
+                );
+            }
+        }
+    }
+
+    /// Writes a fake debugger into `dir` that keeps the arguments it's given
+    /// in `args`, each ended by a NUL, and returns its path.
+    fn recording_debugger(dir: &TestDir, args: &Path) -> PathBuf {
+        fake_debugger(
+            dir,
+            &format!("printf '%s\\0' \"$@\" > '{}'", args.display()),
+        )
+    }
+
+    /// Loads the call stack that the hook for `shell` handed to a
+    /// [`recording_debugger`], from the arguments it kept in `args`.
+    fn recorded_call_stack(args: &Path, shell: Shell) -> Vec<CallCtx> {
+        let recorded = std::fs::read_to_string(args).unwrap();
+        let args = Cli::try_parse_from(
+            std::iter::once("flox-debugger").chain(recorded.split_terminator('\0')),
+        )
+        .unwrap();
+        let input = match (&args.bash_source, &args.funcname, &args.bash_lineno) {
+            (Some(source), Some(funcname), Some(lineno)) => StackInput::BashArrays {
+                source,
+                funcname,
+                lineno,
+            },
+            _ => StackInput::Formatted(args.call_stack.as_deref().unwrap_or_default()),
+        };
+        let frames = load_call_stack(input, shell).unwrap().frames;
+        frames.into_iter().map(|frame| frame.ctx).collect()
+    }
+
+    #[test]
+    fn hooks_label_frames_with_the_function_their_line_is_in() {
+        let dir = TestDir::new("functions");
+        let args = dir.join("args");
+        let debugger = recording_debugger(&dir, &args);
+        let hook = dir.join("hook");
+        let run = dir.join("run");
+        for shell in [Shell::Bash, Shell::Zsh] {
+            std::fs::write(&hook, render_hook(shell, &debugger, false)).unwrap();
+            std::fs::write(
+                &run,
+                format!(
+                    "source '{}'\n{}\nsetup() {{\n\tfdb_tracepoint inside\n}}\nsetup\n",
+                    hook.display(),
+                    set_var_cmd(shell, TRACEPOINT_VAR_NAME, "all"),
+                ),
+            )
+            .unwrap();
+            if run_in(shell, &format!("{shell} '{}'", run.display())).is_some() {
+                let frames = recorded_call_stack(&args, shell)
+                    .into_iter()
+                    .map(|ctx| (ctx.file, ctx.line, ctx.function, ctx.kind))
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    frames,
+                    [
+                        (run.clone(), 4, "setup".to_string(), FrameKind::Func),
+                        (run.clone(), 6, "<script>".to_string(), FrameKind::Func),
+                    ],
+                    "{shell}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_mark_sourced_files_in_the_call_stack() {
+        let dir = TestDir::new("sourced");
+        let args = dir.join("args");
+        let debugger = recording_debugger(&dir, &args);
+        let hook = dir.join("hook");
+        let lib = dir.join("lib");
+        let run = dir.join("run");
+        std::fs::write(&lib, "fdb_tracepoint sourced\n").unwrap();
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            std::fs::write(&hook, render_hook(shell, &debugger, false)).unwrap();
+            std::fs::write(
+                &run,
+                format!(
+                    "source '{}'\n{}\nsource '{}'\n",
+                    hook.display(),
+                    set_var_cmd(shell, TRACEPOINT_VAR_NAME, "all"),
+                    lib.display()
+                ),
+            )
+            .unwrap();
+            if run_in(shell, &format!("{shell} '{}'", run.display())).is_some() {
+                let frames = recorded_call_stack(&args, shell);
+                let sourced = frames
+                    .iter()
+                    .find(|ctx| ctx.kind == FrameKind::Source)
+                    .unwrap_or_else(|| panic!("{shell}: {frames:?}"));
+                assert_eq!(sourced.function, lib.to_string_lossy(), "{shell}");
+                // fish labels a line with the function it calls, so its
+                // sourcing frame is the line that sources the file.
+                let expected = match shell {
+                    Shell::Fish => (&run, 3),
+                    _ => (&lib, 1),
+                };
+                assert_eq!((&sourced.file, sourced.line), expected, "{shell}");
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_match_tracepoints_like_the_debugger() {
+        let values = ["", "next", "all", "setup", "py-*", "*-build,setup", "step?"];
+        let names = ["setup", "py-build", "node-build", "step1", "step10"];
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let mut script = render_hook(shell, Path::new(DEBUGGER), false);
+            let mut expected = String::new();
+            for value in values {
+                let disposition = TracepointDisposition::from_var_value(value);
+                script.push_str(&set_var_cmd(shell, TRACEPOINT_VAR_NAME, value));
+                script.push('\n');
+                for name in names {
+                    let check = match shell {
+                        Shell::Fish => "; and echo 1; or echo 0",
+                        Shell::Bash | Shell::Zsh | Shell::Posix => " && echo 1 || echo 0",
+                    };
+                    script.push_str(&format!("__fdb_should_pause {name}{check}\n"));
+                    let pauses = disposition.pauses_at(name).is_some();
+                    expected.push_str(if pauses { "1\n" } else { "0\n" });
+                }
+            }
+            if let Some(printed) = run_in(shell, &script) {
+                assert_eq!(printed, expected, "{shell}");
+            }
+        }
+    }
+
+    #[test]
+    fn every_placeholder_is_filled() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix] {
+            let hook = render_hook(shell, Path::new("flox-debugger"), false);
+            assert!(!hook.contains(INVOCATION_PLACEHOLDER), "{shell}: {hook}");
+            assert!(!hook.contains(STACK_HEADER_PLACEHOLDER), "{shell}: {hook}");
+        }
+    }
+}
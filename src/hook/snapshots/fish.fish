@@ -0,0 +1,44 @@
+# flox-debugger integration for fish, generated by
+# `flox-debugger print-hook --shell fish`.
+#
+# Place tracepoints in your scripts with `fdb_tracepoint <name>` and run them
+# with FLOX_DBG_TRACEPOINT set to the name of a tracepoint, several names
+# separated by commas, `next`, or `all`. A name can be a glob, where `*`
+# matches any run of characters and `?` matches any one.
+
+# Prints fish's own stack trace after the header for version 3 of the call
+# stack format, which lists sourced files as `from sourcing file <path>`. The
+# first four lines describe the command substitution and this function, so
+# they're dropped.
+function __fdb_stack_trace
+    set -l st (status stack-trace)
+    echo '#flox-dbg-stack-v3'
+    string join \n -- $st[5..]
+end
+
+# Succeeds if FLOX_DBG_TRACEPOINT asks to pause at the tracepoint named
+# $argv[1].
+function __fdb_should_pause
+    if not set -q FLOX_DBG_TRACEPOINT
+        return 1
+    end
+    if contains -- "$FLOX_DBG_TRACEPOINT" all next
+        return 0
+    end
+    for pattern in (string split , -- "$FLOX_DBG_TRACEPOINT")
+        if string match -q -- $pattern $argv[1]
+            return 0
+        end
+    end
+    return 1
+end
+
+function fdb_tracepoint
+    set -l name $argv[1]
+    __fdb_should_pause $name
+    or return 0
+    set -l call_stack (__fdb_stack_trace | string collect)
+    set -l output ('/opt/flox tools/it\'s/flox-debugger' --shell fish --tracepoint "$name" --call-stack "$call_stack" --env-fd 3 3< (env -0 | psub) | string collect)
+    or return
+    eval $output
+end
@@ -0,0 +1,47 @@
+# flox-debugger integration for POSIX sh, generated by
+# `flox-debugger print-hook --shell sh`.
+#
+# Place tracepoints in your scripts with `fdb_tracepoint <name> "$LINENO"` and
+# run them with FLOX_DBG_TRACEPOINT set to the name of a tracepoint, several
+# names separated by commas, `next`, or `all`. A name can be a glob, where `*`
+# matches any run of characters and `?` matches any one. POSIX sh can't inspect
+# its call stack, so the call stack only contains the tracepoint itself, and
+# only if its line number is passed.
+
+# Succeeds if FLOX_DBG_TRACEPOINT asks to pause at the tracepoint named $1.
+# There are no local variables in sh, so the names are prefixed to avoid
+# clobbering the script's own variables.
+__fdb_should_pause() {
+	case "${FLOX_DBG_TRACEPOINT:-}" in
+	"") return 1 ;;
+	all | next) return 0 ;;
+	esac
+	__fdb_patterns="$FLOX_DBG_TRACEPOINT,"
+	while [ -n "$__fdb_patterns" ]; do
+		__fdb_pattern="${__fdb_patterns%%,*}"
+		__fdb_patterns="${__fdb_patterns#*,}"
+		# Unquoted so that it's matched as a glob.
+		case "$1" in
+		$__fdb_pattern)
+			unset __fdb_patterns __fdb_pattern
+			return 0
+			;;
+		esac
+	done
+	unset __fdb_patterns __fdb_pattern
+	return 1
+}
+
+fdb_tracepoint() {
+	__fdb_should_pause "$1" || return 0
+	__fdb_name="$1"
+	__fdb_call_stack=""
+	if [ -n "${2:-}" ]; then
+		__fdb_file="$(cd "$(dirname "$0")" && pwd)/$(basename "$0")"
+		# Version 3 of the call stack format, with a single frame.
+		__fdb_call_stack="$(printf '#flox-dbg-stack-v3\n%s\t%s\t<script>\tfunc' "$__fdb_file" "$2")"
+	fi
+	__fdb_output="$({ env -0 | '/opt/flox tools/it'\''s/flox-debugger' --shell sh --tracepoint "$__fdb_name" --call-stack "$__fdb_call_stack" --env-fd 3 3<&0 <&4 4<&-; } 4<&0)" || return
+	unset __fdb_name __fdb_call_stack __fdb_file
+	eval "$__fdb_output"
+}
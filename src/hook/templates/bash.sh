@@ -0,0 +1,38 @@
+# flox-debugger integration for bash, generated by
+# `flox-debugger print-hook --shell bash`.
+#
+# Place tracepoints in your scripts with `fdb_tracepoint <name>` and run them
+# with FLOX_DBG_TRACEPOINT set to the name of a tracepoint, several names
+# separated by commas, `next`, or `all`. A name can be a glob, where `*`
+# matches any run of characters and `?` matches any one.
+
+# Succeeds if FLOX_DBG_TRACEPOINT asks to pause at the tracepoint named $1.
+__fdb_should_pause() {
+	case "${FLOX_DBG_TRACEPOINT:-}" in
+	"") return 1 ;;
+	all | next) return 0 ;;
+	esac
+	local patterns="$FLOX_DBG_TRACEPOINT," pattern
+	while [ -n "$patterns" ]; do
+		pattern="${patterns%%,*}"
+		patterns="${patterns#*,}"
+		# Unquoted so that it's matched as a glob.
+		case "$1" in
+		$pattern) return 0 ;;
+		esac
+	done
+	return 1
+}
+
+fdb_tracepoint() {
+	local name="$1"
+	__fdb_should_pause "$name" || return 0
+	# bash's call stack arrays, one entry per line, which the debugger pairs
+	# up into frames. Their first entries are this function's own.
+	local bash_source funcname bash_lineno output
+	bash_source="$(printf '%s\n' "${BASH_SOURCE[@]}")"
+	funcname="$(printf '%s\n' "${FUNCNAME[@]}")"
+	bash_lineno="$(printf '%s\n' "${BASH_LINENO[@]}")"
+	output="$(@INVOCATION@)" || return
+	eval "$output"
+}
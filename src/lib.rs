@@ -0,0 +1,18 @@
+//! A terminal UI for inspecting and changing a shell's environment while a
+//! script is paused at a tracepoint.
+//!
+//! The binary only parses arguments and sets up the terminal. Everything
+//! else lives here so that it can be tested and embedded: build an
+//! [`app::App`] from a [`cli::Cli`], feed it events with
+//! [`app::handle_event`], and read back the commands for the shell with
+//! [`app::App::output`].
+
+pub mod app;
+pub mod cli;
+pub mod config;
+pub mod hook;
+pub mod logging;
+pub mod terminal;
+#[cfg(test)]
+mod test_dir;
+pub mod ui;
@@ -0,0 +1,28 @@
+use std::{fs::File, path::Path, sync::Mutex};
+
+use anyhow::{Context, Error};
+use tracing::Subscriber;
+
+/// Sends log messages to the file at `path`.
+///
+/// Stdout carries the commands for the user's shell and stderr carries the
+/// TUI, so a file is the only place logs can go. Without a log file no
+/// subscriber is installed and logging costs next to nothing.
+pub fn init_logging(path: &Path) -> Result<(), Error> {
+    let subscriber = file_subscriber(path)?;
+    tracing::subscriber::set_global_default(subscriber).context("failed to install the logger")?;
+    Ok(())
+}
+
+/// Creates a subscriber that writes every message to the file at `path`,
+/// truncating it first.
+pub fn file_subscriber(path: &Path) -> Result<impl Subscriber + Send + Sync, Error> {
+    let file = File::create(path)
+        .with_context(|| format!("failed to create log file {}", path.display()))?;
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(tracing::Level::TRACE)
+        .finish();
+    Ok(subscriber)
+}
@@ -1,53 +1,124 @@
-mod app;
-mod cli;
-mod ui;
+use std::io::IsTerminal;
 
 use anyhow::{Context, Error};
 use clap::Parser;
-use ratatui::{
-    Terminal,
-    crossterm::{
-        event::{DisableMouseCapture, EnableMouseCapture},
-        execute,
-        terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
-    },
-    prelude::*,
-};
+use ratatui::{Terminal, prelude::*};
+use tracing::debug;
 
-use crate::{
-    app::{App, run_app},
-    cli::Cli,
+use flox_debugger::{
+    app::{
+        App, ExitDisposition, TRACEPOINT_VAR_NAME,
+        headless::{HeadlessReason, run_headless},
+        output::open_output_fd,
+        run_app,
+        session::{SessionState, default_session_path},
+        summary::SessionSummary,
+    },
+    cli::{
+        Cli, Command,
+        env::{process_env, resolve_env},
+    },
+    hook, logging,
+    terminal::{
+        TerminalInfo,
+        events::EventSource,
+        guard::{TerminalGuard, install_panic_hook},
+        replay::load_replay,
+        signals::install_signal_handlers,
+    },
 };
 
 fn main() -> Result<(), Error> {
     let args = Cli::parse();
+    if let Some(Command::PrintHook { shell, output_fd }) = &args.command {
+        let debugger = std::env::current_exe().context("failed to locate the debugger")?;
+        print!("{}", hook::render_hook(*shell, &debugger, *output_fd));
+        return Ok(());
+    }
+    let args = resolve_env(args, &process_env())?;
+    if let Some(path) = &args.log_file {
+        logging::init_logging(path)?;
+    }
+    let headless_reason = if args.no_tui {
+        Some(HeadlessReason::Requested)
+    } else if !std::io::stderr().is_terminal() {
+        Some(HeadlessReason::NotATerminal)
+    } else {
+        None
+    };
+    if let Some(reason) = headless_reason {
+        let tracepoint = std::env::var(TRACEPOINT_VAR_NAME).unwrap_or_default();
+        return match args.output_fd.map(open_output_fd).transpose()? {
+            Some(mut file) => run_headless(
+                &args,
+                &tracepoint,
+                reason,
+                &mut file,
+                &mut std::io::stderr(),
+            ),
+            None => run_headless(
+                &args,
+                &tracepoint,
+                reason,
+                &mut std::io::stdout(),
+                &mut std::io::stderr(),
+            ),
+        };
+    }
+    // Create the app before touching the terminal so that problems with the
+    // arguments or config file are reported on a normal terminal.
+    let replay = args.replay.as_deref().map(load_replay).transpose()?;
+    let terminal_info = TerminalInfo::probe(&args);
+    let mut app = App::new(&args, &terminal_info)?;
+    let session_path = default_session_path();
+    if let Some(path) = &session_path {
+        app.restore_session(&SessionState::load(path), &args);
+    }
+
     // Setup the terminal in a TUI-friendly state, displaying the TUI via
     // stderr instead of stdout since we'll print shell commands on stdout
-    // when the debugger exits.
-    enable_raw_mode().context("failed to enable raw mode")?;
-    let mut stderr = std::io::stderr();
-    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)
-        .context("failed to enter alternate screen mode")?;
+    // when the debugger exits. The hook and guard put the terminal back
+    // however the TUI ends, and signals that would otherwise kill or stop
+    // the debugger are handled by the event loop instead.
+    install_panic_hook();
+    install_signal_handlers()?;
+    let guard = TerminalGuard::enter()?;
 
     // Create and run the app.
-    let backend = CrosstermBackend::new(stderr);
+    let backend = CrosstermBackend::new(std::io::stderr());
     let mut terminal = Terminal::new(backend).context("failed to get terminal")?;
-    let mut app = App::new(&args)?;
-    let res = run_app(&mut app, &mut terminal);
+    let events = match replay {
+        Some(steps) => EventSource::replay(steps),
+        None => EventSource::new(),
+    };
+    let res = run_app(&mut app, &mut terminal, &events);
+    drop(events);
 
     // Restore the terminal to its normal state.
-    disable_raw_mode().context("failed to disable raw mode")?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )
-    .context("failed to leave alternate screen mode")?;
-    terminal.show_cursor().context("failed to restore cursor")?;
+    guard.restore()?;
 
     // Handle any errors that came from the TUI.
     match res.as_ref() {
         Ok(_) => {
+            if app.cancelled() {
+                eprintln!("flox-debugger: interrupted, discarding changes");
+            } else if app.exit_disposition() == ExitDisposition::Discard {
+                eprintln!("flox-debugger: discarding changes");
+            }
+            // Losing the session only costs the user a few key presses next
+            // time, so it's not worth failing over.
+            if let Some(path) = &session_path
+                && let Err(err) = SessionState::of(&app).save(path)
+            {
+                debug!("failed to save session: {err:#}");
+            }
+            // Whoever asked for the summary can do without it, but the shell
+            // can't do without the commands.
+            if let Some(path) = &args.summary_file
+                && let Err(err) = SessionSummary::of(&app).write(path)
+            {
+                eprintln!("flox-debugger: failed to write summary: {err:#}");
+            }
             app.print_output().context("failed to print commands")?;
         }
         _err => {
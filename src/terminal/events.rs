@@ -0,0 +1,250 @@
+use std::{
+    io::ErrorKind,
+    sync::{
+        Arc, Condvar, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use ratatui::crossterm::event::{self, Event as TermEvent};
+
+/// How long the input thread waits for input before checking whether it has
+/// been paused or stopped.
+const READ_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What the input thread sends: each terminal event in the order it arrived,
+/// or the error that stopped the thread.
+pub type Input = std::io::Result<TermEvent>;
+
+/// Returns the error a reader gives once it has no more input to give, such
+/// as at the end of a replay script. It ends the session as if the user had
+/// exited.
+pub fn end_of_input() -> std::io::Error {
+    std::io::Error::new(ErrorKind::UnexpectedEof, "no more input")
+}
+
+/// Returns whether `err` came from [`end_of_input`].
+pub fn is_end_of_input(err: &std::io::Error) -> bool {
+    err.kind() == ErrorKind::UnexpectedEof
+}
+
+/// Reads terminal input on its own thread, sending it over a channel so that
+/// the event loop can wait for input and timers at once.
+#[derive(Debug)]
+pub struct EventSource {
+    receiver: Receiver<Input>,
+    control: Arc<Control>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReaderState {
+    Running,
+    Paused,
+    Stopped,
+}
+
+/// Lets the event source tell the thread what to do, and find out when it
+/// has stopped reading.
+#[derive(Debug)]
+struct Control {
+    /// What the thread should be doing, and whether it's not reading, either
+    /// because it's paused or because it has ended.
+    state: Mutex<(ReaderState, bool)>,
+    changed: Condvar,
+}
+
+impl EventSource {
+    /// Starts reading from the terminal.
+    // Spawning a thread that reads the terminal is too surprising for
+    // `Default`.
+    #[expect(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::spawn(|timeout| {
+            if event::poll(timeout)? {
+                event::read().map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Starts a thread that calls `read` until it's stopped. `read` waits up
+    /// to the given time for an event, returning `None` if none arrived.
+    pub fn spawn(
+        mut read: impl FnMut(Duration) -> std::io::Result<Option<TermEvent>> + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let control = Arc::new(Control {
+            state: Mutex::new((ReaderState::Running, false)),
+            changed: Condvar::new(),
+        });
+        let thread_control = Arc::clone(&control);
+        let thread = std::thread::spawn(move || {
+            read_until_stopped(&mut read, &sender, &thread_control);
+            // Wake up anyone waiting for the thread to pause.
+            let mut state = thread_control.state.lock().unwrap();
+            state.1 = true;
+            thread_control.changed.notify_all();
+        });
+        Self {
+            receiver,
+            control,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns the channel that input arrives on.
+    pub fn receiver(&self) -> &Receiver<Input> {
+        &self.receiver
+    }
+
+    /// Stops reading input, waiting until the thread is no longer reading so
+    /// that another program can have the terminal.
+    pub fn pause(&self) {
+        let mut state = self.control.state.lock().unwrap();
+        if state.0 == ReaderState::Running {
+            state.0 = ReaderState::Paused;
+            self.control.changed.notify_all();
+        }
+        let _idle = self
+            .control
+            .changed
+            .wait_while(state, |(_, idle)| !*idle)
+            .unwrap();
+    }
+
+    /// Starts reading input again after [`EventSource::pause`].
+    pub fn resume(&self) {
+        let mut state = self.control.state.lock().unwrap();
+        if state.0 == ReaderState::Paused {
+            state.0 = ReaderState::Running;
+            self.control.changed.notify_all();
+        }
+    }
+}
+
+impl Drop for EventSource {
+    fn drop(&mut self) {
+        {
+            let mut state = self.control.state.lock().unwrap();
+            state.0 = ReaderState::Stopped;
+            self.control.changed.notify_all();
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The body of the input thread.
+fn read_until_stopped(
+    read: &mut impl FnMut(Duration) -> std::io::Result<Option<TermEvent>>,
+    sender: &Sender<Input>,
+    control: &Control,
+) {
+    loop {
+        {
+            let mut state = control.state.lock().unwrap();
+            if state.0 == ReaderState::Paused {
+                state.1 = true;
+                control.changed.notify_all();
+                state = control
+                    .changed
+                    .wait_while(state, |(state, _)| *state == ReaderState::Paused)
+                    .unwrap();
+                state.1 = false;
+            }
+            if state.0 == ReaderState::Stopped {
+                return;
+            }
+        }
+        match read(READ_INTERVAL) {
+            Ok(Some(event)) => {
+                if sender.send(Ok(event)).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let _ = sender.send(Err(err));
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+
+    fn key(c: char) -> TermEvent {
+        TermEvent::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    /// Returns a source that produces the letters of the alphabet in order,
+    /// with a resize after each one, and a count of the calls to read.
+    fn scripted_source() -> (EventSource, Arc<AtomicUsize>) {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&reads);
+        let source = EventSource::spawn(move |_timeout| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(1));
+            let letter = char::from(b'a' + (n / 2 % 26) as u8);
+            Ok(Some(if n.is_multiple_of(2) {
+                key(letter)
+            } else {
+                TermEvent::Resize(n as u16, n as u16)
+            }))
+        });
+        (source, reads)
+    }
+
+    #[test]
+    fn events_arrive_in_order() {
+        let (source, _reads) = scripted_source();
+        let events = (0..10)
+            .map(|_| source.receiver().recv().unwrap().unwrap())
+            .collect::<Vec<_>>();
+        let expected = (0..10_u8)
+            .map(|n| {
+                if n.is_multiple_of(2) {
+                    key(char::from(b'a' + n / 2))
+                } else {
+                    TermEvent::Resize(u16::from(n), u16::from(n))
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn pausing_stops_reading_until_resumed() {
+        let (source, reads) = scripted_source();
+        source.receiver().recv().unwrap().unwrap();
+        source.pause();
+        let paused_at = reads.load(Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(reads.load(Ordering::SeqCst), paused_at);
+
+        source.resume();
+        while source.receiver().try_recv().is_ok() {}
+        source.receiver().recv().unwrap().unwrap();
+        assert!(reads.load(Ordering::SeqCst) > paused_at);
+    }
+
+    #[test]
+    fn errors_end_the_stream() {
+        let source = EventSource::spawn(|_timeout| Err(std::io::Error::other("gone")));
+        assert!(source.receiver().recv().unwrap().is_err());
+        assert!(source.receiver().recv().is_err());
+        // Pausing a thread that already stopped doesn't wait forever.
+        source.pause();
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::{Context, Error};
+use ratatui::crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+
+/// Puts the terminal in the state the TUI needs, and puts it back when
+/// dropped so that no early return or panic can leave it unusable.
+///
+/// The TUI is drawn on stderr, since stdout is reserved for the commands
+/// printed on exit.
+#[derive(Debug)]
+pub struct TerminalGuard {
+    restored: bool,
+}
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self, Error> {
+        enable_raw_mode().context("failed to enable raw mode")?;
+        // From here on the guard undoes whatever was done, even if entering
+        // the alternate screen fails.
+        let guard = Self { restored: false };
+        execute!(std::io::stderr(), EnterAlternateScreen, EnableMouseCapture)
+            .context("failed to enter alternate screen mode")?;
+        Ok(guard)
+    }
+
+    /// Restores the terminal, reporting any problems doing so that would be
+    /// ignored if the guard were just dropped.
+    pub fn restore(mut self) -> Result<(), Error> {
+        self.restored = true;
+        restore_terminal()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = restore_terminal();
+        }
+    }
+}
+
+/// Undoes everything [`TerminalGuard::enter`] and drawing the TUI did to the
+/// terminal. Doing so when the terminal is already restored is harmless.
+pub fn restore_terminal() -> Result<(), Error> {
+    disable_raw_mode().context("failed to disable raw mode")?;
+    execute!(
+        std::io::stderr(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    )
+    .context("failed to leave alternate screen mode")
+}
+
+/// Makes panics restore the terminal before the panic message is printed, so
+/// that the message is readable and the shell is left usable.
+///
+/// Nothing is printed on stdout, so the calling shell has nothing to run.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        process::{Command, Stdio},
+    };
+
+    use super::*;
+    use crate::terminal::pty::{is_cooked, open_pty, read_available};
+
+    /// Set in the child process that the panic test runs itself in.
+    const PANIC_CHILD_VAR: &str = "FLOX_DBG_TEST_PANIC_CHILD";
+
+    #[test]
+    fn panic_restores_terminal() {
+        if std::env::var_os(PANIC_CHILD_VAR).is_some() {
+            install_panic_hook();
+            let _guard = TerminalGuard::enter().unwrap();
+            panic!("forced panic");
+        }
+
+        let (controller, terminal) = open_pty();
+        let child = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "terminal::guard::tests::panic_restores_terminal",
+                "--nocapture",
+            ])
+            .env(PANIC_CHILD_VAR, "1")
+            .stdin(File::from(terminal.try_clone().unwrap()))
+            .stderr(File::from(terminal.try_clone().unwrap()))
+            .stdout(Stdio::piped())
+            .output()
+            .unwrap();
+        assert!(!child.status.success());
+        assert!(is_cooked(&terminal), "still in raw mode");
+
+        // Everything the child wrote is buffered, so a nonblocking read gets
+        // it without waiting for the terminal end to close.
+        let written = read_available(&controller);
+        let left_alternate_screen = written.find("\x1b[?1049l").expect("left alternate screen");
+        let panic_message = written.find("forced panic").expect("printed the panic");
+        assert!(left_alternate_screen < panic_message, "{written:?}");
+    }
+}
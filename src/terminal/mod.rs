@@ -0,0 +1,279 @@
+pub mod events;
+pub mod guard;
+pub mod pager;
+#[cfg(test)]
+mod pty;
+pub mod replay;
+pub mod signals;
+pub mod suspend;
+
+use std::{
+    io::Write,
+    os::fd::AsRawFd,
+    time::{Duration, Instant},
+};
+
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+
+use crate::{app::theme::ThemeChoice, cli::Cli};
+
+/// How long to wait for the terminal to answer a query before giving up.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Whether the terminal draws light text on a dark background or the reverse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+/// How many colors the terminal can display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// The 16 ANSI colors, whose exact shades are chosen by the terminal.
+    Ansi16,
+    /// The xterm 256-color palette.
+    Ansi256,
+    /// Arbitrary 24-bit RGB colors.
+    #[default]
+    TrueColor,
+}
+
+/// What the debugger knows about the terminal it's drawing to.
+#[derive(Debug, Clone, Default)]
+pub struct TerminalInfo {
+    /// The terminal's background, if it could be detected.
+    pub background: Option<Background>,
+    pub color_depth: ColorDepth,
+}
+
+impl TerminalInfo {
+    /// Inspects the terminal, only querying it for information that the
+    /// arguments don't already settle.
+    pub fn probe(args: &Cli) -> Self {
+        // The config file may still pick a preset, but it's cheaper to ask
+        // needlessly than to load the config file twice.
+        let background = match args.theme {
+            None | Some(ThemeChoice::Auto) if !args.no_color => detect_background(),
+            _ => None,
+        };
+        let color_depth = detect_color_depth(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        );
+        Self {
+            background,
+            color_depth,
+        }
+    }
+}
+
+/// Determines the supported color depth from the `COLORTERM` and `TERM`
+/// variables.
+///
+/// Terminals that support 24-bit color advertise it via `COLORTERM`, but
+/// that variable is often lost over ssh or inside tmux, in which case `TERM`
+/// is the best remaining hint.
+pub fn detect_color_depth(colorterm: Option<&str>, term: Option<&str>) -> ColorDepth {
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return ColorDepth::TrueColor;
+    }
+    match term {
+        Some(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+        Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+/// Detects the terminal background from `COLORFGBG`, falling back to asking
+/// the terminal for its background color.
+fn detect_background() -> Option<Background> {
+    if let Some(background) = std::env::var("COLORFGBG")
+        .ok()
+        .and_then(|value| parse_colorfgbg(&value))
+    {
+        return Some(background);
+    }
+    query_background()
+}
+
+/// Parses the `COLORFGBG` variable set by some terminals, e.g. `15;0`, whose
+/// last field is the ANSI color index of the background.
+pub fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let bg = value.rsplit(';').next()?.parse::<u8>().ok()?;
+    match bg {
+        0..=6 | 8 => Some(Background::Dark),
+        7 | 9..=15 => Some(Background::Light),
+        _ => None,
+    }
+}
+
+/// Asks the terminal for its background color with an OSC 11 query.
+///
+/// The query is followed by a primary device attributes request, which
+/// practically every terminal answers. Terminals answer in order, so once the
+/// device attributes arrive we know that no background color is coming, and
+/// nothing is left unread to show up later as key presses.
+fn query_background() -> Option<Background> {
+    let stdin = std::io::stdin();
+    let mut stderr = std::io::stderr();
+    // SAFETY: `isatty` only inspects the file descriptors.
+    let is_tty = unsafe { libc::isatty(stdin.as_raw_fd()) == 1 && libc::isatty(2) == 1 };
+    if !is_tty {
+        return None;
+    }
+    enable_raw_mode().ok()?;
+    let reply = stderr
+        .write_all(b"\x1b]11;?\x1b\\\x1b[c")
+        .and_then(|_| stderr.flush())
+        .ok()
+        .map(|_| read_reply(stdin.as_raw_fd()));
+    disable_raw_mode().ok()?;
+    parse_osc11_reply(&reply?)
+}
+
+/// Reads from `fd` until the device attributes reply arrives or the query
+/// times out.
+fn read_reply(fd: i32) -> Vec<u8> {
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    while !ends_with_device_attributes(&reply) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `pollfd` is a valid, initialized array of length 1.
+        let ready = unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+        let mut buf = [0u8; 256];
+        // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if n <= 0 {
+            break;
+        }
+        reply.extend_from_slice(&buf[..n as usize]);
+    }
+    reply
+}
+
+/// Returns whether `bytes` ends with a device attributes reply such as
+/// `ESC [ ? 62 ; 22 c`.
+fn ends_with_device_attributes(bytes: &[u8]) -> bool {
+    let Some(start) = bytes.windows(3).rposition(|w| w == b"\x1b[?") else {
+        return false;
+    };
+    let rest = &bytes[start + 3..];
+    rest.last() == Some(&b'c')
+        && rest[..rest.len() - 1]
+            .iter()
+            .all(|b| b.is_ascii_digit() || *b == b';')
+}
+
+/// Parses a reply to an OSC 11 query such as
+/// `ESC ] 11 ; rgb:1e1e/1e1e/2e2e ESC \`, classifying the color by its
+/// luminance. Anything else in `bytes`, such as the device attributes reply,
+/// is ignored.
+pub fn parse_osc11_reply(bytes: &[u8]) -> Option<Background> {
+    let text = String::from_utf8_lossy(bytes);
+    let start = text.find("\x1b]11;")? + "\x1b]11;".len();
+    let rest = &text[start..];
+    let end = rest.find(['\x07', '\x1b'])?;
+    let spec = rest[..end].strip_prefix("rgb:")?;
+    let mut channels = spec.split('/').map(parse_channel);
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    if channels.next().is_some() {
+        return None;
+    }
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    if luminance > 0.5 {
+        Some(Background::Light)
+    } else {
+        Some(Background::Dark)
+    }
+}
+
+/// Parses a color channel of 1-4 hex digits into the range `0.0..=1.0`.
+fn parse_channel(hex: &str) -> Option<f64> {
+    if hex.is_empty() || hex.len() > 4 {
+        return None;
+    }
+    let value = u16::from_str_radix(hex, 16).ok()?;
+    let max = (1u32 << (4 * hex.len())) - 1;
+    Some(f64::from(value) / f64::from(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_color_depth() {
+        let cases = [
+            (
+                Some("truecolor"),
+                Some("xterm-256color"),
+                ColorDepth::TrueColor,
+            ),
+            (Some("24bit"), Some("xterm"), ColorDepth::TrueColor),
+            (None, Some("xterm-kitty-direct"), ColorDepth::TrueColor),
+            (None, Some("screen-256color"), ColorDepth::Ansi256),
+            (None, Some("tmux-256color"), ColorDepth::Ansi256),
+            (Some(""), Some("xterm-256color"), ColorDepth::Ansi256),
+            (None, Some("xterm"), ColorDepth::Ansi16),
+            (None, Some("linux"), ColorDepth::Ansi16),
+            (None, None, ColorDepth::Ansi16),
+        ];
+        for (colorterm, term, expected) in cases {
+            assert_eq!(
+                detect_color_depth(colorterm, term),
+                expected,
+                "COLORTERM={colorterm:?} TERM={term:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_colorfgbg() {
+        assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("12;default;7"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("15;default"), None);
+        assert_eq!(parse_colorfgbg(""), None);
+    }
+
+    #[test]
+    fn parses_osc11_replies() {
+        let dark = b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\\x1b[?62;22c";
+        assert_eq!(parse_osc11_reply(dark), Some(Background::Dark));
+        let light = b"\x1b]11;rgb:ffff/fbfb/f0f0\x07";
+        assert_eq!(parse_osc11_reply(light), Some(Background::Light));
+        let short = b"\x1b]11;rgb:f/f/f\x07";
+        assert_eq!(parse_osc11_reply(short), Some(Background::Light));
+    }
+
+    #[test]
+    fn rejects_malformed_osc11_replies() {
+        // The terminal only answered the device attributes query.
+        assert_eq!(parse_osc11_reply(b"\x1b[?62;22c"), None);
+        assert_eq!(parse_osc11_reply(b"\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(parse_osc11_reply(b"\x1b]11;rgb:zz/00/00\x07"), None);
+        assert_eq!(parse_osc11_reply(b"\x1b]11;rgb:ffff/ffff/ffff"), None);
+        assert_eq!(parse_osc11_reply(b""), None);
+    }
+
+    #[test]
+    fn detects_end_of_device_attributes() {
+        assert!(ends_with_device_attributes(
+            b"\x1b]11;rgb:0/0/0\x07\x1b[?1;2c"
+        ));
+        assert!(!ends_with_device_attributes(b"\x1b]11;rgb:0/0/0\x07"));
+        assert!(!ends_with_device_attributes(b"\x1b[?1;2"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{Context, Error, bail};
+
+use crate::terminal::{events::EventSource, suspend::with_terminal_released};
+
+/// The pager used when `$PAGER` isn't set.
+pub const DEFAULT_PAGER: &str = "less -R";
+
+/// Tells apart the files paged by one debugger, since the process ID alone
+/// doesn't.
+static PAGED_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the command to page with: `$PAGER` if it's set to anything, or
+/// [`DEFAULT_PAGER`] otherwise.
+pub fn pager_command(pager_var: Option<&str>) -> &str {
+    pager_var
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or(DEFAULT_PAGER)
+}
+
+/// Writes `content` to a new file that only the current user can read,
+/// returning its path.
+///
+/// The file must not already exist, so nothing else can have put a file or
+/// a symlink there first.
+fn write_private_file(content: &str) -> Result<PathBuf, Error> {
+    let n = PAGED_FILES.fetch_add(1, Ordering::SeqCst);
+    let path = std::env::temp_dir().join(format!("flox-debugger-{}-{n}.txt", std::process::id()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    if let Err(err) = file.write_all(content.as_bytes()) {
+        let _ = fs::remove_file(&path);
+        return Err(Error::new(err).context(format!("failed to write {}", path.display())));
+    }
+    Ok(path)
+}
+
+/// Returns whether `pager` runs `less`, which can be told where to start.
+fn is_less(pager: &str) -> bool {
+    pager
+        .split_whitespace()
+        .next()
+        .and_then(|program| Path::new(program).file_name())
+        .is_some_and(|name| name == "less")
+}
+
+/// Builds the command that runs `pager` on the file at `path`. The pager is
+/// run by `sh` so that it can come with arguments, as in `less -R`, while the
+/// path is passed on its own so that nothing in it is taken as shell syntax.
+///
+/// With a `line`, `less` starts there with the lines numbered. Other pagers
+/// don't agree on a way to do that, so they're only given the file.
+pub fn pager_invocation(pager: &str, path: &Path, line: Option<usize>) -> Command {
+    let script = match line {
+        Some(line) if is_less(pager) => format!("{pager} +{line} -N \"$1\""),
+        _ => format!("{pager} \"$1\""),
+    };
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(script).arg("sh").arg(path);
+    command
+}
+
+/// Runs `pager` on the file at `path`, see [`pager_invocation`], and waits
+/// for it to exit.
+fn run_pager(pager: &str, path: &Path, line: Option<usize>) -> Result<(), Error> {
+    let status = pager_invocation(pager, path, line)
+        .status()
+        .with_context(|| format!("failed to run {pager}"))?;
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(126 | 127) => bail!("failed to run {pager}"),
+        _ => bail!("{pager} failed ({status})"),
+    }
+}
+
+/// Shows `content` in `pager`, handing it the terminal until it exits.
+///
+/// The outer result is for the terminal, which the TUI can't go on without.
+/// The inner one is for the pager, which the user only needs to be told
+/// about. The content is written to a private temporary file that's removed
+/// afterwards either way, since it may hold a secret.
+pub fn page(events: &EventSource, pager: &str, content: &str) -> Result<Result<(), Error>, Error> {
+    let path = match write_private_file(content) {
+        Ok(path) => path,
+        Err(err) => return Ok(Err(err)),
+    };
+    let result = with_terminal_released(events, || run_pager(pager, &path, None));
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Shows the file at `path` in `pager` from `line`, handing it the terminal
+/// until it exits. The results are as for [`page`].
+pub fn page_file(
+    events: &EventSource,
+    pager: &str,
+    path: &Path,
+    line: usize,
+) -> Result<Result<(), Error>, Error> {
+    with_terminal_released(events, || run_pager(pager, path, Some(line)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn pager_defaults_to_less() {
+        assert_eq!(pager_command(None), DEFAULT_PAGER);
+        assert_eq!(pager_command(Some(" ")), DEFAULT_PAGER);
+        assert_eq!(pager_command(Some("most")), "most");
+    }
+
+    #[test]
+    fn paged_files_are_private() {
+        let path = write_private_file("SECRET=hunter2").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "SECRET=hunter2");
+        // Copying the file stands in for a pager, which reads it in place.
+        let copy = path.with_extension("copy");
+        run_pager(&format!("cp \"$1\" {}; :", copy.display()), &path, None).unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "SECRET=hunter2");
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&copy).unwrap();
+    }
+
+    #[test]
+    fn files_are_paged_from_a_line() {
+        let args = |pager, line| {
+            let path = Path::new("/tmp/my hooks/hook.sh");
+            let command = pager_invocation(pager, path, line);
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            args("less", Some(12)),
+            ["-c", "less +12 -N \"$1\"", "sh", "/tmp/my hooks/hook.sh"]
+        );
+        assert_eq!(
+            args("/usr/bin/less -R", Some(3))[1],
+            "/usr/bin/less -R +3 -N \"$1\""
+        );
+        assert_eq!(args("less", None)[1], "less \"$1\"");
+        assert_eq!(args("most", Some(12))[1], "most \"$1\"");
+        assert_eq!(args("lesspipe", Some(12))[1], "lesspipe \"$1\"");
+
+        // The path reaches the pager in one piece.
+        let dir = TestDir::new("page it");
+        let path = dir.join("hook file.sh");
+        let copy = dir.join("copy");
+        fs::write(&path, "echo hi\n").unwrap();
+        let pager = format!("cp \"$1\" '{}'; :", copy.display());
+        run_pager(&pager, &path, Some(1)).unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "echo hi\n");
+    }
+
+    #[test]
+    fn missing_pagers_are_reported() {
+        let path = write_private_file("").unwrap();
+        let err = run_pager("/nonexistent/pager", &path, None).unwrap_err();
+        assert_eq!(err.to_string(), "failed to run /nonexistent/pager");
+        fs::remove_file(&path).unwrap();
+    }
+}
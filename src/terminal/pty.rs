@@ -0,0 +1,53 @@
+//! Pseudoterminals for tests that need a real terminal, such as checking
+//! what the debugger leaves the terminal in after it exits.
+
+use std::{
+    fs::File,
+    io::Read,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+/// Opens a pseudoterminal, returning its (controller, terminal) ends.
+pub fn open_pty() -> (OwnedFd, OwnedFd) {
+    let (mut controller, mut terminal) = (-1, -1);
+    // SAFETY: The out pointers are valid and the optional arguments may be
+    // null.
+    let ret = unsafe {
+        libc::openpty(
+            &mut controller,
+            &mut terminal,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            std::ptr::null(),
+        )
+    };
+    assert_eq!(ret, 0, "openpty failed");
+    // SAFETY: `openpty` succeeded, so both are open descriptors that nothing
+    // else owns.
+    unsafe {
+        (
+            OwnedFd::from_raw_fd(controller),
+            OwnedFd::from_raw_fd(terminal),
+        )
+    }
+}
+
+/// Returns whether the terminal at `fd` is in cooked mode rather than raw.
+pub fn is_cooked(fd: &impl AsRawFd) -> bool {
+    // SAFETY: `termios` is plain data that `tcgetattr` fills in.
+    let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+    // SAFETY: The descriptor is open and `termios` is valid to write to.
+    assert_eq!(unsafe { libc::tcgetattr(fd.as_raw_fd(), &mut termios) }, 0);
+    let cooked = libc::ICANON | libc::ECHO;
+    termios.c_lflag & cooked == cooked
+}
+
+/// Returns everything written to the terminal end that hasn't been read from
+/// the controller yet, without waiting for more.
+pub fn read_available(controller: &OwnedFd) -> String {
+    // SAFETY: The descriptor is open.
+    unsafe { libc::fcntl(controller.as_raw_fd(), libc::F_SETFL, libc::O_NONBLOCK) };
+    let mut written = Vec::new();
+    let _ = File::from(controller.try_clone().unwrap()).read_to_end(&mut written);
+    String::from_utf8_lossy(&written).into_owned()
+}
@@ -0,0 +1,189 @@
+//! Scripted input for `--replay`, which drives the debugger without anyone
+//! at the keyboard.
+//!
+//! A script is a list of steps separated by whitespace, and lines starting
+//! with `#` are comments:
+//!
+//! - a key as it's written in the config file, such as `down`, `enter` or
+//!   `ctrl+r`
+//! - `char:X` for the single character `X`, such as `char:q` or `char:#`
+//! - `text:WORD` to type each character of `WORD` in turn, with `space` for
+//!   anything in between
+//! - `sleep:N` with a unit of `ms` or `s`, such as `sleep:200ms`, to wait
+//!   before the next step
+
+use std::{collections::VecDeque, fmt, path::Path, time::Duration};
+
+use anyhow::{Context, Error};
+use ratatui::crossterm::event::{Event as TermEvent, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    app::key_bindings::KeyCombo,
+    terminal::events::{EventSource, end_of_input},
+};
+
+/// One step of a replay script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayStep {
+    Key(KeyEvent),
+    Sleep(Duration),
+}
+
+/// A step in a script that couldn't be parsed, and the line it's on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Parses a replay script, see the [module docs](self) for the format.
+pub fn parse_replay(script: &str) -> Result<Vec<ReplayStep>, ReplayError> {
+    let mut steps = Vec::new();
+    for (idx, line) in script.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        for word in line.split_whitespace() {
+            parse_step(word, &mut steps).map_err(|message| ReplayError {
+                line: idx + 1,
+                message,
+            })?;
+        }
+    }
+    Ok(steps)
+}
+
+fn parse_step(word: &str, steps: &mut Vec<ReplayStep>) -> Result<(), String> {
+    let char_key = |c| ReplayStep::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    if let Some(rest) = word.strip_prefix("char:") {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => steps.push(char_key(c)),
+            _ => return Err(format!("'{word}' must be exactly one character")),
+        }
+    } else if let Some(text) = word.strip_prefix("text:") {
+        if text.is_empty() {
+            return Err("'text:' needs something to type".to_string());
+        }
+        steps.extend(text.chars().map(char_key));
+    } else if let Some(duration) = word.strip_prefix("sleep:") {
+        steps.push(ReplayStep::Sleep(parse_duration(duration).ok_or_else(
+            || format!("invalid duration in '{word}', expected e.g. 200ms or 2s"),
+        )?));
+    } else {
+        let key = word.parse::<KeyCombo>().map_err(|err| err.to_string())?;
+        steps.push(ReplayStep::Key(KeyEvent::new(key.code, key.modifiers)));
+    }
+    Ok(())
+}
+
+fn parse_duration(duration: &str) -> Option<Duration> {
+    if let Some(ms) = duration.strip_suffix("ms") {
+        ms.parse().ok().map(Duration::from_millis)
+    } else {
+        duration
+            .strip_suffix('s')?
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+}
+
+/// Reads and parses the replay script at `path`.
+pub fn load_replay(path: &Path) -> Result<Vec<ReplayStep>, Error> {
+    let script = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read replay script {}", path.display()))?;
+    parse_replay(&script).with_context(|| format!("invalid replay script {}", path.display()))
+}
+
+impl EventSource {
+    /// Feeds `steps` to the event loop instead of reading the terminal,
+    /// ending the input once they run out.
+    pub fn replay(steps: Vec<ReplayStep>) -> Self {
+        let mut steps = VecDeque::from(steps);
+        Self::spawn(move |_timeout| {
+            loop {
+                match steps.pop_front() {
+                    Some(ReplayStep::Key(key)) => return Ok(Some(TermEvent::Key(key))),
+                    Some(ReplayStep::Sleep(duration)) => std::thread::sleep(duration),
+                    None => return Err(end_of_input()),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::events::is_end_of_input;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> ReplayStep {
+        ReplayStep::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn scripts_parse_keys_text_and_sleeps() {
+        let script =
+            "# skip ahead\ndown Down tab\nchar:q char:# ctrl+r\ntext:a=b sleep:200ms sleep:1s\n";
+        assert_eq!(
+            parse_replay(script).unwrap(),
+            [
+                key(KeyCode::Down, KeyModifiers::NONE),
+                key(KeyCode::Down, KeyModifiers::NONE),
+                key(KeyCode::Tab, KeyModifiers::NONE),
+                key(KeyCode::Char('q'), KeyModifiers::NONE),
+                key(KeyCode::Char('#'), KeyModifiers::NONE),
+                key(KeyCode::Char('r'), KeyModifiers::CONTROL),
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Char('='), KeyModifiers::NONE),
+                key(KeyCode::Char('b'), KeyModifiers::NONE),
+                ReplayStep::Sleep(Duration::from_millis(200)),
+                ReplayStep::Sleep(Duration::from_secs(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn script_errors_name_the_line() {
+        let err = |script| parse_replay(script).unwrap_err().to_string();
+        assert_eq!(
+            err("down\n# typo ahead\ndwon"),
+            "line 3: unknown key 'dwon' in key 'dwon'"
+        );
+        assert_eq!(
+            err("char:ab"),
+            "line 1: 'char:ab' must be exactly one character"
+        );
+        assert_eq!(
+            err("enter\nsleep:soon"),
+            "line 2: invalid duration in 'sleep:soon', expected e.g. 200ms or 2s"
+        );
+        assert_eq!(err("text:"), "line 1: 'text:' needs something to type");
+    }
+
+    #[test]
+    fn replays_end_the_input_once_the_script_runs_out() {
+        let source = EventSource::replay(parse_replay("char:x sleep:1ms enter").unwrap());
+        let mut keys = Vec::new();
+        loop {
+            match source.receiver().recv().unwrap() {
+                Ok(TermEvent::Key(key)) => keys.push(key.code),
+                Ok(event) => panic!("unexpected {event:?}"),
+                Err(err) => {
+                    assert!(is_end_of_input(&err), "{err}");
+                    break;
+                }
+            }
+        }
+        assert_eq!(keys, [KeyCode::Char('x'), KeyCode::Enter]);
+    }
+}
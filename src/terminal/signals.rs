@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+use anyhow::{Error, bail};
+
+/// The last signal that asked the debugger to stop, or 0 if there wasn't one.
+static SHUTDOWN_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Whether SIGTSTP asked the debugger to suspend.
+static SUSPEND_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the debugger was continued after being stopped.
+static CONTINUED: AtomicBool = AtomicBool::new(false);
+
+/// The signals that the debugger handles itself rather than letting them
+/// kill or stop it with the terminal still in raw mode.
+///
+/// SIGINT and SIGTERM quit without changes, SIGTSTP suspends after handing
+/// back the terminal, and SIGCONT takes the terminal back.
+const HANDLED_SIGNALS: [libc::c_int; 4] =
+    [libc::SIGINT, libc::SIGTERM, libc::SIGTSTP, libc::SIGCONT];
+
+/// Only records the signal, since almost nothing else is safe to do in a
+/// signal handler. The event loop notices it on its next tick.
+extern "C" fn record_signal(signal: libc::c_int) {
+    match signal {
+        libc::SIGTSTP => SUSPEND_REQUESTED.store(true, Ordering::SeqCst),
+        libc::SIGCONT => CONTINUED.store(true, Ordering::SeqCst),
+        _ => SHUTDOWN_SIGNAL.store(signal, Ordering::SeqCst),
+    }
+}
+
+/// Makes the signals in [`HANDLED_SIGNALS`] notify the event loop instead of
+/// taking their default action.
+pub fn install_signal_handlers() -> Result<(), Error> {
+    for signal in HANDLED_SIGNALS {
+        // SAFETY: `sigaction` is plain data, and an empty mask with no flags
+        // is a valid configuration. The handler only touches an atomic,
+        // which is async-signal-safe.
+        let ret = unsafe {
+            let mut action = std::mem::zeroed::<libc::sigaction>();
+            action.sa_sigaction = record_signal as *const () as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, std::ptr::null_mut())
+        };
+        if ret != 0 {
+            bail!(
+                "failed to handle signal {signal}: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether SIGTSTP arrived since this was last called.
+pub fn take_suspend_request() -> bool {
+    SUSPEND_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Returns whether SIGCONT arrived since this was last called.
+pub fn take_continued() -> bool {
+    CONTINUED.swap(false, Ordering::SeqCst)
+}
+
+/// Returns the signal that asked the debugger to stop, if one arrived since
+/// this was last called.
+pub fn take_shutdown_signal() -> Option<i32> {
+    match SHUTDOWN_SIGNAL.swap(0, Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Error};
+use ratatui::crossterm::{
+    event::EnableMouseCapture,
+    execute,
+    terminal::{EnterAlternateScreen, enable_raw_mode},
+};
+
+use crate::terminal::{events::EventSource, guard::restore_terminal};
+
+/// Hands the terminal back to the shell while `run` runs, then takes it back
+/// for the TUI.
+///
+/// Input is paused first so that nothing is read from the terminal while
+/// something else has it. The caller should redraw everything afterwards,
+/// since whatever used the terminal will have drawn over the TUI.
+pub fn with_terminal_released<T>(
+    events: &EventSource,
+    run: impl FnOnce() -> T,
+) -> Result<T, Error> {
+    events.pause();
+    restore_terminal()?;
+    let result = run();
+    reenter_terminal()?;
+    events.resume();
+    Ok(result)
+}
+
+/// Puts the terminal back in the state the TUI needs after something else
+/// has had it.
+pub fn reenter_terminal() -> Result<(), Error> {
+    enable_raw_mode().context("failed to enable raw mode")?;
+    execute!(std::io::stderr(), EnterAlternateScreen, EnableMouseCapture)
+        .context("failed to enter alternate screen mode")
+}
+
+/// Stops the debugger the way Ctrl+Z would outside of raw mode, leaving the
+/// shell a usable terminal until the debugger is resumed with `fg`.
+pub fn suspend(events: &EventSource) -> Result<(), Error> {
+    with_terminal_released(events, || {
+        // SIGSTOP rather than SIGTSTP, since it can't be caught or ignored,
+        // so the debugger's own handler doesn't need to be uninstalled
+        // around it. This returns once the debugger is continued.
+        // SAFETY: Raising a signal has no memory safety requirements.
+        unsafe { libc::raise(libc::SIGSTOP) };
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    use super::*;
+    use crate::terminal::{
+        guard::TerminalGuard,
+        pty::{is_cooked, open_pty, read_available},
+    };
+
+    /// Set in the child process that the suspend test runs itself in.
+    const SUSPEND_CHILD_VAR: &str = "FLOX_DBG_TEST_SUSPEND_CHILD";
+
+    #[test]
+    fn suspending_releases_the_terminal_until_continued() {
+        if std::env::var_os(SUSPEND_CHILD_VAR).is_some() {
+            let _guard = TerminalGuard::enter().unwrap();
+            let events = EventSource::new();
+            // Anything printed on stdout while suspended would end up
+            // between these markers.
+            print!("<suspend>");
+            std::io::stdout().flush().unwrap();
+            suspend(&events).unwrap();
+            print!("</suspend>");
+            std::io::stdout().flush().unwrap();
+            assert!(!is_cooked(&std::io::stdin()), "not back in raw mode");
+            eprint!("resumed");
+            return;
+        }
+
+        let (controller, terminal) = open_pty();
+        let child = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "terminal::suspend::tests::suspending_releases_the_terminal_until_continued",
+                "--nocapture",
+            ])
+            .env(SUSPEND_CHILD_VAR, "1")
+            .stdin(File::from(terminal.try_clone().unwrap()))
+            .stderr(File::from(terminal.try_clone().unwrap()))
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let pid = child.id() as libc::pid_t;
+
+        let mut status = 0;
+        // SAFETY: `status` is valid to write to. Waiting for a stop doesn't
+        // reap the child, so it can still be waited on afterwards.
+        assert_eq!(
+            unsafe { libc::waitpid(pid, &mut status, libc::WUNTRACED) },
+            pid
+        );
+        assert!(libc::WIFSTOPPED(status), "child didn't stop: {status}");
+        assert!(is_cooked(&terminal), "still in raw mode while stopped");
+        let while_stopped = read_available(&controller);
+        let entered = while_stopped
+            .find("\x1b[?1049h")
+            .expect("entered alternate screen");
+        let left = while_stopped
+            .rfind("\x1b[?1049l")
+            .expect("left alternate screen");
+        assert!(entered < left, "{while_stopped:?}");
+
+        // SAFETY: The child hasn't been reaped, so `pid` is still its.
+        assert_eq!(unsafe { libc::kill(pid, libc::SIGCONT) }, 0);
+        let output = child.wait_with_output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{stdout}");
+        assert!(stdout.contains("<suspend></suspend>"), "{stdout}");
+        let after_continuing = read_available(&controller);
+        let reentered = after_continuing
+            .find("\x1b[?1049h")
+            .expect("entered alternate screen again");
+        let resumed = after_continuing.find("resumed").expect("resumed");
+        assert!(reentered < resumed, "{after_continuing:?}");
+    }
+}
@@ -0,0 +1,619 @@
+//! Snapshot tests of whole screens, rendered into a `TestBackend`.
+//!
+//! Each snapshot is the text of the rendered buffer, stored in
+//! `src/ui/snapshots`. Run the tests with `UPDATE_SNAPSHOTS=1` to write the
+//! snapshots after an intended change to the UI, then review the diff.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    Terminal,
+    backend::TestBackend,
+    buffer::{Buffer, Cell},
+    style::Color,
+};
+
+use crate::{
+    app::{
+        App, AppEvent, Event, NavEvent, Screen, Shell,
+        commands::TracepointDisposition,
+        handle_event,
+        modal::ModalEvent,
+        origins::parse_var_origins,
+        output::OutputEvent,
+        theme::{ThemeChoice, ThemePreset},
+        trace::STACK_VERSION_HEADER,
+        vars::VarsEvent,
+    },
+    cli::{Cli, test_args},
+    terminal::TerminalInfo,
+    ui::{draw_ui, text::display_width},
+};
+
+/// The sizes every screen is snapshotted at: the smallest common terminal
+/// and a roomier one.
+const SIZES: [(u16, u16); 2] = [(80, 24), (120, 40)];
+
+/// A call stack whose files don't exist, so the snapshots don't depend on
+/// anything outside the repository.
+const CALL_STACK: &str =
+    "/nonexistent/hooks.sh:14:fdb_tracepoint\n/nonexistent/activate.sh:24:run_hook\n";
+
+fn args() -> Cli {
+    Cli {
+        tracepoint: Some("on-activate".to_string()),
+        no_color: true,
+        theme: Some(ThemeChoice::Preset(ThemePreset::Default)),
+        ..test_args()
+    }
+}
+
+/// Returns an app showing `screen` with a small, fixed environment.
+fn app(args: &Cli, screen: Screen) -> App {
+    let env = [
+        ("EDITOR", "vim"),
+        ("HOME", "/home/user"),
+        ("LANG", "C.UTF-8"),
+        ("PATH", "/home/user/.local/bin:/usr/local/bin:/usr/bin:/bin"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect::<HashMap<_, _>>();
+    // Starting the clock after the snapshots are drawn keeps it at 00:00.
+    App::new(args, &TerminalInfo::default())
+        .unwrap()
+        .with_started(Instant::now() + Duration::from_secs(3600))
+        .with_env(&env)
+        .with_shell(Shell::Bash)
+        .with_screen(screen)
+}
+
+/// Feeds `events` through the app as if they came from key presses.
+fn feed(app: &mut App, events: &[Event]) {
+    for event in events {
+        handle_event(app, event);
+    }
+}
+
+fn render(app: &mut App, width: u16, height: u16) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| draw_ui(app, frame)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+/// Returns the text of `buffer`, one line per row without trailing spaces.
+///
+/// The cells covered by a wide character are skipped, so the text lines up
+/// the way it does on screen.
+fn buffer_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut row = String::new();
+        let mut x = area.left();
+        while x < area.right() {
+            let symbol = buffer[(x, y)].symbol();
+            row.push_str(symbol);
+            x += display_width(symbol).max(1) as u16;
+        }
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Returns the text of `buffer` like [`buffer_text`], with each run of
+/// styled cells quoted and followed by its style in braces, such as
+/// `«unset BAZ»{Red}`.
+fn buffer_styled_text(buffer: &Buffer) -> String {
+    let label = |cell: &Cell| {
+        let mut parts = Vec::new();
+        for color in [cell.fg, cell.bg] {
+            if color != Color::Reset {
+                parts.push(format!("{color:?}"));
+            }
+        }
+        if !cell.modifier.is_empty() {
+            parts.push(format!("{:?}", cell.modifier));
+        }
+        parts.join(" ")
+    };
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut row = String::new();
+        let mut run = String::new();
+        let mut run_label = String::new();
+        let mut x = area.left();
+        while x < area.right() {
+            let cell = &buffer[(x, y)];
+            let cell_label = label(cell);
+            if cell_label != run_label {
+                push_run(&mut row, &run, &run_label);
+                run.clear();
+                run_label = cell_label;
+            }
+            run.push_str(cell.symbol());
+            x += display_width(cell.symbol()).max(1) as u16;
+        }
+        push_run(&mut row, &run, &run_label);
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+fn push_run(row: &mut String, run: &str, label: &str) {
+    if label.is_empty() {
+        row.push_str(run);
+    } else {
+        row.push_str(&format!("«{run}»{{{label}}}"));
+    }
+}
+
+/// Compares `actual` to the snapshot called `name`, or updates the snapshot
+/// when `UPDATE_SNAPSHOTS` is set.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/ui/snapshots")
+        .join(format!("{name}.txt"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "no snapshot at {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "{name} doesn't match its snapshot, run with UPDATE_SNAPSHOTS=1 to update it\n\
+         expected:\n{expected}\nactual:\n{actual}"
+    );
+}
+
+/// Renders `app` at every size in [`SIZES`], comparing each to its snapshot.
+fn assert_screen_snapshots(name: &str, app: &mut App) {
+    for (width, height) in SIZES {
+        let text = buffer_text(&render(app, width, height));
+        assert_snapshot(&format!("{name}_{width}x{height}"), &text);
+    }
+}
+
+/// Like [`assert_screen_snapshots`], with the styles in the snapshots too.
+fn assert_styled_snapshots(name: &str, app: &mut App) {
+    for (width, height) in SIZES {
+        let text = buffer_styled_text(&render(app, width, height));
+        assert_snapshot(&format!("{name}_{width}x{height}"), &text);
+    }
+}
+
+#[test]
+fn home_screen() {
+    let mut app = app(&args(), Screen::Home);
+    assert_screen_snapshots("home", &mut app);
+}
+
+#[test]
+fn header_session_info() {
+    let long = Cli {
+        tracepoint: Some("after-the-profile-scripts-have-all-run".to_string()),
+        ..args()
+    };
+    let none = Cli {
+        tracepoint: None,
+        ..args()
+    };
+    for (name, args) in [
+        ("header_long_tracepoint", long),
+        ("header_no_tracepoint", none),
+    ] {
+        let mut app = app(&args, Screen::Home);
+        for (width, height) in [(60, 16), (80, 24)] {
+            let text = buffer_text(&render(&mut app, width, height));
+            let header = text.lines().take(4).collect::<Vec<_>>().join("\n");
+            assert_snapshot(&format!("{name}_{width}x{height}"), &header);
+        }
+    }
+}
+
+#[test]
+fn vars_screen_raw() {
+    let mut app = app(&args(), Screen::Vars);
+    feed(&mut app, &[Event::Nav(NavEvent::Down)]);
+    assert_screen_snapshots("vars_raw", &mut app);
+}
+
+#[test]
+fn vars_screen_reload_conflict() {
+    let mut app = app(&args(), Screen::Vars);
+    app.env_mut()
+        .set_conflicts(BTreeSet::from(["EDITOR".to_string()]));
+    assert_screen_snapshots("vars_reload_conflict", &mut app);
+}
+
+#[test]
+fn watched_variables() {
+    let mut app = app(&args(), Screen::Home);
+    for name in ["EDITOR", "PATH", "PAGER"] {
+        app.watch_var(name);
+    }
+    let env = app
+        .env()
+        .vars()
+        .iter()
+        .zip(app.env().values())
+        .map(|(name, value)| {
+            let value = if name == "EDITOR" { "nano" } else { value };
+            (name.clone(), value.to_string())
+        })
+        .collect::<HashMap<_, _>>();
+    app.env_mut().reload(&env);
+    app.refresh_watched();
+    assert_screen_snapshots("home_watched", &mut app);
+    app.set_screen(Screen::Vars);
+    assert_screen_snapshots("vars_watched", &mut app);
+}
+
+#[test]
+fn vars_screen_split() {
+    let mut app = app(&args(), Screen::Vars);
+    // PATH is the last variable.
+    feed(
+        &mut app,
+        &[
+            Event::Nav(NavEvent::Bottom),
+            Event::Vars(VarsEvent::SplitDetail),
+        ],
+    );
+    assert_screen_snapshots("vars_split", &mut app);
+}
+
+#[test]
+fn vars_screen_stripes() {
+    // The dark theme doesn't stripe lists, to keep its contrast.
+    for (name, preset) in [
+        ("vars_striped", ThemePreset::Default),
+        ("vars_unstriped", ThemePreset::Dark),
+    ] {
+        let args = Cli {
+            no_color: false,
+            theme: Some(ThemeChoice::Preset(preset)),
+            ..args()
+        };
+        let mut app = app(&args, Screen::Vars);
+        feed(
+            &mut app,
+            &[
+                Event::Nav(NavEvent::Bottom),
+                Event::Vars(VarsEvent::SplitDetail),
+            ],
+        );
+        assert_styled_snapshots(name, &mut app);
+    }
+}
+
+#[test]
+fn vars_screen_wide_characters() {
+    let env = [
+        ("GREETING_WITH_A_VERY_LONG_NAME_日本語", "こんにちは"),
+        (
+            "PATH",
+            "/opt/日本語/bin:/home/user/🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀🦀/bin:/usr/bin",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect::<HashMap<_, _>>();
+    let mut app = app(&args(), Screen::Vars).with_env(&env);
+    feed(
+        &mut app,
+        &[
+            Event::Nav(NavEvent::Bottom),
+            Event::Vars(VarsEvent::SplitDetail),
+            Event::Nav(NavEvent::Right),
+            Event::Nav(NavEvent::Down),
+        ],
+    );
+    assert_screen_snapshots("vars_wide_characters", &mut app);
+}
+
+#[test]
+fn vars_screen_tree() {
+    let env = [
+        "EDITOR", "LC_ALL", "LC_CTYPE", "LC_TIME", "PATH", "XDG_A", "XDG_B", "XDG_C",
+    ]
+    .into_iter()
+    .map(|name| (name.to_string(), format!("value of {name}")))
+    .collect::<HashMap<_, _>>();
+    let mut app = app(&args(), Screen::Vars).with_env(&env);
+    feed(
+        &mut app,
+        &[
+            Event::Nav(NavEvent::Down),
+            Event::Vars(VarsEvent::ToggleTree),
+            Event::Nav(NavEvent::Down),
+        ],
+    );
+    assert_screen_snapshots("vars_tree", &mut app);
+}
+
+#[test]
+fn vars_screen_origins() {
+    let origins = parse_var_origins("EDITOR=manifest\nHOME=hook\n", false).unwrap();
+    let mut app = app(&args(), Screen::Vars).with_var_origins(origins);
+    assert_screen_snapshots("vars_origins", &mut app);
+    feed(&mut app, &[Event::Vars(VarsEvent::CycleOrigin)]);
+    assert_screen_snapshots("vars_origins_filtered", &mut app);
+}
+
+#[test]
+fn vars_screen_command_preview() {
+    let env = [
+        ("GITHUB_TOKEN", "ghp_not_a_real_token"),
+        ("GREETING", "héllo 🦀 日本語"),
+        ("MOTD", "first line\nsecond line"),
+        ("QUOTED", "it's \"quoted\" $HOME `pwd`"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect::<HashMap<_, _>>();
+    let mut app = app(&args(), Screen::Vars).with_env(&env);
+    feed(&mut app, &[Event::Vars(VarsEvent::ToggleCommandPreview)]);
+    assert_screen_snapshots("vars_command_hidden", &mut app);
+    feed(&mut app, &[Event::Vars(VarsEvent::RevealValue)]);
+    assert_screen_snapshots("vars_command_revealed", &mut app);
+    for name in ["unicode", "newlines", "quotes"] {
+        feed(&mut app, &[Event::Nav(NavEvent::Down)]);
+        assert_screen_snapshots(&format!("vars_command_{name}"), &mut app);
+    }
+}
+
+/// How long drawing the vars screen, and loading the environment for it, may
+/// take with a large environment. The budgets are generous for unoptimized
+/// builds on slow machines, and are only meant to catch work that grows with
+/// the size of the environment.
+const LARGE_ENV_FRAME_BUDGET: Duration = Duration::from_millis(100);
+const LARGE_ENV_STARTUP_BUDGET: Duration = Duration::from_secs(1);
+
+#[test]
+fn vars_screen_large_environment() {
+    // 10,000 variables holding about 10 MB of values, with a PATH-like
+    // variable selected that splits into 10,000 entries.
+    let mut env = (0..10_000)
+        .map(|i| (format!("VAR_{i:05}"), format!("{i}-").repeat(250)))
+        .collect::<HashMap<_, _>>();
+    let path = (0..10_000)
+        .map(|i| format!("/nix/store/{i:032}/bin"))
+        .collect::<Vec<_>>()
+        .join(":");
+    env.insert("VAR_05000".to_string(), path);
+    let start = Instant::now();
+    let mut app = app(&args(), Screen::Vars).with_env(&env);
+    let startup = start.elapsed();
+
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    let mut slowest = Duration::ZERO;
+    for events in [
+        vec![Event::Nav(NavEvent::Down)],
+        vec![Event::Nav(NavEvent::Bottom)],
+        // Selects VAR_05000 in the middle of the list.
+        vec![Event::Nav(NavEvent::Top)],
+        vec![Event::Nav(NavEvent::Down); 5000],
+        vec![Event::Vars(VarsEvent::SplitDetail)],
+        vec![Event::Nav(NavEvent::Right), Event::Nav(NavEvent::Bottom)],
+    ] {
+        feed(&mut app, &events);
+        let start = Instant::now();
+        terminal.draw(|frame| draw_ui(&mut app, frame)).unwrap();
+        slowest = slowest.max(start.elapsed());
+    }
+    let text = buffer_text(terminal.backend().buffer());
+    assert!(text.contains("VAR_05000"), "{text}");
+    assert!(
+        text.contains(&format!("/nix/store/{:032}/bin", 9_999)),
+        "{text}"
+    );
+    assert!(
+        slowest < LARGE_ENV_FRAME_BUDGET,
+        "slowest frame took {slowest:?}"
+    );
+    assert!(
+        startup < LARGE_ENV_STARTUP_BUDGET,
+        "loading the environment took {startup:?}"
+    );
+}
+
+/// How long a frame showing a value of several megabytes may take. Only
+/// what's on screen should be wrapped, so this is as generous as the large
+/// environment's budget and still far below what wrapping all of it takes.
+const HUGE_VALUE_FRAME_BUDGET: Duration = LARGE_ENV_FRAME_BUDGET;
+
+#[test]
+fn vars_screen_huge_value() {
+    // Over 5 MB of numbered lines with wide characters along the way.
+    let value = (0..100_000)
+        .map(|i| format!("line {i:06} of a file that ended up in a variable, 日本語 🦀\n"))
+        .collect::<String>();
+    assert!(value.len() > 5_000_000);
+    let env = HashMap::from([("HUGE".to_string(), value)]);
+    let mut app = app(&args(), Screen::Vars).with_env(&env);
+    feed(&mut app, &[Event::Nav(NavEvent::Right)]);
+
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    let mut slowest = Duration::ZERO;
+    for events in [
+        vec![],
+        vec![Event::Nav(NavEvent::Down); 3],
+        vec![Event::Nav(NavEvent::Up)],
+        vec![Event::Nav(NavEvent::Bottom)],
+    ] {
+        feed(&mut app, &events);
+        let start = Instant::now();
+        terminal.draw(|frame| draw_ui(&mut app, frame)).unwrap();
+        slowest = slowest.max(start.elapsed());
+    }
+    assert!(
+        slowest < HUGE_VALUE_FRAME_BUDGET,
+        "slowest frame took {slowest:?}"
+    );
+    let text = buffer_text(terminal.backend().buffer());
+    assert!(text.contains("line 099999 of a file"), "{text}");
+    feed(
+        &mut app,
+        &[Event::Nav(NavEvent::Top), Event::Nav(NavEvent::Down)],
+    );
+    assert_screen_snapshots("vars_huge_value", &mut app);
+}
+
+#[test]
+fn trace_screen_without_call_stack() {
+    let mut app = app(&args(), Screen::Trace);
+    assert_screen_snapshots("trace_no_call_stack", &mut app);
+}
+
+#[test]
+fn trace_screen_with_unsupported_call_stack() {
+    let args = Cli {
+        call_stack: Some(format!("{STACK_VERSION_HEADER}9\n{CALL_STACK}")),
+        ..args()
+    };
+    let mut app = app(&args, Screen::Trace);
+    assert_screen_snapshots("trace_unsupported_call_stack", &mut app);
+}
+
+#[test]
+fn trace_screen_with_call_stack() {
+    let args = Cli {
+        call_stack: Some(CALL_STACK.to_string()),
+        ..args()
+    };
+    let mut app = app(&args, Screen::Trace);
+    feed(&mut app, &[Event::Nav(NavEvent::Down)]);
+    assert_screen_snapshots("trace_call_stack", &mut app);
+}
+
+#[test]
+fn trace_screen_with_sourced_file() {
+    let args = Cli {
+        call_stack: Some(format!(
+            "{STACK_VERSION_HEADER}3\n/nonexistent/lib.sh\t2\tsetup\tfunc\n/nonexistent/activate.sh\t24\t/nonexistent/lib.sh\tsource\n"
+        )),
+        ..args()
+    };
+    let mut app = app(&args, Screen::Trace);
+    feed(&mut app, &[Event::Nav(NavEvent::Down)]);
+    assert_screen_snapshots("trace_sourced_file", &mut app);
+}
+
+#[test]
+fn output_screen() {
+    let mut app = app(&args(), Screen::Output);
+    app.push_output_line("export FOO='bar'");
+    app.push_output_line("unset BAZ");
+    assert_screen_snapshots("output", &mut app);
+}
+
+#[test]
+fn output_function() {
+    let mut app = app(&args(), Screen::Output);
+    app.queue_function("up", "cd ..; ls -l; echo 'a; b'")
+        .unwrap();
+    app.push_output_line("unset BAZ");
+    feed(&mut app, &[Event::Nav(NavEvent::Down)]);
+    assert_screen_snapshots("output_function", &mut app);
+}
+
+#[test]
+fn output_long_lines() {
+    let mut app = app(&args(), Screen::Output);
+    let path = (0..12)
+        .map(|idx| format!("/nix/store/{idx:02}-profile/bin"))
+        .collect::<Vec<_>>()
+        .join(":");
+    app.push_output_line(&format!("export PATH='{path}'"));
+    app.push_output_line("export GREETING='こんにちは世界'");
+    feed(
+        &mut app,
+        &[
+            Event::Nav(NavEvent::Down),
+            Event::Nav(NavEvent::Right),
+            Event::Nav(NavEvent::Right),
+        ],
+    );
+    assert_screen_snapshots("output_scrolled", &mut app);
+    feed(&mut app, &[Event::Output(OutputEvent::ToggleWrap)]);
+    assert_screen_snapshots("output_wrapped", &mut app);
+}
+
+#[test]
+fn output_colors() {
+    for (name, no_color) in [("output_colors", false), ("output_colors_no_color", true)] {
+        let mut app = app(&Cli { no_color, ..args() }, Screen::Output);
+        app.queue_set_var("FOO", "bar").unwrap();
+        app.queue_unset_var("BAZ").unwrap();
+        app.queue_raw("echo hi");
+        app.queue_alias("ll", "ls -l").unwrap();
+        app.set_tracepoint_disposition(TracepointDisposition::PauseAtAll);
+        assert_styled_snapshots(name, &mut app);
+    }
+}
+
+#[test]
+fn exit_modal() {
+    let mut app = app(&args(), Screen::Vars);
+    feed(
+        &mut app,
+        &[
+            Event::App(AppEvent::ExitRequested),
+            Event::Modal(ModalEvent::Previous),
+        ],
+    );
+    assert_screen_snapshots("exit_modal", &mut app);
+}
+
+#[test]
+fn exit_modal_with_changes() {
+    let mut app = app(&args(), Screen::Output);
+    app.push_output_line("export FOO='bar'");
+    app.push_output_line("unset BAZ");
+    feed(&mut app, &[Event::App(AppEvent::ExitRequested)]);
+    assert_screen_snapshots("exit_modal_with_changes", &mut app);
+}
+
+/// The exit modal at the full size, the minimum size, and under it, where
+/// only a text confirmation is left.
+#[test]
+fn exit_modal_sizes() {
+    let mut app = app(&args(), Screen::Output);
+    for i in 0..12 {
+        app.push_output_line(&format!("export VAR_{i}='a value that is {i} long'"));
+    }
+    feed(&mut app, &[Event::App(AppEvent::ExitRequested)]);
+    for (width, height) in [(80, 24), (60, 16), (40, 12)] {
+        let text = buffer_text(&render(&mut app, width, height));
+        assert_snapshot(&format!("exit_modal_sized_{width}x{height}"), &text);
+    }
+}
+
+#[test]
+fn confirm_modal() {
+    let mut app = app(&args(), Screen::Output);
+    app.push_output_line("export FOO='bar'");
+    feed(&mut app, &[Event::Output(OutputEvent::Clear)]);
+    assert_screen_snapshots("confirm_modal", &mut app);
+}
+
+#[test]
+fn protected_var_modal() {
+    let mut app = app(&args(), Screen::Vars);
+    app.queue_unset_var("HOME").unwrap();
+    assert_screen_snapshots("protected_var_modal", &mut app);
+}
@@ -0,0 +1,125 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Styled,
+    text::{Line, Span},
+    widgets::{Block, Clear, List, Paragraph},
+};
+
+use crate::app::{
+    App,
+    help::Overlay,
+    key_bindings::{BindingScope, DisplayKeyCombo},
+};
+
+pub fn render_overlay(app: &mut App, frame: &mut Frame) {
+    let area = popup_area(frame.area());
+    frame.render_widget(Clear, area);
+    match app.overlay() {
+        Some(Overlay::Help) => render_help(app, frame, area),
+        Some(Overlay::Bindings(_)) => render_bindings_editor(app, frame, area),
+        None => {}
+    }
+}
+
+/// Returns a centered area for the overlay that leaves a bit of the screen
+/// behind it visible.
+fn popup_area(area: Rect) -> Rect {
+    let [vertical_area] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup_area] = Layout::horizontal([Constraint::Percentage(70)])
+        .flex(Flex::Center)
+        .areas(vertical_area);
+    popup_area
+}
+
+fn render_help(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme();
+    let screen = app.screen();
+    let mut lines = app
+        .key_bindings()
+        .entries()
+        .into_iter()
+        .filter(|entry| match &entry.scope {
+            BindingScope::Global => true,
+            BindingScope::Screen(s) => *s == screen,
+            BindingScope::PromptInput => false,
+        })
+        .map(|entry| {
+            Line::from(vec![
+                format!("{:>12}", entry.key.display_key_combo()).set_style(theme.flox_purple),
+                "  ".into(),
+                entry.description.set_style(theme.fg),
+            ])
+        })
+        .collect::<Vec<_>>();
+    lines.extend(app.key_bindings().macros().iter().map(|macro_def| {
+        Line::from(vec![
+            format!("{:>12}", macro_def.key.display_key_combo()).set_style(theme.flox_purple),
+            "  ".into(),
+            format!("Macro: {}", macro_def.name).set_style(theme.fg),
+        ])
+    }));
+    lines.push(Line::default());
+    lines.push(Line::from(vec![
+        "b".set_style(theme.flox_purple),
+        ": Edit bindings  ".set_style(theme.fg_dim),
+        "Esc".set_style(theme.flox_purple),
+        ": Close".set_style(theme.fg_dim),
+    ]));
+    let help = Paragraph::new(lines).block(
+        Block::bordered()
+            .border_style(theme.border)
+            .title(format!(" Help: {screen} ")),
+    );
+    frame.render_widget(help, area);
+}
+
+fn render_bindings_editor(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme().clone();
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Key Bindings ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [list_area, status_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+    let items = app
+        .key_bindings()
+        .entries()
+        .into_iter()
+        .map(|entry| {
+            Line::from(vec![
+                format!("{:<8}", entry.section).set_style(theme.fg_dim),
+                format!("{:<24}", entry.description).set_style(theme.fg),
+                entry.key.to_string().set_style(theme.flox_purple),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let list = List::new(items).highlight_style(theme.highlighted_text);
+
+    let Some(Overlay::Bindings(editor)) = app.overlay_mut() else {
+        return;
+    };
+    let status: Vec<Span> = if editor.capturing() {
+        vec!["Press a key to bind… (Esc to cancel)".set_style(theme.flox_purple)]
+    } else if let Some(message) = editor.message() {
+        match message {
+            Ok(message) => vec![message.clone().set_style(theme.success)],
+            Err(message) => vec![message.clone().set_style(theme.error)],
+        }
+    } else {
+        vec![
+            "Enter".set_style(theme.flox_purple),
+            ": Rebind  ".set_style(theme.fg_dim),
+            "x".set_style(theme.flox_purple),
+            ": Save to config  ".set_style(theme.fg_dim),
+            "Esc".set_style(theme.flox_purple),
+            ": Close".set_style(theme.fg_dim),
+        ]
+    };
+    frame.render_widget(Line::from(status), status_area);
+    frame.render_stateful_widget(list, list_area, editor.list_state());
+}
@@ -1,34 +1,78 @@
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Layout, Rect},
-    style::{Styled, Stylize},
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
+    style::{Style, Styled, Stylize},
     symbols,
     text::{Line, Text},
-    widgets::{Block, Paragraph, Wrap},
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
 };
 use tui_big_text::{BigTextBuilder, PixelSize};
 
-use crate::app::App;
+use crate::{
+    app::{App, home::HomeAction, watch::shown_value},
+    ui::{
+        WATCH_MARKER, focusable_block, row_style,
+        text::{display_width, truncate_to_width},
+    },
+};
 
-pub fn render_home_screen(app: &App, frame: &mut Frame, area: Rect) {
-    let theme = app.theme();
-    frame.render_widget(Block::bordered(), area);
-    let [_blank, splash_area, info_area, description_area] = Layout::vertical([
-        Constraint::Length(1),
-        Constraint::Length(7),
-        Constraint::Length(5),
+/// The height of the big "flox-debugger" text, with a blank line above it.
+const SPLASH_HEIGHT: u16 = 8;
+/// The height of the short description below the splash.
+const INFO_HEIGHT: u16 = 5;
+/// The most watched variables listed before the rest are left out.
+const MAX_WATCHED_ROWS: usize = 6;
+
+pub fn render_home_screen(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme().clone();
+    frame.render_widget(Block::bordered().border_style(theme.border), area);
+    // The menu is worth more than the watched variables, which are worth
+    // more than the splash, so each is cut back in that order when there
+    // isn't room for everything.
+    let menu_height = HomeAction::ALL.len() as u16 + 2;
+    let inner_height = area.height.saturating_sub(2);
+    let watched_rows = app
+        .watch_list()
+        .len()
+        .min(MAX_WATCHED_ROWS)
+        .min(inner_height.saturating_sub(INFO_HEIGHT + menu_height + 2) as usize);
+    let watched_height = match watched_rows {
+        0 => 0,
+        rows => rows as u16 + 2,
+    };
+    let splash_height =
+        if inner_height >= SPLASH_HEIGHT + INFO_HEIGHT + menu_height + watched_height {
+            SPLASH_HEIGHT
+        } else {
+            0
+        };
+    let [
+        splash_area,
+        info_area,
+        menu_area,
+        watched_area,
+        description_area,
+    ] = Layout::vertical([
+        Constraint::Length(splash_height),
+        Constraint::Length(INFO_HEIGHT),
+        Constraint::Length(menu_height),
+        Constraint::Length(watched_height),
         Constraint::Percentage(100),
     ])
     .margin(1)
     .areas(area);
 
     // Renders the big "flox-debugger" pixel text
-    let splash_text = BigTextBuilder::default()
-        .pixel_size(PixelSize::Sextant)
-        .lines(["flox-".into(), "debugger".into()])
-        .alignment(Alignment::Center)
-        .build();
-    frame.render_widget(splash_text, splash_area);
+    if splash_height > 0 {
+        let [_blank, splash_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(splash_area);
+        let splash_text = BigTextBuilder::default()
+            .pixel_size(PixelSize::Sextant)
+            .lines(["flox-".into(), "debugger".into()])
+            .alignment(Alignment::Center)
+            .build();
+        frame.render_widget(splash_text, splash_area);
+    }
 
     // Renders the short description below the "flox-debugger" text
     let info_text = Text::from(vec![
@@ -46,11 +90,60 @@ pub fn render_home_screen(app: &App, frame: &mut Frame, area: Rect) {
     ]);
     frame.render_widget(Paragraph::new(info_text).centered(), info_area);
 
-    // Renders the description on the home page.
+    // Renders the menu of things to do next, which is the only part of the
+    // screen that takes input.
+    let menu_width = HomeAction::ALL
+        .iter()
+        .map(|action| display_width(action.label()))
+        .max()
+        .unwrap_or_default() as u16
+        + 6;
+    let [menu_area] = Layout::horizontal([Constraint::Length(menu_width)])
+        .flex(Flex::Center)
+        .areas(menu_area);
+    let items = HomeAction::ALL.iter().enumerate().map(|(idx, action)| {
+        ListItem::new(format!(" {}", action.label())).style(row_style(&theme, idx))
+    });
+    let menu = List::new(items)
+        .block(focusable_block(" Actions ", true, &theme))
+        .highlight_style(theme.highlighted_text);
+    frame.render_stateful_widget(menu, menu_area, app.home_mut().list_state());
+
+    if watched_height > 0 {
+        render_watched(app, frame, watched_area, watched_rows);
+    }
+
+    // Renders the description on the home page, after any warnings from
+    // startup.
     let [prose_area] = Layout::vertical([Constraint::Percentage(100)])
         .margin(2)
         .areas(description_area);
-    let description = Paragraph::new(Text::from(vec![
+    let mut lines = app
+        .warnings()
+        .iter()
+        .map(|warning| Line::from(format!("Warning: {warning}").set_style(theme.warning)))
+        .collect::<Vec<_>>();
+    if !lines.is_empty() {
+        lines.push(Line::default());
+    }
+    let known_tracepoints = app.trace().known_tracepoints();
+    if !known_tracepoints.is_empty() {
+        let current = app.trace().tracepoint();
+        let mut line = vec!["Tracepoints: ".set_style(theme.fg_dim)];
+        for (i, name) in known_tracepoints.iter().enumerate() {
+            if i > 0 {
+                line.push(", ".set_style(theme.fg_dim));
+            }
+            if Some(name) == current {
+                line.push(name.as_str().set_style(theme.highlighted_text));
+            } else {
+                line.push(name.as_str().into());
+            }
+        }
+        lines.push(Line::from(line));
+        lines.push(Line::default());
+    }
+    lines.extend([
         Line::from(vec![
             "This debugger allows you to pause the activation of an environment, ".into(),
             "inspect its state, *modify* its state, and determine whether/where to ".into(),
@@ -63,8 +156,60 @@ pub fn render_home_screen(app: &App, frame: &mut Frame, area: Rect) {
         Line::from("- Vars: inspect and modify environment variables"),
         Line::from("- Trace: see a stack trace of shell execution"),
         Line::from("- Output: see the commands that will be sourced when the debugger exits"),
-    ]))
-    .wrap(Wrap::default())
-    .left_aligned();
+    ]);
+    let description = Paragraph::new(Text::from(lines))
+        .wrap(Wrap::default())
+        .left_aligned();
     frame.render_widget(description, prose_area);
 }
+
+/// Renders the watched variables with their values, marking the ones that
+/// changed when the environment was last reloaded. Only the first `rows`
+/// are listed, and the title says how many were left out.
+fn render_watched(app: &App, frame: &mut Frame, area: Rect, rows: usize) {
+    let theme = app.theme();
+    let [area] = Layout::horizontal([Constraint::Percentage(100)])
+        .horizontal_margin(1)
+        .areas(area);
+    let watch_list = app.watch_list();
+    let shown = watch_list.iter().take(rows).collect::<Vec<_>>();
+    let name_width = shown
+        .iter()
+        .map(|watched| display_width(&watched.name))
+        .max()
+        .unwrap_or_default();
+    // The border and the columns before the value.
+    let value_width = (area.width as usize).saturating_sub(2 + WATCH_MARKER.len() + name_width + 2);
+    let lines = shown
+        .iter()
+        .map(|watched| {
+            let (marker, name_style) = if watched.changed {
+                (WATCH_MARKER, theme.warning)
+            } else {
+                ("  ", Style::default())
+            };
+            let padding = " ".repeat(name_width - display_width(&watched.name) + 2);
+            let value = shown_value(app.env(), &watched.name);
+            let value_style = if app.env().value_of(&watched.name).is_none() {
+                theme.fg_dim
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                marker.set_style(theme.warning),
+                watched.name.as_str().set_style(name_style),
+                padding.into(),
+                truncate_to_width(&value, value_width)
+                    .into_owned()
+                    .set_style(value_style),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let title = if watch_list.len() > shown.len() {
+        format!(" Watched ({} of {}) ", shown.len(), watch_list.len())
+    } else {
+        " Watched ".to_string()
+    };
+    let panel = Paragraph::new(lines).block(focusable_block(title, false, theme));
+    frame.render_widget(panel, area);
+}
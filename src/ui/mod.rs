@@ -1,51 +1,227 @@
-mod home;
-mod output;
-mod trace;
-mod vars;
+#[cfg(test)]
+mod harness;
+mod help;
+pub(crate) mod home;
+pub(crate) mod output;
+pub(crate) mod prompt;
+pub(crate) mod text;
+pub(crate) mod trace;
+pub(crate) mod vars;
+
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
-    style::{Styled, Stylize},
-    text::{Line, Span},
-    widgets::{Block, Clear, Tabs},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Rect},
+    style::{Style, Styled, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Clear, ListState, Padding, Paragraph, Tabs},
 };
 
 use crate::{
-    app::{App, ExitOption, ExitState, Screen, key_bindings::DisplayKeyBindings},
-    ui::{
-        home::render_home_screen, output::render_output_screen, trace::render_trace_screen,
-        vars::render_vars_screen,
+    app::{
+        App, Screen,
+        countdown::format_clock,
+        key_bindings::{DisplayKeyCombo, display_key_sequence},
+        messages::Severity,
+        modal::Modal,
+        theme::Theme,
+        watch::shown_value,
     },
+    ui::help::render_overlay,
 };
 
+/// Marks a watched variable whose value changed when the environment was
+/// last reloaded.
+const WATCH_MARKER: &str = "~ ";
+
+/// Returns a bordered pane, styled to show whether it has focus.
+///
+/// Every pane that can take focus is drawn through this so that focus looks
+/// the same on every screen.
+fn focusable_block<'a>(title: impl Into<Line<'a>>, focused: bool, theme: &Theme) -> Block<'a> {
+    let mut title = title.into();
+    if !focused {
+        return Block::bordered().border_style(theme.border).title(title);
+    }
+    if let Some(marker) = theme.focus_marker {
+        title
+            .spans
+            .insert(0, marker.set_style(theme.border_focused));
+    }
+    Block::bordered()
+        .border_style(theme.border_focused)
+        .title(title)
+}
+
+/// Returns the style of the row at `idx` of a list, which is striped every
+/// other row. `idx` counts from the top of the whole list rather than the
+/// rows on screen, so the stripes don't shift as the list scrolls.
+fn row_style(theme: &Theme, idx: usize) -> Style {
+    if idx % 2 == 1 {
+        theme.stripe
+    } else {
+        Style::default()
+    }
+}
+
+/// Scrolls `state` the way a `List` would to keep the selection in view,
+/// returning the range of the `len` items that fit in `height` rows.
+///
+/// Long lists are drawn from just this range so that a frame doesn't build
+/// an item for every entry. The returned state is for a `List` of just
+/// those items.
+fn visible_window(state: &mut ListState, len: usize, height: usize) -> (Range<usize>, ListState) {
+    if len == 0 {
+        state.select(None);
+        return (0..0, ListState::default());
+    }
+    if state.selected().is_some_and(|selected| selected >= len) {
+        state.select(Some(len - 1));
+    }
+    let mut offset = state.offset().min(len - 1);
+    if let Some(selected) = state.selected() {
+        if selected < offset {
+            offset = selected;
+        } else if height > 0 && selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+    }
+    *state.offset_mut() = offset;
+    let window = offset..(offset + height).min(len);
+    let window_state =
+        ListState::default().with_selected(state.selected().map(|selected| selected - offset));
+    (window, window_state)
+}
+
+/// The smallest terminal that the screens are laid out for.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 16;
+
 pub fn draw_ui(app: &mut App, frame: &mut Frame) {
-    // This creates the header box and the main box below it.
-    let [header_area, body_area, footer_area] = Layout::new(
+    let area = frame.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        render_too_small(app, frame);
+        return;
+    }
+    // This creates the header box and the main box below it, with room for
+    // a message between them when there's one to show.
+    let message_height = if app.messages().is_empty() { 0 } else { 3 };
+    let footer = footer_lines(app, area.width.saturating_sub(4) as usize);
+    // Home lists the watched variables itself, so only the other screens
+    // get the line of them above the footer.
+    let watch_height = if app.watch_list().is_empty() || app.screen() == Screen::Home {
+        0
+    } else {
+        1
+    };
+    let [
+        header_area,
+        message_area,
+        body_area,
+        watch_area,
+        footer_area,
+    ] = Layout::new(
         Direction::Vertical,
         [
             Constraint::Length(3),
+            Constraint::Length(message_height),
             Constraint::Min(0),
-            Constraint::Length(3),
+            Constraint::Length(watch_height),
+            Constraint::Length(footer.len() as u16 + 2),
         ],
     )
     .margin(1)
     .areas(frame.area());
 
     render_header(app, frame, header_area);
-    render_footer(app, frame, footer_area);
-    match app.screen() {
-        Screen::Home => render_home_screen(app, frame, body_area),
-        Screen::Prompt => render_dummy_screen("Prompt Screen", frame, body_area),
-        Screen::Vars => render_vars_screen(app, frame, body_area),
-        Screen::Trace => render_trace_screen(app, frame, body_area),
-        Screen::Output => render_output_screen(app, frame, body_area),
+    render_message(app, frame, message_area);
+    render_footer(app, frame, footer_area, footer);
+    if watch_height > 0 {
+        render_watch_line(app, frame, watch_area);
+    }
+    app.screen().controller().render(app, frame, body_area);
+    if app.overlay().is_some() {
+        render_overlay(app, frame);
     }
-    if matches!(app.exit_state(), ExitState::PresentModal { .. }) {
-        render_exit_modal(app, frame);
+    render_toasts(app, frame, body_area.inner(Margin::new(1, 1)));
+    if app.modal().is_some() {
+        render_modal(app, frame);
     }
 }
 
+/// Replaces everything with a note asking for a bigger terminal, since the
+/// screens can't be laid out in less than the minimum size. The note is
+/// split over two lines when it doesn't fit on one.
+///
+/// An open modal still has to be answerable, so its question and buttons
+/// are listed as plain text above the note, which is left out when there's
+/// no room for both.
+fn render_too_small(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let sizes = format!(
+        "(needs {MIN_WIDTH}×{MIN_HEIGHT}, have {}×{})",
+        area.width, area.height
+    );
+    let message = format!("Terminal too small {sizes}");
+    let lines = if text::display_width(&message) <= area.width as usize {
+        vec![message]
+    } else {
+        vec!["Terminal too small".to_string(), sizes]
+    };
+    let mut lines = lines
+        .into_iter()
+        .map(|line| Line::from(line.set_style(app.theme().warning)))
+        .collect::<Vec<_>>();
+    if let Some(modal) = app.modal() {
+        let confirmation = modal_text(app, modal);
+        if confirmation.len() + 1 + lines.len() <= area.height as usize {
+            lines = confirmation
+                .into_iter()
+                .chain([Line::default()])
+                .chain(lines)
+                .collect();
+        } else {
+            lines = confirmation;
+        }
+    }
+    let [message_area] = Layout::vertical([Constraint::Length(lines.len() as u16)])
+        .flex(Flex::Center)
+        .areas(area);
+    frame.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Center),
+        message_area,
+    );
+}
+
+/// Returns `modal` as plain lines, for terminals too small to draw it: the
+/// question, the exit summary's header, and then the buttons one per line.
+fn modal_text<'a>(app: &App, modal: &'a Modal) -> Vec<Line<'a>> {
+    let theme = app.theme();
+    let mut lines = modal
+        .message()
+        .lines()
+        .map(|line| Line::from(line.set_style(theme.fg)))
+        .collect::<Vec<_>>();
+    if let Modal::Exit { .. } = modal {
+        lines.push(Line::from(exit_summary(app).0.set_style(theme.fg_dim)));
+    }
+    lines.push(Line::default());
+    let (labels, highlighted) = modal.buttons();
+    for (idx, label) in labels.iter().enumerate() {
+        let style = if idx == highlighted {
+            theme.highlighted_text
+        } else {
+            theme.fg
+        };
+        lines.push(Line::from(label.set_style(style)));
+    }
+    lines
+}
+
 fn render_header(app: &App, frame: &mut Frame, area: Rect) {
     let theme = app.theme();
     let header_title: Vec<Span> = vec![
@@ -55,23 +231,45 @@ fn render_header(app: &App, frame: &mut Frame, area: Rect) {
         env!("CARGO_PKG_VERSION").bold(),
         " ".into(),
     ];
-    let header_box = Block::bordered()
+    let mut header_box = Block::bordered()
+        .border_style(theme.border)
         .title(header_title)
         .title_alignment(Alignment::Center);
+    // The clock isn't part of the view's fingerprint, so it only moves with
+    // the once a second redraw rather than redrawing on its own.
+    let now = Instant::now();
+    let clock = match app.countdown().remaining(now) {
+        Some(remaining) => {
+            // Round up, so the clock reads 00:00 only as the time runs out.
+            let remaining = Duration::from_secs(remaining.as_secs_f64().ceil() as u64);
+            let style = match app.countdown().visible_remaining(now) {
+                Some(_) => theme.warning,
+                None => theme.fg_dim,
+            };
+            format!(" Continuing in {} ", format_clock(remaining)).set_style(style)
+        }
+        None => format!(" Paused {} ", format_clock(app.elapsed(now))).set_style(theme.fg_dim),
+    };
+    header_box = header_box.title(Line::from(clock).right_aligned());
     frame.render_widget(header_box, area);
-    let [tabs_area] = Layout::horizontal([Constraint::Percentage(100)])
-        .margin(1)
-        .areas(area);
+    // The tabs are padded by a space on each side and divided by a `|`.
+    let tabs_width = Screen::TABS
+        .iter()
+        .map(|screen| text::display_width(&screen.to_string()) + 3)
+        .sum::<usize>()
+        - 1;
+    let [tabs_area, info_area] =
+        Layout::horizontal([Constraint::Length(tabs_width as u16), Constraint::Fill(1)])
+            .spacing(1)
+            .margin(1)
+            .areas(area);
+    if let Some(info) = session_info(app, info_area.width as usize) {
+        frame.render_widget(info.right_aligned(), info_area);
+    }
     let tabs = Tabs::new(
-        [
-            Screen::Home,
-            Screen::Prompt,
-            Screen::Vars,
-            Screen::Trace,
-            Screen::Output,
-        ]
-        .into_iter()
-        .map(|s| s.to_string().set_style(theme.flox_purple)),
+        Screen::TABS
+            .iter()
+            .map(|s| s.to_string().set_style(theme.flox_purple)),
     )
     .highlight_style(theme.selected_tab)
     .divider("|".set_style(theme.fg_dim))
@@ -79,93 +277,1071 @@ fn render_header(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(tabs, tabs_area);
 }
 
-fn render_footer(app: &App, frame: &mut Frame, area: Rect) {
-    let screen_bindings = match app.screen() {
-        Screen::Home => app.key_bindings().home().displayable(),
-        Screen::Prompt => app.key_bindings().prompt().displayable(),
-        Screen::Vars => app.key_bindings().vars().displayable(),
-        Screen::Trace => app.key_bindings().trace().displayable(),
-        Screen::Output => app.key_bindings().output().displayable(),
+/// Returns what the header shows after the tabs: the tracepoint the shell is
+/// paused at and the shell the output is written for, with the tracepoint
+/// cut short to fit in `width` columns. Only the shell is shown when there's
+/// no room for any of the tracepoint, and nothing when there's no room for
+/// the shell either.
+fn session_info(app: &App, width: usize) -> Option<Line<'static>> {
+    let theme = app.theme();
+    let shell = app.shell().to_string();
+    let shell_width = text::display_width(&shell) + 1;
+    if width < shell_width {
+        return None;
+    }
+    let shell = vec![shell.set_style(theme.flox_purple), " ".into()];
+    let (tracepoint, style) = match app.trace().tracepoint() {
+        Some(tracepoint) => (tracepoint.as_str(), theme.fg),
+        None => ("no tracepoint", theme.fg_dim),
     };
-    let applicable_bindings = {
-        let mut bindings = app.key_bindings().global().displayable();
-        bindings.extend_from_slice(&screen_bindings);
-        bindings
+    // Leave room for the separator and at least a character and an ellipsis.
+    let Some(room) = width.checked_sub(shell_width + 3).filter(|room| *room >= 2) else {
+        return Some(Line::from(shell));
     };
+    let tracepoint = text::truncate_to_width(tracepoint, room).into_owned();
+    let mut spans = vec![tracepoint.set_style(style), " · ".set_style(theme.fg_dim)];
+    spans.extend(shell);
+    Some(Line::from(spans))
+}
+
+/// Shows the oldest message that hasn't been dismissed, and how many more
+/// are waiting behind it.
+fn render_message(app: &App, frame: &mut Frame, area: Rect) {
+    let Some(message) = app.messages().current() else {
+        return;
+    };
+    let theme = app.theme();
+    let style = severity_style(theme, message.severity);
+    let count = app.messages().len();
+    let title = if count > 1 {
+        format!(" {} (1 of {count}) ", message.severity)
+    } else {
+        format!(" {} ", message.severity)
+    };
+    let dismiss = app.key_bindings().global().dismiss_message();
+    let block = Block::bordered()
+        .border_style(style)
+        .title(title.set_style(style))
+        .title_bottom(
+            Line::from(vec![
+                " ".into(),
+                dismiss.display_key_combo().set_style(theme.flox_purple),
+                ": Dismiss ".set_style(theme.fg_dim),
+            ])
+            .right_aligned(),
+        );
+    let text = Line::from(message.text.as_str().set_style(theme.fg));
+    frame.render_widget(Paragraph::new(text).block(block), area);
+}
+
+/// Returns the style that messages of `severity` are drawn in.
+fn severity_style(theme: &Theme, severity: Severity) -> Style {
+    match severity {
+        Severity::Success => theme.success,
+        Severity::Warning => theme.warning,
+        Severity::Error => theme.error,
+    }
+}
+
+/// Stacks the toasts on screen in the bottom right corner of `area`, with
+/// the newest nearest the corner.
+fn render_toasts(app: &App, frame: &mut Frame, area: Rect) {
+    if app.toasts().is_empty() {
+        return;
+    }
+    let theme = app.theme();
+    let toasts = app.toasts().visible().collect::<Vec<_>>();
+    let [stack_area] = Layout::vertical([Constraint::Length(3 * toasts.len() as u16)])
+        .flex(Flex::End)
+        .areas(area);
+    let rows = Layout::vertical(toasts.iter().map(|_| Constraint::Length(3))).split(stack_area);
+    for (toast, row) in toasts.into_iter().zip(rows.iter()) {
+        let style = severity_style(theme, toast.severity);
+        let text = Line::from(toast.text.as_str().set_style(theme.fg));
+        // Leave most of the screen visible however long the text is.
+        let width = (text.width() as u16 + 4).min(area.width / 2);
+        let [toast_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::End)
+            .areas(*row);
+        let block = Block::bordered()
+            .border_style(style)
+            .padding(Padding::horizontal(1));
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(Paragraph::new(text).block(block), toast_area);
+    }
+}
+
+/// The most rows the footer grows to. Bindings that don't fit in them are
+/// left to the help overlay.
+const MAX_FOOTER_ROWS: usize = 2;
+
+/// How the footer's bindings are spread over its rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FooterLayout {
+    /// The indices of the bindings on each row.
+    rows: Vec<Range<usize>>,
+    /// Whether some bindings didn't fit, which the last row then says.
+    truncated: bool,
+}
+
+/// Returns the width of a binding as the footer shows it, ` [keys: desc]`.
+fn binding_width(keys: &str, desc: &str) -> usize {
+    text::display_width(keys) + text::display_width(desc) + 5
+}
+
+/// Returns the end of the row of `bindings` that starts at `start` and fits
+/// in `room` columns. A binding wider than a whole row gets one to itself.
+fn fill_row(bindings: &[(String, &'static str)], start: usize, room: usize) -> usize {
+    let mut end = start;
+    let mut used = 0;
+    while let Some((keys, desc)) = bindings.get(end) {
+        used += binding_width(keys, desc);
+        if used > room && end > start {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/// Spreads `bindings` over the rows of a footer `width` columns wide, in
+/// order, after `lead_width` columns of notes on the first row. When they
+/// don't fit in [`MAX_FOOTER_ROWS`] rows, the last row makes room for a
+/// `more_width` wide pointer to the rest.
+///
+/// This only depends on its arguments, so the footer doesn't change from
+/// frame to frame while they don't.
+fn footer_layout(
+    bindings: &[(String, &'static str)],
+    lead_width: usize,
+    width: usize,
+    more_width: usize,
+) -> FooterLayout {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    // Every row ends with a space to balance the one each binding starts
+    // with.
+    let mut room = width.saturating_sub(lead_width + 1);
+    while start < bindings.len() {
+        let end = fill_row(bindings, start, room);
+        if rows.len() + 1 == MAX_FOOTER_ROWS && end < bindings.len() {
+            let end = fill_row(bindings, start, room.saturating_sub(more_width));
+            rows.push(start..end);
+            return FooterLayout {
+                rows,
+                truncated: true,
+            };
+        }
+        rows.push(start..end);
+        start = end;
+        room = width.saturating_sub(1);
+    }
+    if rows.is_empty() {
+        rows.push(0..0);
+    }
+    FooterLayout {
+        rows,
+        truncated: false,
+    }
+}
+
+/// Returns the rows of the footer for a terminal whose footer has `width`
+/// columns inside its border.
+fn footer_lines(app: &mut App, width: usize) -> Vec<Line<'static>> {
+    let bindings = app.footer_bindings();
     let theme = app.theme();
-    let mut formatted_bindings = applicable_bindings
+    let mut lead = Vec::new();
+    if let Some(prefix) = app.key_sequence().pending() {
+        lead.extend([
+            " [".set_style(theme.fg_dim),
+            display_key_sequence(&[prefix]).set_style(theme.flox_purple),
+            " …]".set_style(theme.fg_dim),
+        ]);
+    }
+    if app.interrupt_armed() {
+        lead.extend([
+            " [".set_style(theme.fg_dim),
+            "Ctrl+C".set_style(theme.flox_purple),
+            " again: Quit without changes]".set_style(theme.warning),
+        ]);
+    }
+    if app.read_only() {
+        lead.push("READ-ONLY ".set_style(theme.warning));
+    }
+    let lead_width = lead.iter().map(Span::width).sum();
+    let help = app.key_bindings().global().help().display_key_combo();
+    let more = format!(" …more ({help})");
+    let layout = footer_layout(&bindings, lead_width, width, text::display_width(&more));
+    let last_row = layout.rows.len() - 1;
+    layout
+        .rows
         .into_iter()
-        .flat_map(|(keys, desc)| {
-            vec![
-                " [".set_style(theme.fg_dim),
-                keys.set_style(theme.flox_purple),
-                ": ".set_style(theme.fg_dim),
-                desc.set_style(theme.fg),
-                "]".set_style(theme.fg_dim),
-            ]
+        .enumerate()
+        .map(|(row, range)| {
+            let mut spans = if row == 0 {
+                std::mem::take(&mut lead)
+            } else {
+                Vec::new()
+            };
+            for (keys, desc) in &bindings[range] {
+                spans.extend([
+                    " [".set_style(theme.fg_dim),
+                    keys.clone().set_style(theme.flox_purple),
+                    ": ".set_style(theme.fg_dim),
+                    desc.set_style(theme.fg),
+                    "]".set_style(theme.fg_dim),
+                ]);
+            }
+            if layout.truncated && row == last_row {
+                spans.push(more.clone().set_style(theme.fg_dim));
+            }
+            spans.push(" ".into());
+            Line::from(spans)
         })
-        .collect::<Vec<Span>>();
-    formatted_bindings.push(" ".into());
-    let line = Line::from(formatted_bindings).alignment(Alignment::Center);
-    let [line_area] = Layout::horizontal([Constraint::Percentage(100)])
+        .collect()
+}
+
+fn render_footer(app: &App, frame: &mut Frame, area: Rect, lines: Vec<Line<'static>>) {
+    let theme = app.theme();
+    let [lines_area] = Layout::horizontal([Constraint::Percentage(100)])
         .margin(1)
         .areas(area);
-    frame.render_widget(Block::bordered(), area);
-    frame.render_widget(line, line_area);
+    frame.render_widget(Block::bordered().border_style(theme.border), area);
+    frame.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Center),
+        lines_area,
+    );
+}
+
+/// The most columns of a value on the line of watched variables, so one long
+/// value can't push the rest off the screen.
+const WATCH_LINE_VALUE_WIDTH: usize = 24;
+
+/// Renders the watched variables as `NAME=value` on one line, cut off at the
+/// edge of the screen, with the ones that changed at the last reload marked.
+fn render_watch_line(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme();
+    let mut spans = vec![Span::styled(" Watching:", theme.fg_dim)];
+    for watched in app.watch_list().iter() {
+        spans.push(Span::raw(" "));
+        let name = if watched.changed {
+            spans.push(Span::styled(WATCH_MARKER.trim_end(), theme.warning));
+            Span::styled(watched.name.clone(), theme.warning)
+        } else {
+            Span::raw(watched.name.clone())
+        };
+        spans.push(name);
+        let value = shown_value(app.env(), &watched.name);
+        let value = text::truncate_to_width(&value, WATCH_LINE_VALUE_WIDTH);
+        spans.push(Span::styled(format!("={value}"), theme.fg_dim));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
-fn render_exit_modal(app: &App, frame: &mut Frame) {
+/// The smallest width of a modal, which fits a short question and two
+/// buttons.
+const MODAL_MIN_WIDTH: u16 = 30;
+
+/// The most of the terminal's width a modal takes up, in percent, so the
+/// screen it's over stays recognizable around it.
+const MODAL_MAX_WIDTH_PERCENT: u16 = 90;
+
+/// The most lines of the exit summary shown at once, even when more would
+/// fit.
+const MAX_SUMMARY_LINES: usize = 8;
+
+/// What the exit modal says will happen on Ok: how many commands were
+/// queued, then every line that will be printed for the shell.
+///
+/// The lines come from the same place as what's printed on exit, so the
+/// summary can't disagree with it.
+fn exit_summary(app: &App) -> (String, Vec<String>) {
+    let n_queued = app.queued_commands().len();
+    let header = match n_queued {
+        0 => "No pending changes".to_string(),
+        1 => "1 pending command:".to_string(),
+        n => format!("{n} pending commands:"),
+    };
+    let lines = app.final_output().lines().map(String::from).collect();
+    (header, lines)
+}
+
+/// What a modal has to make room for, measured in cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ModalContent {
+    /// The width and height of the question.
+    desc: (u16, u16),
+    /// The width of each button's label, from left to right.
+    buttons: Vec<u16>,
+    /// For the exit modal, the width of the summary's widest line, counting
+    /// its header, and how many lines it has below the header.
+    summary: Option<(u16, usize)>,
+}
+
+/// How big a modal is drawn, as worked out by [`modal_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ModalSize {
+    width: u16,
+    height: u16,
+    /// Whether the buttons are stacked one per row, since they don't fit
+    /// side by side.
+    stacked: bool,
+    /// How many rows the buttons take up.
+    buttons_height: u16,
+    /// How many of the summary's lines are shown at once.
+    summary_lines: usize,
+}
+
+/// Works out how big to draw a modal with `content` in a terminal of
+/// `frame` (width, height).
+///
+/// The modal is as wide as its widest part, but no narrower than
+/// [`MODAL_MIN_WIDTH`] or wider than [`MODAL_MAX_WIDTH_PERCENT`] of the
+/// terminal. Buttons that don't fit side by side are stacked. The question
+/// and buttons always get their rows, and the summary gets whatever height
+/// is left, up to [`MAX_SUMMARY_LINES`].
+fn modal_size(content: &ModalContent, frame: (u16, u16)) -> ModalSize {
+    let (frame_width, frame_height) = frame;
+    let max_width = (frame_width * MODAL_MAX_WIDTH_PERCENT / 100).max(1);
+    // Border and padding on either side
+    let chrome = 4;
+    let row_width = content.buttons.iter().map(|width| width + 2).sum::<u16>();
+    let stacked = row_width + chrome > max_width;
+    let buttons_width = if stacked {
+        content.buttons.iter().copied().max().unwrap_or(0)
+    } else {
+        row_width
+    };
+    let summary_width = content.summary.map_or(0, |(width, _)| width);
+    let width = (content.desc.0.max(buttons_width).max(summary_width) + chrome)
+        .max(MODAL_MIN_WIDTH)
+        .min(max_width);
+
+    let buttons_height = if stacked {
+        content.buttons.len() as u16
+    } else {
+        1
+    };
+    // Border, question, gap, buttons
+    let fixed_height = content.desc.1 + 3 + buttons_height;
+    let mut summary_lines = 0;
+    let mut height = fixed_height;
+    if let Some((_, n_lines)) = content.summary {
+        // Gap and header
+        let room = frame_height.saturating_sub(fixed_height + 2) as usize;
+        summary_lines = n_lines.min(MAX_SUMMARY_LINES).min(room);
+        height += 2 + summary_lines as u16;
+    }
+    ModalSize {
+        width,
+        height: height.min(frame_height),
+        stacked,
+        buttons_height,
+        summary_lines,
+    }
+}
+
+/// Draws the open modal centered over a cleared screen, sized to fit its
+/// content and the terminal by [`modal_size`].
+fn render_modal(app: &mut App, frame: &mut Frame) {
+    let Some(modal) = app.modal().cloned() else {
+        return;
+    };
     let area = frame.area();
-    let ExitState::PresentModal { highlighted_option } = app.exit_state() else {
-        panic!("tried to draw exit modal in wrong state");
+    let theme = app.theme().clone();
+    let desc = Text::from(modal.message()).alignment(Alignment::Center);
+    let (labels, highlighted) = modal.buttons();
+    let summary = match modal {
+        Modal::Exit { scroll, .. } => Some((exit_summary(app), scroll)),
+        Modal::Confirm { .. } => None,
     };
-    let theme = app.theme();
 
     // First clear the entire screen
     frame.render_widget(Clear, area);
 
-    // Now create a layout to place the popup inside of
-    let [vertical_area] = Layout::vertical([Constraint::Length(5)])
+    let content = ModalContent {
+        desc: (desc.width() as u16, desc.height() as u16),
+        buttons: labels
+            .iter()
+            .map(|label| Span::raw(*label).width() as u16)
+            .collect(),
+        summary: summary.as_ref().map(|((header, lines), _)| {
+            let width = lines
+                .iter()
+                .map(|line| Span::raw(line).width())
+                .chain([Span::raw(header).width()])
+                .max()
+                .unwrap_or(0);
+            (width as u16, lines.len())
+        }),
+    };
+    let size = modal_size(&content, (area.width, area.height));
+    let n_visible = size.summary_lines;
+    let [vertical_area] = Layout::vertical([Constraint::Length(size.height)])
         .flex(Flex::Center)
         .areas(area);
-    let [popup_area] = Layout::horizontal([Constraint::Length(30)])
+    let [popup_area] = Layout::horizontal([Constraint::Length(size.width)])
         .flex(Flex::Center)
         .areas(vertical_area);
 
-    // Draw the popup border
-    frame.render_widget(Block::bordered(), popup_area);
-
     // Create the internal layout of the popup
-    let [desc_area, buttons_area] =
-        Layout::vertical([Constraint::Length(1), Constraint::Length(3)])
-            .margin(1)
-            .spacing(1)
-            .areas(popup_area);
+    let mut constraints = vec![Constraint::Length(content.desc.1)];
+    if summary.is_some() {
+        constraints.push(Constraint::Length(1 + n_visible as u16));
+    }
+    constraints.push(Constraint::Length(size.buttons_height));
+    let areas = Layout::vertical(constraints)
+        .margin(1)
+        .spacing(1)
+        .split(popup_area);
+    let (desc_area, buttons_area) = (areas[0], areas[areas.len() - 1]);
 
-    let desc = Line::from("Exit?").alignment(Alignment::Center);
+    // Draw the popup border, noting which part of the summary is shown when
+    // it doesn't all fit
+    let mut block = Block::bordered().border_style(theme.border);
+    if let Some(((header, lines), scroll)) = summary {
+        // Only now is it known how much fits, so keep the scroll where the
+        // last page starts rather than past it.
+        let start = scroll.min(lines.len().saturating_sub(n_visible));
+        if let Some(Modal::Exit { scroll, .. }) = app.modal_mut() {
+            *scroll = start;
+        }
+        if n_visible < lines.len() {
+            let end = start + n_visible;
+            block = block.title_bottom(
+                Line::from(format!(" ↑↓ {}-{end} of {} ", start + 1, lines.len())).right_aligned(),
+            );
+        }
+        let [header_area, lines_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(areas[1]);
+        frame.render_widget(Line::from(header).alignment(Alignment::Center), header_area);
+        let shown = lines
+            .into_iter()
+            .skip(start)
+            .take(n_visible)
+            .map(|line| Line::from(line.set_style(theme.fg)))
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(shown), lines_area.inner(Margin::new(1, 0)));
+    }
+    frame.render_widget(block, popup_area);
     frame.render_widget(desc, desc_area);
 
-    // Create the areas for each button and determine the style based on
-    // which button is selected
-    let [ok_area, cancel_area] =
-        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .flex(Flex::Center)
-            .spacing(1)
-            .areas(buttons_area);
-    let (ok_style, cancel_style) = if highlighted_option == ExitOption::Ok {
-        (theme.highlighted_text, theme.fg)
+    // Create the areas for each button, side by side or stacked, and style
+    // the highlighted one
+    let button_areas = if size.stacked {
+        Layout::vertical(labels.iter().map(|_| Constraint::Length(1))).split(buttons_area)
     } else {
-        (theme.fg, theme.highlighted_text)
+        Layout::horizontal(
+            content
+                .buttons
+                .iter()
+                .map(|width| Constraint::Length(*width)),
+        )
+        .flex(Flex::SpaceAround)
+        .spacing(1)
+        .split(buttons_area)
     };
+    for (idx, (label, button_area)) in labels.iter().zip(button_areas.iter()).enumerate() {
+        let style = if idx == highlighted {
+            theme.highlighted_text
+        } else {
+            theme.fg
+        };
+        let button = Line::from(label.set_style(style)).alignment(Alignment::Center);
+        frame.render_widget(button, *button_area);
+    }
+}
 
-    let ok_button = Line::from("[   Ok   ]".set_style(ok_style)).alignment(Alignment::Center);
-    let cancel_button =
-        Line::from("[ Cancel ]".set_style(cancel_style)).alignment(Alignment::Center);
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend, buffer::Buffer, style::Style};
 
-    frame.render_widget(ok_button, ok_area);
-    frame.render_widget(cancel_button, cancel_area);
-}
+    use super::*;
+    use crate::{
+        app::{
+            Action, AppEvent, Event, NavEvent, handle_event,
+            messages::Severity,
+            modal::ModalEvent,
+            output::OutputEvent,
+            prompt::handle_prompt_event,
+            theme::{ThemeChoice, ThemePreset},
+            toasts::TOAST_TTL,
+            vars::{VarDetailState, VarsEvent},
+        },
+        cli::{self, Cli},
+        terminal::TerminalInfo,
+    };
+
+    fn test_app() -> App {
+        test_app_with_color(true)
+    }
+
+    fn test_app_with_color(color: bool) -> App {
+        App::new(&test_args(color), &TerminalInfo::default()).unwrap()
+    }
+
+    fn test_args(color: bool) -> Cli {
+        Cli {
+            no_color: !color,
+            theme: Some(ThemeChoice::Preset(ThemePreset::Default)),
+            ..cli::test_args()
+        }
+    }
+
+    fn render(app: &mut App) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| draw_ui(app, frame)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// Returns the style of the top-left corner of the pane whose title
+    /// contains `title`.
+    fn border_style(buffer: &Buffer, title: &str) -> Style {
+        let area = buffer.area;
+        for y in area.top()..area.bottom() {
+            let row = (area.left()..area.right())
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>();
+            if let Some(byte_idx) = row.find(title) {
+                let col = row[..byte_idx].chars().count() as u16;
+                let corner = (area.left()..col)
+                    .rev()
+                    .find(|&x| buffer[(x, y)].symbol() == "┌")
+                    .expect("title is on a border");
+                return buffer[(corner, y)].style();
+            }
+        }
+        panic!("no pane titled {title:?}");
+    }
+
+    #[test]
+    fn focused_and_unfocused_borders_differ() {
+        let mut app = test_app();
+        app.set_screen(Screen::Vars);
+        let buffer = render(&mut app);
+        let theme = app.theme();
+        let focused = border_style(&buffer, " Variables ");
+        let unfocused = border_style(&buffer, " Name ");
+        assert_ne!(focused, unfocused);
+        assert_eq!(focused.fg, theme.border_focused.fg);
+        assert_ne!(unfocused.fg, theme.border_focused.fg);
+    }
+
+    fn contains(buffer: &Buffer, text: &str) -> bool {
+        let area = buffer.area;
+        (area.top()..area.bottom()).any(|y| {
+            (area.left()..area.right())
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .contains(text)
+        })
+    }
+
+    #[test]
+    fn focus_marker_follows_focus_without_color() {
+        let mut app = test_app_with_color(false);
+        app.set_screen(Screen::Vars);
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, "▶ Variables "));
+        assert!(!contains(&buffer, "▶ Raw"));
+
+        app.set_screen(Screen::Prompt);
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, "▶ Scrollback "));
+        assert!(!contains(&buffer, "▶ Command "));
+    }
 
-fn render_dummy_screen(name: &str, frame: &mut Frame, area: Rect) {
-    frame.render_widget(Line::from(name).alignment(Alignment::Center), area);
+    #[test]
+    fn resizing_clamps_scrollback_and_guards_small_sizes() {
+        let mut app = test_app();
+        app.set_screen(Screen::Prompt);
+        for i in 0..50 {
+            app.prompt_mut().print(format!("line {i}"));
+        }
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let mut draw = |app: &mut App, width, height| {
+            terminal.backend_mut().resize(width, height);
+            terminal.draw(|frame| draw_ui(app, frame)).unwrap();
+            terminal.backend().buffer().clone()
+        };
+
+        draw(&mut app, 100, 30);
+        handle_prompt_event(&mut app, &Event::Nav(NavEvent::Top));
+        let scrolled_to_top = app.prompt().scroll_offset();
+        assert!(contains(&draw(&mut app, 100, 30), "line 0"));
+
+        // Shrinking keeps the view where it was scrolled to.
+        let buffer = draw(&mut app, 100, 20);
+        assert_eq!(app.prompt().scroll_offset(), scrolled_to_top);
+        assert!(!contains(&buffer, "line 49"));
+
+        // Growing the terminal past the length of the scrollback shows all
+        // of it, rather than leaving the view scrolled past the top.
+        let buffer = draw(&mut app, 100, 80);
+        assert_eq!(app.prompt().scroll_offset(), 0);
+        assert!(contains(&buffer, "line 0"));
+        assert!(contains(&buffer, "line 49"));
+
+        // Too small to lay out, so the guard is shown instead.
+        let buffer = draw(&mut app, 43, 9);
+        assert!(contains(
+            &buffer,
+            "Terminal too small (needs 60×16, have 43×9)"
+        ));
+        let buffer = draw(&mut app, 100, 30);
+        assert!(contains(&buffer, " Scrollback "));
+    }
+
+    #[test]
+    fn tiny_terminals_only_show_the_guard() {
+        let mut app = test_app();
+        app.notify(Severity::Warning, "a toast".to_string());
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let mut draw = |app: &mut App, width, height| {
+            terminal.backend_mut().resize(width, height);
+            terminal.draw(|frame| draw_ui(app, frame)).unwrap();
+            terminal.backend().buffer().clone()
+        };
+        let mut check = |app: &mut App| {
+            for (width, height) in [(0, 0), (1, 1), (80, 0), (0, 24), (1, 40)] {
+                draw(app, width, height);
+            }
+            let buffer = draw(app, 59, 30);
+            assert!(contains(
+                &buffer,
+                "Terminal too small (needs 60×16, have 59×30)"
+            ));
+            assert!(!contains(&buffer, "Prompt"));
+            assert!(!contains(&buffer, "a toast"));
+            assert!(contains(&draw(app, 100, 15), "have 100×15"));
+            // Too narrow for the whole note, so it goes over two lines.
+            let buffer = draw(app, 30, 8);
+            assert!(contains(&buffer, "Terminal too small"));
+            assert!(contains(&buffer, "(needs 60×16, have 30×8)"));
+            assert!(!contains(&draw(app, 60, 16), "Terminal too small"));
+        };
+        for tab in Screen::TABS {
+            app.set_screen(tab);
+            check(&mut app);
+        }
+        handle_event(&mut app, &Event::App(AppEvent::ShowHelp));
+        check(&mut app);
+
+        // A modal can still be answered, and when there's only room for it
+        // the note is left out.
+        app.set_overlay(None);
+        handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+        let buffer = draw(&mut app, 40, 12);
+        assert!(contains(&buffer, "Exit?"));
+        assert!(contains(&buffer, "[ Discard & Exit ]"));
+        assert!(contains(&buffer, "Terminal too small"));
+        let buffer = draw(&mut app, 30, 6);
+        assert!(contains(&buffer, "[ Cancel ]"));
+        assert!(!contains(&buffer, "Terminal too small"));
+    }
+
+    #[test]
+    fn header_shows_how_long_the_shell_has_been_paused() {
+        let now = Instant::now();
+        let mut app = test_app().with_started(now - Duration::from_secs(75));
+        assert!(contains(&render(&mut app), " Paused 01:15 "));
+
+        let args = Cli {
+            timeout: Some(300),
+            ..test_args(true)
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, " Continuing in 05:00 "));
+        let warning = app.theme().warning;
+        let clock_style = |buffer: &Buffer| {
+            let row = (0..buffer.area.width)
+                .map(|x| buffer[(x, 1)].symbol())
+                .collect::<String>();
+            let x = row.find("Continuing").unwrap();
+            // The row is all box drawing and ASCII before the clock.
+            let x = row[..x].chars().count() as u16;
+            buffer[(x, 1)].style()
+        };
+        assert_ne!(clock_style(&buffer).fg, warning.fg);
+        let args = Cli {
+            timeout: Some(20),
+            ..test_args(true)
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, " Continuing in 00:20 "));
+        assert_eq!(clock_style(&buffer).fg, warning.fg);
+    }
+
+    #[test]
+    fn session_info_gives_way_to_the_tabs() {
+        let app = test_app();
+        let info = |width| session_info(&app, width).map(|line| line.to_string());
+        assert_eq!(info(40).as_deref(), Some("no tracepoint · bash "));
+        assert_eq!(info(14).as_deref(), Some("no tr… · bash "));
+        assert_eq!(info(9).as_deref(), Some("bash "));
+        assert_eq!(info(4), None);
+    }
+
+    #[test]
+    fn demo_shows_source_of_call_sites() {
+        let args = Cli {
+            demo: true,
+            ..test_args(true)
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Trace);
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, "Current tracepoint: on-activate"));
+        assert!(contains(&buffer, "fdb_tracepoint \"$hook\""));
+
+        app.trace_mut().list_state().unwrap().select_next();
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, "run_hook on-activate"));
+    }
+
+    #[test]
+    fn unreadable_source_is_reported_and_the_session_continues() {
+        let args = Cli {
+            call_stack: Some(
+                "/nonexistent/hooks.sh:14:fdb_tracepoint\n/nonexistent/hooks.sh:20:run_hook\n"
+                    .to_string(),
+            ),
+            ..test_args(true)
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Trace);
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, " Warning "));
+        assert!(contains(
+            &buffer,
+            "couldn't read the source of /nonexistent/hooks.sh"
+        ));
+        // The frames are still listed without their source.
+        assert!(contains(&buffer, "fdb_tracepoint"));
+
+        let action = handle_event(&mut app, &Event::Nav(NavEvent::Down));
+        assert_eq!(action, Action::Continue);
+        let action = handle_event(&mut app, &Event::App(AppEvent::DismissMessage));
+        assert_eq!(action, Action::Continue);
+        let buffer = render(&mut app);
+        assert!(!contains(&buffer, " Warning "));
+        assert!(contains(&buffer, "run_hook"));
+    }
+
+    #[test]
+    fn exit_summary_fits_small_terminals_and_scrolls() {
+        let mut app = test_app();
+        for i in 0..20 {
+            app.push_output_line(&format!("export VAR_{i}={i}"));
+        }
+        handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+        let mut terminal = Terminal::new(TestBackend::new(MIN_WIDTH, MIN_HEIGHT)).unwrap();
+        let mut draw = |app: &mut App| {
+            terminal.draw(|frame| draw_ui(app, frame)).unwrap();
+            terminal.backend().buffer().clone()
+        };
+
+        // Everything but the lines that don't fit is shown, including both
+        // edges of the border.
+        let buffer = draw(&mut app);
+        assert!(contains(&buffer, "20 pending commands:"));
+        assert!(contains(&buffer, "export VAR_0=0"));
+        assert!(contains(&buffer, "export VAR_7=7"));
+        assert!(!contains(&buffer, "export VAR_8=8"));
+        assert!(contains(&buffer, " ↑↓ 1-8 of 20 "));
+        assert!(contains(&buffer, "┌"));
+        assert!(contains(&buffer, "┘"));
+        assert!(contains(&buffer, "[ Cancel ]"));
+
+        // Scrolling stops once the last line is shown.
+        for _ in 0..30 {
+            handle_event(&mut app, &Event::Modal(ModalEvent::ScrollDown));
+        }
+        let buffer = draw(&mut app);
+        assert!(contains(&buffer, "export VAR_19=19"));
+        assert!(!contains(&buffer, "export VAR_11=11"));
+        assert!(contains(&buffer, " ↑↓ 13-20 of 20 "));
+        handle_event(&mut app, &Event::Modal(ModalEvent::ScrollUp));
+        let buffer = draw(&mut app);
+        assert!(contains(&buffer, " ↑↓ 12-19 of 20 "));
+    }
+
+    #[test]
+    fn modals_are_sized_to_their_content_and_the_terminal() {
+        let exit = |n_lines| ModalContent {
+            desc: (5, 1),
+            buttons: vec![10, 18, 10],
+            summary: Some((20, n_lines)),
+        };
+        let confirm = ModalContent {
+            desc: (5, 1),
+            buttons: vec![10, 10],
+            summary: None,
+        };
+
+        // The buttons side by side set the width, and a short question
+        // still gets the minimum.
+        let size = modal_size(&exit(2), (80, 24));
+        assert_eq!(
+            size,
+            ModalSize {
+                width: 48,
+                height: 9,
+                stacked: false,
+                buttons_height: 1,
+                summary_lines: 2,
+            }
+        );
+        assert_eq!(modal_size(&confirm, (80, 24)).width, MODAL_MIN_WIDTH);
+        assert_eq!(modal_size(&confirm, (80, 24)).height, 5);
+
+        // A long summary is cut off at the limit, or sooner when the
+        // terminal is short.
+        assert_eq!(
+            modal_size(&exit(20), (80, 24)).summary_lines,
+            MAX_SUMMARY_LINES
+        );
+        let size = modal_size(&exit(20), (60, 12));
+        assert_eq!((size.summary_lines, size.height), (5, 12));
+
+        // Wide content is kept within the terminal.
+        let wide = ModalContent {
+            summary: Some((200, 1)),
+            ..exit(1)
+        };
+        assert_eq!(modal_size(&wide, (80, 24)).width, 72);
+
+        // Buttons that don't fit side by side are stacked.
+        let size = modal_size(&exit(0), (50, 24));
+        assert_eq!(
+            size,
+            ModalSize {
+                width: 30,
+                height: 9,
+                stacked: true,
+                buttons_height: 3,
+                summary_lines: 0,
+            }
+        );
+        assert!(!modal_size(&confirm, (50, 24)).stacked);
+    }
+
+    /// Returns where each row of `layout` ends, since each starts where the
+    /// one before it ended.
+    fn row_ends(layout: &FooterLayout) -> Vec<usize> {
+        layout.rows.iter().map(|row| row.end).collect()
+    }
+
+    /// Returns where each screen's lists and scrollback are, after drawing
+    /// the screen so that the offsets are up to date.
+    fn view_state(app: &mut App) -> Vec<String> {
+        let screen = app.screen();
+        let mut views = Vec::new();
+        for tab in Screen::TABS {
+            app.set_screen(tab);
+            render(app);
+        }
+        app.set_screen(screen);
+        let env = app.env_mut();
+        let list = env.var_list_state().clone();
+        let detail = match env.var_detail_state_mut() {
+            VarDetailState::Split { list_state, .. } => Some(list_state.clone()),
+            VarDetailState::Raw => None,
+        };
+        views.push(format!(
+            "vars: {:?} {list:?} detail focused: {} {detail:?} raw: {}",
+            env.selected_var(),
+            !env.var_list_focused(),
+            env.raw_scroll()
+        ));
+        let trace = app.trace_mut().list_state().cloned();
+        views.push(format!("trace: {trace:?}"));
+        let output = app.output_state_mut().list_state().clone();
+        views.push(format!("output: {output:?}"));
+        views.push(format!("prompt: {}", app.prompt().scroll_offset()));
+        views
+    }
+
+    #[test]
+    fn switching_tabs_keeps_each_screens_view() {
+        let call_stack = (0..40)
+            .map(|line| format!("/nonexistent/hooks.sh:{line}:hook\n"))
+            .collect::<String>();
+        let args = Cli {
+            tracepoint: Some("on-activate".to_string()),
+            call_stack: Some(call_stack),
+            ..test_args(true)
+        };
+        let dirs = (0..30).map(|n| format!("/dir/{n}")).collect::<Vec<_>>();
+        let env = (0..60)
+            .map(|n| (format!("VAR_{n:02}"), n.to_string()))
+            .chain([("XDG_DATA_DIRS".to_string(), dirs.join(":"))])
+            .collect();
+        let mut app = App::new(&args, &TerminalInfo::default())
+            .unwrap()
+            .with_env(&env);
+        for n in 0..40 {
+            app.push_output_line(&format!("echo {n}"));
+            app.prompt_mut().print(format!("line {n}"));
+        }
+        let down = |app: &mut App, times| {
+            for _ in 0..times {
+                handle_event(app, &Event::Nav(NavEvent::Down));
+            }
+        };
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Vars)));
+        handle_event(&mut app, &Event::Nav(NavEvent::Bottom));
+        handle_event(&mut app, &Event::Vars(VarsEvent::SplitDetail));
+        handle_event(&mut app, &Event::Nav(NavEvent::Right));
+        down(&mut app, 20);
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Trace)));
+        down(&mut app, 30);
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Output)));
+        down(&mut app, 25);
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Prompt)));
+        for _ in 0..10 {
+            handle_event(&mut app, &Event::Nav(NavEvent::Up));
+        }
+        let before = view_state(&mut app);
+        assert!(before[0].contains("selected: Some(20)"), "{before:?}");
+        assert!(!before[1].contains("offset: 0"), "{before:?}");
+        assert!(!before[2].contains("offset: 0"), "{before:?}");
+        assert_eq!(before[3], "prompt: 10");
+
+        // Going around every tab, drawing each, leaves all of them as they
+        // were, as does jumping straight to each one and back.
+        for _ in Screen::TABS {
+            handle_event(&mut app, &Event::App(AppEvent::NextTab));
+            render(&mut app);
+            assert_eq!(view_state(&mut app), before);
+        }
+        for tab in Screen::TABS {
+            handle_event(&mut app, &Event::App(AppEvent::GoTo(tab)));
+            render(&mut app);
+            handle_event(&mut app, &Event::App(AppEvent::PrevTab));
+            render(&mut app);
+        }
+        assert_eq!(view_state(&mut app), before);
+    }
+
+    #[test]
+    fn footer_wraps_and_then_points_at_help() {
+        let bindings =
+            ["Exit", "Help", "Tab", "Nav", "Split", "Tree"].map(|desc| ("K".to_string(), desc));
+        // " [K: Exit]" is 10 columns wide and each row ends with a space.
+        let widths = bindings
+            .iter()
+            .map(|(keys, desc)| binding_width(keys, desc))
+            .collect::<Vec<_>>();
+        assert_eq!(widths, [10, 10, 9, 9, 11, 10]);
+        let fits = footer_layout(&bindings, 0, 60, 9);
+        assert_eq!(row_ends(&fits), [6]);
+        assert!(!fits.truncated);
+        let wrapped = footer_layout(&bindings, 0, 40, 9);
+        assert_eq!(row_ends(&wrapped), [4, 6]);
+        assert!(!wrapped.truncated);
+        // The notes before the bindings push them onto the next row sooner.
+        let led = footer_layout(&bindings, 11, 40, 9);
+        assert_eq!(row_ends(&led), [2, 6]);
+        let truncated = footer_layout(&bindings, 0, 30, 9);
+        assert_eq!(row_ends(&truncated), [3, 5]);
+        assert!(truncated.truncated);
+        let truncated = footer_layout(&bindings, 0, 20, 9);
+        assert_eq!(row_ends(&truncated), [1, 2]);
+        assert_eq!(row_ends(&footer_layout(&[], 10, 60, 9)), [0]);
+    }
+
+    #[test]
+    fn footer_grows_a_row_for_bindings_that_dont_fit() {
+        let mut app = test_app();
+        handle_event(&mut app, &Event::App(AppEvent::GoTo(Screen::Vars)));
+        let buffer = render(&mut app);
+        assert!(contains(&buffer, "[P: Pager]"));
+        assert!(contains(&buffer, "[Q: Exit]"));
+        // Rendering again gives the same footer.
+        assert_eq!(render(&mut app), buffer);
+    }
+
+    #[test]
+    fn toasts_stack_in_the_corner_until_they_expire() {
+        let args = Cli {
+            read_only: true,
+            ..test_args(true)
+        };
+        let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+        app.set_screen(Screen::Output);
+        handle_event(&mut app, &Event::Output(OutputEvent::Clear));
+        app.notify(Severity::Success, "Copied");
+        let buffer = render(&mut app);
+        let row_of = |text: &str| {
+            (0..buffer.area.height)
+                .find(|&y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect::<String>()
+                        .contains(text)
+                })
+                .unwrap()
+        };
+        let read_only = row_of("Read-only mode: clearing");
+        let copied = row_of("Copied");
+        assert!(read_only < copied, "the newest toast is nearest the corner");
+        let copied_row = (0..buffer.area.width)
+            .map(|x| buffer[(x, copied)].symbol())
+            .collect::<String>();
+        assert!(
+            copied_row.trim_end().ends_with("│ Copied │"),
+            "{copied_row:?}"
+        );
+
+        app.tick(Instant::now() + TOAST_TTL);
+        let buffer = render(&mut app);
+        assert!(!contains(&buffer, "Copied"));
+    }
+
+    #[test]
+    fn visible_window_scrolls_like_a_list() {
+        let items = (0..50).map(|i| i.to_string()).collect::<Vec<_>>();
+        let area = Rect::new(0, 0, 10, 10);
+        let mut buffer = Buffer::empty(area);
+        let mut list_state = ListState::default();
+        let mut window_state = ListState::default();
+        for selected in [
+            Some(0),
+            Some(9),
+            Some(10),
+            Some(30),
+            Some(25),
+            Some(2),
+            None,
+            Some(49),
+        ] {
+            list_state.select(selected);
+            window_state.select(selected);
+            ratatui::widgets::StatefulWidget::render(
+                ratatui::widgets::List::new(items.clone()),
+                area,
+                &mut buffer,
+                &mut list_state,
+            );
+            let (window, inner) = visible_window(&mut window_state, items.len(), 10);
+            assert_eq!(window_state.offset(), list_state.offset(), "{selected:?}");
+            assert_eq!(window.start, list_state.offset());
+            assert_eq!(window.len(), 10);
+            assert_eq!(
+                inner.selected().map(|idx| window.start + idx),
+                list_state.selected()
+            );
+        }
+
+        // Selections past the end are pulled back, like a List does.
+        window_state.select(Some(80));
+        let (window, inner) = visible_window(&mut window_state, items.len(), 10);
+        assert_eq!(window, 40..50);
+        assert_eq!(inner.selected(), Some(9));
+        assert_eq!(visible_window(&mut window_state, 0, 10).0, 0..0);
+        assert_eq!(window_state.selected(), None);
+    }
 }
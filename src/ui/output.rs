@@ -1,11 +1,18 @@
 use ratatui::{
     Frame,
     layout::{Constraint, Layout, Rect},
+    style::Styled,
     text::Line,
-    widgets::{Block, Paragraph, Wrap},
+    widgets::{List, ListItem, Paragraph, Wrap},
 };
 
-use crate::app::App;
+use crate::{
+    app::{App, commands::unfold_function, theme::Theme, undo::QueuedKind},
+    ui::{
+        focusable_block, row_style,
+        text::{display_width, skip_width, truncate_to_width, wrap_to_width},
+    },
+};
 
 pub fn render_output_screen(app: &mut App, frame: &mut Frame, area: Rect) {
     let [desc_area, output_area] = Layout::vertical([Constraint::Max(2), Constraint::Fill(1)])
@@ -13,18 +20,91 @@ pub fn render_output_screen(app: &mut App, frame: &mut Frame, area: Rect) {
         .spacing(1)
         .areas(area);
 
-    let desc_text = Line::from(vec![
-        "These commands will be sourced by your shell when the debugger exits.".into(),
-    ]);
+    let theme = app.theme();
+    let desc_text = vec![
+        Line::from("These commands will be sourced by your shell when the debugger exits."),
+        legend(theme),
+    ];
     let desc = Paragraph::new(desc_text).wrap(Wrap { trim: false });
     frame.render_widget(desc, desc_area);
 
-    let block = Block::bordered().title(" Output ");
-    frame.render_widget(block, output_area);
+    // Leave room for the borders.
+    let width = output_area.width.saturating_sub(2) as usize;
+    let shell = app.shell();
+    // Function definitions are queued on one line but laid out like they'd be
+    // written, so their bodies are readable.
+    let commands = app
+        .output()
+        .lines()
+        .map(|line| {
+            let rows = unfold_function(shell, line).unwrap_or_else(|| vec![line.to_string()]);
+            (line.to_string(), rows)
+        })
+        .collect::<Vec<_>>();
+    let wrap = app.output_state().wrap();
+    let widest = commands
+        .iter()
+        .flat_map(|(_, rows)| rows.iter().map(|row| display_width(row)))
+        .max()
+        .unwrap_or(0);
+    let h_offset = app.output_state_mut().clamp_h_offset(widest, width);
 
-    let [output_area_inner] = Layout::vertical([Constraint::Percentage(100)])
-        .margin(1)
-        .areas(output_area);
-    let output = Paragraph::new(app.output());
-    frame.render_widget(output, output_area_inner);
+    // Long commands either wrap onto more rows or are cut off at the edge,
+    // scrolled sideways together. Aliases only matter to interactive shells,
+    // so they're set apart from everything else.
+    let theme = app.theme();
+    let items = commands
+        .iter()
+        .enumerate()
+        .map(|(idx, (line, rows))| {
+            let lines = rows
+                .iter()
+                .flat_map(|row| {
+                    if wrap {
+                        wrap_to_width(row, width)
+                            .into_iter()
+                            .map(|row| Line::from(row.to_string()))
+                            .collect()
+                    } else {
+                        let shown =
+                            truncate_to_width(&skip_width(row, h_offset), width).into_owned();
+                        vec![Line::from(shown)]
+                    }
+                })
+                .collect::<Vec<_>>();
+            let style = match app.output_line_kind(line) {
+                Some(QueuedKind::Set { .. }) => theme.success,
+                Some(QueuedKind::Unset { .. }) => theme.error,
+                _ if line.starts_with("alias ") => theme.flox_purple,
+                Some(QueuedKind::Raw | QueuedKind::Other) => theme.fg,
+                // The debugger's own commands, such as where to pause next.
+                None => theme.fg_dim,
+            };
+            ListItem::new(lines).style(row_style(theme, idx).patch(style))
+        })
+        .collect::<Vec<_>>();
+    let title = if wrap {
+        " Output (wrapped) "
+    } else {
+        " Output (no wrap) "
+    };
+    let output = List::new(items)
+        // The command list is the only pane on this screen.
+        .block(focusable_block(title, true, theme))
+        .highlight_style(theme.highlighted_text);
+    frame.render_stateful_widget(output, output_area, app.output_state_mut().list_state());
+}
+
+/// Spells out what the colors of the commands mean.
+fn legend(theme: &Theme) -> Line<'static> {
+    Line::from(vec![
+        "Colors: ".into(),
+        "set".set_style(theme.success),
+        " · ".into(),
+        "unset".set_style(theme.error),
+        " · ".into(),
+        "other".set_style(theme.fg),
+        " · ".into(),
+        "automatic".set_style(theme.fg_dim),
+    ])
 }
@@ -0,0 +1,70 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::Styled,
+    text::Line,
+    widgets::{Paragraph, Wrap},
+};
+
+use crate::{
+    app::App,
+    ui::{focusable_block, text::display_width},
+};
+
+pub fn render_prompt_screen(app: &mut App, frame: &mut Frame, area: Rect) {
+    let [scrollback_area, input_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+            .spacing(1)
+            .areas(area);
+
+    render_scrollback(app, frame, scrollback_area);
+    render_input(app, frame, input_area);
+}
+
+fn render_scrollback(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme();
+    // Up and Down scroll the scrollback while the input isn't focused.
+    let focused = !app.prompt().input_focused();
+    frame.render_widget(focusable_block(" Scrollback ", focused, theme), area);
+    let [inner_area] = Layout::vertical([Constraint::Percentage(100)])
+        .margin(1)
+        .areas(area);
+
+    // Show the newest lines at the bottom of the pane, shifted up by however
+    // far the user has scrolled.
+    let visible_lines = inner_area.height as usize;
+    app.prompt_mut().set_viewport_height(visible_lines);
+    app.prompt_mut()
+        .set_viewport_width(inner_area.width as usize);
+    let prompt = app.prompt();
+    let end = prompt
+        .scrollback()
+        .len()
+        .saturating_sub(prompt.scroll_offset());
+    let start = end.saturating_sub(visible_lines);
+    let lines = prompt.scrollback()[start..end]
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect::<Vec<_>>();
+    frame.render_widget(Paragraph::new(lines), inner_area);
+}
+
+fn render_input(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme();
+    let prompt = app.prompt();
+    let block = focusable_block(" Command ", prompt.input_focused(), theme);
+    let input = Line::from(vec![
+        "> ".set_style(theme.fg_dim),
+        prompt.input().set_style(theme.fg),
+    ]);
+    frame.render_widget(
+        Paragraph::new(input)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+    if prompt.input_focused() {
+        let cursor_x = area.x + 3 + display_width(prompt.input()) as u16;
+        frame.set_cursor_position((cursor_x.min(area.right().saturating_sub(2)), area.y + 1));
+    }
+}
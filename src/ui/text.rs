@@ -0,0 +1,245 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// What's put at the end of text that was cut short.
+const ELLIPSIS: &str = "…";
+
+/// Returns how many columns `text` takes up on screen.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Cuts `text` down to at most `max_width` columns, ending it with an
+/// ellipsis if anything was cut.
+///
+/// Text is measured by how wide it's drawn rather than by bytes or chars,
+/// so wide characters such as CJK and emoji count twice, and it's only cut
+/// between graphemes, so combining marks and joined emoji stay whole.
+pub fn truncate_to_width(text: &str, max_width: usize) -> Cow<'_, str> {
+    if text.width() <= max_width {
+        return Cow::Borrowed(text);
+    }
+    let Some(budget) = max_width.checked_sub(ELLIPSIS.width()) else {
+        return Cow::Borrowed("");
+    };
+    let mut width = 0;
+    let mut end = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        width += grapheme.width();
+        if width > budget {
+            break;
+        }
+        end = idx + grapheme.len();
+    }
+    Cow::Owned(format!("{}{ELLIPSIS}", &text[..end]))
+}
+
+/// Drops the first `columns` columns of `text`, for scrolling it sideways.
+///
+/// A wide character cut in two by the edge is replaced by a space for each
+/// of its columns still in view, so the rest of the text stays in the same
+/// columns as it would be unscrolled.
+pub fn skip_width(text: &str, columns: usize) -> Cow<'_, str> {
+    let mut width = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        if width >= columns {
+            return Cow::Borrowed(&text[idx..]);
+        }
+        width += grapheme.width();
+        if width > columns {
+            let rest = &text[idx + grapheme.len()..];
+            return Cow::Owned(format!("{}{rest}", " ".repeat(width - columns)));
+        }
+    }
+    Cow::Borrowed("")
+}
+
+/// Returns where the row starting at byte `start` of `text` ends, and where
+/// the next one starts unless it's the last. Rows break at newlines and
+/// wrap at `width` columns, between graphemes, wherever they run out of room.
+fn row_end(text: &str, start: usize, width: usize) -> (usize, Option<usize>) {
+    let mut columns = 0;
+    for (idx, grapheme) in text[start..].grapheme_indices(true) {
+        let idx = start + idx;
+        if grapheme == "\n" || grapheme == "\r\n" {
+            return (idx, Some(idx + grapheme.len()));
+        }
+        let grapheme_width = grapheme.width();
+        // A grapheme wider than the row gets one to itself.
+        if columns > 0 && columns + grapheme_width > width {
+            return (idx, Some(idx));
+        }
+        columns += grapheme_width;
+    }
+    (text.len(), None)
+}
+
+/// Returns up to `rows` rows of `text` wrapped at `width` columns, starting
+/// with the row at byte `start`. Only those rows are looked at, so this is
+/// cheap however long `text` is.
+pub fn wrapped_rows(text: &str, start: usize, width: usize, rows: usize) -> Vec<&str> {
+    let mut lines = Vec::with_capacity(rows);
+    let mut start = start;
+    while lines.len() < rows {
+        let (end, next) = row_end(text, start, width);
+        lines.push(&text[start..end]);
+        match next {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+    lines
+}
+
+/// Returns all of `text` wrapped at `width` columns, like [`wrapped_rows`].
+pub fn wrap_to_width(text: &str, width: usize) -> Vec<&str> {
+    let mut rows = Vec::new();
+    let mut start = Some(0);
+    while let Some(row_start) = start {
+        let (end, next) = row_end(text, row_start, width);
+        rows.push(&text[row_start..end]);
+        start = next;
+    }
+    rows
+}
+
+/// Returns where the row after the one at byte `start` of `text` starts,
+/// unless it's the last row.
+pub fn next_row_start(text: &str, start: usize, width: usize) -> Option<usize> {
+    row_end(text, start, width).1
+}
+
+/// Returns where the row `count` rows before the one at byte `start` of
+/// `text` starts, or 0 if there aren't that many. Rows only start at known
+/// places at the start of each line, so this wraps the lines before `start`
+/// from their beginnings.
+pub fn rows_back(text: &str, start: usize, width: usize, count: usize) -> usize {
+    let mut start = start;
+    let mut count = count;
+    while count > 0 && start > 0 {
+        let before = &text[..start];
+        // The row before one that starts a line is the end of the line before.
+        let before = before.strip_suffix('\n').unwrap_or(before);
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let mut starts = vec![line_start];
+        while let Some(next) = next_row_start(text, starts[starts.len() - 1], width)
+            && next < start
+        {
+            starts.push(next);
+        }
+        let back = count.min(starts.len());
+        start = starts[starts.len() - back];
+        count -= back;
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_that_fits_is_untouched() {
+        assert!(matches!(
+            truncate_to_width("PATH", 4),
+            Cow::Borrowed("PATH")
+        ));
+        assert_eq!(truncate_to_width("", 0), "");
+        assert_eq!(truncate_to_width("日本", 4), "日本");
+    }
+
+    #[test]
+    fn text_is_cut_by_display_width() {
+        assert_eq!(truncate_to_width("/usr/local/bin", 8), "/usr/lo…");
+        // Each of these is two columns wide.
+        assert_eq!(truncate_to_width("日本語の値", 6), "日本…");
+        assert_eq!(truncate_to_width("日本語の値", 5), "日本…");
+        assert_eq!(truncate_to_width("🦀🦀🦀", 4), "🦀…");
+        assert_eq!(truncate_to_width("🦀🦀🦀", 1), "…");
+        assert_eq!(truncate_to_width("🦀🦀🦀", 0), "");
+        for width in 0..12 {
+            assert!(display_width(&truncate_to_width("a日b本c語d", width)) <= width);
+        }
+    }
+
+    #[test]
+    fn graphemes_are_never_split() {
+        // "e" followed by a combining acute accent is one column.
+        let accented = "cafe\u{301}s";
+        assert_eq!(display_width(accented), 5);
+        assert_eq!(truncate_to_width(accented, 5), accented);
+        assert_eq!(truncate_to_width(accented, 4), "caf…");
+        assert_eq!(truncate_to_width("cafe\u{301}ss", 5), "cafe\u{301}…");
+
+        // A family emoji is several emoji joined with zero-width joiners.
+        let family = "👨\u{200d}👩\u{200d}👧 home";
+        assert_eq!(display_width("👨\u{200d}👩\u{200d}👧"), 2);
+        assert_eq!(truncate_to_width(family, 4), "👨\u{200d}👩\u{200d}👧 …");
+        assert_eq!(truncate_to_width(family, 2), "…");
+    }
+
+    #[test]
+    fn scrolled_text_keeps_its_columns() {
+        assert_eq!(skip_width("PATH=/bin", 0), "PATH=/bin");
+        assert_eq!(skip_width("PATH=/bin", 5), "/bin");
+        assert_eq!(skip_width("PATH", 4), "");
+        assert_eq!(skip_width("PATH", 10), "");
+        // Half of a wide character is left as a space.
+        assert_eq!(skip_width("日本語", 1), " 本語");
+        assert_eq!(skip_width("日本語", 2), "本語");
+        assert_eq!(skip_width("cafe\u{301}!", 3), "e\u{301}!");
+
+        assert_eq!(wrap_to_width("abcdef\ngh", 4), ["abcd", "ef", "gh"]);
+        assert_eq!(wrap_to_width("", 4), [""]);
+    }
+
+    #[test]
+    fn rows_wrap_by_width_and_break_at_newlines() {
+        let text = "abcdef\ngh\n\nij";
+        assert_eq!(wrapped_rows(text, 0, 4, 10), ["abcd", "ef", "gh", "", "ij"]);
+        assert_eq!(wrapped_rows(text, 0, 4, 2), ["abcd", "ef"]);
+        assert_eq!(wrapped_rows(text, 4, 4, 2), ["ef", "gh"]);
+        assert_eq!(next_row_start(text, 0, 4), Some(4));
+        assert_eq!(next_row_start(text, 4, 4), Some(7));
+        assert_eq!(next_row_start(text, 13, 4), None);
+        assert_eq!(wrapped_rows("", 0, 4, 3), [""]);
+
+        // Going back lands on the same rows as going forward.
+        let starts = [0, 4, 7, 10, 11];
+        for (idx, start) in starts.iter().enumerate() {
+            for count in 0..=idx {
+                assert_eq!(rows_back(text, *start, 4, count), starts[idx - count]);
+            }
+            assert_eq!(rows_back(text, *start, 4, idx + 5), 0);
+        }
+        // From the end, going back one row lands on the last one.
+        assert_eq!(rows_back(text, text.len(), 4, 1), 11);
+        assert_eq!(rows_back("abc\n", 4, 4, 1), 0);
+    }
+
+    #[test]
+    fn rows_never_split_graphemes() {
+        let text = "日本語🦀cafe\u{301}👨\u{200d}👩\u{200d}👧x";
+        let rows = wrapped_rows(text, 0, 5, 10);
+        assert_eq!(
+            rows,
+            ["日本", "語🦀c", "afe\u{301}👨\u{200d}👩\u{200d}👧", "x"]
+        );
+        assert_eq!(rows.concat(), text);
+        for row in &rows {
+            assert!(display_width(row) <= 5, "{row}");
+        }
+        let mut start = 0;
+        for row in &rows[..rows.len() - 1] {
+            start = next_row_start(text, start, 5).unwrap();
+            assert!(text.is_char_boundary(start), "{row}");
+        }
+        assert_eq!(rows_back(text, start, 5, 2), "日本".len());
+
+        // A grapheme wider than the row still gets shown.
+        assert_eq!(wrapped_rows("🦀🦀", 0, 1, 5), ["🦀", "🦀"]);
+        assert_eq!(wrapped_rows("a\r\nb", 0, 4, 5), ["a", "b"]);
+    }
+}
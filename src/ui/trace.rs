@@ -1,14 +1,21 @@
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Flex, Layout, Rect},
-    style::Styled,
-    text::{Line, Text},
-    widgets::{Block, List, Paragraph},
+    text::{Line, Span, Text},
+    widgets::{Block, List, ListItem, Paragraph},
 };
 
-use crate::app::App;
+use crate::{
+    app::{App, trace::FrameKind},
+    ui::{focusable_block, row_style, text::truncate_to_width},
+};
+
+/// Marks the frames of files being sourced in the list of frames, which are
+/// otherwise only numbered.
+const SOURCED_GLYPH: &str = "⤷";
 
 pub fn render_trace_screen(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = app.theme().clone();
     let [tracepoint_area, call_stack_area] =
         Layout::vertical([Constraint::Length(3), Constraint::Percentage(100)])
             .margin(1)
@@ -25,19 +32,29 @@ pub fn render_trace_screen(app: &mut App, frame: &mut Frame, area: Rect) {
             .tracepoint()
             .unwrap_or(&"<no tracepoint provided>".to_string())
     ));
-    frame.render_widget(Block::bordered(), tracepoint_area);
+    frame.render_widget(
+        Block::bordered().border_style(theme.border),
+        tracepoint_area,
+    );
     frame.render_widget(label, tracepoint_area_inner);
 
-    // Render a "no call stack provided" message if the call stack is missing.
-    if app.trace().call_stack().is_none() {
+    // Render a "no call stack provided" message if the call stack is missing,
+    // or why it couldn't be read if that's the reason.
+    let Some(n_frames) = app.trace().call_stack().map(|stack| stack.frames.len()) else {
         frame.render_widget(
             Block::bordered()
+                .border_style(theme.border)
                 .title(" Call Stack ")
                 .title_alignment(Alignment::Center),
             call_stack_area,
         );
 
-        let text = Text::from("<no call stack provided>");
+        let message = match app.trace().call_stack_error() {
+            Some(error) => format!("<{error}>"),
+            None => "<no call stack provided>".to_string(),
+        };
+        let width = call_stack_area.width.saturating_sub(4) as usize;
+        let text = Text::from(truncate_to_width(&message, width).into_owned());
         let [center_horiz] = Layout::horizontal([Constraint::Length(text.width() as u16 + 2)])
             .flex(Flex::Center)
             .areas(call_stack_area);
@@ -47,25 +64,55 @@ pub fn render_trace_screen(app: &mut App, frame: &mut Frame, area: Rect) {
             .areas(center_horiz);
         frame.render_widget(text, center_vert);
         return;
-    }
+    };
 
     // Render the call stack with a list on the left and the source on the right.
-    // SAFETY: Since we know the call stack exists at this point, it's safe to
-    //         unwrap it from now on.
     let [list_area, call_site_area] =
         Layout::horizontal([Constraint::Percentage(25), Constraint::Percentage(75)])
             .spacing(1)
             .areas(call_stack_area);
 
-    // This renders the list of stack frames.
-    let n_frames = app.trace().call_stack().unwrap().frames.len();
-    let frame_titles = (0..n_frames)
-        .map(|i| format!("Frame #{i}"))
+    // This renders the list of stack frames. Nothing may have been selected
+    // yet if the app wasn't set up through the usual path, in which case the
+    // first frame is shown.
+    let idx = match app.trace_mut().list_state() {
+        Some(state) => *state.selected_mut().get_or_insert(0),
+        None => 0,
+    }
+    .min(n_frames.saturating_sub(1));
+    let title_width = list_area.width.saturating_sub(2) as usize;
+    let frame_titles = app
+        .trace()
+        .call_stack()
+        .map(|stack| stack.frames.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, stack_frame)| {
+            let title = match stack_frame.ctx.kind {
+                FrameKind::Func => format!("Frame #{i}"),
+                FrameKind::Source => {
+                    format!("Frame #{i} {SOURCED_GLYPH} {}", stack_frame.ctx.label())
+                }
+            };
+            let title = truncate_to_width(&title, title_width).into_owned();
+            ListItem::new(title).style(row_style(&theme, i))
+        })
         .collect::<Vec<_>>();
     let list = List::new(frame_titles)
-        .block(Block::bordered().title(" Call Stack "))
-        .highlight_style(app.theme().highlighted_text);
-    frame.render_stateful_widget(list, list_area, app.trace_mut().list_state().unwrap());
+        // The frame list is the only pane on this screen that takes input.
+        .block(focusable_block(" Call Stack ", true, &theme))
+        .highlight_style(theme.highlighted_text);
+    if let Some(state) = app.trace_mut().list_state() {
+        frame.render_stateful_widget(list, list_area, state);
+    }
+    let Some(stack_frame) = app
+        .trace()
+        .call_stack()
+        .and_then(|stack| stack.frames.get(idx))
+    else {
+        return;
+    };
 
     // This divides up the source view area into the actual source view
     // and call site information.
@@ -74,7 +121,12 @@ pub fn render_trace_screen(app: &mut App, frame: &mut Frame, area: Rect) {
         .areas(call_site_area);
 
     // Render the call site information.
-    frame.render_widget(Block::bordered().title(" Call Site Info "), info_area);
+    frame.render_widget(
+        Block::bordered()
+            .border_style(theme.border)
+            .title(" Call Site Info "),
+        info_area,
+    );
     let [file_area, line_area, function_area] = Layout::vertical([
         Constraint::Length(1),
         Constraint::Length(1),
@@ -82,47 +134,54 @@ pub fn render_trace_screen(app: &mut App, frame: &mut Frame, area: Rect) {
     ])
     .margin(1)
     .areas(info_area);
-    {
-        let idx = app.trace_mut().list_state().unwrap().selected().unwrap();
-        let stack_frame = app.trace().call_stack().unwrap().frames.get(idx).unwrap();
-        frame.render_widget(
-            format!("File: {}", stack_frame.ctx.file.display()),
-            file_area,
-        );
-        frame.render_widget(format!("Line: {}", stack_frame.ctx.line), line_area);
-        frame.render_widget(
-            format!("Function: {}", stack_frame.ctx.function),
-            function_area,
-        );
-    }
+    let info_width = file_area.width as usize;
+    let file = format!("File: {}", stack_frame.ctx.file.display());
+    frame.render_widget(Line::from(truncate_to_width(&file, info_width)), file_area);
+    frame.render_widget(format!("Line: {}", stack_frame.ctx.line), line_area);
+    let function = match stack_frame.ctx.kind {
+        FrameKind::Func => format!("Function: {}", stack_frame.ctx.function),
+        FrameKind::Source => format!("Sourcing: {}", stack_frame.ctx.function),
+    };
+    frame.render_widget(
+        Line::from(truncate_to_width(&function, info_width)),
+        function_area,
+    );
 
     // This renders the source view for the highlighted stack frame.
     let [source_area_inner] = Layout::vertical([Constraint::Percentage(100)])
         .margin(1)
         .areas(source_area);
-    frame.render_widget(Block::bordered().title(" Call Site "), source_area);
+    frame.render_widget(
+        Block::bordered()
+            .border_style(theme.border)
+            .title(" Call Site "),
+        source_area,
+    );
     let visible_lines = source_area_inner.height as usize;
-    let idx = app.trace_mut().list_state().unwrap().selected().unwrap();
-    let stack_frame = app.trace().call_stack().unwrap().frames.get(idx).unwrap();
-    let line_number = stack_frame.ctx.line;
-    let (lines, call_line) = if line_number < (visible_lines / 2) {
-        (
-            stack_frame.lines.as_ref().unwrap()[..visible_lines].to_vec(),
-            line_number,
-        )
-    } else {
-        let offset = line_number - (visible_lines / 2);
-        let call_line = visible_lines / 2 - 1;
-        let lines = stack_frame.lines.as_ref().unwrap()[offset..(offset + visible_lines)].to_vec();
-        (lines, call_line)
+    let Some(source) = stack_frame.lines.as_ref() else {
+        frame.render_widget("<source unavailable>", source_area_inner);
+        return;
     };
-    let style = app.theme().highlighted_text;
+    // Center the call site in the view where possible, without scrolling
+    // past either end of the file. Line numbers start at 1.
+    let call_idx = stack_frame.ctx.line.saturating_sub(1);
+    let start = call_idx
+        .saturating_sub(visible_lines / 2)
+        .min(source.len().saturating_sub(visible_lines));
+    let end = (start + visible_lines).min(source.len());
+    let source_width = source_area_inner.width as usize;
+    let lines = source[start..end]
+        .iter()
+        .map(|line| truncate_to_width(line, source_width))
+        .collect::<Vec<_>>();
+    let call_line = call_idx - start;
+    let style = theme.highlighted_text;
     let lines = lines
         .into_iter()
         .enumerate()
         .map(|(i, line)| {
             if i == call_line {
-                Line::from(line.set_style(style))
+                Line::from(Span::styled(line, style))
             } else {
                 Line::from(line)
             }
@@ -1,12 +1,27 @@
 use ratatui::{
     Frame,
     layout::{Constraint, Layout, Rect},
-    style::{Modifier, Style, Styled},
+    style::Styled,
     text::Line,
-    widgets::{Block, List, Paragraph, Wrap},
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
 };
 
-use crate::app::{App, vars::VarDetailState};
+use crate::{
+    app::{
+        App,
+        theme::Theme,
+        var_tree::VarRow,
+        vars::{Env, LARGE_VALUE_BYTES, VarDetailState},
+    },
+    ui::{
+        focusable_block, row_style,
+        text::{display_width, truncate_to_width, wrapped_rows},
+        visible_window,
+    },
+};
+
+/// Marks the variables whose queued changes conflict with a reload.
+const CONFLICT_MARKER: &str = "! ";
 
 pub fn render_vars_screen(app: &mut App, frame: &mut Frame, area: Rect) {
     let [var_list_area, detail_area] =
@@ -19,19 +34,134 @@ pub fn render_vars_screen(app: &mut App, frame: &mut Frame, area: Rect) {
 }
 
 fn render_var_list(app: &mut App, frame: &mut Frame, area: Rect) {
-    let theme = app.theme();
-    let block = Block::bordered().title(" Variables ");
-    let block = if app.env().var_list_focused() {
-        block.border_style(theme.flox_purple)
-    } else {
-        block
-    };
+    let theme = app.theme().clone();
+    let mut title = Line::from(match app.env().filter_label() {
+        Some(label) => format!(" Variables ({label}) "),
+        None => " Variables ".to_string(),
+    });
+    // Says why the selection moved when the variable it was on went away.
+    if let Some(name) = app.env().displaced() {
+        // Leave room for the corners and the focus marker.
+        let room = (area.width as usize).saturating_sub(title.width() + 4);
+        let note = truncate_to_width(&format!("· {name} hidden "), room).into_owned();
+        title.push_span(note.set_style(theme.fg_dim));
+    }
+    let block = focusable_block(title, app.env().var_list_focused(), &theme);
     let env = app.env_mut();
-    let vars = env.vars().to_vec();
-    let var_list = List::new(vars)
+    // Leave room for the borders.
+    let width = area.width.saturating_sub(2) as usize;
+    let height = area.height.saturating_sub(2) as usize;
+    // Only the rows that fit are copied, since the environment can be large.
+    // The first row's index in the whole list is kept for the stripes.
+    let (rows, mut window_state, first) = match tree_labels(env) {
+        Some(labels) => {
+            let tree = env.tree_mut().expect("the labels are for the tree");
+            let (window, window_state) = visible_window(tree.row_state(), labels.len(), height);
+            let first = window.start;
+            (labels[window].to_vec(), window_state, first)
+        }
+        None => {
+            let (window, window_state, first) = match env.filtered_vars() {
+                Some((filtered, list_state)) => {
+                    let (window, window_state) = visible_window(list_state, filtered.len(), height);
+                    let first = window.start;
+                    (filtered[window].to_vec(), window_state, first)
+                }
+                None => {
+                    let len = env.vars().len();
+                    let (window, window_state) = visible_window(env.var_list_state(), len, height);
+                    let first = window.start;
+                    (window.collect(), window_state, first)
+                }
+            };
+            let rows = window
+                .into_iter()
+                .map(|idx| {
+                    let name = &env.vars()[idx];
+                    let marker = conflict_marker(env, name);
+                    (format!("{marker}{name}"), Some(idx))
+                })
+                .collect();
+            (rows, window_state, first)
+        }
+    };
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(row, (text, var))| {
+            let text = match var {
+                Some(idx) if !env.origins().is_empty() => {
+                    let origin = env.origins().origin_of(&env.vars()[*idx]);
+                    with_origin(text, origin, width)
+                }
+                _ => truncate_to_width(text, width).into_owned(),
+            };
+            let style = match var {
+                Some(idx) if env.conflicts().contains(&env.vars()[*idx]) => {
+                    row_style(&theme, first + row).patch(theme.warning)
+                }
+                _ => row_style(&theme, first + row),
+            };
+            ListItem::new(text).style(style)
+        })
+        .collect::<Vec<_>>();
+    let var_list = List::new(rows)
         .block(block)
         .highlight_style(theme.highlighted_text);
-    frame.render_stateful_widget(var_list, area, env.var_list_state());
+    frame.render_stateful_widget(var_list, area, &mut window_state);
+}
+
+/// Labels each row of the tree of variables, if that's how they're shown,
+/// along with the variable on the row if it isn't a group. Groups show how
+/// many of their variables are shown, and variables are indented under the
+/// groups they're in.
+fn tree_labels(env: &Env) -> Option<Vec<(String, Option<usize>)>> {
+    let tree = env.tree()?;
+    let labels = tree
+        .rows(env.vars().len())
+        .into_iter()
+        .map(|row| match row {
+            VarRow::Group { group, expanded } => {
+                let marker = if expanded { '▾' } else { '▸' };
+                let label = format!(
+                    "{marker} {}* ({})",
+                    tree.group(group).prefix,
+                    tree.shown_vars(group).len()
+                );
+                (label, None)
+            }
+            VarRow::Var { idx, nested } => {
+                let indent = if nested { "    " } else { "  " };
+                let name = &env.vars()[idx];
+                let marker = conflict_marker(env, name);
+                (format!("{indent}{marker}{name}"), Some(idx))
+            }
+        })
+        .collect();
+    Some(labels)
+}
+
+/// Returns the marker for `name` if its queued changes conflict with a
+/// reload, and nothing otherwise.
+fn conflict_marker(env: &Env, name: &str) -> &'static str {
+    if env.conflicts().contains(name) {
+        CONFLICT_MARKER
+    } else {
+        ""
+    }
+}
+
+/// Fits `label` into `width` columns with `origin` at the right edge, as
+/// long as there's room for both.
+fn with_origin(label: &str, origin: &str, width: usize) -> String {
+    let origin_width = display_width(origin);
+    // Leave a space between them, and at least a few columns of the label.
+    let Some(label_width) = width.checked_sub(origin_width + 1).filter(|w| *w >= 4) else {
+        return truncate_to_width(label, width).into_owned();
+    };
+    let label = truncate_to_width(label, label_width);
+    let padding = label_width - display_width(&label);
+    format!("{label}{} {origin}", " ".repeat(padding))
 }
 
 fn render_var_detail(app: &mut App, frame: &mut Frame, area: Rect) {
@@ -39,19 +169,40 @@ fn render_var_detail(app: &mut App, frame: &mut Frame, area: Rect) {
         Layout::vertical([Constraint::Length(3), Constraint::Percentage(100)])
             .spacing(1)
             .areas(area);
+    let detail_sub_area = if app.env().command_preview() {
+        let command = command_preview(app);
+        let height = command_height(&command, detail_sub_area.width.saturating_sub(2))
+            .min(detail_sub_area.height / 2);
+        let [value_area, command_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(height)])
+                .areas(detail_sub_area);
+        render_command_preview(app, frame, command_area, command);
+        value_area
+    } else {
+        detail_sub_area
+    };
 
     // Render the boxed variable name
+    let theme = app.theme();
     let selected_var = app.env().selected_var();
-    frame.render_widget(Block::bordered().title(" Name "), var_name_area);
+    let mut name_block = Block::bordered().border_style(theme.border).title(" Name ");
+    if let Some(origin) = app.env().selected_var_origin() {
+        name_block = name_block.title(
+            Line::from(format!(" {origin} "))
+                .style(theme.selected_option)
+                .right_aligned(),
+        );
+    }
+    frame.render_widget(name_block, var_name_area);
     let [var_name_area_inner] = Layout::vertical([Constraint::Percentage(100)])
         .margin(1)
         .areas(var_name_area);
+    let selected_var = selected_var.unwrap_or("<No variable selected>".to_string());
     frame.render_widget(
-        Paragraph::new(
-            selected_var
-                .clone()
-                .unwrap_or("<No variable selected>".to_string()),
-        ),
+        Paragraph::new(truncate_to_width(
+            &selected_var,
+            var_name_area_inner.width as usize,
+        )),
         var_name_area_inner,
     );
 
@@ -60,40 +211,59 @@ fn render_var_detail(app: &mut App, frame: &mut Frame, area: Rect) {
         .selected_detail_item()
         .unwrap_or("<No item selected>".to_string());
 
-    let detail_block_title = var_detail_block_title(app.env().var_detail_state());
-    let theme = app.theme();
-    let block = Block::bordered().title(detail_block_title);
-    let block = if app.env().var_list_focused() {
-        block
-    } else {
-        block.border_style(theme.flox_purple)
-    };
+    let theme = app.theme().clone();
+    let detail_block_title = var_detail_block_title(app.env().var_detail_state(), &theme);
+    let block = focusable_block(detail_block_title, !app.env().var_list_focused(), &theme);
     match app.env_mut().var_detail_state_mut() {
         VarDetailState::Raw => {
             let [text_area] = Layout::vertical([Constraint::Percentage(100)])
                 .margin(1)
                 .areas(detail_sub_area);
-            let selected_var = app
-                .env_mut()
-                .selected_var_value()
-                .unwrap_or("<No variable selected>".to_string());
             frame.render_widget(block, detail_sub_area);
-            frame.render_widget(
-                Paragraph::new(selected_var).wrap(Wrap { trim: false }),
-                text_area,
-            );
+            if app
+                .env()
+                .selected_var_value()
+                .is_some_and(|value| value.len() >= LARGE_VALUE_BYTES)
+            {
+                render_large_value(app, frame, text_area);
+                return;
+            }
+            let value = app
+                .env()
+                .selected_var_value()
+                .unwrap_or("<No variable selected>");
+            // Only wrap as much of a large value as could fit, since
+            // wrapping all of it on every frame is slow. Every char takes up
+            // at least a column unless it's zero-width.
+            let max_chars = text_area.width as usize * text_area.height as usize;
+            let value = match value.char_indices().nth(max_chars) {
+                Some((end, _)) => &value[..end],
+                None => value,
+            };
+            frame.render_widget(Paragraph::new(value).wrap(Wrap { trim: false }), text_area);
         }
         VarDetailState::Split { items, list_state } => {
             let [list_area, value_area] =
                 Layout::vertical([Constraint::Fill(1), Constraint::Length(4)])
                     .spacing(1)
                     .areas(detail_sub_area);
-            let var_list = List::new(items.iter().map(|s| s.as_str()))
+            let width = list_area.width.saturating_sub(2) as usize;
+            let height = list_area.height.saturating_sub(2) as usize;
+            let (window, mut window_state) = visible_window(list_state, items.len(), height);
+            let var_list =
+                List::new(items[window.clone()].iter().zip(window).map(|(item, idx)| {
+                    ListItem::new(truncate_to_width(item, width)).style(row_style(&theme, idx))
+                }))
                 .block(block)
                 .highlight_style(theme.highlighted_text);
-            frame.render_stateful_widget(var_list, list_area, list_state);
+            frame.render_stateful_widget(var_list, list_area, &mut window_state);
 
-            frame.render_widget(Block::bordered().title(" Selected "), value_area);
+            frame.render_widget(
+                Block::bordered()
+                    .border_style(theme.border)
+                    .title(" Selected "),
+                value_area,
+            );
             let [value_area_inner] = Layout::vertical([Constraint::Percentage(100)])
                 .margin(1)
                 .areas(value_area);
@@ -105,22 +275,108 @@ fn render_var_detail(app: &mut App, frame: &mut Frame, area: Rect) {
     }
 }
 
-fn var_detail_block_title(state: &VarDetailState) -> Line<'static> {
+/// Draws only the rows of a large value that are on screen, beneath a
+/// banner giving its size, since wrapping all of it would stall every frame.
+fn render_large_value(app: &mut App, frame: &mut Frame, area: Rect) {
+    let [banner_area, rows_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+    let (width, height) = (rows_area.width as usize, rows_area.height as usize);
+    app.env_mut().set_raw_viewport(width, height);
+    let theme = app.theme();
+    let env = app.env();
+    let value = env.selected_var_value().unwrap_or_default();
+    let banner = format!(
+        "Showing part of a {} value, scroll with ↑↓",
+        format_size(value.len())
+    );
+    frame.render_widget(
+        Paragraph::new(truncate_to_width(&banner, width)).style(theme.warning),
+        banner_area,
+    );
+    let rows = wrapped_rows(value, env.raw_scroll(), width, height);
+    frame.render_widget(Paragraph::new(rows.join("\n")), rows_area);
+}
+
+/// Formats a number of bytes the way people read them, such as `5.0 MiB`.
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = "bytes";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    if unit == "bytes" {
+        format!("{bytes} bytes")
+    } else {
+        format!("{size:.1} {unit}")
+    }
+}
+
+/// Returns the command that sets the selected variable, or a placeholder
+/// while its value is hidden.
+fn command_preview(app: &App) -> String {
+    match (app.env().selected_var(), app.selected_var_command()) {
+        (Some(name), Some(_)) if app.env().value_hidden() => {
+            format!("<hidden, {name} looks like a secret>")
+        }
+        (_, Some(command)) => command,
+        _ => "<No variable selected>".to_string(),
+    }
+}
+
+/// Returns the height of a box that fits `command` wrapped to `width`.
+fn command_height(command: &str, width: u16) -> u16 {
+    let width = (width as usize).max(1);
+    let lines = command
+        .split('\n')
+        .map(|line| display_width(line).div_ceil(width).max(1))
+        .sum::<usize>();
+    u16::try_from(lines + 2).unwrap_or(u16::MAX)
+}
+
+fn render_command_preview(app: &App, frame: &mut Frame, area: Rect, command: String) {
+    let theme = app.theme();
+    let label = if app.env().value_hidden() {
+        " hidden ".to_string()
+    } else {
+        format!(" {} ", app.shell())
+    };
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Command ")
+        .title(
+            Line::from(label)
+                .style(theme.selected_option)
+                .right_aligned(),
+        );
+    frame.render_widget(
+        Paragraph::new(command)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn var_detail_block_title(state: &VarDetailState, theme: &Theme) -> Line<'static> {
     match state {
         VarDetailState::Raw => Line::from(vec![
             " ".into(),
-            "Raw".set_style(Style::new().add_modifier(Modifier::UNDERLINED)),
+            "Raw".set_style(theme.selected_option),
             " / ".into(),
-            "S".set_style(Style::new().add_modifier(Modifier::UNDERLINED)),
+            "S".set_style(theme.selected_option),
             "plit".into(),
             " ".into(),
         ]),
         VarDetailState::Split { .. } => Line::from(vec![
             " ".into(),
-            "R".set_style(Style::new().add_modifier(Modifier::UNDERLINED)),
+            "R".set_style(theme.selected_option),
             "aw".into(),
             " / ".into(),
-            "Split".set_style(Style::new().add_modifier(Modifier::UNDERLINED)),
+            "Split".set_style(theme.selected_option),
             " ".into(),
         ]),
     }
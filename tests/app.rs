@@ -0,0 +1,172 @@
+//! Drives the debugger through the library, the way an embedder would.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    os::{fd::FromRawFd, unix::net::UnixStream},
+    time::Duration,
+};
+
+use clap::Parser;
+use flox_debugger::{
+    app::{
+        Action, App, AppEvent, Event, Screen, Shell, handle_event, modal::ModalEvent,
+        prompt::PromptEvent, run_app, trace::load_call_stack,
+    },
+    cli::Cli,
+    terminal::{TerminalInfo, events::EventSource},
+};
+use ratatui::{Terminal, backend::TestBackend};
+use serde_json::{Value, json};
+
+// Shared with the unit tests, which use more of it than this file does.
+#[path = "../src/test_dir.rs"]
+#[allow(dead_code)]
+mod test_dir;
+
+use test_dir::TestDir;
+
+fn app(shell: &str) -> App {
+    let args = Cli::try_parse_from([
+        "flox-debugger",
+        "--shell",
+        shell,
+        "--config",
+        "/nonexistent/config.toml",
+    ])
+    .unwrap();
+    App::new(&args, &TerminalInfo::default()).unwrap()
+}
+
+fn run(app: &mut App, command: &str) {
+    handle_event(app, &Event::App(AppEvent::GoTo(Screen::Prompt)));
+    app.prompt_mut().set_input_focused(true);
+    app.prompt_mut().set_input(command);
+    handle_event(app, &Event::Prompt(PromptEvent::Submit));
+}
+
+fn written_output(app: &App) -> String {
+    let mut stdout = Vec::new();
+    app.write_output(&mut stdout).unwrap();
+    String::from_utf8(stdout).unwrap()
+}
+
+#[test]
+fn queued_commands_are_printed_on_exit() {
+    let env = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+    let mut app = app("bash").with_env(&env).with_screen(Screen::Vars);
+    assert_eq!(app.env().vars(), ["FOO"]);
+
+    run(&mut app, "raw export FOO=baz");
+    assert!(app.output().ends_with("export FOO=baz\n"));
+
+    handle_event(&mut app, &Event::App(AppEvent::ExitRequested));
+    assert_eq!(
+        handle_event(&mut app, &Event::Modal(ModalEvent::Accept)),
+        Action::Exit
+    );
+    assert_eq!(written_output(&app), app.output());
+}
+
+#[test]
+fn commands_can_be_printed_to_another_file_descriptor() {
+    let mut fds = [0; 2];
+    // SAFETY: `pipe` only writes the two descriptors it opens into `fds`.
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let [read_end, write_end] = fds;
+    let args = Cli::try_parse_from([
+        "flox-debugger",
+        "--shell",
+        "bash",
+        "--config",
+        "/nonexistent/config.toml",
+        "--output-fd",
+        &write_end.to_string(),
+    ])
+    .unwrap();
+    let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+    run(&mut app, "raw export FOO=baz");
+    app.print_output().unwrap();
+    // The app owns the write end, so the pipe is only done once it's gone.
+    drop(app);
+    let mut printed = String::new();
+    // SAFETY: The read end was never handed to anything else.
+    unsafe { File::from_raw_fd(read_end) }
+        .read_to_string(&mut printed)
+        .unwrap();
+    assert!(printed.ends_with("export FOO=baz\n"), "{printed:?}");
+}
+
+#[test]
+fn commands_follow_the_chosen_shell() {
+    let mut app = app("bash").with_shell(Shell::Fish);
+    assert!(matches!(app.shell(), Shell::Fish));
+    run(&mut app, "break setup");
+    assert!(app.output().contains("set -gx"), "{}", app.output());
+}
+
+#[test]
+fn call_stacks_load_without_a_terminal() {
+    let stack = load_call_stack("/etc/hooks.sh:14:fdb_tracepoint\n", Shell::Bash).unwrap();
+    assert_eq!(stack.frames.len(), 1);
+    assert_eq!(stack.frames[0].ctx.line, 14);
+    assert_eq!(stack.frames[0].ctx.function, "fdb_tracepoint");
+}
+
+#[test]
+fn the_control_socket_drives_the_event_loop() {
+    let dir = TestDir::new("app-control");
+    let path = dir.join("control.sock");
+    let args = Cli::try_parse_from([
+        "flox-debugger".as_ref(),
+        "--shell".as_ref(),
+        "bash".as_ref(),
+        "--config".as_ref(),
+        "/nonexistent/config.toml".as_ref(),
+        "--control-socket".as_ref(),
+        path.as_os_str(),
+    ])
+    .unwrap();
+    let mut app = App::new(&args, &TerminalInfo::default()).unwrap();
+
+    let client = std::thread::spawn({
+        let path = path.clone();
+        move || {
+            let stream = UnixStream::connect(&path).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = |request: Value| {
+                writeln!(&stream, "{request}").unwrap();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                serde_json::from_str::<Value>(&line).unwrap()
+            };
+            let status = request(json!({"request": "status"}));
+            assert_eq!(status["response"], "status");
+            assert_eq!(status["paused"], true);
+            assert_eq!(status["pending_commands"], 0);
+            let queued = request(json!({"request": "queue", "cmd": "export FOO=baz"}));
+            assert_eq!(queued["pending_commands"], 1, "{queued}");
+            let bad = request(json!({"request": "queue", "cmd": ""}));
+            assert_eq!(bad["response"], "error");
+            let continuing = request(json!({"request": "continue"}));
+            assert_eq!(continuing["response"], "continuing");
+        }
+    });
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    let events = EventSource::spawn(|timeout| {
+        std::thread::sleep(timeout.min(Duration::from_millis(10)));
+        Ok(None)
+    });
+    run_app(&mut app, &mut terminal, &events).unwrap();
+    client.join().unwrap();
+    assert!(
+        app.output().ends_with("export FOO=baz\n"),
+        "{}",
+        app.output()
+    );
+
+    drop(app);
+    assert!(!path.exists());
+}
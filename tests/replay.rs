@@ -0,0 +1,104 @@
+//! Runs the debugger binary on a pseudo-terminal, the way a shell would, with
+//! its key presses coming from a `--replay` script.
+
+use std::{
+    fs::{self, File},
+    io::Read,
+    os::fd::{FromRawFd, OwnedFd},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+#[path = "../src/test_dir.rs"]
+mod test_dir;
+
+use test_dir::TestDir;
+
+/// Opens a pseudo-terminal, returning the controlling side and the side the
+/// debugger gets as its terminal.
+fn open_pty() -> (OwnedFd, OwnedFd) {
+    let mut controller = -1;
+    let mut terminal = -1;
+    let size = libc::winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let ret = unsafe {
+        libc::openpty(
+            &mut controller,
+            &mut terminal,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &size,
+        )
+    };
+    assert_eq!(ret, 0, "openpty: {}", std::io::Error::last_os_error());
+    unsafe {
+        (
+            OwnedFd::from_raw_fd(controller),
+            OwnedFd::from_raw_fd(terminal),
+        )
+    }
+}
+
+#[test]
+fn replayed_edits_are_printed_on_exit() {
+    let dir = TestDir::new("replay");
+    let env_file = dir.join("env");
+    fs::write(&env_file, "FOO=bar\0HOME=/nonexistent\0").unwrap();
+    let script = dir.join("script");
+    fs::write(
+        &script,
+        "# Queue a command from the prompt, then run out of input.\n\
+         char:p char:i sleep:10ms\n\
+         text:raw space text:export space text:FOO=baz enter\n",
+    )
+    .unwrap();
+
+    let (controller, terminal) = open_pty();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_flox-debugger"))
+        .args(["--shell", "bash", "--theme", "dark"])
+        .args(["--config", "/nonexistent/config.toml"])
+        .arg("--env-file")
+        .arg(&env_file)
+        .arg("--replay")
+        .arg(&script)
+        .env("HOME", dir.path())
+        .env("XDG_STATE_HOME", dir.path())
+        .env("TERM", "xterm-256color")
+        .stdin(Stdio::from(terminal.try_clone().unwrap()))
+        .stderr(Stdio::from(terminal))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The TUI is drawn on the terminal, which has to be read from for it not
+    // to fill up.
+    let drain = std::thread::spawn(move || {
+        let mut drawn = Vec::new();
+        let _ = File::from(controller).read_to_end(&mut drawn);
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            panic!("the debugger didn't exit once the script ran out");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(status.success(), "{status}");
+    assert!(stdout.contains("export FOO=baz"), "{stdout:?}");
+    drop(drain);
+}